@@ -0,0 +1,246 @@
+//! Procedural macros for the `jwt` crate, which re-exports them under its `macros` feature.
+//!
+//! `jwt!` and `claims!` take JSON literals, check at compile time that they make a valid token or
+//! claim set, and expand to code constructing it. This catches malformed fixtures in tests when
+//! they are compiled, rather than when they are run.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Delimiter, Span, TokenStream as TokenStream2, TokenTree};
+use quote::{quote, quote_spanned};
+use serde_json::{Map, Value};
+
+#[derive(Debug)]
+// A compile error, reported at the span of the offending tokens.
+struct Error {
+    span: Span,
+    message: String,
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+fn error<T>(span: Span, message: &str) -> Result<T> {
+    Err(Error { span, message: String::from(message) })
+}
+
+impl Error {
+    fn into_compile_error(self) -> TokenStream {
+        let message = self.message;
+        TokenStream::from(quote_spanned!(self.span=> compile_error!(#message)))
+    }
+}
+
+// The algorithms which jwt::header::JWTHeader can represent.
+const ALGORITHMS: &[&str; 12] = &[
+    "none", "HS256", "HS384", "HS512", "RS256", "RS384", "RS512", "ES256", "ES384", "ES512",
+    "ES256K", "EdDSA",
+];
+
+/// Constructs a `jwt::JWT` from a JSON header and a JSON claim set, checking at compile time that
+/// the header names a supported `alg`, that neither object has duplicate members, and that the
+/// registered claims have the types RFC 7519 gives them. Strings which must be valid URIs (claim
+/// names, `iss` and `sub` values containing a colon) are only checked at run time.
+///
+/// ```ignore
+/// let token = jwt!({"alg": "none"}, {"sub": "test", "exp": 1516239022});
+/// ```
+#[proc_macro]
+pub fn jwt(input: TokenStream) -> TokenStream {
+    let expand = || -> Result<TokenStream2> {
+        let args = split_args(input.into())?;
+        if args.len() != 2 {
+            return error(Span::call_site(), "expected a header and a claim set: jwt!({..}, {..})")
+        }
+        let header = parse_object(&args[0])?;
+        check_header(&header, args[0].span())?;
+        let claims = parse_object(&args[1])?;
+        check_claims(&claims, args[1].span())?;
+
+        let header = Value::Object(header).to_string();
+        let claims = Value::Object(claims).to_string();
+        Ok(quote! {{
+            let mut jwt = ::jwt::JWT::new();
+            jwt.header =
+                <::jwt::header::JWTHeader as ::jwt::JsonSerializable>::decode_str(#header)
+                .expect("the header was checked by jwt!");
+            jwt.claim_set =
+                <::jwt::claims::ClaimSet as ::jwt::JsonSerializable>::decode_str(#claims)
+                .expect("the claim set was checked by jwt!");
+            jwt
+        }})
+    };
+    expand().map(TokenStream::from).unwrap_or_else(Error::into_compile_error)
+}
+
+/// Constructs a `jwt::claims::ClaimSet` from a JSON object, with the checks `jwt!` makes.
+///
+/// ```ignore
+/// let claim_set = claims!({"sub": "test", "aud": ["a", "b"]});
+/// ```
+#[proc_macro]
+pub fn claims(input: TokenStream) -> TokenStream {
+    let expand = || -> Result<TokenStream2> {
+        let args = split_args(input.into())?;
+        if args.len() != 1 {
+            return error(Span::call_site(), "expected a claim set: claims!({..})")
+        }
+        let claims = parse_object(&args[0])?;
+        check_claims(&claims, args[0].span())?;
+
+        let claims = Value::Object(claims).to_string();
+        Ok(quote! {
+            <::jwt::claims::ClaimSet as ::jwt::JsonSerializable>::decode_str(#claims)
+                .expect("the claim set was checked by claims!")
+        })
+    };
+    expand().map(TokenStream::from).unwrap_or_else(Error::into_compile_error)
+}
+
+// Splits macro input on its top-level commas, requiring each argument to be one token tree.
+fn split_args(input: TokenStream2) -> Result<Vec<TokenTree>> {
+    let mut args = Vec::<TokenTree>::new();
+    let mut expect_comma = false;
+    for tree in input {
+        match &tree {
+            TokenTree::Punct(punct) if punct.as_char() == ',' && expect_comma => {
+                expect_comma = false;
+            }
+            _ if !expect_comma => {
+                args.push(tree);
+                expect_comma = true;
+            }
+            _ => return error(tree.span(), "expected `,` between JSON objects"),
+        }
+    }
+    Ok(args)
+}
+
+// Writes tokens back out as JSON text. Stringifying a token stream directly would space out
+// negative numbers ("- 1"), which JSON does not allow.
+fn to_json(tokens: TokenStream2, out: &mut String) {
+    for tree in tokens {
+        match tree {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::None => ("", ""),
+                };
+                out.push_str(open);
+                to_json(group.stream(), out);
+                out.push_str(close);
+            }
+            TokenTree::Punct(punct) => out.push(punct.as_char()),
+            TokenTree::Ident(ident) => out.push_str(&ident.to_string()),
+            TokenTree::Literal(literal) => out.push_str(&literal.to_string()),
+        }
+    }
+}
+
+// Parses a braced token tree as a JSON object, rejecting duplicate member names.
+fn parse_object(tree: &TokenTree) -> Result<Map<String, Value>> {
+    let group = match tree {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => group,
+        _ => return error(tree.span(), "expected a JSON object"),
+    };
+
+    let mut json = String::new();
+    to_json(TokenStream2::from(tree.clone()), &mut json);
+    let object = match serde_json::from_str::<Value>(&json) {
+        Ok(Value::Object(object)) => object,
+        Ok(_) => return error(tree.span(), "expected a JSON object"),
+        Err(e) => return error(tree.span(), &format!("invalid JSON: {}", e)),
+    };
+
+    // serde_json keeps only the last of several members with the same name, so count the names
+    // from the tokens: each is the literal at the start of the object or after a comma.
+    let mut names = Vec::<String>::new();
+    let mut at_name = true;
+    for tree in group.stream() {
+        match &tree {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => at_name = true,
+            TokenTree::Literal(literal) if at_name => {
+                // The object parsed, so this literal is a JSON string.
+                let name: String = serde_json::from_str(&literal.to_string()).unwrap();
+                if names.contains(&name) {
+                    return error(tree.span(), &format!("duplicate member \"{}\"", name))
+                }
+                names.push(name);
+                at_name = false;
+            }
+            _ => at_name = false,
+        }
+    }
+    Ok(object)
+}
+
+fn check_header(header: &Map<String, Value>, span: Span) -> Result<()> {
+    match header.get("alg").and_then(|alg| alg.as_str()) {
+        Some(alg) if ALGORITHMS.contains(&alg) => Ok(()),
+        Some(alg) => error(span, &format!("unsupported alg \"{}\"", alg)),
+        None => error(span, "the header must have a string \"alg\" member"),
+    }
+}
+
+// Checks the registered claims have the types RFC 7519 section 4.1 gives them, as
+// jwt::claims::Claim::parse does at run time.
+fn check_claims(claims: &Map<String, Value>, span: Span) -> Result<()> {
+    let strings = |value: &Value| -> bool {
+        value.is_string() ||
+        value.as_array().is_some_and(|values| values.iter().all(Value::is_string))
+    };
+    for (claim_name, claim_value) in claims {
+        let (valid, expected) = match claim_name.as_str() {
+            "exp" | "nbf" | "iat" => (claim_value.is_number(), "a NumericDate"),
+            "iss" | "sub" | "jti" => (claim_value.is_string(), "a string"),
+            "aud" => (strings(claim_value), "a string or an array of strings"),
+            _ => (true, ""),
+        };
+        if !valid {
+            return error(span, &format!("\"{}\" must be {}", claim_name, expected))
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(input: &str) -> TokenTree {
+        input.parse::<TokenStream2>().unwrap().into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_parse_object() {
+        let object =
+            parse_object(&tree("{\"a\": -1, \"b\": [true, null, {\"c\": 1.5}]}")).unwrap();
+        assert_eq!(
+            Value::Object(object),
+            serde_json::json!({"a": -1, "b": [true, null, {"c": 1.5}]})
+        );
+        assert!(parse_object(&tree("{\"a\": 1, \"b\": {\"a\": 2}}")).is_ok());
+        assert!(parse_object(&tree("{\"a\": 1, \"a\": 2}")).is_err());
+        assert!(parse_object(&tree("{\"a\" 1}")).is_err());
+        assert!(parse_object(&tree("[1]")).is_err());
+    }
+
+    #[test]
+    fn test_split_args() {
+        let args = split_args("{\"alg\": \"none\"}, {}".parse().unwrap()).unwrap();
+        assert_eq!(args.len(), 2);
+        assert!(split_args("{} {}".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_checks() {
+        let object = |input: &str| parse_object(&tree(input)).unwrap();
+        let span = Span::call_site();
+        assert!(check_header(&object("{\"alg\": \"none\"}"), span).is_ok());
+        assert!(check_header(&object("{\"alg\": \"HS1\"}"), span).is_err());
+        assert!(check_header(&object("{}"), span).is_err());
+        assert!(check_claims(&object("{\"exp\": 1, \"aud\": [\"a\"], \"x\": {}}"), span).is_ok());
+        assert!(check_claims(&object("{\"exp\": \"tomorrow\"}"), span).is_err());
+        assert!(check_claims(&object("{\"aud\": [1]}"), span).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use serde_json::Value;
+
+use crate::claims::{Audience, Claim, ClaimSet};
+use crate::err;
+
+/// Sets the `aud` claim of `claim_set` to `audiences`, and the OpenID Connect `azp` (authorized
+/// party) claim to `azp`, which must be one of them. A single audience is written as a string
+/// and several as an array. Returns a `err::JWTError::SchemaError` if `audiences` is empty, if
+/// `azp` is not among them, or if `claim_set` already has an `aud` or `azp` claim.
+///
+/// # Examples
+/// ```
+/// use jwt::audience;
+/// use jwt::claims::ClaimSet;
+///
+/// let mut cs = ClaimSet::new();
+/// audience::set_audiences(&mut cs, &["web", "api"], "web").unwrap();
+/// assert!(audience::check_azp(&cs, "web").is_ok());
+/// assert!(audience::check_azp(&cs, "api").is_err());
+/// ```
+pub fn set_audiences(claim_set: &mut ClaimSet, audiences: &[&str], azp: &str)
+    -> err::Result<()> {
+    if !audiences.contains(&azp) || claim_set.get("azp").is_ok() {
+        return Err(err::JWTError::SchemaError)
+    }
+    let aud = match audiences {
+        [audience] => Value::from(*audience),
+        _ => Value::from(audiences.to_vec()),
+    };
+    claim_set.insert(Claim::parse(String::from("aud"), aud)?)?;
+    claim_set.insert(Claim::parse(String::from("azp"), Value::from(azp))?)
+}
+
+/// Returns the audiences named by the `aud` claim of `claim_set`, which may be a string or an
+/// array of strings, or a `err::JWTError::SchemaError` if it has none.
+pub fn audiences(claim_set: &ClaimSet) -> err::Result<Vec<String>> {
+    let audience = Audience::from_claim_set(claim_set)?.ok_or(err::JWTError::SchemaError)?;
+    Ok(audience.as_slice().to_vec())
+}
+
+/// Checks that `claim_set` was issued to the client `client_id`, following OpenID Connect Core
+/// section 3.1.3.7: `client_id` must be one of its audiences; if there are several audiences, an
+/// `azp` claim must be present; and if an `azp` claim is present, it must be `client_id`.
+/// Returns a `err::JWTError::SchemaError` otherwise.
+pub fn check_azp(claim_set: &ClaimSet, client_id: &str) -> err::Result<()> {
+    let audiences = audiences(claim_set)?;
+    if !audiences.iter().any(|audience| audience == client_id) {
+        return Err(err::JWTError::SchemaError)
+    }
+    match claim_set.get("azp").ok().map(|azp| &azp.claim_value) {
+        None if audiences.len() > 1 => Err(err::JWTError::SchemaError),
+        None => Ok(()),
+        Some(azp) if azp == client_id => Ok(()),
+        Some(_) => Err(err::JWTError::SchemaError),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::JsonSerializable;
+
+    #[test]
+    fn test_set_audiences() {
+        let mut cs = ClaimSet::new();
+        set_audiences(&mut cs, &["web"], "web").unwrap();
+        assert_eq!(cs.get("aud").unwrap().claim_value, "web");
+        assert_eq!(audiences(&cs).unwrap(), vec!["web"]);
+
+        let mut cs = ClaimSet::new();
+        set_audiences(&mut cs, &["web", "api"], "web").unwrap();
+        assert_eq!(cs.get("aud").unwrap().claim_value, serde_json::json!(["web", "api"]));
+        assert_eq!(cs.get("azp").unwrap().claim_value, "web");
+
+        assert!(set_audiences(&mut ClaimSet::new(), &["web", "api"], "other").is_err());
+        assert!(set_audiences(&mut ClaimSet::new(), &[], "web").is_err());
+        assert!(set_audiences(&mut cs, &["web"], "web").is_err());
+    }
+
+    #[test]
+    fn test_check_azp() {
+        let cs = |s: &str| ClaimSet::decode_str(s).unwrap();
+        assert!(check_azp(&cs("{\"aud\": \"web\"}"), "web").is_ok());
+        assert!(check_azp(&cs("{\"aud\": \"web\"}"), "api").is_err());
+        assert!(check_azp(&cs("{\"aud\": [\"web\"], \"azp\": \"web\"}"), "web").is_ok());
+        assert!(check_azp(&cs("{\"aud\": [\"web\", \"api\"]}"), "web").is_err());
+        assert!(check_azp(&cs("{\"aud\": [\"web\", \"api\"], \"azp\": \"api\"}"), "web").is_err());
+        assert!(check_azp(&cs("{\"aud\": [\"web\", \"api\"], \"azp\": \"web\"}"), "web").is_ok());
+        assert!(check_azp(&cs("{\"azp\": \"web\"}"), "web").is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use crate::err;
+use crate::expiring::ExpiringMap;
+use crate::keys::{EcPublicKey, Ed25519PublicKey, RsaPublicKey, VerificationKey};
+use crate::traits::{Clock, SystemClock};
+use crate::verify::{VerifiedJWT, VerifyOptions};
+use crate::JWT;
+
+#[derive(Debug, PartialEq)]
+// The key a token was verified under. HMAC keys are held as their SHA-256 digest, so that the
+// cache holds no secrets.
+enum CachedKey {
+    Hmac([u8; 32]),
+    Rsa(RsaPublicKey),
+    Ec(EcPublicKey),
+    Ed25519(Ed25519PublicKey),
+}
+
+impl From<VerificationKey<'_>> for CachedKey {
+    fn from(key: VerificationKey) -> CachedKey {
+        match key {
+            VerificationKey::Hmac(key) => CachedKey::Hmac(Sha256::digest(key).into()),
+            VerificationKey::Rsa(key) => CachedKey::Rsa(key.clone()),
+            VerificationKey::Ec(key) => CachedKey::Ec(key.clone()),
+            VerificationKey::Ed25519(key) => CachedKey::Ed25519(key.clone()),
+        }
+    }
+}
+
+impl CachedKey {
+    // Returns whether this is `key`.
+    fn is(&self, key: VerificationKey) -> bool {
+        match (self, key) {
+            (CachedKey::Hmac(digest), VerificationKey::Hmac(key)) => {
+                *digest == <[u8; 32]>::from(Sha256::digest(key))
+            }
+            (CachedKey::Rsa(cached), VerificationKey::Rsa(key)) => cached == key,
+            (CachedKey::Ec(cached), VerificationKey::Ec(key)) => cached == key,
+            (CachedKey::Ed25519(cached), VerificationKey::Ed25519(key)) => cached == key,
+            _ => false,
+        }
+    }
+}
+
+/// A cache of successful signature verifications, for services which see the same bearer token
+/// many times over and would otherwise verify it each time. Each token is remembered until its
+/// `exp` claim, or for at most the cache's maximum TTL. Failed verifications are not cached.
+///
+/// A cached verification is only returned for the key it was made under; under any other key,
+/// the token is verified afresh. Cached tokens are still checked against the `VerifyOptions`
+/// passed each time. Only the signature is cached; check the claims of every token returned, with
+/// a `claims::Validator`.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use jwt::{JsonSerializable, JWT};
+/// use jwt::cache::VerificationCache;
+/// use jwt::header::Alg;
+/// use jwt::verify::VerifyOptions;
+///
+/// let key = [7; 32];
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\", \"exp\": 1e10}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// let token = jwt.sign(&key).unwrap();
+///
+/// let cache = VerificationCache::new(Duration::from_secs(300));
+/// let options = VerifyOptions::new(&[Alg::HS256]);
+/// assert_eq!(cache.verify(&token, &key, &options).unwrap().claim_set, jwt.claim_set);
+/// assert_eq!(cache.len(), 1);
+/// assert!(cache.verify("e30.e30.", &key, &options).is_err());
+/// assert_eq!(cache.len(), 1);
+/// ```
+pub struct VerificationCache {
+    // Each verified token, keyed by the SHA-256 digest of its encoding, with the key it was
+    // verified under.
+    verified: ExpiringMap<[u8; 32], (CachedKey, VerifiedJWT)>,
+    max_ttl: Duration,
+}
+
+impl VerificationCache {
+    /// Constructs an empty cache which remembers tokens for at most `max_ttl`, telling expired
+    /// entries by the system clock.
+    pub fn new(max_ttl: Duration) -> VerificationCache {
+        VerificationCache::with_clock(max_ttl, SystemClock)
+    }
+
+    /// Constructs an empty cache which remembers tokens for at most `max_ttl`, telling expired
+    /// entries by `clock`.
+    pub fn with_clock<C: Clock + 'static>(max_ttl: Duration, clock: C) -> VerificationCache {
+        VerificationCache { verified: ExpiringMap::with_clock(clock), max_ttl }
+    }
+
+    /// Returns the number of entries held, including expired ones not yet purged.
+    pub fn len(&self) -> usize {
+        self.verified.len()
+    }
+
+    /// Returns whether the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decodes the compact, base64url-encoded `token` and verifies its signature under `key`
+    /// against `options`, as `verify::UnverifiedJWT::verify` does, unless it was verified under
+    /// `key` before and has not expired since.
+    pub fn verify<'a, K>(&self, token: &str, key: K, options: &VerifyOptions)
+        -> err::Result<VerifiedJWT>
+        where K: Into<VerificationKey<'a>> {
+        let key = key.into();
+        let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        let cached = self.verified.get(&digest, |(cached_key, jwt)| {
+            if cached_key.is(key) { Some(jwt.clone()) } else { None }
+        });
+        if let Some(jwt) = cached.flatten() {
+            // The token may have been verified under other options.
+            if !options.allows(jwt.header.alg) {
+                return Err(err::JWTError::SignatureError)
+            }
+            options.check_key(jwt.header.alg, &key)?;
+            return Ok(jwt)
+        }
+        let jwt = JWT::decode_unverified(token)?.verify(key, options)?;
+        let now = self.verified.now();
+        let exp = jwt.claim_set.claims.get("exp").and_then(|claim| claim.claim_value.as_f64());
+        let expiry = exp.unwrap_or(f64::INFINITY).min(now + self.max_ttl.as_secs_f64());
+        if expiry > now {
+            self.verified.insert(digest, expiry, (CachedKey::from(key), jwt.clone()));
+        }
+        Ok(jwt)
+    }
+}
+
+// The clock is a trait object, which need not implement Debug, and the entries hold tokens.
+impl fmt::Debug for VerificationCache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VerificationCache")
+            .field("len", &self.len())
+            .field("max_ttl", &self.max_ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::expiring::TestClock;
+    use crate::header::Alg;
+
+    fn options() -> VerifyOptions {
+        VerifyOptions::new(&[Alg::HS256, Alg::EdDSA])
+    }
+
+    fn token(claims: &str) -> String {
+        let mut jwt = JWT::from_plain_str(claims).unwrap();
+        jwt.header.alg = Alg::HS256;
+        jwt.sign(&[7; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_verify_until_exp() {
+        let now = Arc::new(Mutex::new(100));
+        let cache = VerificationCache::with_clock(Duration::from_secs(60), TestClock(now.clone()));
+        let token = token("{\"exp\": 130}");
+        assert!(cache.verify(&token, &[8; 32], &options()).is_err());
+        assert!(cache.is_empty());
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+
+        // The cached verification is only returned for the key it was made under.
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+        assert!(cache.verify(&token, &[8; 32], &options()).is_err());
+        *now.lock().unwrap() = 130;
+        assert!(cache.verify(&token, &[8; 32], &options()).is_err());
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_max_ttl() {
+        let now = Arc::new(Mutex::new(100));
+        let cache = VerificationCache::with_clock(Duration::from_secs(60), TestClock(now.clone()));
+        let token = token("{\"sub\": \"u1\"}");
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+        *now.lock().unwrap() = 159;
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+        *now.lock().unwrap() = 160;
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_other_key() {
+        let key = crate::keys::Ed25519PrivateKey::from_seed(&[7; 32]).unwrap();
+        let other = crate::keys::Ed25519PrivateKey::from_seed(&[8; 32]).unwrap();
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        jwt.header.alg = Alg::EdDSA;
+        let token = jwt.sign_eddsa(&key).unwrap();
+        let cache = VerificationCache::new(Duration::from_secs(60));
+        assert!(cache.verify(&token, &key.public_key(), &options()).is_ok());
+        assert!(matches!(
+            cache.verify(&token, &other.public_key(), &options()),
+            Err(err::JWTError::SignatureError)
+        ));
+        assert!(cache.verify(&token, &[7; 32][..], &options()).is_err());
+        assert!(cache.verify(&token, &key.public_key(), &options()).is_ok());
+    }
+
+    #[test]
+    fn test_options() {
+        let cache = VerificationCache::new(Duration::from_secs(60));
+        let token = token("{\"sub\": \"u1\"}");
+        assert!(cache.verify(&token, &[7; 32], &options()).is_ok());
+        // A cached verification is checked against the options of each call.
+        let hs512 = VerifyOptions::new(&[Alg::HS512]);
+        assert!(matches!(
+            cache.verify(&token, &[7; 32], &hs512), Err(err::JWTError::SignatureError)
+        ));
+        assert!(cache.verify(&token, &[7; 32], &VerifyOptions::new(&[Alg::HS256])).is_ok());
+
+        // So are the key minimums.
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u2\"}").unwrap();
+        jwt.header.alg = Alg::HS256;
+        let weak = jwt.sign(&[7; 16]).unwrap();
+        let short = VerifyOptions::new(&[Alg::HS256]).allow_short_hmac_keys();
+        assert!(cache.verify(&weak, &[7; 16], &short).is_ok());
+        assert!(matches!(
+            cache.verify(&weak, &[7; 16], &options()), Err(err::JWTError::KeyTooWeak(_))
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::claims::{Claim, ClaimSet};
+use crate::err;
+
+/// The name of the claim holding the `jti` of the token a token was re-issued from.
+pub const PREV_JTI_CLAIM: &str = "prev_jti";
+
+// The claims which are set afresh whenever a token is re-issued.
+const REISSUED_CLAIMS: &[&str; 5] = &["iat", "nbf", "exp", "jti", PREV_JTI_CLAIM];
+
+/// Re-issues the token with claims `previous`: the result carries the same claims, except for a
+/// new `jti`, an `iat` of `issued_at`, an `exp` of `issued_at + ttl`, no `nbf`, and a `prev_jti`
+/// claim holding the `jti` of `previous`. Following `prev_jti` back from a token therefore walks
+/// the history of a long-lived session. Returns a `err::JWTError::SchemaError` if `previous` has
+/// no string `jti` to link to, or if the `exp` would overflow.
+///
+/// # Examples
+/// ```
+/// use jwt::chain;
+/// use jwt::claims::ClaimSet;
+/// use jwt::JsonSerializable;
+///
+/// let first = ClaimSet::decode_str("{\"sub\": \"u1\", \"jti\": \"a\", \"exp\": 100}").unwrap();
+/// let second = chain::reissue(&first, 90, 100).unwrap();
+/// assert_eq!(chain::prev_jti(&second), Some("a"));
+/// assert_eq!(second.get("exp").unwrap().claim_value, 190);
+/// ```
+pub fn reissue(previous: &ClaimSet, issued_at: u64, ttl: u64) -> err::Result<ClaimSet> {
+    let prev_jti = previous.get("jti")?.claim_value.as_str().ok_or(err::JWTError::SchemaError)?;
+    let exp = issued_at.checked_add(ttl).ok_or(err::JWTError::SchemaError)?;
+
+    let mut claim_set = previous.clone();
+    claim_set.claims.retain(|claim_name, _| !REISSUED_CLAIMS.contains(&claim_name.as_str()));
+    for (claim_name, claim_value) in [
+        ("iat", Value::from(issued_at)),
+        ("exp", Value::from(exp)),
+        ("jti", Value::from(Uuid::new_v4().to_string())),
+        (PREV_JTI_CLAIM, Value::from(prev_jti)),
+    ] {
+        claim_set.insert(Claim::parse(String::from(claim_name), claim_value)?)?;
+    }
+    Ok(claim_set)
+}
+
+/// Returns the `jti` of the token `claim_set` was re-issued from, if it was re-issued.
+pub fn prev_jti(claim_set: &ClaimSet) -> Option<&str> {
+    claim_set.get(PREV_JTI_CLAIM).ok().and_then(|claim| claim.claim_value.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::JsonSerializable;
+
+    #[test]
+    fn test_reissue() {
+        let first = ClaimSet::decode_str(
+            "{\"sub\": \"u1\", \"jti\": \"a\", \"nbf\": 1, \"iat\": 1, \"exp\": 10}"
+        ).unwrap();
+        let second = reissue(&first, 5, 10).unwrap();
+        let third = reissue(&second, 12, 10).unwrap();
+
+        assert_eq!(prev_jti(&first), None);
+        assert_eq!(prev_jti(&second), Some("a"));
+        assert_eq!(prev_jti(&third), second.get("jti").unwrap().claim_value.as_str());
+        assert_eq!(third.get("sub").unwrap().claim_value, "u1");
+        assert_eq!(third.get("iat").unwrap().claim_value, 12);
+        assert_eq!(third.get("exp").unwrap().claim_value, 22);
+        assert!(third.get("nbf").is_err());
+    }
+
+    #[test]
+    fn test_reissue_without_jti() {
+        let claim_set = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(matches!(reissue(&claim_set, 0, 1), Err(err::JWTError::SchemaError)));
+    }
+
+    #[test]
+    fn test_reissue_overflow() {
+        let claim_set = ClaimSet::decode_str("{\"jti\": \"a\"}").unwrap();
+        assert!(matches!(reissue(&claim_set, u64::MAX, 1), Err(err::JWTError::SchemaError)));
+    }
+}
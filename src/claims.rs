@@ -1,13 +1,25 @@
 use std::fmt;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use url::Url;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::ser::SerializeMap;
 use serde_json::{Map, Value};
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
+use crate::config::{DuplicatePolicy, ParseConfig};
 use crate::err;
 use crate::traits::JsonSerializable;
 
-#[derive(Debug)]
+mod validator;
+
+pub use validator::{
+    CustomRule, TrustedIssuer, ValidationReport, Validator, STRICT_LEEWAY, STRICT_MAX_TOKEN_SIZE,
+};
+pub use crate::typed::Audience;
+
+#[derive(Debug, Clone)]
 /// The JWT specification states that claim names must be legal `StringOrURI` values. For names
 /// lacking a colon `:`, a `StringOrURI` is a (valid UTF-8) string. For names containing a colon,
 /// a `StringOrURI` is a `URI`, and is expected to follow the `URI` schema.
@@ -49,12 +61,26 @@ impl StringOrURI {
     /// the string could not be parsed; this should only happen if the string contains a colon `:`,
     /// indicating that it is a URI, but it fails to parse as one.
     pub fn parse(inp: String) -> err::Result<StringOrURI> {
-        if inp.contains(":") {
-            Url::parse(&inp)
+        StringOrURI::parse_with(inp, UriMode::Strict)
+    }
+
+    /// Parses a string into a new `StringOrURI` value, using the given `UriMode` for values
+    /// containing a colon. See `StringOrURI::parse`.
+    pub fn parse_with(inp: String, mode: UriMode) -> err::Result<StringOrURI> {
+        if !inp.contains(':') {
+            return Ok(StringOrURI::String(inp))
+        }
+        match mode {
+            UriMode::Strict => Url::parse(&inp)
                 .map(|inner| { StringOrURI::URI(String::from(inner.as_str())) })
-                .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
-        } else {
-            Ok(StringOrURI::String(inp))
+                .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) }),
+            UriMode::Iri => {
+                if is_iri(&inp) {
+                    Ok(StringOrURI::URI(inp))
+                } else {
+                    Err(err::JWTError::ParseError(format!("{} is not a valid IRI", inp)))
+                }
+            },
         }
     }
 
@@ -65,9 +91,153 @@ impl StringOrURI {
             StringOrURI::URI(value) => value,
         }
     }
+
+    /// Returns the value in normalized form: Unicode NFC, plus, for URIs, the syntax-based
+    /// normalization of RFC 3986 section 6.2.2 (see `Comparison::Normalized`).
+    pub fn normalized(&self) -> String {
+        match self {
+            StringOrURI::String(value) => value.nfc().collect(),
+            StringOrURI::URI(value) => normalize_uri(value),
+        }
+    }
+
+    /// Compares two `StringOrURI` values using the given `Comparison` mode.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{Comparison, StringOrURI};
+    ///
+    /// let a = StringOrURI::URI(String::from("HTTPS://Example.COM/a%7eb"));
+    /// let b = StringOrURI::URI(String::from("https://example.com/a~b"));
+    /// assert!(!a.compare(&b, Comparison::Exact));
+    /// assert!(a.compare(&b, Comparison::Normalized));
+    /// ```
+    pub fn compare(&self, other: &StringOrURI, mode: Comparison) -> bool {
+        match mode {
+            Comparison::Exact => self.as_str() == other.as_str(),
+            Comparison::Normalized => self.normalized() == other.normalized(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The ways in which a `StringOrURI` value containing a colon may be parsed.
+pub enum UriMode {
+    /// Parses the value as a URL using the WHATWG URL standard (via the `url` crate), storing the
+    /// serialized URL. This rejects some values which are legal RFC 3986 URIs (e.g. an `https`
+    /// URI with an out of range port), and rewrites others (e.g. internationalized hosts are
+    /// punycode-encoded). This is the default.
+    Strict,
+    /// Checks the value against the generic syntax of RFC 3987 (IRIs, which include all URIs),
+    /// and stores it verbatim. Use this for issuers and claim names produced by stacks whose
+    /// URIs the `url` crate does not accept, such as URNs and IRIs with non-ASCII characters.
+    Iri,
+}
+
+// Checks a value against the generic RFC 3987 IRI syntax: a scheme, a colon, and then a sequence
+// of characters which are either allowed in IRIs or well-formed percent-encodings. The
+// component structure of the remainder is deliberately not checked, as it depends on the scheme.
+fn is_iri(inp: &str) -> bool {
+    let (scheme, rest) = match inp.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let mut scheme_chars = scheme.chars();
+    let scheme_ok = scheme_chars.next().is_some_and(|c| c.is_ascii_alphabetic()) &&
+        scheme_chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+    let chars = rest.chars().collect::<Vec<char>>();
+    let rest_ok = chars.iter().enumerate().all(|(i, c)| {
+        match c {
+            '%' => chars.get(i + 1..i + 3)
+                .is_some_and(|hex| hex.iter().all(|h| h.is_ascii_hexdigit())),
+            c if c.is_ascii() => {
+                c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=".contains(*c)
+            },
+            c => !c.is_control() && !c.is_whitespace(),
+        }
+    });
+    scheme_ok && rest_ok
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The ways in which two `StringOrURI` values may be compared.
+///
+/// RFC 7519 specifies that `StringOrURI` values are compared as case-sensitive strings, with no
+/// transformations or canonicalizations applied; this is `Comparison::Exact`. In practice,
+/// different stacks serialize the same issuer or audience differently (`HTTPS://IdP.example`
+/// versus `https://idp.example`, `%7e` versus `~`, composed versus decomposed accents), so
+/// `Comparison::Normalized` is offered for deployments which need these to match.
+pub enum Comparison {
+    /// Code point for code point comparison, as RFC 7519 prescribes.
+    Exact,
+    /// Comparison after Unicode NFC normalization. URIs are additionally compared with a
+    /// case-insensitive scheme and host, with percent-encoded unreserved characters decoded, and
+    /// with the hex digits of remaining percent-encodings uppercased.
+    Normalized,
+}
+
+// Applies RFC 3986 percent-encoding normalization: percent-encoded unreserved characters are
+// decoded, and the hex digits of all other percent-encodings are uppercased.
+fn normalize_percent_encoding(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::<u8>::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes.get(i + 1..i + 3)
+            .filter(|hex| bytes[i] == b'%' && hex.iter().all(|b| b.is_ascii_hexdigit()));
+        match escape {
+            Some(hex) => {
+                // Both characters are ASCII hex digits, so this cannot fail.
+                let hex = std::str::from_utf8(hex).unwrap();
+                let value = u8::from_str_radix(hex, 16).unwrap();
+                if value.is_ascii_alphanumeric() || b"-._~".contains(&value) {
+                    out.push(value);
+                } else {
+                    out.push(b'%');
+                    out.extend(hex.to_ascii_uppercase().bytes());
+                }
+                i += 3;
+            },
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            },
+        }
+    }
+    // Only ASCII sequences were replaced, with ASCII, so the output is still valid UTF-8.
+    String::from_utf8(out).unwrap()
 }
 
-#[derive(Debug)]
+fn normalize_uri(uri: &str) -> String {
+    let uri: String = uri.nfc().collect();
+    let (scheme, rest) = match uri.find(':') {
+        Some(i) => (&uri[..i], &uri[i + 1..]),
+        None => return normalize_percent_encoding(&uri),
+    };
+
+    let mut out = scheme.to_ascii_lowercase() + ":";
+    match rest.strip_prefix("//") {
+        Some(hierarchical) => {
+            let end = hierarchical.find(['/', '?', '#'])
+                .unwrap_or(hierarchical.len());
+            let (authority, path) = hierarchical.split_at(end);
+            // The userinfo component is case-sensitive; only the host (and port) are not.
+            let (userinfo, host) = match authority.rfind('@') {
+                Some(i) => authority.split_at(i + 1),
+                None => ("", authority),
+            };
+            out += "//";
+            out += &normalize_percent_encoding(userinfo);
+            out += &normalize_percent_encoding(&host.to_lowercase());
+            out += &normalize_percent_encoding(path);
+        },
+        None => out += &normalize_percent_encoding(rest),
+    }
+    out
+}
+
+#[derive(Debug, Clone)]
 /// Claims fall into one of three types.
 ///
 /// **Registered claims** are those which have been formally registered with the IETF, and are
@@ -88,7 +258,17 @@ pub enum ClaimType {
     Private,
 }
 
-#[derive(Debug)]
+impl fmt::Display for ClaimType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClaimType::Registered => write!(f, "registered"),
+            ClaimType::Public => write!(f, "public"),
+            ClaimType::Private => write!(f, "private"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 /// A **claim** is a statement of fact, consisting of a *claim name* (a `StringOrURI`) and a
 /// *claim value* (an arbitrary JSON fragment). A set of claims (a `ClaimSet`) composes the
 /// payload of a JWT.
@@ -115,13 +295,19 @@ pub struct Claim {
     pub claim_value: Value,
 }
 
+impl Default for Claim {
+    fn default() -> Claim {
+        Claim::new()
+    }
+}
+
 impl fmt::Display for Claim {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.encode_str())
     }
 }
 
-const REGISTERED_CLAIMS: &[&str; 7] = &["iss", "sub", "aud", "exp", "nbf", "iat", "jti"];
+pub(crate) const REGISTERED_CLAIMS: &[&str; 7] = &["iss", "sub", "aud", "exp", "nbf", "iat", "jti"];
 
 impl Claim {
     /// Constructs a new (empty) private claim.
@@ -148,26 +334,62 @@ impl Claim {
         }
     }
 
-    /// Constructs a new claim from an input string.
+    /// Constructs a new claim from an input string. The claim name is normalized to Unicode NFC,
+    /// so that names which differ only in their Unicode representation are treated as the same.
     pub fn parse(claim_name: String, claim_value: Value) -> err::Result<Claim> {
+        Claim::parse_with(claim_name, claim_value, UriMode::Strict)
+    }
+
+    /// Constructs a new claim from an input string, parsing the claim name with the given
+    /// `UriMode`. See `Claim::parse`.
+    pub fn parse_with(claim_name: String, claim_value: Value, mode: UriMode)
+        -> err::Result<Claim> {
         let mut claim = Claim::new();
 
-        // Early return to unpack the non-error header.
-        let claim_name: StringOrURI = match StringOrURI::parse(claim_name) {
-            Ok(claim_name) => claim_name,
-            Err(e) => return Err(e)
-        };
+        let claim_name: StringOrURI = StringOrURI::parse_with(claim_name.nfc().collect(), mode)?;
 
         claim.claim_name = claim_name;
         claim.claim_type = Claim::get_claim_type(&claim.claim_name);
+        if let ClaimType::Registered = claim.claim_type {
+            Claim::check_registered_value(claim.claim_name.as_str(), &claim_value, mode)?;
+        }
         claim.claim_value = claim_value;
         Ok(claim)
     }
 
+    // Checks that the value of a registered claim has the type RFC 7519 section 4.1 gives it.
+    fn check_registered_value(claim_name: &str, claim_value: &Value, mode: UriMode)
+        -> err::Result<()> {
+        let string_or_uri = |value: &Value| -> bool {
+            value.as_str()
+                .map(|value| StringOrURI::parse_with(String::from(value), mode).is_ok())
+                .unwrap_or(false)
+        };
+        let (valid, expected) = match claim_name {
+            "exp" | "nbf" | "iat" => (claim_value.is_number(), "a NumericDate"),
+            "iss" | "sub" => (string_or_uri(claim_value), "a StringOrURI"),
+            "aud" => (
+                string_or_uri(claim_value) || claim_value.as_array()
+                    .map(|values| values.iter().all(string_or_uri))
+                    .unwrap_or(false),
+                "a StringOrURI or an array of StringOrURI values"
+            ),
+            "jti" => (claim_value.is_string(), "a string"),
+            _ => (true, ""),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(err::JWTError::ClaimTypeError(format!(
+                "\"{}\" must be {}, not {}", claim_name, expected, claim_value
+            )))
+        }
+    }
+
     /// Returns the `Claim` in string format.
     pub fn encode_str(&self) -> String {
         // TODO: why can this fail? Investigate why unwrap is necessary here.
-        String::from("{\"") + self.claim_name.as_str() + "\":" +
+        String::from("{") + &serde_json::to_string(self.claim_name.as_str()).unwrap() + ":" +
         &serde_json::to_string(&self.claim_value).unwrap() + "}"
     }
 
@@ -186,7 +408,7 @@ pub fn generate_collision_name(fragment: &str) -> String {
     uuid.to_string() + "-" + fragment
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 /// A **ClaimSet** is a set of (uniquely named) claims. It is the payload portion of a complete
 /// `JWT`.
 ///
@@ -196,6 +418,10 @@ pub fn generate_collision_name(fragment: &str) -> String {
 /// Note that, according to RFC 7519, it is the choice of the implementation whether or not to
 /// enforce that JWT claims have unique names. We chose to enforce the constraint that they do.
 ///
+/// Two `ClaimSet`s are equal if they have the same claim names with the same claim values,
+/// regardless of order (including the order of members of object claim values). `Hash` is
+/// consistent with this, hashing the canonical form returned by `ClaimSet::canonical`.
+///
 /// # Examples
 /// ```
 /// use jwt::claims::ClaimSet;
@@ -213,6 +439,62 @@ pub struct ClaimSet {
     pub claims: HashMap<String, Claim>,
 }
 
+impl Default for ClaimSet {
+    fn default() -> ClaimSet {
+        ClaimSet::new()
+    }
+}
+
+impl PartialEq for ClaimSet {
+    fn eq(&self, other: &ClaimSet) -> bool {
+        self.claims.len() == other.claims.len() &&
+        self.claims.iter().all(|(claim_name, claim)| {
+            other.claims.get(claim_name)
+                .is_some_and(|other| other.claim_value == claim.claim_value)
+        })
+    }
+}
+
+impl Eq for ClaimSet {}
+
+impl Hash for ClaimSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.canonical().hash(state);
+    }
+}
+
+// Writes a JSON value compactly, with the members of every object sorted by name.
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(members) => {
+            let mut names = members.keys().collect::<Vec<&String>>();
+            names.sort();
+            out.push('{');
+            for (i, name) in names.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                // Serializing a string cannot fail.
+                *out += &serde_json::to_string(name).unwrap();
+                out.push(':');
+                write_canonical(&members[name], out);
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(value, out);
+            }
+            out.push(']');
+        }
+        _ => *out += &value.to_string(),
+    }
+}
+
 impl fmt::Display for ClaimSet {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.encode_str())
@@ -230,67 +512,128 @@ impl ClaimSet {
     pub fn insert(&mut self, claim: Claim) -> err::Result<()> {
         let claim_name_str = claim.claim_name.as_str();
         if self.claims.contains_key(claim_name_str) {
-            return err::Result::<()>::Err(err::JWTError::SchemaError)
+            err::Result::<()>::Err(err::JWTError::SchemaError)
         } else {
             self.claims.insert(String::from(claim_name_str), claim);
             Ok(())
         }
     }
 
+    /// Returns the canonical form of the `ClaimSet`: compact JSON with the claims, and the members
+    /// of any object claim values, sorted by name. Claim sets are equal exactly when their
+    /// canonical forms are.
+    pub fn canonical(&self) -> String {
+        let mut out = String::new();
+        // Serializing a ClaimSet cannot fail.
+        write_canonical(&serde_json::to_value(self).unwrap(), &mut out);
+        out
+    }
+
     /// Returns the `Claim` with the given name from the `ClaimSet`, or a
     /// `err::JWTError::SchemaError` if none is found.
     pub fn get(&self, claim_name: &str) -> err::Result<&Claim> {
         self.claims.get(claim_name).ok_or(err::JWTError::SchemaError)
     }
-}
 
-impl JsonSerializable for ClaimSet {
-    /// Constructs a new `ClaimSet` from a valid JSON string of key-value pairs. Returns a
-    /// `err::JWTError::ParseError` if the input string is not valid JSON.
-    fn decode_str(claim_set: &str) -> err::Result<ClaimSet> {
-        let parse: err::Result<Map<String, serde_json::Value>> =
-            serde_json::from_str(&claim_set)
-            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) });
-
-        // Early return to unpack the parse error.
-        let parse = match parse {
-            Ok(r) => r,
-            Err(e) => return Err(e),
-        };
+    // Decodes a plaintext claim set as decode_str_with does, also returning the names of any
+    // duplicate claims which the DuplicatePolicy resolved.
+    pub(crate) fn decode_str_reporting(claim_set: &str, config: &ParseConfig)
+        -> err::Result<(ClaimSet, Vec<String>)> {
+        config.check_size(claim_set)?;
 
         let mut result = ClaimSet::new();
-        for claim_name in parse.keys() {
-            // Using unwrap here is fine because this is a safe operation.
-            let claim_value = parse.get(claim_name).unwrap();
-
-            // Early return to work around a potential URI parse error.
-            // Q: Why is clone necessary here?
-            // A: claim_name and claim_value are pointer references to data owned by the parse
-            //    value reference. We cannot dereference them because doing so would be a Move
-            //    that invalidates the parse value reference, which is not legal to do here
-            //    because we are inside a parse.keys() iterator. Since we have another live
-            //    reference that a deference would implicitly destroy, the dereference is
-            //    forbidden.
-            //
-            //    There may be a more clever way to handle this situation, but a clone() is an
-            //    easy workaround for right now.
-            let claim = Claim::parse(claim_name.clone(), claim_value.clone());
-            let claim = match claim {
-                Ok(claim) => claim,
-                Err(e) => return Err(e)
-            };
-
-            match result.insert(claim) {
-                Err(e) => return Err(e),
-                _ => ()
+        let mut duplicates = Vec::<String>::new();
+        for (claim_name, claim_value) in parse_members(claim_set)? {
+            let claim = Claim::parse_with(claim_name, claim_value, config.uri_mode)?;
+            let claim_name = String::from(claim.claim_name.as_str());
+            if result.claims.contains_key(&claim_name) {
+                match config.duplicate_claims {
+                    DuplicatePolicy::Reject => return Err(err::JWTError::SchemaError),
+                    DuplicatePolicy::First => (),
+                    DuplicatePolicy::Last => { result.claims.insert(claim_name.clone(), claim); },
+                }
+                duplicates.push(claim_name);
+            } else {
+                result.claims.insert(claim_name, claim);
             }
         };
+        Ok((result, duplicates))
+    }
+}
+
+// A ClaimSet serializes as the JSON object of its claims, which lets it be used wherever serde
+// expects a claims type (e.g. as the payload type of other JWT libraries).
+impl Serialize for ClaimSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.claims.len()))?;
+        for (claim_name, claim) in &self.claims {
+            map.serialize_entry(claim_name, &claim.claim_value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ClaimSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ClaimSet, D::Error> {
+        let parse = Map::<String, Value>::deserialize(deserializer)?;
+        let mut result = ClaimSet::new();
+        for (claim_name, claim_value) in parse {
+            Claim::parse(claim_name, claim_value)
+                .and_then(|claim| result.insert(claim))
+                .map_err(serde::de::Error::custom)?;
+        }
         Ok(result)
     }
+}
+
+// Parses a JSON object into its members, in document order. Unlike serde_json::Map, which
+// silently keeps only the last of several members with the same name, duplicates are retained so
+// that a DuplicatePolicy can be applied to them.
+fn parse_members(input: &str) -> err::Result<Vec<(String, Value)>> {
+    struct Members(Vec<(String, Value)>);
+
+    impl<'de> Deserialize<'de> for Members {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Members, D::Error> {
+            struct MembersVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for MembersVisitor {
+                type Value = Members;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    write!(f, "a JSON object")
+                }
+
+                fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A)
+                    -> Result<Members, A::Error> {
+                    let mut members = Vec::<(String, Value)>::new();
+                    while let Some(member) = map.next_entry::<String, Value>()? {
+                        members.push(member);
+                    }
+                    Ok(Members(members))
+                }
+            }
+
+            deserializer.deserialize_map(MembersVisitor)
+        }
+    }
+
+    serde_json::from_str::<Members>(input)
+        .map(|members| members.0)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+impl JsonSerializable for ClaimSet {
+    /// Constructs a new `ClaimSet` from a valid JSON string of key-value pairs. Returns a
+    /// `err::JWTError::ParseError` if the input string is not valid JSON, or a
+    /// `err::JWTError::SchemaError` if it contains a duplicate claim name and the configured
+    /// `DuplicatePolicy` is `Reject`.
+    fn decode_str_with(claim_set: &str, config: &ParseConfig) -> err::Result<ClaimSet> {
+        ClaimSet::decode_str_reporting(claim_set, config).map(|(result, _)| result)
+    }
 
     /// Returns the `ClaimSet` in `String` format.
     fn encode_str(&self) -> String {
-        if self.claims.len() == 0 {
+        if self.claims.is_empty() {
             return String::from("{}")
         }
 
@@ -311,14 +654,9 @@ impl JsonSerializable for ClaimSet {
         base64::encode(self.encode_str())
     }
 
-    fn decode_b64(input: &str) -> err::Result<ClaimSet> {
-        base64::decode(input)
-            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
-            .and_then(|inner| {
-                String::from_utf8(inner)
-                .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
-            })
-            .and_then( |inner| { ClaimSet::decode_str(&inner) })
+    fn decode_b64_with(input: &str, config: &ParseConfig) -> err::Result<ClaimSet> {
+        config.check_size(input)?;
+        ClaimSet::decode_str_with(&config.decode_base64(input)?, config)
     }
 }
 
@@ -326,6 +664,48 @@ impl JsonSerializable for ClaimSet {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_claim_set_eq_hash() {
+        use std::collections::HashSet;
+
+        let a = ClaimSet::decode_str("{\"sub\": \"u1\", \"x\": {\"b\": 1, \"a\": [2, {}]}}")
+            .unwrap();
+        let b = ClaimSet::decode_str("{\"x\": {\"a\": [2, {}], \"b\": 1}, \"sub\": \"u1\"}")
+            .unwrap();
+        let c = ClaimSet::decode_str("{\"x\": {\"a\": [{}, 2], \"b\": 1}, \"sub\": \"u1\"}")
+            .unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, ClaimSet::new());
+        assert_eq!(a.canonical(), "{\"sub\":\"u1\",\"x\":{\"a\":[2,{}],\"b\":1}}");
+        assert_eq!(vec![a, b, c].into_iter().collect::<HashSet<ClaimSet>>().len(), 2);
+    }
+
+    #[test]
+    fn test_registered_claim_types() {
+        for valid in &[
+            "{\"exp\": 1, \"nbf\": 1.5, \"iat\": 0}",
+            "{\"iss\": \"https://idp.example.com\", \"sub\": \"u1\", \"jti\": \"a:b c\"}",
+            "{\"aud\": \"api\"}",
+            "{\"aud\": [\"api\", \"urn:example:api\"]}",
+        ] {
+            assert!(ClaimSet::decode_str(valid).is_ok(), "{}", valid);
+        }
+        for invalid in &[
+            "{\"exp\": \"tomorrow\"}",
+            "{\"iat\": null}",
+            "{\"iss\": 1}",
+            "{\"sub\": \"not a scheme:u1\"}",
+            "{\"aud\": [\"api\", 1]}",
+            "{\"jti\": 1}",
+        ] {
+            assert!(
+                matches!(ClaimSet::decode_str(invalid), Err(err::JWTError::ClaimTypeError(_))),
+                "{}", invalid
+            );
+        }
+    }
+
     #[test]
     fn test_stringoruri_string() {
         let s = StringOrURI::parse(String::from("foo")).unwrap();
@@ -340,13 +720,64 @@ mod tests {
         assert_eq!(s.as_str(), "foo:bar");
     }
 
+    #[test]
+    fn test_stringoruri_iri() {
+        // Rewritten by the url crate, preserved in IRI mode.
+        let iri = String::from("https://\u{4f8b}\u{3048}.jp/\u{30d1}\u{30b9}");
+        let s = StringOrURI::parse_with(iri.clone(), UriMode::Strict).unwrap();
+        assert_ne!(s.as_str(), iri);
+        let s = StringOrURI::parse_with(iri.clone(), UriMode::Iri).unwrap();
+        assert_eq!(s.as_str(), iri);
+
+        // Rejected by the url crate, but a syntactically valid URI.
+        let uri = String::from("https://example.com:99999/x");
+        assert!(StringOrURI::parse_with(uri.clone(), UriMode::Strict).is_err());
+        assert!(matches!(StringOrURI::parse_with(uri, UriMode::Iri), Ok(StringOrURI::URI(_))));
+    }
+
+    #[test]
+    fn test_stringoruri_iri_invalid() {
+        for inp in &["1urn:a", "urn:a b", "urn:a%zz", "urn:a<b>", ":a"] {
+            assert!(StringOrURI::parse_with(String::from(*inp), UriMode::Iri).is_err());
+        }
+    }
+
+    #[test]
+    fn test_stringoruri_compare() {
+        let a = StringOrURI::URI(String::from("HTTPS://User@Example.COM:443/a%7eb%2fc"));
+        let b = StringOrURI::URI(String::from("https://User@example.com:443/a~b%2Fc"));
+        let c = StringOrURI::URI(String::from("https://user@example.com:443/a~b%2Fc"));
+        assert!(!a.compare(&b, Comparison::Exact));
+        assert!(a.compare(&b, Comparison::Normalized));
+        assert!(!a.compare(&c, Comparison::Normalized));
+
+        let urn_a = StringOrURI::URI(String::from("URN:example:%41"));
+        let urn_b = StringOrURI::URI(String::from("urn:example:A"));
+        assert!(urn_a.compare(&urn_b, Comparison::Normalized));
+    }
+
+    #[test]
+    fn test_stringoruri_compare_nfc() {
+        let composed = StringOrURI::String(String::from("caf\u{e9}"));
+        let decomposed = StringOrURI::String(String::from("cafe\u{301}"));
+        assert!(!composed.compare(&decomposed, Comparison::Exact));
+        assert!(composed.compare(&decomposed, Comparison::Normalized));
+    }
+
+    #[test]
+    fn test_claim_name_nfc() {
+        let cs = ClaimSet::decode_str(r#"{"cafe\u0301": 1}"#).unwrap();
+        assert_eq!(cs.get("caf\u{e9}").unwrap().claim_value, 1);
+        assert!(ClaimSet::decode_str(r#"{"cafe\u0301": 1, "caf\u00e9": 2}"#).is_err());
+    }
+
     #[test]
     fn test_claim_registered() {
         let c = Claim::parse(
             String::from("iss"), 
-            serde_json::json!("{foo:bar}")
+            serde_json::json!("foo:bar")
         ).unwrap();
-        assert_eq!(c.claim_value, "{foo:bar}");
+        assert_eq!(c.claim_value, "foo:bar");
         assert!(matches!(c.claim_type, ClaimType::Registered));
     }
 
@@ -376,6 +807,28 @@ mod tests {
         assert_eq!(c.claims.get("a").unwrap().claim_value, "b");
     }
 
+    #[test]
+    fn test_claim_set_duplicate_policy() {
+        let input = "{\"a\": 1, \"a\": 2}";
+        assert!(matches!(ClaimSet::decode_str(input), Err(err::JWTError::SchemaError)));
+
+        let config = ParseConfig { duplicate_claims: DuplicatePolicy::First, ..ParseConfig::new() };
+        let cs = ClaimSet::decode_str_with(input, &config).unwrap();
+        assert_eq!(cs.get("a").unwrap().claim_value, 1);
+
+        let config = ParseConfig { duplicate_claims: DuplicatePolicy::Last, ..ParseConfig::new() };
+        let cs = ClaimSet::decode_str_with(input, &config).unwrap();
+        assert_eq!(cs.get("a").unwrap().claim_value, 2);
+    }
+
+    #[test]
+    fn test_claim_set_decode_uri_mode() {
+        let input = "{\"https://example.com:99999/x\": 1}";
+        assert!(ClaimSet::decode_str(input).is_err());
+        let config = ParseConfig { uri_mode: UriMode::Iri, ..ParseConfig::new() };
+        assert!(ClaimSet::decode_str_with(input, &config).is_ok());
+    }
+
     #[test]
     fn test_claim_set_encode_str() {
         let v = "{\"a\":\"b\"}";
@@ -383,6 +836,14 @@ mod tests {
         assert_eq!(cs.encode_str(), v);
     }
 
+    #[test]
+    fn test_claim_set_serde_roundtrip() {
+        let cs: ClaimSet = serde_json::from_str("{\"a\": \"b\", \"c\": 1}").unwrap();
+        assert_eq!(cs.get("c").unwrap().claim_value, 1);
+        let v = serde_json::to_value(&cs).unwrap();
+        assert_eq!(v, serde_json::json!({"a": "b", "c": 1}));
+    }
+
     #[test]
     fn test_claim_set_encode_b64() {
         // TODO: roundtrip here using decode_b64, once it's implemented.
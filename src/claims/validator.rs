@@ -0,0 +1,1047 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde_json::Value;
+
+use super::{Audience, Claim, ClaimSet, Comparison, StringOrURI};
+use crate::{audience, chain, token_pair};
+use crate::err::ValidationError;
+use crate::header::{Alg, JWTHeader, Typ};
+use crate::token_pair::TokenUse;
+use crate::traits::{Clock, ReplayStore, SystemClock};
+use crate::JWT;
+
+/// The leeway of `Validator::strict`.
+pub const STRICT_LEEWAY: Duration = Duration::from_secs(30);
+
+/// The maximum encoded token size, in bytes, of `Validator::strict`.
+pub const STRICT_MAX_TOKEN_SIZE: usize = 8192;
+
+#[derive(Clone)]
+/// Checks the registered claims of a `ClaimSet` against what the application expects, once the
+/// signature of the token carrying it has been verified. The rules on the other claims are
+/// opt-in, while the time-based claims `exp`, `nbf` and `iat` are always checked when present.
+///
+/// # Examples
+/// ```
+/// use jwt::claims::{ClaimSet, Validator};
+/// use jwt::err::ValidationError;
+/// use jwt::traits::JsonSerializable;
+///
+/// let validator = Validator::new().issuer("https://idp").audience("api://me");
+/// let cs = ClaimSet::decode_str("{\"iss\": \"https://idp\", \"aud\": [\"api://me\", \"web\"]}")
+///     .unwrap();
+/// assert!(validator.validate(&cs).is_ok());
+///
+/// let cs = ClaimSet::decode_str("{\"iss\": \"https://idp\"}").unwrap();
+/// assert_eq!(validator.validate(&cs), Err(ValidationError::MissingClaim(String::from("aud"))));
+/// ```
+pub struct Validator {
+    /// The claims tokens must have, whatever their values.
+    pub required_claims: Vec<String>,
+    /// The issuers whose tokens are accepted. Empty if the `iss` claim is not checked.
+    pub issuers: Vec<TrustedIssuer>,
+    /// The audiences the `aud` claim must name at least one of, unless the token's issuer has
+    /// audiences of its own. Empty if it is not checked.
+    pub audiences: Vec<String>,
+    /// Whether tokens are rejected when no audiences apply to them, rather than their `aud` claim
+    /// going unchecked.
+    pub require_audience: bool,
+    /// The required `sub` claim.
+    pub subject: Option<String>,
+    /// The client the token must have been issued to, checked with `audience::check_azp`.
+    pub authorized_party: Option<String>,
+    /// The required `token_use` claim, which tells access tokens from refresh tokens (see
+    /// `token_pair::TokenPair`).
+    pub token_use: Option<TokenUse>,
+    /// How far the clock of the issuer may drift from the validator's: tokens are accepted for
+    /// this long after they expire, and this long before they become valid.
+    pub leeway: Duration,
+    /// How long after it was issued, going by its `iat` claim, a token is accepted for, whatever
+    /// its `exp` claim says. Setting this requires tokens to have an `iat` claim.
+    pub max_age: Option<Duration>,
+    /// How `iss`, `sub` and `aud` values are compared with the expected ones. Defaults to
+    /// `Comparison::Exact`.
+    pub comparison: Comparison,
+    /// The clock time-based claims are checked against. Defaults to `SystemClock`.
+    pub clock: Arc<dyn Clock>,
+    /// Where the `jti` claims of accepted tokens are recorded, if each token may only be used
+    /// once. Setting this requires tokens to have `jti` and `exp` claims.
+    pub replay_store: Option<Arc<dyn ReplayStore>>,
+    /// Whether re-issued tokens are only accepted if the token named by their `prev_jti` claim
+    /// was recorded in the replay store, and has not expired from it since.
+    pub require_chain: bool,
+    /// The required `typ` header parameter, checked by `validate_header` and `validate_jwt`.
+    pub typ: Option<Typ>,
+    /// Whether unsecured tokens (`"alg": "none"`) are rejected by `validate_header` and
+    /// `validate_jwt`.
+    pub reject_unsecured: bool,
+    /// The maximum size, in bytes, of a decoded token's encoding, checked by `validate_jwt`.
+    pub max_token_size: Option<usize>,
+    /// Application-specific rules, checked after the others.
+    pub custom_rules: Vec<CustomRule>,
+}
+
+/// An application-specific rule of a `Validator`, which checks a claim set.
+pub type CustomRule = Arc<dyn Fn(&ClaimSet) -> Result<(), ValidationError> + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq)]
+/// An issuer a `Validator` accepts tokens from, with the audiences its tokens must be intended
+/// for. A service trusting several identity providers often knows itself by a different audience
+/// at each.
+pub struct TrustedIssuer {
+    /// The `iss` claim of the issuer's tokens.
+    pub issuer: String,
+    /// The audiences the `aud` claim of the issuer's tokens must name at least one of. If empty,
+    /// those of the `Validator` apply instead.
+    pub audiences: Vec<String>,
+}
+
+impl TrustedIssuer {
+    /// Constructs a `TrustedIssuer` for `issuer`, whose tokens must be intended for one of
+    /// `audiences`.
+    pub fn new(issuer: &str, audiences: &[&str]) -> TrustedIssuer {
+        TrustedIssuer {
+            issuer: String::from(issuer),
+            audiences: audiences.iter().map(|audience| String::from(*audience)).collect(),
+        }
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Validator {
+        Validator::new()
+    }
+}
+
+// Wraps a claim value as the StringOrURI it is, going by whether it contains a colon, without
+// parsing it: a value is only ever compared, and a malformed URI simply will not match.
+fn string_or_uri(value: &str) -> StringOrURI {
+    if value.contains(':') {
+        StringOrURI::URI(String::from(value))
+    } else {
+        StringOrURI::String(String::from(value))
+    }
+}
+
+impl Validator {
+    /// Constructs a `Validator` with no rules and no leeway.
+    pub fn new() -> Validator {
+        Validator {
+            required_claims: Vec::new(),
+            issuers: Vec::new(),
+            audiences: Vec::new(),
+            require_audience: false,
+            subject: None,
+            authorized_party: None,
+            token_use: None,
+            leeway: Duration::from_secs(0),
+            max_age: None,
+            comparison: Comparison::Exact,
+            clock: Arc::new(SystemClock),
+            replay_store: None,
+            require_chain: false,
+            typ: None,
+            reject_unsecured: false,
+            max_token_size: None,
+            custom_rules: Vec::new(),
+        }
+    }
+
+    /// Constructs a `Validator` with hardened defaults: tokens must have `exp` and `aud` claims,
+    /// unsecured tokens are rejected, the leeway is `STRICT_LEEWAY`, and tokens may be at most
+    /// `STRICT_MAX_TOKEN_SIZE` bytes. The accepted audiences must still be given with `audience`
+    /// or `trusted_issuer`; until they are, every token is rejected (see `require_audience`).
+    /// Each default may be relaxed through the corresponding builder method or field.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::err::ValidationError;
+    /// use jwt::traits::JsonSerializable;
+    ///
+    /// let validator = Validator::strict().audience("api");
+    /// let cs = ClaimSet::decode_str("{\"aud\": \"api\"}").unwrap();
+    /// let missing_exp = ValidationError::MissingClaim(String::from("exp"));
+    /// assert_eq!(validator.validate(&cs), Err(missing_exp));
+    ///
+    /// let mut validator = validator;
+    /// validator.required_claims.retain(|claim_name| claim_name != "exp");
+    /// assert!(validator.validate(&cs).is_ok());
+    /// ```
+    pub fn strict() -> Validator {
+        Validator {
+            leeway: STRICT_LEEWAY,
+            require_audience: true,
+            reject_unsecured: true,
+            max_token_size: Some(STRICT_MAX_TOKEN_SIZE),
+            ..Validator::new()
+        }.require_claims(&["exp", "aud"])
+    }
+
+    /// Requires tokens to have every claim in `claim_names`. Each missing claim fails validation
+    /// with a `ValidationError::MissingClaim`.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::err::ValidationError;
+    /// use jwt::traits::JsonSerializable;
+    ///
+    /// let validator = Validator::new().require_claims(&["exp", "sub", "tenant_id"]);
+    /// let cs = ClaimSet::decode_str("{\"exp\": 1e10, \"sub\": \"u1\"}").unwrap();
+    /// assert_eq!(
+    ///     validator.validate(&cs),
+    ///     Err(ValidationError::MissingClaim(String::from("tenant_id")))
+    /// );
+    /// ```
+    pub fn require_claims(mut self, claim_names: &[&str]) -> Validator {
+        for claim_name in claim_names {
+            if !self.required_claims.iter().any(|required| required == claim_name) {
+                self.required_claims.push(String::from(*claim_name));
+            }
+        }
+        self
+    }
+
+    /// Accepts tokens issued by `issuer`. Once any issuer is given, tokens must have been issued
+    /// by one of them.
+    pub fn issuer(mut self, issuer: &str) -> Validator {
+        self.issuers.push(TrustedIssuer::new(issuer, &[]));
+        self
+    }
+
+    /// Accepts tokens issued by `issuer` which are intended for one of `audiences`, whatever
+    /// audiences are required of other issuers' tokens.
+    pub fn trusted_issuer(mut self, issuer: &str, audiences: &[&str]) -> Validator {
+        self.issuers.push(TrustedIssuer::new(issuer, audiences));
+        self
+    }
+
+    /// Accepts tokens intended for `audience`, which must be the `aud` claim or one of the values
+    /// of an array `aud` claim. Once any audience is given, tokens must be intended for one of
+    /// them.
+    pub fn audience(mut self, audience: &str) -> Validator {
+        self.audiences.push(String::from(audience));
+        self
+    }
+
+    /// Accepts tokens intended for any of `audiences`, as `audience` does for each.
+    pub fn audiences(mut self, audiences: &[&str]) -> Validator {
+        self.audiences.extend(audiences.iter().map(|audience| String::from(*audience)));
+        self
+    }
+
+    /// Rejects tokens to which no audiences apply, so that a validator which was never told its
+    /// audience fails closed. Such tokens fail validation with a `ValidationError::InvalidClaim`
+    /// naming `aud`.
+    pub fn require_audience(mut self) -> Validator {
+        self.require_audience = true;
+        self
+    }
+
+    /// Requires the `sub` claim to be `subject`.
+    pub fn subject(mut self, subject: &str) -> Validator {
+        self.subject = Some(String::from(subject));
+        self
+    }
+
+    /// Requires the token to have been issued to the client `client_id`, following OpenID Connect
+    /// Core section 3.1.3.7 (see `audience::check_azp`).
+    pub fn authorized_party(mut self, client_id: &str) -> Validator {
+        self.authorized_party = Some(String::from(client_id));
+        self
+    }
+
+    /// Requires the `token_use` claim to be that of `token_use`, so that a refresh token is not
+    /// accepted where an access token is expected, or the other way around. Tokens without it
+    /// fail validation with a `ValidationError::MissingClaim`, and tokens of the other use with a
+    /// `ValidationError::InvalidClaim`.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::token_pair::{TokenPair, TokenUse};
+    ///
+    /// let pair = TokenPair::issue(&ClaimSet::new(), 1_600_000_000, 900, 1_000_000_000).unwrap();
+    /// let validator = Validator::new().token_use(TokenUse::Refresh);
+    /// assert!(validator.validate(&pair.refresh.claim_set).is_ok());
+    /// assert!(validator.validate(&pair.access.claim_set).is_err());
+    /// ```
+    pub fn token_use(mut self, token_use: TokenUse) -> Validator {
+        self.token_use = Some(token_use);
+        self
+    }
+
+    /// Sets the clock skew tolerated in time-based checks.
+    pub fn leeway(mut self, leeway: Duration) -> Validator {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Rejects tokens issued more than `max_age` ago, as limited session lifetimes require.
+    pub fn max_age(mut self, max_age: Duration) -> Validator {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets how claim values are compared with the expected ones.
+    pub fn comparison(mut self, comparison: Comparison) -> Validator {
+        self.comparison = comparison;
+        self
+    }
+
+    /// Checks time-based claims against `clock` rather than the system clock.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Validator {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Accepts each token only once, recording its `jti` in `store`, which may be shared with
+    /// other validators.
+    pub fn replay_store(mut self, store: Arc<dyn ReplayStore>) -> Validator {
+        self.replay_store = Some(store);
+        self
+    }
+
+    /// Requires an unbroken chain of re-issued tokens (see `chain::reissue`): a token with a
+    /// `prev_jti` claim is rejected with a `ValidationError::InvalidClaim` unless the token it
+    /// names was accepted with the replay store set by `replay_store`, and has not expired since.
+    /// Tokens without `prev_jti` start a chain. Without a replay store, every re-issued token is
+    /// rejected.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::sync::Arc;
+    /// use jwt::chain;
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::replay::MemoryReplayStore;
+    /// use jwt::traits::JsonSerializable;
+    ///
+    /// let validator = Validator::new()
+    ///     .replay_store(Arc::new(MemoryReplayStore::new()))
+    ///     .require_chain();
+    /// let first = ClaimSet::decode_str("{\"jti\": \"a\", \"exp\": 1e10}").unwrap();
+    /// let second = chain::reissue(&first, 1_600_000_000, 1_000_000_000).unwrap();
+    /// let third = chain::reissue(&second, 1_600_000_000, 1_000_000_000).unwrap();
+    /// assert!(validator.validate(&second).is_err());
+    /// assert!(validator.validate(&first).is_ok());
+    /// assert!(validator.validate(&second).is_ok());
+    /// assert!(validator.validate(&third).is_ok());
+    /// ```
+    pub fn require_chain(mut self) -> Validator {
+        self.require_chain = true;
+        self
+    }
+
+    /// Requires the `typ` header parameter to be `typ` (see `Typ::parse`), as token profiles such
+    /// as `at+jwt` access tokens do. Header rules are checked by `validate_header` and
+    /// `validate_jwt`, not by `validate`.
+    pub fn typ(mut self, typ: &str) -> Validator {
+        self.typ = Some(Typ::parse(typ));
+        self
+    }
+
+    /// Rejects unsecured tokens, whose header names the algorithm `none`.
+    pub fn reject_unsecured(mut self) -> Validator {
+        self.reject_unsecured = true;
+        self
+    }
+
+    /// Rejects decoded tokens whose encoding is larger than `max_token_size` bytes. Such tokens
+    /// are better refused before decoding, with `ParseConfig::max_size`; this guards against
+    /// callers which did not.
+    pub fn max_token_size(mut self, max_token_size: usize) -> Validator {
+        self.max_token_size = Some(max_token_size);
+        self
+    }
+
+    /// Adds the application-specific rule `rule`, which is given the claim set and returns the
+    /// `ValidationError` it fails with, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::err::ValidationError;
+    /// use jwt::traits::JsonSerializable;
+    ///
+    /// let validator = Validator::new().rule(|cs| match cs.get("scope") {
+    ///     Ok(scope) if scope.claim_value.as_str().is_some_and(|s| s.contains("read")) => Ok(()),
+    ///     _ => Err(ValidationError::InvalidClaim(String::from("scope"))),
+    /// });
+    /// let cs = ClaimSet::decode_str("{\"scope\": \"read write\"}").unwrap();
+    /// assert!(validator.validate(&cs).is_ok());
+    /// assert!(validator.validate(&ClaimSet::new()).is_err());
+    /// ```
+    pub fn rule<F>(mut self, rule: F) -> Validator
+        where F: Fn(&ClaimSet) -> Result<(), ValidationError> + Send + Sync + 'static {
+        self.custom_rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Adds an application-specific rule on the claim `claim_name`, which tokens must then have.
+    /// `rule` is given the claim, and returns the `ValidationError` it fails with, if any.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::err::ValidationError;
+    /// use jwt::traits::JsonSerializable;
+    ///
+    /// let validator = Validator::new().claim_rule("tenant_id", |claim| {
+    ///     match claim.claim_value.as_str() {
+    ///         Some(tenant) if tenant.starts_with("t-") => Ok(()),
+    ///         _ => Err(ValidationError::InvalidClaim(String::from("tenant_id"))),
+    ///     }
+    /// });
+    /// let cs = ClaimSet::decode_str("{\"tenant_id\": \"t-1\"}").unwrap();
+    /// assert!(validator.validate(&cs).is_ok());
+    /// assert_eq!(
+    ///     validator.validate(&ClaimSet::new()),
+    ///     Err(ValidationError::MissingClaim(String::from("tenant_id")))
+    /// );
+    /// ```
+    pub fn claim_rule<F>(self, claim_name: &str, rule: F) -> Validator
+        where F: Fn(&Claim) -> Result<(), ValidationError> + Send + Sync + 'static {
+        let claim_name = String::from(claim_name);
+        self.rule(move |claim_set| match claim_set.claims.get(&claim_name) {
+            Some(claim) => rule(claim),
+            None => Err(ValidationError::MissingClaim(claim_name.clone())),
+        })
+    }
+
+    // Returns the string value of the claim `claim_name`, or a ValidationError::MissingClaim if
+    // it is absent. Registered claims are type checked when decoded, so other values only reach
+    // here from claim sets built by hand, and are reported as invalid.
+    fn string_claim<'a>(claim_set: &'a ClaimSet, claim_name: &str)
+        -> Result<&'a str, ValidationError> {
+        match claim_set.claims.get(claim_name).map(|claim| &claim.claim_value) {
+            Some(Value::String(value)) => Ok(value),
+            Some(_) => Err(ValidationError::InvalidClaim(String::from(claim_name))),
+            None => Err(ValidationError::MissingClaim(String::from(claim_name))),
+        }
+    }
+
+    // Checks that the claim `claim_name` is `expected`.
+    fn check_string_claim(&self, claim_set: &ClaimSet, claim_name: &str, expected: &str)
+        -> Result<(), ValidationError> {
+        let value = Validator::string_claim(claim_set, claim_name)?;
+        if string_or_uri(value).compare(&string_or_uri(expected), self.comparison) {
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidClaim(String::from(claim_name)))
+        }
+    }
+
+    // Returns the value of the NumericDate claim `claim_name`, if present. NumericDates may have
+    // a fractional part.
+    fn numeric_date(claim_set: &ClaimSet, claim_name: &str)
+        -> Result<Option<f64>, ValidationError> {
+        match claim_set.claims.get(claim_name) {
+            Some(claim) => claim.claim_value.as_f64().map(Some)
+                .ok_or_else(|| ValidationError::InvalidClaim(String::from(claim_name))),
+            None => Ok(None),
+        }
+    }
+
+    // Returns the time on the clock, in seconds since the epoch. A clock set before the epoch is
+    // treated as standing at it.
+    fn now(&self) -> f64 {
+        self.clock.now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0)
+    }
+
+    // A token is expired from its exp on.
+    fn check_exp(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        match Validator::numeric_date(claim_set, "exp")? {
+            Some(exp) if self.now() >= exp + self.leeway.as_secs_f64() => {
+                Err(ValidationError::Expired)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // A token is valid from its nbf on.
+    fn check_nbf(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        match Validator::numeric_date(claim_set, "nbf")? {
+            Some(nbf) if self.now() + self.leeway.as_secs_f64() < nbf => {
+                Err(ValidationError::NotYetValid)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // A token issued in the future is invalid.
+    fn check_iat(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        match Validator::numeric_date(claim_set, "iat")? {
+            Some(iat) if self.now() + self.leeway.as_secs_f64() < iat => {
+                Err(ValidationError::InvalidClaim(String::from("iat")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_max_age(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        if let Some(max_age) = self.max_age {
+            let iat = Validator::numeric_date(claim_set, "iat")?
+                .ok_or_else(|| ValidationError::MissingClaim(String::from("iat")))?;
+            if self.now() > iat + max_age.as_secs_f64() + self.leeway.as_secs_f64() {
+                return Err(ValidationError::TooOld)
+            }
+        }
+        Ok(())
+    }
+
+    // Returns the trusted issuer of the token, or None if no issuers are configured.
+    fn trusted_issuer_of(&self, claim_set: &ClaimSet)
+        -> Result<Option<&TrustedIssuer>, ValidationError> {
+        if self.issuers.is_empty() {
+            return Ok(None)
+        }
+        let iss = string_or_uri(Validator::string_claim(claim_set, "iss")?);
+        self.issuers.iter()
+            .find(|trusted| iss.compare(&string_or_uri(&trusted.issuer), self.comparison))
+            .map(Some)
+            .ok_or_else(|| ValidationError::InvalidClaim(String::from("iss")))
+    }
+
+    fn check_issuer(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        self.trusted_issuer_of(claim_set).map(|_| ())
+    }
+
+    fn check_subject(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        match &self.subject {
+            Some(subject) => self.check_string_claim(claim_set, "sub", subject),
+            None => Ok(()),
+        }
+    }
+
+    // The audiences of the token's issuer apply if it has any, and otherwise the validator's,
+    // including when the issuer is not trusted.
+    fn check_audience(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        let audiences = match self.trusted_issuer_of(claim_set) {
+            Ok(Some(issuer)) if !issuer.audiences.is_empty() => &issuer.audiences,
+            _ => &self.audiences,
+        };
+        if audiences.is_empty() {
+            return if self.require_audience {
+                Err(ValidationError::InvalidClaim(String::from("aud")))
+            } else {
+                Ok(())
+            }
+        }
+        let audience = Audience::from_claim_set(claim_set)
+            .map_err(|_| ValidationError::InvalidClaim(String::from("aud")))?
+            .ok_or_else(|| ValidationError::MissingClaim(String::from("aud")))?;
+        let matched = audience.as_slice().iter().any(|audience| {
+            let audience = string_or_uri(audience);
+            audiences.iter()
+                .any(|expected| audience.compare(&string_or_uri(expected), self.comparison))
+        });
+        if matched { Ok(()) } else { Err(ValidationError::InvalidClaim(String::from("aud"))) }
+    }
+
+    fn check_authorized_party(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        if let Some(client_id) = &self.authorized_party {
+            if claim_set.get("aud").is_err() {
+                return Err(ValidationError::MissingClaim(String::from("aud")))
+            }
+            audience::check_azp(claim_set, client_id)
+                .map_err(|_| ValidationError::InvalidClaim(String::from("azp")))?;
+        }
+        Ok(())
+    }
+
+    fn check_token_use(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        match self.token_use {
+            Some(token_use) => {
+                let value = Validator::string_claim(claim_set, token_pair::TOKEN_USE_CLAIM)?;
+                if value == token_use.as_str() {
+                    Ok(())
+                } else {
+                    Err(ValidationError::InvalidClaim(String::from(token_pair::TOKEN_USE_CLAIM)))
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    // A re-issued token is only accepted if the token it was re-issued from was recorded in the
+    // replay store.
+    fn check_chain(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        if !claim_set.claims.contains_key(chain::PREV_JTI_CLAIM) {
+            return Ok(())
+        }
+        let prev_jti = Validator::string_claim(claim_set, chain::PREV_JTI_CLAIM)?;
+        match &self.replay_store {
+            Some(store) if store.recorded(prev_jti) => Ok(()),
+            _ => Err(ValidationError::InvalidClaim(String::from(chain::PREV_JTI_CLAIM))),
+        }
+    }
+
+    // Checks the chain of a re-issued token, if required, and records the token in the replay
+    // store. This must only run once every other rule has passed, so that tokens which are
+    // rejected anyway are not recorded.
+    fn check_replay(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        if self.require_chain {
+            self.check_chain(claim_set)?;
+        }
+        if let Some(store) = &self.replay_store {
+            let jti = Validator::string_claim(claim_set, "jti")?;
+            let exp = Validator::numeric_date(claim_set, "exp")?
+                .ok_or_else(|| ValidationError::MissingClaim(String::from("exp")))?;
+            // The token is rejected as expired from exp plus the leeway on.
+            if store.seen(jti, (exp + self.leeway.as_secs_f64()).ceil() as u64) {
+                return Err(ValidationError::Replayed)
+            }
+        }
+        Ok(())
+    }
+
+    // Returns a ValidationError::MissingClaim for each required claim `claim_set` lacks.
+    fn missing_claims<'a>(&'a self, claim_set: &'a ClaimSet)
+        -> impl Iterator<Item = ValidationError> + 'a {
+        self.required_claims.iter()
+            .filter(move |claim_name| !claim_set.claims.contains_key(claim_name.as_str()))
+            .map(|claim_name| ValidationError::MissingClaim(claim_name.clone()))
+    }
+
+    /// Checks `claim_set` against every configured rule, and its time-based claims against the
+    /// clock, returning the first failure.
+    pub fn validate(&self, claim_set: &ClaimSet) -> Result<(), ValidationError> {
+        if let Some(missing) = self.missing_claims(claim_set).next() {
+            return Err(missing)
+        }
+        for rule in RULES {
+            rule(self, claim_set)?;
+        }
+        for rule in &self.custom_rules {
+            rule(claim_set)?;
+        }
+        self.check_replay(claim_set)
+    }
+
+    /// Checks `claim_set` as `validate` does, but reports every rule it fails rather than only
+    /// the first. A token is only recorded in the replay store, and so only reported as
+    /// `ValidationError::Replayed`, if it passes every other rule.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::claims::{ClaimSet, Validator};
+    /// use jwt::err::ValidationError;
+    /// use jwt::traits::JsonSerializable;
+    ///
+    /// let validator = Validator::new().issuer("https://idp").audience("api");
+    /// let cs = ClaimSet::decode_str("{\"iss\": \"https://other\", \"exp\": 1}").unwrap();
+    /// let report = validator.validate_all(&cs);
+    /// assert!(!report.is_valid());
+    /// assert_eq!(report.failures, [
+    ///     ValidationError::Expired,
+    ///     ValidationError::InvalidClaim(String::from("iss")),
+    ///     ValidationError::MissingClaim(String::from("aud")),
+    /// ]);
+    /// ```
+    pub fn validate_all(&self, claim_set: &ClaimSet) -> ValidationReport {
+        let mut failures = self.missing_claims(claim_set).collect::<Vec<ValidationError>>();
+        let results = RULES.iter().map(|rule| rule(self, claim_set))
+            .chain(self.custom_rules.iter().map(|rule| rule(claim_set)));
+        for result in results {
+            // A malformed claim may fail several rules the same way.
+            if let Err(e) = result {
+                if !failures.contains(&e) {
+                    failures.push(e);
+                }
+            }
+        }
+        if failures.is_empty() {
+            failures.extend(self.check_replay(claim_set).err());
+        }
+        ValidationReport { failures }
+    }
+
+    /// Checks `header` against the rules on header parameters.
+    pub fn validate_header(&self, header: &JWTHeader) -> Result<(), ValidationError> {
+        if self.reject_unsecured && header.alg == Alg::None {
+            return Err(ValidationError::InvalidHeader(String::from("alg")))
+        }
+        match &self.typ {
+            Some(typ) if *typ != header.typ => {
+                Err(ValidationError::InvalidHeader(String::from("typ")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the header and claim set of `jwt` against every configured rule, returning the
+    /// first failure.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::claims::Validator;
+    ///
+    /// let mut jwt = JWT::new();
+    /// jwt.header.typ = jwt::header::Typ::parse("at+jwt");
+    /// assert!(Validator::new().typ("at+jwt").validate_jwt(&jwt).is_ok());
+    /// assert!(Validator::new().typ("dpop+jwt").validate_jwt(&jwt).is_err());
+    /// ```
+    pub fn validate_jwt(&self, jwt: &JWT) -> Result<(), ValidationError> {
+        self.check_token_size(jwt)?;
+        self.validate_header(&jwt.header)?;
+        self.validate(&jwt.claim_set)
+    }
+}
+
+impl Validator {
+    // Checks the size of the encoding `jwt` was decoded from, if it was. Its signature was
+    // base64url-encoded without padding.
+    fn check_token_size(&self, jwt: &JWT) -> Result<(), ValidationError> {
+        if let (Some(max_size), Some(raw)) = (self.max_token_size, &jwt.raw) {
+            let signature_len = (jwt.signature.len() * 4).div_ceil(3);
+            if raw.header.len() + raw.payload.len() + signature_len + 2 > max_size {
+                return Err(ValidationError::TooLarge)
+            }
+        }
+        Ok(())
+    }
+}
+
+// A rule of a Validator, checking a claim set.
+type Rule = fn(&Validator, &ClaimSet) -> Result<(), ValidationError>;
+
+// The rules checked by a Validator, in order, before any custom rules and the replay check.
+const RULES: &[Rule] = &[
+    Validator::check_exp,
+    Validator::check_nbf,
+    Validator::check_iat,
+    Validator::check_max_age,
+    Validator::check_issuer,
+    Validator::check_subject,
+    Validator::check_audience,
+    Validator::check_authorized_party,
+    Validator::check_token_use,
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Every rule of a `Validator` which a claim set failed, as reported by `Validator::validate_all`.
+pub struct ValidationReport {
+    pub failures: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    /// Returns whether the claim set passed every rule.
+    pub fn is_valid(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "the token passed validation.")
+        }
+        for (i, failure) in self.failures.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+// The clock, replay store and custom rules are trait objects, which need not implement Debug.
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("required_claims", &self.required_claims)
+            .field("issuers", &self.issuers)
+            .field("audiences", &self.audiences)
+            .field("require_audience", &self.require_audience)
+            .field("subject", &self.subject)
+            .field("authorized_party", &self.authorized_party)
+            .field("token_use", &self.token_use)
+            .field("leeway", &self.leeway)
+            .field("max_age", &self.max_age)
+            .field("comparison", &self.comparison)
+            .field("require_chain", &self.require_chain)
+            .field("typ", &self.typ)
+            .field("reject_unsecured", &self.reject_unsecured)
+            .field("max_token_size", &self.max_token_size)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::{FixedClock, JsonSerializable};
+
+    fn invalid(claim_name: &str) -> Result<(), ValidationError> {
+        Err(ValidationError::InvalidClaim(String::from(claim_name)))
+    }
+
+    #[test]
+    fn test_validate() {
+        let cs = ClaimSet::decode_str(
+            "{\"iss\": \"https://idp\", \"sub\": \"u1\", \"aud\": [\"web\", \"api://me\"]}"
+        ).unwrap();
+        assert!(Validator::new().validate(&cs).is_ok());
+        assert!(Validator::new().validate(&ClaimSet::new()).is_ok());
+
+        let validator = Validator::new().issuer("https://idp").subject("u1").audience("api://me");
+        assert!(validator.validate(&cs).is_ok());
+        assert_eq!(Validator::new().issuer("https://other").validate(&cs), invalid("iss"));
+        assert_eq!(validator.clone().subject("u2").validate(&cs), invalid("sub"));
+        assert_eq!(Validator::new().audience("api").validate(&cs), invalid("aud"));
+        assert!(Validator::new().audiences(&["api", "web"]).validate(&cs).is_ok());
+        let cs_single = ClaimSet::decode_str("{\"aud\": \"api\"}").unwrap();
+        assert!(Validator::new().audiences(&["web", "api"]).validate(&cs_single).is_ok());
+        assert_eq!(
+            validator.validate(&ClaimSet::decode_str("{\"iss\": \"https://idp\"}").unwrap()),
+            Err(ValidationError::MissingClaim(String::from("sub")))
+        );
+
+        // Several audiences require an azp claim naming the client.
+        assert_eq!(Validator::new().authorized_party("web").validate(&cs), invalid("azp"));
+        let cs = ClaimSet::decode_str("{\"aud\": \"web\"}").unwrap();
+        assert!(Validator::new().authorized_party("web").validate(&cs).is_ok());
+    }
+
+    #[test]
+    fn test_trusted_issuers() {
+        let validator = Validator::new()
+            .trusted_issuer("https://a", &["api://a"])
+            .trusted_issuer("https://b", &["api://b", "b"])
+            .issuer("https://c")
+            .audience("api://me");
+        let cs = |iss, aud| {
+            ClaimSet::decode_str(&format!("{{\"iss\": \"{}\", \"aud\": \"{}\"}}", iss, aud))
+                .unwrap()
+        };
+        assert!(validator.validate(&cs("https://a", "api://a")).is_ok());
+        assert!(validator.validate(&cs("https://b", "b")).is_ok());
+        assert!(validator.validate(&cs("https://c", "api://me")).is_ok());
+        assert_eq!(validator.validate(&cs("https://a", "api://b")), invalid("aud"));
+        assert_eq!(validator.validate(&cs("https://a", "api://me")), invalid("aud"));
+        assert_eq!(validator.validate(&cs("https://c", "api://a")), invalid("aud"));
+        assert_eq!(validator.validate(&cs("https://d", "api://me")), invalid("iss"));
+    }
+
+    #[test]
+    fn test_times() {
+        let cs = ClaimSet::decode_str("{\"nbf\": 100, \"iat\": 100.5, \"exp\": 200}").unwrap();
+        let at = |now| Validator::new().clock(FixedClock(UNIX_EPOCH + Duration::from_secs(now)));
+        assert_eq!(at(99).validate(&cs), Err(ValidationError::NotYetValid));
+        assert_eq!(at(100).validate(&cs), invalid("iat"));
+        assert!(at(101).validate(&cs).is_ok());
+        assert!(at(199).validate(&cs).is_ok());
+        assert_eq!(at(200).validate(&cs), Err(ValidationError::Expired));
+        assert_eq!(Validator::new().validate(&cs), Err(ValidationError::Expired));
+
+        let leeway = Duration::from_secs(30);
+        assert_eq!(at(69).leeway(leeway).validate(&cs), Err(ValidationError::NotYetValid));
+        assert!(at(71).leeway(leeway).validate(&cs).is_ok());
+        assert!(at(229).leeway(leeway).validate(&cs).is_ok());
+        assert_eq!(at(230).leeway(leeway).validate(&cs), Err(ValidationError::Expired));
+    }
+
+    #[test]
+    fn test_max_age() {
+        let cs = ClaimSet::decode_str("{\"iat\": 100, \"exp\": 1000}").unwrap();
+        let clock = |now| FixedClock(UNIX_EPOCH + Duration::from_secs(now));
+        let validator = Validator::new().max_age(Duration::from_secs(60));
+        assert!(validator.clone().clock(clock(160)).validate(&cs).is_ok());
+        assert_eq!(validator.clone().clock(clock(161)).validate(&cs), Err(ValidationError::TooOld));
+        let validator = validator.leeway(Duration::from_secs(5));
+        assert!(validator.clone().clock(clock(165)).validate(&cs).is_ok());
+
+        let cs = ClaimSet::decode_str("{\"exp\": 1000}").unwrap();
+        assert_eq!(
+            validator.clock(clock(100)).validate(&cs),
+            Err(ValidationError::MissingClaim(String::from("iat")))
+        );
+    }
+
+    #[test]
+    fn test_replay_store() {
+        use crate::replay::MemoryReplayStore;
+
+        let store = Arc::new(MemoryReplayStore::new());
+        let validator = Validator::new().audience("api").replay_store(store.clone());
+        let cs = ClaimSet::decode_str("{\"jti\": \"a\", \"aud\": \"api\", \"exp\": 1e10}")
+            .unwrap();
+        assert!(validator.validate(&cs).is_ok());
+        assert_eq!(validator.validate(&cs), Err(ValidationError::Replayed));
+        assert_eq!(
+            Validator::new().replay_store(store.clone()).validate(&cs),
+            Err(ValidationError::Replayed)
+        );
+
+        // Tokens failing other rules are not recorded.
+        let cs = ClaimSet::decode_str("{\"jti\": \"b\", \"aud\": \"web\", \"exp\": 1e10}")
+            .unwrap();
+        assert_eq!(validator.validate(&cs), invalid("aud"));
+        assert_eq!(store.len(), 1);
+        let cs = ClaimSet::decode_str("{\"jti\": \"b\"}").unwrap();
+        assert_eq!(
+            Validator::new().replay_store(store).validate(&cs),
+            Err(ValidationError::MissingClaim(String::from("exp")))
+        );
+    }
+
+    #[test]
+    fn test_require_chain() {
+        use crate::replay::MemoryReplayStore;
+
+        let first = ClaimSet::decode_str("{\"jti\": \"a\", \"exp\": 1e10}").unwrap();
+        let second = chain::reissue(&first, 0, 1 << 40).unwrap();
+        let third = chain::reissue(&second, 0, 1 << 40).unwrap();
+        assert_eq!(Validator::new().require_chain().validate(&second), invalid("prev_jti"));
+        assert!(Validator::new().require_chain().validate(&first).is_ok());
+
+        let validator = Validator::new()
+            .replay_store(Arc::new(MemoryReplayStore::new()))
+            .require_chain();
+        assert!(validator.validate(&first).is_ok());
+        // The chain breaks where a token was never presented.
+        assert_eq!(validator.validate(&third), invalid("prev_jti"));
+        assert!(validator.validate(&second).is_ok());
+        assert!(validator.validate(&third).is_ok());
+    }
+
+    #[test]
+    fn test_token_use() {
+        use crate::token_pair::TokenPair;
+
+        let pair = TokenPair::issue(&ClaimSet::new(), 0, 1 << 40, 1 << 41).unwrap();
+        let validator = Validator::new().token_use(TokenUse::Access);
+        assert!(validator.validate(&pair.access.claim_set).is_ok());
+        assert_eq!(validator.validate(&pair.refresh.claim_set), invalid("token_use"));
+        assert_eq!(
+            validator.validate(&ClaimSet::new()),
+            Err(ValidationError::MissingClaim(String::from("token_use")))
+        );
+    }
+
+    #[test]
+    fn test_validate_all() {
+        let validator = Validator::new()
+            .trusted_issuer("https://a", &["api://a"])
+            .subject("u1")
+            .max_age(Duration::from_secs(60))
+            .clock(FixedClock(UNIX_EPOCH + Duration::from_secs(1000)));
+        let cs = ClaimSet::decode_str(
+            "{\"iss\": \"https://a\", \"sub\": \"u2\", \"aud\": \"web\", \"iat\": 10, \
+              \"nbf\": 2000}"
+        ).unwrap();
+        let report = validator.validate_all(&cs);
+        assert_eq!(report.failures, [
+            ValidationError::NotYetValid,
+            ValidationError::TooOld,
+            ValidationError::InvalidClaim(String::from("sub")),
+            ValidationError::InvalidClaim(String::from("aud")),
+        ]);
+        assert_eq!(validator.validate(&cs), Err(ValidationError::NotYetValid));
+        assert_eq!(report.to_string().lines().count(), 4);
+
+        let cs = ClaimSet::decode_str(
+            "{\"iss\": \"https://a\", \"sub\": \"u1\", \"aud\": \"api://a\", \"iat\": 990}"
+        ).unwrap();
+        assert!(validator.validate_all(&cs).is_valid());
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let validator = Validator::new()
+            .claim_rule("tenant_id", |claim| match claim.claim_value.as_str() {
+                Some(tenant) if tenant.starts_with("t-") => Ok(()),
+                _ => invalid("tenant_id"),
+            })
+            .rule(|claim_set| match claim_set.get("scope") {
+                Ok(_) => Ok(()),
+                Err(_) => Err(ValidationError::MissingClaim(String::from("scope"))),
+            })
+            .subject("u1");
+        let cs = ClaimSet::decode_str("{\"sub\": \"u1\", \"tenant_id\": \"t-1\", \"scope\": \"\"}")
+            .unwrap();
+        assert!(validator.validate(&cs).is_ok());
+
+        let cs = ClaimSet::decode_str("{\"sub\": \"u2\", \"tenant_id\": \"acme\"}").unwrap();
+        assert_eq!(validator.validate(&cs), invalid("sub"));
+        assert_eq!(validator.validate_all(&cs).failures, [
+            ValidationError::InvalidClaim(String::from("sub")),
+            ValidationError::InvalidClaim(String::from("tenant_id")),
+            ValidationError::MissingClaim(String::from("scope")),
+        ]);
+    }
+
+    #[test]
+    fn test_typ() {
+        let header = JWTHeader::decode_str("{\"alg\": \"none\", \"typ\": \"application/at+jwt\"}")
+            .unwrap();
+        assert!(Validator::new().validate_header(&header).is_ok());
+        assert!(Validator::new().typ("AT+JWT").validate_header(&header).is_ok());
+        assert_eq!(
+            Validator::new().typ("JWT").validate_header(&header),
+            Err(ValidationError::InvalidHeader(String::from("typ")))
+        );
+        let header = JWTHeader::decode_str("{\"alg\": \"none\"}").unwrap();
+        assert!(Validator::new().typ("dpop+jwt").validate_header(&header).is_err());
+    }
+
+    #[test]
+    fn test_require_claims() {
+        let validator = Validator::new()
+            .require_claims(&["sub", "iat", "tenant_id"])
+            .require_claims(&["sub"])
+            .max_age(Duration::from_secs(60));
+        assert_eq!(validator.required_claims, ["sub", "iat", "tenant_id"]);
+        let cs = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+        let missing = |claim_name| ValidationError::MissingClaim(String::from(claim_name));
+        assert_eq!(validator.validate(&cs), Err(missing("iat")));
+        assert_eq!(validator.validate_all(&cs).failures, [missing("iat"), missing("tenant_id")]);
+    }
+
+    #[test]
+    fn test_strict() {
+        let validator = Validator::strict().audience("api");
+        let cs = ClaimSet::decode_str("{\"aud\": \"api\", \"exp\": 1e10}").unwrap();
+        assert!(validator.validate(&cs).is_ok());
+        assert_eq!(
+            validator.validate(&ClaimSet::decode_str("{\"exp\": 1e10}").unwrap()),
+            Err(ValidationError::MissingClaim(String::from("aud")))
+        );
+
+        // Until its audience is given, a strict validator accepts no tokens.
+        assert_eq!(
+            Validator::strict().validate(&ClaimSet::decode_str(
+                "{\"aud\": \"anything\", \"exp\": 1e10}"
+            ).unwrap()),
+            Err(ValidationError::InvalidClaim(String::from("aud")))
+        );
+        assert!(Validator::strict().trusted_issuer("https://idp", &["api"])
+            .validate(&ClaimSet::decode_str(
+                "{\"iss\": \"https://idp\", \"aud\": \"api\", \"exp\": 1e10}"
+            ).unwrap())
+            .is_ok());
+
+        let jwt = JWT::decode_b64("eyJhbGciOiJub25lIn0.eyJhdWQiOiJhcGkiLCJleHAiOjFlMTB9.").unwrap();
+        assert_eq!(
+            validator.validate_jwt(&jwt),
+            Err(ValidationError::InvalidHeader(String::from("alg")))
+        );
+        let mut validator = validator;
+        validator.reject_unsecured = false;
+        assert!(validator.validate_jwt(&jwt).is_ok());
+        assert_eq!(validator.max_token_size(40).validate_jwt(&jwt), Err(ValidationError::TooLarge));
+    }
+
+    #[test]
+    fn test_comparison() {
+        let cs = ClaimSet::decode_str("{\"iss\": \"HTTPS://IdP.example/a%7eb\"}").unwrap();
+        let validator = Validator::new().issuer("https://idp.example/a~b");
+        assert_eq!(validator.validate(&cs), invalid("iss"));
+        assert!(validator.comparison(Comparison::Normalized).validate(&cs).is_ok());
+    }
+}
@@ -0,0 +1,209 @@
+// Subcommands of the jwt binary. Each submodule defines the clap arguments for one subcommand and
+// a run function that executes it; helpers shared between subcommands live here.
+use std::io::{IsTerminal, Read};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use serde_json::Value;
+
+use jwt::header::Alg;
+use jwt::{compression, err, ParseConfig};
+
+pub mod convert;
+pub mod decode;
+pub mod inspect;
+pub mod keygen;
+pub mod sign;
+pub mod verify;
+
+// Exit codes of the jwt binary. These are stable, so that scripts and CI gates may rely on them.
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_EXPIRED: i32 = 2;
+pub const EXIT_BAD_SIGNATURE: i32 = 3;
+pub const EXIT_PARSE_ERROR: i32 = 4;
+pub const EXIT_INVALID: i32 = 5;
+pub const EXIT_UNSUPPORTED: i32 = 6;
+
+/// Help text describing the exit codes, shown at the bottom of `jwt --help`.
+pub const EXIT_CODE_HELP: &str = "Exit codes:
+  0  success (for inspect: the token is valid)
+  1  usage or I/O error
+  2  the token has expired
+  3  the token's signature is invalid
+  4  the token could not be parsed
+  5  the token failed validation for a reason other than expiry
+  6  the token uses an algorithm or feature which is not supported";
+
+/// Maps an error onto the exit code reported for it.
+pub fn exit_code(e: &err::JWTError) -> i32 {
+    match e {
+        err::JWTError::ValidationError(err::ValidationError::Expired) => EXIT_EXPIRED,
+        err::JWTError::ValidationError(_) => EXIT_INVALID,
+        err::JWTError::ParseError(_) |
+        err::JWTError::SchemaError |
+        err::JWTError::ClaimTypeError(_) => EXIT_PARSE_ERROR,
+        err::JWTError::NotImplementedError => EXIT_UNSUPPORTED,
+        err::JWTError::SignatureError | err::JWTError::UnknownKid(_) => EXIT_BAD_SIGNATURE,
+        err::JWTError::HttpError(_) | err::JWTError::IoError(_) |
+        err::JWTError::KeyTooWeak(_) => EXIT_FAILURE,
+    }
+}
+
+/// Returns the token given on the command line or, if it was omitted or given as `-`, reads it
+/// from stdin. Surrounding whitespace is trimmed either way.
+pub fn read_token(token: &Option<String>) -> err::Result<String> {
+    match token.as_deref() {
+        Some(token) if token != "-" => Ok(String::from(token.trim())),
+        _ => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)
+                .map_err(|e| { err::JWTError::IoError(format!("{}", e)) })?;
+            Ok(String::from(buf.trim()))
+        },
+    }
+}
+
+/// Parses an algorithm name as it appears in the `alg` header parameter, for clap.
+pub fn parse_alg(alg: &str) -> Result<Alg, String> {
+    serde_json::from_value(Value::from(alg)).map_err(|_| format!("unknown algorithm \"{}\"", alg))
+}
+
+/// Returns the current time as a NumericDate.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text.
+    Table,
+    /// A JSON report.
+    Json,
+    /// A YAML report.
+    Yaml,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ColorChoice {
+    /// Color table output when stdout is a terminal and NO_COLOR is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Args)]
+pub struct OutputArgs {
+    /// The format to print results in.
+    #[arg(long, value_enum, default_value = "table")]
+    pub output: OutputFormat,
+
+    /// Whether to color table output. JSON and YAML output is never colored.
+    #[arg(long, value_enum, default_value = "auto")]
+    pub color: ColorChoice,
+}
+
+impl OutputArgs {
+    /// Returns whether table output should be colored.
+    pub fn use_color(&self) -> bool {
+        match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+            },
+        }
+    }
+
+    /// Prints `report` in the requested format, using `table` to render the table format.
+    pub fn print<T: Serialize>(&self, report: &T, table: fn(&T, bool) -> String)
+        -> err::Result<()> {
+        let out = match self.output {
+            OutputFormat::Table => table(report, self.use_color()),
+            OutputFormat::Json => serde_json::to_string_pretty(report)
+                .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })? + "\n",
+            OutputFormat::Yaml => serde_yaml::to_string(report)
+                .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?,
+        };
+        print!("{}", out);
+        Ok(())
+    }
+}
+
+// ANSI SGR codes used by table output.
+pub const BOLD: &str = "1";
+pub const RED: &str = "31";
+pub const YELLOW: &str = "33";
+
+/// Wraps `text` in the given ANSI SGR code when `color` is set.
+pub fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\u{1b}[{}m{}\u{1b}[0m", code, text)
+    } else {
+        String::from(text)
+    }
+}
+
+/// The registered claims which hold NumericDate values.
+pub const NUMERIC_DATE_CLAIMS: &[&str; 3] = &["exp", "nbf", "iat"];
+
+/// Decodes a single base64-encoded token component into JSON, accepting either base64 alphabet.
+pub fn decode_component(component: &str) -> err::Result<Value> {
+    let component = ParseConfig::lenient().decode_base64(component)?;
+    serde_json::from_str(&component)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+/// Decodes the payload component of a token with the decoded `header` into the claim set it
+/// carries, as text. An unencoded payload (RFC 7797) is the claim set as it is; otherwise it is
+/// base64-encoded in either alphabet, and inflated if `cty` is `deflate`.
+pub fn decode_payload(header: &Value, component: &str) -> err::Result<String> {
+    let config = ParseConfig::lenient();
+    let mut payload = if header.get("b64") == Some(&Value::Bool(false)) {
+        component.as_bytes().to_vec()
+    } else {
+        config.decode_base64_bytes(component)?
+    };
+    if header.get("cty").and_then(|cty| cty.as_str()) == Some("deflate") {
+        payload = compression::inflate(&payload, config.max_decompressed_size)?;
+    }
+    String::from_utf8(payload).map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+/// Formats a NumericDate (seconds since the epoch) as an RFC 3339 UTC timestamp. Returns `None`
+/// if the value is not a non-negative number.
+pub fn format_numeric_date(value: &Value) -> Option<String> {
+    let secs = value.as_u64().or_else(|| {
+        value.as_f64().filter(|f| *f >= 0.0).map(|f| f as u64)
+    })?;
+    UNIX_EPOCH.checked_add(Duration::from_secs(secs))
+        .map(|t| humantime::format_rfc3339_seconds(t).to_string())
+}
+
+/// Pretty-prints a JSON value. Object keys come out sorted.
+pub fn pretty(value: &Value) -> String {
+    // Serializing a Value cannot fail.
+    serde_json::to_string_pretty(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code() {
+        let invalid = err::JWTError::ValidationError(err::ValidationError::Replayed);
+        let expired = err::JWTError::ValidationError(err::ValidationError::Expired);
+        assert_eq!(exit_code(&expired), EXIT_EXPIRED);
+        assert_eq!(exit_code(&invalid), EXIT_INVALID);
+        assert_eq!(exit_code(&err::JWTError::SchemaError), EXIT_PARSE_ERROR);
+        assert_eq!(exit_code(&err::JWTError::NotImplementedError), EXIT_UNSUPPORTED);
+    }
+
+    #[test]
+    fn test_parse_alg() {
+        assert_eq!(parse_alg("ES256K"), Ok(Alg::ES256K));
+        assert_eq!(parse_alg("none"), Ok(Alg::None));
+        assert!(parse_alg("es256").is_err());
+    }
+}
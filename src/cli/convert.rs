@@ -0,0 +1,172 @@
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use jwt::{err, JWT};
+use crate::cli;
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    /// The compact serialization, HEADER.PAYLOAD.SIGNATURE.
+    Compact,
+    /// The flattened JWS JSON serialization.
+    Flattened,
+    /// The general JWS JSON serialization.
+    General,
+    /// Pretty-printed header and payload JSON. Output only: re-encoding JSON cannot reproduce the
+    /// exact octets a signature was computed over.
+    Decoded,
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    /// The token to convert, in compact, flattened JSON, or general JSON form. The input form is
+    /// detected automatically. Read from stdin if omitted or `-`.
+    pub token: Option<String>,
+
+    /// The form to convert the token into.
+    #[arg(long, value_enum, default_value = "compact")]
+    pub to: Format,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Signature {
+    protected: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    header: Option<Value>,
+    #[serde(default)]
+    signature: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Flattened {
+    payload: String,
+    #[serde(flatten)]
+    signature: Signature,
+}
+
+#[derive(Serialize, Deserialize)]
+struct General {
+    payload: String,
+    signatures: Vec<Signature>,
+}
+
+// The parts of a token which every form shares. Components are kept in their encoded form
+// throughout, so that signatures remain valid.
+struct Parts {
+    payload: String,
+    signatures: Vec<Signature>,
+}
+
+fn parse_error(e: serde_json::Error) -> err::JWTError {
+    err::JWTError::ParseError(format!("{}", e))
+}
+
+fn parse(token: &str) -> err::Result<Parts> {
+    let token = token.trim();
+    if !token.starts_with('{') {
+        let components = JWT::split_into_components(token)?;
+        let mut components = components.into_iter();
+        // split_into_components guarantees exactly three components.
+        let protected = components.next().unwrap();
+        let payload = components.next().unwrap();
+        let signature = components.next().unwrap();
+        return Ok(Parts {
+            payload,
+            signatures: vec![Signature { protected, header: None, signature }],
+        })
+    }
+
+    let value: Value = serde_json::from_str(token).map_err(parse_error)?;
+    if value.get("signatures").is_some() {
+        let general: General = serde_json::from_value(value).map_err(parse_error)?;
+        Ok(Parts { payload: general.payload, signatures: general.signatures })
+    } else {
+        let flattened: Flattened = serde_json::from_value(value).map_err(parse_error)?;
+        Ok(Parts { payload: flattened.payload, signatures: vec![flattened.signature] })
+    }
+}
+
+// Returns the token's only signature. Forms other than the general JSON serialization can only
+// hold one, so converting a multi-signature token into them would drop signatures.
+fn single_signature(parts: Parts) -> err::Result<(String, Signature)> {
+    if parts.signatures.len() != 1 {
+        return Err(err::JWTError::SchemaError)
+    }
+    let mut signatures = parts.signatures;
+    Ok((parts.payload, signatures.remove(0)))
+}
+
+/// Converts `token` into the given `format`. Returns a `err::JWTError::SchemaError` if the
+/// conversion would lose information, e.g. converting a token with several signatures, or with an
+/// unprotected header, into the compact form.
+pub fn convert(token: &str, format: Format) -> err::Result<String> {
+    let parts = parse(token)?;
+    match format {
+        Format::General => {
+            let general = General { payload: parts.payload, signatures: parts.signatures };
+            serde_json::to_string_pretty(&general).map_err(parse_error)
+        },
+        Format::Flattened => {
+            let (payload, signature) = single_signature(parts)?;
+            serde_json::to_string_pretty(&Flattened { payload, signature }).map_err(parse_error)
+        },
+        Format::Compact => {
+            let (payload, signature) = single_signature(parts)?;
+            if signature.header.is_some() {
+                return Err(err::JWTError::SchemaError)
+            }
+            Ok(signature.protected + "." + &payload + "." + &signature.signature)
+        },
+        Format::Decoded => {
+            let (payload, signature) = single_signature(parts)?;
+            let header = cli::decode_component(&signature.protected)?;
+            let payload = cli::decode_component(&payload)?;
+            Ok(cli::pretty(&header) + "\n.\n" + &cli::pretty(&payload) + "\n.\n" +
+               &signature.signature)
+        },
+    }
+}
+
+pub fn run(args: &ConvertArgs) -> err::Result<i32> {
+    println!("{}", convert(&cli::read_token(&args.token)?, args.to)?);
+    Ok(cli::EXIT_OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPACT: &str = "eyJhbGciOiAibm9uZSJ9.eyJmb28iOiJiYXIifQ==.c2ln";
+
+    #[test]
+    fn test_compact_json_roundtrip() {
+        let flattened = convert(COMPACT, Format::Flattened).unwrap();
+        assert!(flattened.contains("\"signature\": \"c2ln\""));
+        assert_eq!(convert(&flattened, Format::Compact).unwrap(), COMPACT);
+
+        let general = convert(COMPACT, Format::General).unwrap();
+        assert!(general.contains("\"signatures\""));
+        assert_eq!(convert(&general, Format::Compact).unwrap(), COMPACT);
+    }
+
+    #[test]
+    fn test_decoded() {
+        let decoded = convert(COMPACT, Format::Decoded).unwrap();
+        assert_eq!(decoded, "{\n  \"alg\": \"none\"\n}\n.\n{\n  \"foo\": \"bar\"\n}\n.\nc2ln");
+    }
+
+    #[test]
+    fn test_lossy_conversions_rejected() {
+        let general = r#"{"payload": "e30=", "signatures": [
+            {"protected": "eyJhbGciOiAibm9uZSJ9", "signature": ""},
+            {"protected": "eyJhbGciOiAibm9uZSJ9", "signature": ""}
+        ]}"#;
+        assert!(matches!(convert(general, Format::Compact), Err(err::JWTError::SchemaError)));
+        assert!(convert(general, Format::General).is_ok());
+
+        let flattened = r#"{"payload": "e30=", "protected": "eyJhbGciOiAibm9uZSJ9",
+            "header": {"kid": "1"}, "signature": ""}"#;
+        assert!(matches!(convert(flattened, Format::Compact), Err(err::JWTError::SchemaError)));
+    }
+}
@@ -0,0 +1,136 @@
+use clap::Args;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use jwt::{err, JWT};
+use crate::cli;
+
+pub const UNVERIFIED_BANNER: &str =
+    "WARNING: signature NOT verified. Do not trust these claims.";
+
+#[derive(Args)]
+pub struct DecodeArgs {
+    /// The base64-encoded token to decode. Read from stdin if omitted or `-`.
+    pub token: Option<String>,
+
+    #[command(flatten)]
+    pub output: cli::OutputArgs,
+}
+
+#[derive(Serialize)]
+/// The decoded contents of a token. `verified` is always false, and is included so that tooling
+/// consuming the JSON or YAML output cannot mistake a decode for a verification.
+pub struct Report {
+    pub verified: bool,
+    pub header: Value,
+    pub claims: Value,
+    pub timestamps: Map<String, Value>,
+}
+
+pub fn run(args: &DecodeArgs) -> err::Result<i32> {
+    let report = report(&cli::read_token(&args.token)?)?;
+    args.output.print(&report, render_table)?;
+    Ok(cli::EXIT_OK)
+}
+
+/// Decodes the header and claims of `token`, along with human-readable versions of any
+/// NumericDate claims. The segments are decoded directly rather than through `JWT`, so that
+/// tokens the library would refuse, such as those with unsupported algorithms or critical
+/// extensions, can still be read.
+pub fn report(token: &str) -> err::Result<Report> {
+    let components = JWT::split_into_components(token)?;
+    let header = cli::decode_component(&components[0])?;
+    let claims = serde_json::from_str::<Value>(&cli::decode_payload(&header, &components[1])?)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+    if !claims.is_object() {
+        return Err(err::JWTError::SchemaError)
+    }
+
+    let mut timestamps = Map::new();
+    for claim_name in cli::NUMERIC_DATE_CLAIMS {
+        if let Some(claim_value) = claims.get(claim_name) {
+            let formatted = cli::format_numeric_date(claim_value)
+                .unwrap_or_else(|| String::from("not a valid NumericDate"));
+            timestamps.insert(String::from(*claim_name), Value::from(formatted));
+        }
+    }
+    Ok(Report { verified: false, header, claims, timestamps })
+}
+
+/// Renders a `Report` as human-readable text.
+pub fn render_table(report: &Report, color: bool) -> String {
+    let heading = |text: &str| cli::paint(text, cli::BOLD, color);
+
+    let mut out = cli::paint(UNVERIFIED_BANNER, cli::YELLOW, color) + "\n\n";
+    out += &(heading("Header:") + "\n" + &cli::pretty(&report.header) + "\n\n");
+    out += &(heading("Claims:") + "\n" + &cli::pretty(&report.claims) + "\n");
+
+    if !report.timestamps.is_empty() {
+        out += &(String::from("\n") + &heading("Timestamps:") + "\n");
+        for claim_name in cli::NUMERIC_DATE_CLAIMS {
+            if let Some(Value::String(formatted)) = report.timestamps.get(*claim_name) {
+                out += &format!("  {}: {}\n", claim_name, formatted);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jwt::JsonSerializable;
+
+    fn token() -> String {
+        let mut jwt = JWT::new();
+        jwt.claim_set = jwt::claims::ClaimSet::decode_str(
+            "{\"sub\": \"u1\", \"exp\": 1516239022}"
+        ).unwrap();
+        jwt.encode_b64()
+    }
+
+    #[test]
+    fn test_render_table() {
+        let out = render_table(&report(&token()).unwrap(), false);
+        assert!(out.starts_with(UNVERIFIED_BANNER));
+        assert!(out.contains("\"alg\": \"none\""));
+        assert!(out.contains("\"sub\": \"u1\""));
+        assert!(out.contains("exp: 2018-01-18T01:30:22Z"));
+
+        let out = render_table(&report(&token()).unwrap(), true);
+        assert!(out.starts_with("\u{1b}[33mWARNING"));
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = serde_json::to_value(report(&token()).unwrap()).unwrap();
+        assert_eq!(report["verified"], false);
+        assert_eq!(report["claims"]["sub"], "u1");
+        assert_eq!(report["timestamps"]["exp"], "2018-01-18T01:30:22Z");
+    }
+
+    #[test]
+    fn test_report_url_safe() {
+        // {"alg": "none"} . {"x":"??>"}, with the claims in the URL-safe alphabet.
+        let report = report("eyJhbGciOiAibm9uZSJ9.eyJ4IjoiPz8-In0.").unwrap();
+        assert_eq!(report.claims["x"], "??>");
+    }
+
+    #[test]
+    fn test_report_unsupported() {
+        // {"alg": "PS256", "crit": ["exp"], "exp": 1} . {"exp": "soon"} . sig
+        let report = report(
+            "eyJhbGciOiAiUFMyNTYiLCAiY3JpdCI6IFsiZXhwIl0sICJleHAiOiAxfQ.eyJleHAiOiAic29vbiJ9.c2ln"
+        ).unwrap();
+        assert_eq!(report.header["alg"], "PS256");
+        assert_eq!(report.claims["exp"], "soon");
+        assert_eq!(report.timestamps["exp"], "not a valid NumericDate");
+    }
+
+    #[test]
+    fn test_report_invalid() {
+        assert!(report("not-a-token").is_err());
+        // {"alg": "none"} . [1]
+        assert!(report("eyJhbGciOiAibm9uZSJ9.WzFd.").is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value;
+
+use jwt::claims::ClaimSet;
+use jwt::explain;
+use jwt::{err, JsonSerializable, ParseConfig, JWT};
+use crate::cli;
+
+#[derive(Args)]
+pub struct InspectArgs {
+    /// The base64-encoded token to inspect. Read from stdin if omitted or `-`.
+    pub token: Option<String>,
+
+    #[command(flatten)]
+    pub output: cli::OutputArgs,
+}
+
+#[derive(Serialize)]
+pub struct ClaimReport {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub claim_type: String,
+    pub value: Value,
+}
+
+#[derive(Serialize)]
+/// The result of inspecting a token. Claims are sorted by name.
+pub struct Report {
+    pub claims: Vec<ClaimReport>,
+    pub header_findings: Vec<String>,
+    pub validity: Vec<String>,
+    pub expired: bool,
+}
+
+/// Lints a decoded JOSE header, returning a description of each suspicious parameter found.
+pub fn header_findings(header: &Value) -> Vec<String> {
+    let mut findings = Vec::<String>::new();
+    if header.get("alg").and_then(|alg| alg.as_str()).is_none() {
+        findings.push(String::from("header has no (string) alg parameter"));
+    }
+    if let Some(header) = header.as_object() {
+        findings.extend(
+            explain::header_parameters(header).into_iter().filter_map(|param| param.warning)
+        );
+    }
+    findings
+}
+
+// Describes how far away from `now` the NumericDate `date` is.
+fn relative(date: u64, now: u64) -> String {
+    if date >= now {
+        format!("in {}", humantime::format_duration(Duration::from_secs(date - now)))
+    } else {
+        format!("{} ago", humantime::format_duration(Duration::from_secs(now - date)))
+    }
+}
+
+/// Describes the validity window of a claim set relative to `now` (seconds since the epoch).
+pub fn time_findings(claim_set: &ClaimSet, now: u64) -> Vec<String> {
+    let mut findings = Vec::<String>::new();
+    match claim_set.get("exp").map(|claim| claim.claim_value.as_u64()) {
+        Err(_) => findings.push(String::from("no exp claim: the token never expires")),
+        Ok(None) => findings.push(String::from("exp is not a valid NumericDate")),
+        Ok(Some(exp)) if exp <= now => findings.push(format!("expired {}", relative(exp, now))),
+        Ok(Some(exp)) => findings.push(format!("expires {}", relative(exp, now))),
+    }
+    if let Ok(Some(nbf)) = claim_set.get("nbf").map(|claim| claim.claim_value.as_u64()) {
+        if nbf > now {
+            findings.push(format!("not valid yet: becomes valid {}", relative(nbf, now)));
+        }
+    }
+    if let Ok(Some(iat)) = claim_set.get("iat").map(|claim| claim.claim_value.as_u64()) {
+        if iat > now {
+            findings.push(format!("issued in the future ({})", relative(iat, now)));
+        }
+    }
+    findings
+}
+
+/// Returns whether the claim set carries an `exp` claim at or before `now`.
+pub fn is_expired(claim_set: &ClaimSet, now: u64) -> bool {
+    match claim_set.get("exp").map(|claim| claim.claim_value.as_u64()) {
+        Ok(Some(exp)) => exp <= now,
+        _ => false,
+    }
+}
+
+/// Inspects `token`, evaluating times against `now`.
+pub fn report(token: &str, now: u64) -> err::Result<Report> {
+    let components = JWT::split_into_components(token)?;
+    let header = cli::decode_component(&components[0])?;
+    let payload = cli::decode_payload(&header, &components[1])?;
+    let claim_set = ClaimSet::decode_str_with(&payload, &ParseConfig::lenient())?;
+
+    let mut claims = claim_set.claims.iter()
+        .map(|(claim_name, claim)| ClaimReport {
+            name: claim_name.clone(),
+            claim_type: claim.claim_type.to_string(),
+            value: claim.claim_value.clone(),
+        })
+        .collect::<Vec<ClaimReport>>();
+    claims.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(Report {
+        claims,
+        header_findings: header_findings(&header),
+        validity: time_findings(&claim_set, now),
+        expired: is_expired(&claim_set, now),
+    })
+}
+
+/// Renders a `Report` as human-readable text.
+pub fn render_table(report: &Report, color: bool) -> String {
+    let width = report.claims.iter().map(|claim| claim.name.len()).max().unwrap_or(0);
+
+    let mut out = cli::paint("Claims:", cli::BOLD, color) + "\n";
+    for claim in &report.claims {
+        out += &format!(
+            "  {:width$}  {:10}  {}\n", claim.name, claim.claim_type, claim.value, width = width
+        );
+    }
+
+    out += &(String::from("\n") + &cli::paint("Header findings:", cli::BOLD, color) + "\n");
+    if report.header_findings.is_empty() {
+        out += "  none\n";
+    }
+    for finding in &report.header_findings {
+        out += &format!("  - {}\n", cli::paint(finding, cli::YELLOW, color));
+    }
+
+    out += &(String::from("\n") + &cli::paint("Validity:", cli::BOLD, color) + "\n");
+    let validity_color = if report.expired { cli::RED } else { cli::YELLOW };
+    for (i, finding) in report.validity.iter().enumerate() {
+        // The first finding is always about exp, and only it is colored by expiry.
+        let code = if i == 0 { validity_color } else { cli::YELLOW };
+        out += &format!("  - {}\n", cli::paint(finding, code, color));
+    }
+    out
+}
+
+pub fn run(args: &InspectArgs) -> err::Result<i32> {
+    let report = report(&cli::read_token(&args.token)?, cli::now())?;
+    args.output.print(&report, render_table)?;
+    Ok(if report.expired { cli::EXIT_EXPIRED } else { cli::EXIT_OK })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_header_findings() {
+        assert!(header_findings(&json!({"alg": "HS256"})).is_empty());
+        assert_eq!(header_findings(&json!({"alg": "nOnE"})).len(), 1);
+        assert_eq!(header_findings(&json!({"alg": "HS256", "jwk": {}, "crit": ["exp"]})).len(), 2);
+        assert_eq!(header_findings(&json!({})).len(), 1);
+    }
+
+    #[test]
+    fn test_time_findings() {
+        let cs = ClaimSet::decode_str("{\"exp\": 100, \"nbf\": 50}").unwrap();
+        assert_eq!(
+            time_findings(&cs, 40), vec!["expires in 1m", "not valid yet: becomes valid in 10s"]
+        );
+        assert_eq!(time_findings(&cs, 160), vec!["expired 1m ago"]);
+        assert_eq!(
+            time_findings(&ClaimSet::new(), 0), vec!["no exp claim: the token never expires"]
+        );
+    }
+
+    #[test]
+    fn test_is_expired() {
+        let cs = ClaimSet::decode_str("{\"exp\": 100}").unwrap();
+        assert!(!is_expired(&cs, 99));
+        assert!(is_expired(&cs, 100));
+        assert!(!is_expired(&ClaimSet::new(), 100));
+    }
+
+    #[test]
+    fn test_report_compressed() {
+        let mut jwt = JWT::new();
+        jwt.header.cty = jwt::header::Cty::Deflate;
+        jwt.claim_set = ClaimSet::decode_str("{\"exp\": 100}").unwrap();
+        assert!(report(&jwt.encode_b64(), 200).unwrap().expired);
+    }
+
+    #[test]
+    fn test_render() {
+        let mut jwt = JWT::new();
+        jwt.claim_set = ClaimSet::decode_str(
+            "{\"iss\": \"me\", \"foo\": 1, \"https://example.com/role\": \"admin\"}"
+        ).unwrap();
+        let report = report(&jwt.encode_b64(), 0).unwrap();
+        assert!(!report.expired);
+        let out = render_table(&report, false);
+        assert!(out.contains("  iss                       registered  \"me\"\n"));
+        assert!(out.contains("  foo                       private     1\n"));
+        assert!(out.contains("  https://example.com/role  public      \"admin\"\n"));
+        assert!(out.contains("the token is unsecured"));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use clap::Args;
+
+use jwt::header::Alg;
+use jwt::jwk::JwkSet;
+use jwt::keys::PrivateKey;
+use jwt::{err, keygen};
+use crate::cli;
+
+#[derive(Args)]
+pub struct KeygenArgs {
+    /// The algorithm the key is to sign with, such as RS256, ES256 or EdDSA. RSA keys are 2048
+    /// bits long.
+    #[arg(long, value_parser = cli::parse_alg)]
+    pub alg: Alg,
+
+    /// The file to write the private key to, as PKCS#8 PEM which only its owner may read. Written
+    /// to stdout if omitted.
+    #[arg(long)]
+    pub out_priv: Option<PathBuf>,
+
+    /// The file to write the public key to, as SubjectPublicKeyInfo PEM.
+    #[arg(long)]
+    pub out_pub: Option<PathBuf>,
+
+    /// Print the public key as a JWK too, with its RFC 7638 thumbprint as its kid.
+    #[arg(long)]
+    pub jwk: bool,
+}
+
+/// Returns the JWK of the public half of `key`, pretty-printed, as `jwt::jwk::JwkSet::from_keys`
+/// writes it.
+pub fn public_jwk(key: &PrivateKey) -> String {
+    let jwk = &JwkSet::from_keys(&[key]).keys[0];
+    // A serialized JWK is always valid JSON.
+    cli::pretty(&serde_json::from_str(&jwk.to_json()).unwrap())
+}
+
+// Writes `contents` to the file at `path`. If the file is created, and `private` is set, only its
+// owner may read it.
+fn write(path: &Path, contents: &str, private: bool) -> err::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if private {
+            options.mode(0o600);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options.open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|e| { err::JWTError::IoError(format!("{}: {}", path.display(), e)) })
+}
+
+pub fn run(args: &KeygenArgs) -> err::Result<i32> {
+    let pem = keygen::generate_pem(args.alg)?;
+    let key = PrivateKey::from_pem(&pem)?;
+    match &args.out_priv {
+        Some(path) => write(path, &pem, true)?,
+        None => print!("{}", pem),
+    }
+    if let Some(path) = &args.out_pub {
+        write(path, &key.public_key().to_pem(), false)?;
+    }
+    if args.jwk {
+        println!("{}", public_jwk(&key));
+    }
+    Ok(cli::EXIT_OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn test_public_jwk() {
+        let key = PrivateKey::from_pem(include_str!("../testdata/ec_p256_private_pkcs8.pem"))
+            .unwrap();
+        let jwk: Value = serde_json::from_str(&public_jwk(&key)).unwrap();
+        assert_eq!(jwk["kty"], "EC");
+        assert_eq!(jwk["crv"], "P-256");
+        assert_eq!(jwk["alg"], "ES256");
+        assert!(jwk.get("d").is_none());
+        assert_eq!(jwk["kid"].as_str().map(str::len), Some(43));
+    }
+}
@@ -0,0 +1,148 @@
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::Duration;
+use clap::Args;
+use serde_json::Value;
+
+use jwt::claims::{Claim, ClaimSet};
+use jwt::header::Alg;
+use jwt::keys::PrivateKey;
+use jwt::{err, JsonSerializable, JWT};
+use crate::cli;
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct SigningKeyArgs {
+    /// A file holding the private key to sign with, as PEM.
+    #[arg(long)]
+    pub key: Option<PathBuf>,
+
+    /// The HMAC secret to sign with, as text.
+    #[arg(long)]
+    pub hmac_secret: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SignArgs {
+    /// A file holding the claims to sign, as a JSON object. Read from stdin if omitted or `-`.
+    #[arg(long)]
+    pub claims: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub key: SigningKeyArgs,
+
+    /// The algorithm to sign with. Defaults to the one the key is for: RS256 for RSA keys, the
+    /// ES* algorithm of an elliptic curve key's curve, EdDSA for Ed25519 keys, and HS256 for HMAC
+    /// secrets.
+    #[arg(long, value_parser = cli::parse_alg)]
+    pub alg: Option<Alg>,
+
+    /// How long the token is valid for, such as `15m`. Sets the exp claim, replacing any the
+    /// claims have.
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub exp: Option<Duration>,
+
+    /// The ID of the key, for the kid header parameter.
+    #[arg(long)]
+    pub kid: Option<String>,
+}
+
+// Reads the claims from the file at `path` or, if it was omitted or given as `-`, from stdin.
+fn read_claims(path: &Option<PathBuf>) -> err::Result<String> {
+    let mut claims = String::new();
+    match path {
+        Some(path) if path.as_os_str() != "-" => std::fs::File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut claims))
+            .map_err(|e| { err::JWTError::IoError(format!("{}: {}", path.display(), e)) })?,
+        _ => std::io::stdin().read_to_string(&mut claims)
+            .map_err(|e| { err::JWTError::IoError(format!("{}", e)) })?,
+    };
+    Ok(claims)
+}
+
+/// Signs `claims` as the arguments ask, with an `exp` relative to `now`, and returns the token in
+/// compact serialization.
+pub fn sign(args: &SignArgs, claims: &str, now: u64) -> err::Result<String> {
+    let mut jwt = JWT::new();
+    jwt.claim_set = ClaimSet::decode_str(claims)?;
+    if let Some(exp) = args.exp {
+        jwt.claim_set.claims.remove("exp");
+        jwt.claim_set.insert(Claim::parse(String::from("exp"), Value::from(now + exp.as_secs()))?)?;
+    }
+    jwt.header.kid = args.kid.clone();
+    match args.alg {
+        // An unsecured token has no signature to sign it with.
+        Some(Alg::None) => return Err(err::JWTError::NotImplementedError),
+        // JWT::sign_key infers the algorithm from the key if the header names none.
+        alg => jwt.header.alg = alg.unwrap_or(Alg::None),
+    }
+    match (&args.key.key, &args.key.hmac_secret) {
+        (Some(path), _) => {
+            let pem = std::fs::read_to_string(path)
+                .map_err(|e| { err::JWTError::IoError(format!("{}: {}", path.display(), e)) })?;
+            jwt.sign_key(&PrivateKey::from_pem(&pem)?)
+        }
+        (None, Some(secret)) => {
+            jwt.header.alg = args.alg.unwrap_or(Alg::HS256);
+            jwt.sign(secret.as_bytes())
+        }
+        // clap requires one of the arguments.
+        (None, None) => unreachable!(),
+    }
+}
+
+pub fn run(args: &SignArgs) -> err::Result<i32> {
+    println!("{}", sign(args, &read_claims(&args.claims)?, cli::now())?);
+    Ok(cli::EXIT_OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jwt::keys::EcPrivateKey;
+    use jwt::verify::VerifyOptions;
+
+    fn args(key: Option<&str>, hmac_secret: Option<&str>) -> SignArgs {
+        SignArgs {
+            claims: None,
+            key: SigningKeyArgs {
+                key: key.map(PathBuf::from),
+                hmac_secret: hmac_secret.map(String::from),
+            },
+            alg: None,
+            exp: Some(Duration::from_secs(900)),
+            kid: Some(String::from("mykey")),
+        }
+    }
+
+    #[test]
+    fn test_sign_key() {
+        let path = "src/testdata/ec_p256_private_pkcs8.pem";
+        let token = sign(&args(Some(path), None), "{\"sub\": \"u1\", \"exp\": 1}", 100).unwrap();
+        let public = EcPrivateKey::from_pem(&std::fs::read_to_string(path).unwrap()).unwrap()
+            .public_key();
+        let jwt = JWT::decode_unverified(&token).unwrap()
+            .verify(&public, &VerifyOptions::new(&[Alg::ES256])).unwrap();
+        assert_eq!(jwt.header.kid.as_deref(), Some("mykey"));
+        assert_eq!(jwt.claim_set.get("exp").unwrap().claim_value, 1000);
+        assert_eq!(jwt.claim_set.get("sub").unwrap().claim_value, "u1");
+
+        let mut mismatched = args(Some(path), None);
+        mismatched.alg = Some(Alg::RS256);
+        assert!(matches!(sign(&mismatched, "{}", 0), Err(err::JWTError::NotImplementedError)));
+    }
+
+    #[test]
+    fn test_sign_hmac() {
+        let secret = "k".repeat(48);
+        let mut hs384 = args(None, Some(&secret));
+        hs384.alg = Some(Alg::HS384);
+        let token = sign(&hs384, "{}", 0).unwrap();
+        let options = VerifyOptions::new(&[Alg::HS384]);
+        let jwt = JWT::decode_unverified(&token).unwrap();
+        assert!(jwt.verify(secret.as_bytes(), &options).is_ok());
+
+        hs384.alg = Some(Alg::None);
+        assert!(matches!(sign(&hs384, "{}", 0), Err(err::JWTError::NotImplementedError)));
+    }
+}
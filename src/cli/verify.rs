@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use clap::Args;
+use serde::Serialize;
+
+use jwt::claims::Validator;
+use jwt::err::{self, ValidationError};
+use jwt::header::Alg;
+use jwt::jwk::{Jwk, JwkKey, JwkSet};
+use jwt::keys::{PublicKey, VerificationKey};
+use jwt::verify::{VerifiedJWT, VerifyOptions};
+use jwt::JWT;
+use crate::cli;
+
+// The algorithms tokens are accepted under. Each key only verifies those it suits, so a token
+// cannot be verified under a public key as if it were an HMAC secret.
+const ALGORITHMS: &[Alg] = &[
+    Alg::HS256, Alg::HS384, Alg::HS512, Alg::RS256, Alg::RS384, Alg::RS512, Alg::ES256,
+    Alg::ES384, Alg::ES512, Alg::ES256K, Alg::EdDSA,
+];
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct KeyArgs {
+    /// The HMAC secret the token was signed with, as text.
+    #[arg(long)]
+    pub hmac_secret: Option<String>,
+
+    /// A file holding the public key to verify the token with, as PEM.
+    #[arg(long)]
+    pub pem: Option<PathBuf>,
+
+    /// A file holding the JWK, or JWK Set, to verify the token with. The key of a set is chosen
+    /// by the token's kid.
+    #[arg(long)]
+    pub jwk: Option<PathBuf>,
+
+    /// The URL of the JWK Set to verify the token with, such as an issuer's jwks_uri.
+    #[arg(long)]
+    pub jwks_url: Option<String>,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// The base64-encoded token to verify. Read from stdin if omitted or `-`.
+    pub token: Option<String>,
+
+    #[command(flatten)]
+    pub key: KeyArgs,
+
+    /// The audience the token's aud claim must name.
+    #[arg(long)]
+    pub aud: Option<String>,
+
+    /// The issuer the token's iss claim must be.
+    #[arg(long)]
+    pub iss: Option<String>,
+
+    /// How far the issuer's clock may drift from this one, such as `30s`.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "0s")]
+    pub leeway: Duration,
+
+    #[command(flatten)]
+    pub output: cli::OutputArgs,
+}
+
+// The keys to verify a token with: a single key, whatever the token's kid, or a JWK Set.
+enum Keys {
+    One(JwkKey),
+    Set(JwkSet),
+}
+
+#[derive(Serialize)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    /// Why the check failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+}
+
+#[derive(Serialize)]
+/// The result of verifying a token. The claims are only checked if the signature is valid.
+pub struct Report {
+    pub valid: bool,
+    pub checks: Vec<Check>,
+    #[serde(skip)]
+    pub exit_code: i32,
+}
+
+// Reads the file at `path`.
+fn read(path: &Path) -> err::Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| { err::JWTError::IoError(format!("{}: {}", path.display(), e)) })
+}
+
+// Loads the keys the arguments name.
+fn keys(args: &KeyArgs) -> err::Result<Keys> {
+    if let Some(secret) = &args.hmac_secret {
+        Ok(Keys::One(JwkKey::Oct(secret.as_bytes().to_vec())))
+    } else if let Some(path) = &args.pem {
+        PublicKey::from_pem(&read(path)?).map(|key| Keys::One(JwkKey::from(key)))
+    } else if let Some(path) = &args.jwk {
+        let json = read(path)?;
+        JwkSet::from_json(&json).map(Keys::Set)
+            .or_else(|_| Jwk::from_json(&json).map(|jwk| Keys::One(jwk.key)))
+    } else if let Some(url) = &args.jwks_url {
+        let json = ureq::get(url)
+            .set("Accept", "application/json")
+            .call()
+            .map_err(|e| { err::JWTError::HttpError(format!("{}", e)) })?
+            .into_string()
+            .map_err(|e| { err::JWTError::HttpError(format!("{}", e)) })?;
+        JwkSet::from_json(&json).map(Keys::Set)
+    } else {
+        // clap requires one of the arguments.
+        unreachable!()
+    }
+}
+
+// Names the claim a validation failure is about.
+fn claim_name(failure: &ValidationError) -> &str {
+    match failure {
+        ValidationError::MissingClaim(claim_name) |
+        ValidationError::InvalidClaim(claim_name) |
+        ValidationError::InvalidHeader(claim_name) => claim_name,
+        ValidationError::Expired => "exp",
+        ValidationError::NotYetValid => "nbf",
+        ValidationError::TooOld => "iat",
+        ValidationError::Replayed => "jti",
+        ValidationError::TooLarge => "size",
+    }
+}
+
+/// Checks the claims of the verified token `jwt` against `validator`, reporting a check for each
+/// time-based claim and each claim `validator` requires a value of, and any other which failed.
+pub fn claim_checks(jwt: &VerifiedJWT, validator: &Validator) -> Vec<Check> {
+    let mut names = vec!["exp", "nbf", "iat"];
+    if !validator.issuers.is_empty() {
+        names.push("iss");
+    }
+    if !validator.audiences.is_empty() {
+        names.push("aud");
+    }
+    let failures = validator.validate_all(&jwt.claim_set).failures;
+    for failure in &failures {
+        if !names.contains(&claim_name(failure)) {
+            names.push(claim_name(failure));
+        }
+    }
+    names.iter().map(|name| {
+        let failure = failures.iter().find(|failure| claim_name(failure) == *name);
+        Check {
+            name: String::from(*name),
+            passed: failure.is_none(),
+            failure: failure.map(|failure| failure.to_string()),
+        }
+    }).collect()
+}
+
+/// Verifies `token` under `keys`, and then checks its claims against `validator`.
+fn report(token: &str, keys: &Keys, validator: &Validator) -> err::Result<Report> {
+    let unverified = JWT::decode_unverified(token)?;
+    let options = VerifyOptions::new(ALGORITHMS);
+    let verified = match keys {
+        Keys::One(key) => unverified.verify(VerificationKey::from(key), &options),
+        Keys::Set(keys) => unverified.verify_with_keys(keys, &options),
+    };
+    let signature = Check {
+        name: String::from("signature"),
+        passed: verified.is_ok(),
+        failure: verified.as_ref().err().map(|e| e.to_string()),
+    };
+    let (checks, result) = match verified {
+        Ok(jwt) => {
+            let checks = std::iter::once(signature).chain(claim_checks(&jwt, validator)).collect();
+            (checks, validator.validate(&jwt.claim_set).map_err(err::JWTError::ValidationError))
+        }
+        Err(e) => (vec![signature], Err(e)),
+    };
+    let exit_code = result.map_or_else(|e| cli::exit_code(&e), |_| cli::EXIT_OK);
+    Ok(Report { valid: exit_code == cli::EXIT_OK, checks, exit_code })
+}
+
+/// Renders a `Report` as human-readable text.
+pub fn render_table(report: &Report, color: bool) -> String {
+    let width = report.checks.iter().map(|check| check.name.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for check in &report.checks {
+        let result = match &check.failure {
+            None => String::from("ok"),
+            Some(failure) => cli::paint(&format!("FAILED: {}", failure), cli::RED, color),
+        };
+        out += &format!("  {:width$}  {}\n", check.name, result, width = width);
+    }
+    let verdict = if report.valid { "The token is valid." } else { "The token is NOT valid." };
+    out + "\n" + &cli::paint(verdict, cli::BOLD, color) + "\n"
+}
+
+pub fn run(args: &VerifyArgs) -> err::Result<i32> {
+    let token = cli::read_token(&args.token)?;
+    let mut validator = Validator::new().leeway(args.leeway);
+    if let Some(iss) = &args.iss {
+        validator = validator.issuer(iss);
+    }
+    if let Some(aud) = &args.aud {
+        validator = validator.audience(aud);
+    }
+    let report = report(&token, &keys(&args.key)?, &validator)?;
+    args.output.print(&report, render_table)?;
+    Ok(report.exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jwt::claims::ClaimSet;
+    use jwt::JsonSerializable;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    fn token(claims: &str) -> String {
+        let mut jwt = JWT::new();
+        jwt.header.alg = Alg::HS256;
+        jwt.header.kid = Some(String::from("k1"));
+        jwt.claim_set = ClaimSet::decode_str(claims).unwrap();
+        jwt.sign(&KEY).unwrap()
+    }
+
+    fn names(report: &Report) -> Vec<(&str, bool)> {
+        report.checks.iter().map(|check| (check.name.as_str(), check.passed)).collect()
+    }
+
+    #[test]
+    fn test_report() {
+        let keys = Keys::One(JwkKey::Oct(KEY.to_vec()));
+        let validator = Validator::new().issuer("https://idp").audience("api");
+        let valid = token("{\"iss\": \"https://idp\", \"aud\": \"api\"}");
+        let valid = report(&valid, &keys, &validator).unwrap();
+        assert!(valid.valid);
+        assert_eq!(valid.exit_code, cli::EXIT_OK);
+        assert_eq!(
+            names(&valid),
+            [("signature", true), ("exp", true), ("nbf", true), ("iat", true), ("iss", true),
+             ("aud", true)]
+        );
+
+        let invalid = report(&token("{\"exp\": 1, \"aud\": \"web\"}"), &keys, &validator).unwrap();
+        assert!(!invalid.valid);
+        assert_eq!(invalid.exit_code, cli::EXIT_EXPIRED);
+        assert_eq!(
+            names(&invalid),
+            [("signature", true), ("exp", false), ("nbf", true), ("iat", true), ("iss", false),
+             ("aud", false)]
+        );
+        let table = render_table(&invalid, false);
+        assert!(table.contains("  exp        FAILED: the token has expired.\n"));
+        assert!(table.ends_with("The token is NOT valid.\n"));
+    }
+
+    #[test]
+    fn test_report_bad_signature() {
+        let token = token("{}");
+        let keys = Keys::One(JwkKey::Oct(vec![8; 32]));
+        let forged = report(&token, &keys, &Validator::new()).unwrap();
+        assert_eq!(names(&forged), [("signature", false)]);
+        assert_eq!(forged.exit_code, cli::EXIT_BAD_SIGNATURE);
+
+        // The key of a set is chosen by the token's kid.
+        let keys = JwkSet::from_json(
+            "{\"keys\": [{\"kty\": \"oct\", \"kid\": \"k2\",
+                         \"k\": \"BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc\"}]}"
+        ).unwrap();
+        let unknown = report(&token, &Keys::Set(keys), &Validator::new()).unwrap();
+        assert_eq!(
+            unknown.checks[0].failure.as_deref(), Some("No verification key has the key ID \"k1\".")
+        );
+    }
+}
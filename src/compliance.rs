@@ -0,0 +1,254 @@
+//! The test vectors of the JOSE specifications, and a suite which checks a crypto backend against
+//! them, so that a backend embedded in place of this crate's own can be shown to sign and verify
+//! as the specifications require. Requires the `compliance` feature.
+//!
+//! The vectors are those of RFC 7515 appendix A, which RFC 7518 and RFC 7519 refer to for the
+//! algorithms they share, and of RFC 8037 appendix A.4 for `EdDSA`.
+
+use std::fmt;
+
+use crate::crypto::{self, b64url};
+use crate::err;
+use crate::header::Alg;
+use crate::keys::{PrivateKey, PublicKey, VerificationKey};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The key of a `Vector`.
+pub enum VectorKey {
+    /// An HMAC secret, as base64url.
+    Secret(&'static str),
+    /// A private key, as PKCS#8 (`PRIVATE KEY`) PEM. Signatures are verified under its public
+    /// half.
+    PrivateKeyPem(&'static str),
+}
+
+impl VectorKey {
+    /// Returns the bytes of an HMAC secret, or `None` if the key is not one.
+    pub fn secret(&self) -> Option<Vec<u8>> {
+        match self {
+            // The secrets below are all valid base64url.
+            VectorKey::Secret(secret) => {
+                Some(base64::decode_config(secret, base64::URL_SAFE_NO_PAD).unwrap())
+            }
+            VectorKey::PrivateKeyPem(_) => None,
+        }
+    }
+
+    /// Returns the PEM of a private key, or `None` if the key is an HMAC secret.
+    pub fn pem(&self) -> Option<&'static str> {
+        match self {
+            VectorKey::Secret(_) => None,
+            VectorKey::PrivateKeyPem(pem) => Some(pem),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A signature from a specification: the signature `signature` of `signing_input` under `key`
+/// with `alg`.
+pub struct Vector {
+    /// Where in which specification the vector comes from, such as `"RFC 7515 A.1"`.
+    pub name: &'static str,
+    pub alg: Alg,
+    pub key: VectorKey,
+    pub signing_input: &'static str,
+    /// The signature, as base64url.
+    pub signature: &'static str,
+    /// Whether signing is deterministic, so that a backend must reproduce `signature` rather than
+    /// only accept it. ECDSA signatures are randomized.
+    pub deterministic: bool,
+}
+
+/// RFC 7515 appendix A.1, an `HS256` JWS, which is also the example JWT of RFC 7519 section 3.1.
+pub const RFC7515_A1: Vector = Vector {
+    name: "RFC 7515 A.1",
+    alg: Alg::HS256,
+    key: VectorKey::Secret(
+        "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9CAow",
+    ),
+    signing_input:
+        "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQo\
+         gImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ",
+    signature: "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk",
+    deterministic: true,
+};
+
+/// RFC 7515 appendix A.2, an `RS256` JWS.
+pub const RFC7515_A2: Vector = Vector {
+    name: "RFC 7515 A.2",
+    alg: Alg::RS256,
+    key: VectorKey::PrivateKeyPem(include_str!("testdata/rsa_private_pkcs8.pem")),
+    signing_input:
+        "eyJhbGciOiJSUzI1NiJ9.eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGx\
+         lLmNvbS9pc19yb290Ijp0cnVlfQ",
+    signature:
+        "cC4hiUPoj9Eetdgtv3hF80EGrhuB__dzERat0XF9g2VtQgr9PJbu3XOiZj5RZmh7AAuHIm4Bh-0Qc_lF5YKt_O8W\
+         2Fp5jujGbds9uJdbF9CUAr7t1dnZcAcQjbKBYNX4BAynRFdiuB--f_nZLgrnbyTyWzO75vRK5h6xBArLIARNPvkS\
+         jtQBMHlb1L07Qe7K0GarZRmB_eSN9383LcOLn6_dO--xi12jzDwusC-eOkHWEsqtFZESc6BfI7noOPqvhJ1phCnv\
+         Wh6IeYI2w9QOYEUipUTI8np6LbgGY9Fs98rqVt5AXLIhWkWywlVmtVrBp0igcN_IoypGlUPQGe77Rw",
+    deterministic: true,
+};
+
+/// RFC 7515 appendix A.3, an `ES256` JWS.
+pub const RFC7515_A3: Vector = Vector {
+    name: "RFC 7515 A.3",
+    alg: Alg::ES256,
+    key: VectorKey::PrivateKeyPem(include_str!("testdata/ec_p256_private_pkcs8.pem")),
+    signing_input:
+        "eyJhbGciOiJFUzI1NiJ9.eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGx\
+         lLmNvbS9pc19yb290Ijp0cnVlfQ",
+    signature:
+        "DtEhU3ljbEg8L38VWAfUAqOyKAM6-Xx-F4GawxaepmXFCgfTjDxw5djxLa8ISlSApmWQxfKTUJqPP3-Kg6NU1Q",
+    deterministic: false,
+};
+
+/// RFC 8037 appendix A.4, an `EdDSA` JWS.
+pub const RFC8037_A4: Vector = Vector {
+    name: "RFC 8037 A.4",
+    alg: Alg::EdDSA,
+    key: VectorKey::PrivateKeyPem(include_str!("testdata/ed25519_private.pem")),
+    signing_input: "eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc",
+    signature:
+        "hgyY0il_MGCjP0JzlnLWG1PPOt7-09PGcvMg3AIbQR6dWbhijcNR4ki4iylGjg5BhVsPt9g7sVvpAr_MuM0KAg",
+    deterministic: true,
+};
+
+/// Every vector, in the order `run_compliance_suite` checks them.
+pub const VECTORS: &[Vector] = &[RFC7515_A1, RFC7515_A2, RFC7515_A3, RFC8037_A4];
+
+/// The operations of a crypto backend which `run_compliance_suite` checks.
+pub trait Backend {
+    /// Signs `signing_input` under `key` with `alg`. Returns a `err::JWTError::NotImplementedError`
+    /// for algorithms the backend does not support, whose vectors are then skipped.
+    fn sign(&self, alg: Alg, key: &VectorKey, signing_input: &[u8]) -> err::Result<Vec<u8>>;
+
+    /// Returns whether `signature` is the signature of `signing_input` with `alg` under `key` or,
+    /// for a private key, under its public half.
+    fn verify(&self, alg: Alg, key: &VectorKey, signing_input: &[u8], signature: &[u8]) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// The backend this crate was built with, which passes the suite.
+pub struct BuiltinBackend;
+
+impl Backend for BuiltinBackend {
+    fn sign(&self, alg: Alg, key: &VectorKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        match key {
+            VectorKey::Secret(_) => crypto::hmac(alg, &key.secret().unwrap(), signing_input),
+            VectorKey::PrivateKeyPem(pem) => match PrivateKey::from_pem(pem)? {
+                PrivateKey::Rsa(key) => crypto::rsa_sign(alg, &key, signing_input),
+                PrivateKey::Ec(key) => crypto::ec_sign(alg, &key, signing_input),
+                PrivateKey::Ed25519(key) => crypto::eddsa_sign(alg, &key, signing_input),
+            },
+        }
+    }
+
+    fn verify(&self, alg: Alg, key: &VectorKey, signing_input: &[u8], signature: &[u8]) -> bool {
+        let public = match key {
+            VectorKey::Secret(_) => {
+                let secret = key.secret().unwrap();
+                let key = VerificationKey::Hmac(&secret);
+                return crypto::verify(alg, key, signing_input, signature).is_ok()
+            }
+            VectorKey::PrivateKeyPem(pem) => match PrivateKey::from_pem(pem) {
+                Ok(key) => key.public_key(),
+                Err(_) => return false,
+            },
+        };
+        let key = match &public {
+            PublicKey::Rsa(key) => VerificationKey::Rsa(key),
+            PublicKey::Ec(key) => VerificationKey::Ec(key),
+            PublicKey::Ed25519(key) => VerificationKey::Ed25519(key),
+        };
+        crypto::verify(alg, key, signing_input, signature).is_ok()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A check of `run_compliance_suite` which a backend failed.
+pub struct Failure {
+    /// The name of the vector checked.
+    pub vector: &'static str,
+    pub reason: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.vector, self.reason)
+    }
+}
+
+// Checks `backend` against `vector`, returning the reason it fails if it does.
+fn check(backend: &dyn Backend, vector: &Vector) -> Result<(), String> {
+    let input = vector.signing_input.as_bytes();
+    // The vectors' signatures are all valid base64url.
+    let expected = base64::decode_config(vector.signature, base64::URL_SAFE_NO_PAD).unwrap();
+    let signature = match backend.sign(vector.alg, &vector.key, input) {
+        Ok(signature) => signature,
+        Err(err::JWTError::NotImplementedError) => return Ok(()),
+        Err(e) => return Err(format!("signing failed: {}", e)),
+    };
+    if vector.deterministic && signature != expected {
+        return Err(format!("signed as {}, not {}", b64url(&signature), vector.signature))
+    }
+    if !backend.verify(vector.alg, &vector.key, input, &expected) {
+        return Err(String::from("rejected the specification's signature"))
+    }
+    if !backend.verify(vector.alg, &vector.key, input, &signature) {
+        return Err(String::from("rejected its own signature"))
+    }
+    let mut tampered = input.to_vec();
+    tampered[0] ^= 1;
+    if backend.verify(vector.alg, &vector.key, &tampered, &expected) {
+        return Err(String::from("accepted the signature of a tampered signing input"))
+    }
+    Ok(())
+}
+
+/// Checks `backend` against every vector in `VECTORS`: it must reproduce each deterministic
+/// signature, accept each signature and its own, and reject each signature of a tampered signing
+/// input. Vectors of algorithms the backend does not support are skipped. Returns every check the
+/// backend failed, if it failed any.
+///
+/// # Examples
+/// ```
+/// use jwt::compliance::{run_compliance_suite, BuiltinBackend};
+///
+/// assert_eq!(run_compliance_suite(&BuiltinBackend), Ok(()));
+/// ```
+pub fn run_compliance_suite(backend: &dyn Backend) -> Result<(), Vec<Failure>> {
+    let failures: Vec<Failure> = VECTORS.iter()
+        .filter_map(|vector| check(backend, vector).err()
+            .map(|reason| Failure { vector: vector.name, reason }))
+        .collect();
+    if failures.is_empty() { Ok(()) } else { Err(failures) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A backend which signs correctly but accepts every signature.
+    struct Lenient;
+
+    impl Backend for Lenient {
+        fn sign(&self, alg: Alg, key: &VectorKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+            BuiltinBackend.sign(alg, key, signing_input)
+        }
+
+        fn verify(&self, _: Alg, _: &VectorKey, _: &[u8], _: &[u8]) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_run_compliance_suite() {
+        assert_eq!(run_compliance_suite(&BuiltinBackend), Ok(()));
+        let failures = run_compliance_suite(&Lenient).unwrap_err();
+        assert_eq!(failures.len(), VECTORS.len());
+        assert_eq!(
+            failures[0].to_string(),
+            "RFC 7515 A.1: accepted the signature of a tampered signing input"
+        );
+    }
+}
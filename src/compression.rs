@@ -0,0 +1,75 @@
+//! Payload compression for tokens with large claim sets.
+//!
+//! A token whose header has `"cty": "deflate"` (`header::Cty::Deflate`) carries its claim set
+//! compressed with DEFLATE (RFC 1951) before base64 encoding, in the manner of the JWE `"zip":
+//! "DEF"` parameter. This is a convention of this crate rather than a standard, so only use it
+//! between parties which both use it. Tokens are compressed when encoded or signed, and
+//! decompressed when decoded, enforcing `ParseConfig::max_decompressed_size`.
+//!
+//! # Examples
+//! ```
+//! use jwt::header::{Alg, Cty};
+//! use jwt::JWT;
+//! use jwt::verify::VerifyOptions;
+//!
+//! let mut jwt = JWT::from_plain_str(&format!("{{\"roles\": \"{}\"}}", "admin,".repeat(1000)))
+//!     .unwrap();
+//! jwt.header.alg = Alg::HS256;
+//! let plain = jwt.sign(&[7; 32]).unwrap();
+//! jwt.header.cty = Cty::Deflate;
+//! let compressed = jwt.sign(&[7; 32]).unwrap();
+//! assert!(compressed.len() < plain.len() / 10);
+//! let options = VerifyOptions::new(&[Alg::HS256]);
+//! assert_eq!(JWT::decode_hmac(&compressed, &[7; 32], &options).unwrap().claim_set, jwt.claim_set);
+//! ```
+
+use std::io::{Read, Write};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use crate::err;
+
+/// Compresses `input` with DEFLATE.
+pub fn deflate(input: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec cannot fail.
+    encoder.write_all(input).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Decompresses DEFLATE-compressed `input`, returning a `err::JWTError::ParseError` if it is not
+/// valid DEFLATE data or if it decompresses to more than `max_size` bytes. Decompression stops as
+/// soon as the limit is exceeded.
+pub fn inflate(input: &[u8], max_size: usize) -> err::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    DeflateDecoder::new(input)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+    if out.len() > max_size {
+        return Err(err::JWTError::ParseError(format!(
+            "payload decompresses to more than the limit of {} bytes", max_size
+        )))
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let input = "{\"a\": 1}".repeat(100);
+        assert_eq!(inflate(&deflate(input.as_bytes()), input.len()).unwrap(), input.as_bytes());
+    }
+
+    #[test]
+    fn test_inflate_limit() {
+        let bomb = deflate(&[0u8; 1024 * 1024]);
+        assert!(bomb.len() < 2048);
+        assert!(matches!(inflate(&bomb, 1024), Err(err::JWTError::ParseError(_))));
+        assert!(inflate(b"not deflate", 1024).is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::claims::UriMode;
+use crate::err;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// How forgiving decoding is of the token's layout.
+pub enum Strictness {
+    /// Optional whitespace (space, CR, LF) anywhere in a token is ignored. This matches the
+    /// output of `JWT::encode_b64`, which separates components with line breaks.
+    Lenient,
+    /// Tokens must be in exact compact form: any whitespace is an error.
+    Strict,
+    /// As `Strict`, and additionally any control character (including NUL) is an error, as is
+    /// base64 which is not in canonical form. These checks are made before any JSON is parsed.
+    /// This is the mode to decode untrusted tokens with; see `ParseConfig::hardened`.
+    Hardened,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The base64 alphabet which encoded components are expected to use.
+pub enum Base64Alphabet {
+    /// The standard alphabet (`+` and `/`) of RFC 4648 section 4.
+    Standard,
+    /// The URL and filename safe alphabet (`-` and `_`) of RFC 4648 section 5, which is what
+    /// RFC 7515 specifies for JWS.
+    UrlSafe,
+    /// Either alphabet, detected separately for each component, for ingesting tokens from
+    /// producers which do not follow the specification. Not permitted under
+    /// `Strictness::Hardened`; see `ParseConfig::lenient`.
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// What to do when a claim set contains the same claim name more than once.
+pub enum DuplicatePolicy {
+    /// Fail with a `err::JWTError::SchemaError`.
+    Reject,
+    /// Keep the first occurrence of the claim.
+    First,
+    /// Keep the last occurrence of the claim, which is what most JSON parsers (and hence most
+    /// other JWT libraries) do.
+    Last,
+}
+
+/// The default limit on the size of a decompressed payload; see `ParseConfig`.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 256 * 1024;
+
+/// The component size limit used by `ParseConfig::hardened`.
+pub const HARDENED_MAX_SEGMENT_SIZE: usize = 16 * 1024;
+
+#[derive(Debug, Clone)]
+/// A `ParseConfig` collects every option which affects how tokens are decoded. It is accepted by
+/// the `decode_str_with` and `decode_b64_with` methods of `JsonSerializable`; `decode_str` and
+/// `decode_b64` use `ParseConfig::default()`.
+///
+/// # Examples
+/// ```
+/// use jwt::{JsonSerializable, ParseConfig};
+/// use jwt::claims::ClaimSet;
+/// use jwt::config::DuplicatePolicy;
+///
+/// let config = ParseConfig {
+///     max_size: Some(8192),
+///     duplicate_claims: DuplicatePolicy::Last,
+///     ..ParseConfig::default()
+/// };
+/// let claim_set = ClaimSet::decode_str_with("{\"a\": 1, \"a\": 2}", &config).unwrap();
+/// assert_eq!(claim_set.get("a").unwrap().claim_value, 2);
+/// ```
+pub struct ParseConfig {
+    pub strictness: Strictness,
+    pub base64: Base64Alphabet,
+    /// The maximum size, in bytes, of the input to a decode method, or `None` for no limit.
+    pub max_size: Option<usize>,
+    /// The maximum size, in bytes, of any one component of a token, or `None` for no limit.
+    pub max_segment_size: Option<usize>,
+    /// The maximum size, in bytes, a compressed payload may decompress to. This guards against
+    /// decompression bombs, and so is always enforced.
+    pub max_decompressed_size: usize,
+    pub duplicate_claims: DuplicatePolicy,
+    /// How claim names containing a colon are parsed.
+    pub uri_mode: UriMode,
+    /// The extension header parameters which the caller handles itself, and which tokens may
+    /// therefore list in their `crit` header parameter. Tokens listing any other are rejected
+    /// with a `err::JWTError::NotImplementedError`, as RFC 7515 section 4.1.11 requires.
+    pub handled_crit: Vec<String>,
+}
+
+impl Default for ParseConfig {
+    fn default() -> ParseConfig {
+        ParseConfig::new()
+    }
+}
+
+impl ParseConfig {
+    /// Constructs the default `ParseConfig`: lenient whitespace handling, the standard base64
+    /// alphabet, no size limit (other than `DEFAULT_MAX_DECOMPRESSED_SIZE` for compressed
+    /// payloads), duplicate claims rejected, and strict URI parsing.
+    pub fn new() -> ParseConfig {
+        ParseConfig {
+            strictness: Strictness::Lenient,
+            base64: Base64Alphabet::Standard,
+            max_size: None,
+            max_segment_size: None,
+            max_decompressed_size: DEFAULT_MAX_DECOMPRESSED_SIZE,
+            duplicate_claims: DuplicatePolicy::Reject,
+            uri_mode: UriMode::Strict,
+            handled_crit: Vec::new(),
+        }
+    }
+
+    /// Constructs the `ParseConfig` to decode tokens with before verifying them: as the default,
+    /// but using `Strictness::Hardened` and limiting components to `HARDENED_MAX_SEGMENT_SIZE`
+    /// bytes. `ParseConfig::default()` remains lenient, which suits inspecting tokens.
+    pub fn hardened() -> ParseConfig {
+        ParseConfig {
+            strictness: Strictness::Hardened,
+            max_segment_size: Some(HARDENED_MAX_SEGMENT_SIZE),
+            ..ParseConfig::new()
+        }
+    }
+
+    /// Constructs the most forgiving `ParseConfig`, for tooling which must read tokens from sloppy
+    /// producers: as the default, but detecting the base64 alphabet of each component (padding is
+    /// always optional) and keeping the last of any duplicate claims. Never verify tokens decoded
+    /// with this configuration; use `ParseConfig::hardened` for that.
+    pub fn lenient() -> ParseConfig {
+        ParseConfig {
+            base64: Base64Alphabet::Auto,
+            duplicate_claims: DuplicatePolicy::Last,
+            ..ParseConfig::new()
+        }
+    }
+
+    /// Marks the extension header parameter `name` as handled by the caller, so that tokens
+    /// listing it in their `crit` header parameter are accepted.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::{JsonSerializable, ParseConfig};
+    /// use jwt::header::JWTHeader;
+    ///
+    /// let header = "{\"alg\": \"none\", \"crit\": [\"exp\"], \"exp\": 1363284000}";
+    /// assert!(JWTHeader::decode_str(header).is_err());
+    /// assert!(JWTHeader::decode_str_with(header, &ParseConfig::new().handle_crit("exp")).is_ok());
+    /// ```
+    pub fn handle_crit(mut self, name: &str) -> ParseConfig {
+        self.handled_crit.push(String::from(name));
+        self
+    }
+
+    /// Returns a `err::JWTError::ParseError` if `input` exceeds `max_size`.
+    pub fn check_size(&self, input: &str) -> err::Result<()> {
+        match self.max_size {
+            Some(max_size) if input.len() > max_size => Err(err::JWTError::ParseError(format!(
+                "input of {} bytes exceeds the limit of {} bytes", input.len(), max_size
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns a `err::JWTError::ParseError` if `segment` exceeds `max_segment_size`.
+    pub fn check_segment_size(&self, segment: &str) -> err::Result<()> {
+        match self.max_segment_size {
+            Some(max_size) if segment.len() > max_size => Err(err::JWTError::ParseError(format!(
+                "component of {} bytes exceeds the limit of {} bytes", segment.len(), max_size
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decodes a base64-encoded component using the configured alphabet, returning a
+    /// `err::JWTError::ParseError` if it is not valid base64 or not valid UTF-8. Under
+    /// `Strictness::Hardened`, base64 which does not re-encode to the same characters (for
+    /// example, because its unused trailing bits are set) is also an error; padding is optional.
+    pub fn decode_base64(&self, input: &str) -> err::Result<String> {
+        self.decode_base64_bytes(input).and_then(|inner| {
+            String::from_utf8(inner)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+        })
+    }
+
+    /// Decodes a base64-encoded component into octets, as `decode_base64` does, but without
+    /// requiring them to be UTF-8.
+    pub fn decode_base64_bytes(&self, input: &str) -> err::Result<Vec<u8>> {
+        let alphabet = match self.base64 {
+            Base64Alphabet::Standard => base64::STANDARD,
+            Base64Alphabet::UrlSafe => base64::URL_SAFE,
+            Base64Alphabet::Auto if self.strictness == Strictness::Hardened => {
+                return Err(err::JWTError::ParseError(String::from(
+                    "base64 alphabet detection is not permitted in hardened mode"
+                )))
+            }
+            // The alphabets only differ in these characters, so one which uses neither decodes
+            // identically in both.
+            Base64Alphabet::Auto if input.contains(['-', '_']) => base64::URL_SAFE,
+            Base64Alphabet::Auto => base64::STANDARD,
+        };
+        base64::decode_config(input, alphabet)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+            .and_then(|inner| {
+                // The base64 crate currently rejects set trailing bits itself; re-encoding
+                // keeps the guarantee from depending on that.
+                let canonical = base64::encode_config(&inner, alphabet);
+                if self.strictness == Strictness::Hardened &&
+                    input != canonical && input != canonical.trim_end_matches('=') {
+                    return Err(err::JWTError::ParseError(String::from("non-canonical base64")));
+                }
+                Ok(inner)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_size() {
+        let config = ParseConfig { max_size: Some(3), ..ParseConfig::default() };
+        assert!(config.check_size("abc").is_ok());
+        assert!(matches!(config.check_size("abcd"), Err(err::JWTError::ParseError(_))));
+        assert!(ParseConfig::default().check_size("abcd").is_ok());
+    }
+
+    #[test]
+    fn test_decode_base64_alphabet() {
+        // "?>" encodes to "Pz4=" in both alphabets, "??>" to "Pz8+" or "Pz8-".
+        let config = ParseConfig { base64: Base64Alphabet::UrlSafe, ..ParseConfig::default() };
+        assert_eq!(config.decode_base64("Pz8-").unwrap(), "??>");
+        assert!(config.decode_base64("Pz8+").is_err());
+        assert_eq!(ParseConfig::default().decode_base64("Pz8+").unwrap(), "??>");
+    }
+
+    #[test]
+    fn test_decode_base64_auto() {
+        let lenient = ParseConfig::lenient();
+        assert_eq!(lenient.decode_base64("Pz8-").unwrap(), "??>");
+        assert_eq!(lenient.decode_base64("Pz8+").unwrap(), "??>");
+        assert_eq!(lenient.decode_base64("e30").unwrap(), "{}");
+        assert!(lenient.decode_base64("Pz8+Pz8-").is_err());
+
+        let hardened = ParseConfig { base64: Base64Alphabet::Auto, ..ParseConfig::hardened() };
+        assert!(hardened.decode_base64("e30").is_err());
+    }
+
+    #[test]
+    fn test_decode_base64_canonical() {
+        // "e31=" would decode to "{}" if its unused trailing bits were ignored.
+        let hardened = ParseConfig::hardened();
+        assert_eq!(hardened.decode_base64("e30=").unwrap(), "{}");
+        assert_eq!(hardened.decode_base64("e30").unwrap(), "{}");
+        assert!(matches!(hardened.decode_base64("e31="), Err(err::JWTError::ParseError(_))));
+    }
+}
@@ -0,0 +1,22 @@
+//! Constant-time comparison, for checking secret values such as MACs without revealing through
+//! timing how much of a forgery was right.
+
+use subtle::ConstantTimeEq;
+
+/// Returns whether `a` and `b` are equal, taking time which depends only on their lengths.
+pub fn eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq() {
+        assert!(eq(b"", b""));
+        assert!(eq(b"signature", b"signature"));
+        assert!(!eq(b"signature", b"signaturf"));
+        assert!(!eq(b"signature", b"signatur"));
+    }
+}
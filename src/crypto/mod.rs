@@ -0,0 +1,441 @@
+//! The cryptographic primitives tokens are signed and verified with.
+//!
+//! The primitives come from one of three backends, chosen at compile time with cargo features:
+//! the pure Rust RustCrypto crates (`rust-crypto`, the default), `ring` (`ring`), or the system's
+//! OpenSSL (`openssl`). If several are enabled, OpenSSL is preferred to `ring`, and `ring` to
+//! RustCrypto. The functions here check that the algorithm suits the key before calling into the
+//! backend, so every backend signs and accepts the same tokens. `ring` does not support P-521, so
+//! P-521 keys cannot be loaded with the `ring` backend.
+
+use std::fmt;
+
+use crate::err;
+use crate::header::Alg;
+use crate::keys::{EcPrivateKey, EcPublicKey, Ed25519PrivateKey, Ed25519PublicKey};
+use crate::keys::{PrivateKeyDer, PublicKeyDer, RsaPrivateKey, RsaPublicKey, VerificationKey};
+use crate::traits;
+
+pub mod ct;
+#[cfg(feature = "openssl")]
+mod openssl;
+#[cfg(all(feature = "ring", not(feature = "openssl")))]
+mod ring;
+#[cfg(all(feature = "rust-crypto", not(any(feature = "ring", feature = "openssl"))))]
+mod rust_crypto;
+#[cfg(feature = "secp256k1")]
+mod secp256k1;
+
+#[cfg(not(any(feature = "rust-crypto", feature = "ring", feature = "openssl")))]
+compile_error!(
+    "a crypto backend is required: enable the `rust-crypto`, `ring` or `openssl` feature"
+);
+
+// The backend the crate was built with.
+#[cfg(feature = "openssl")]
+pub(crate) type Active = openssl::OpenSsl;
+#[cfg(all(feature = "ring", not(feature = "openssl")))]
+pub(crate) type Active = ring::Ring;
+#[cfg(all(feature = "rust-crypto", not(any(feature = "ring", feature = "openssl"))))]
+pub(crate) type Active = rust_crypto::RustCrypto;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// The hash function an HMAC or RSA algorithm uses.
+pub(crate) enum Hash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+// The operations a backend implements, over key types of its own. The signing and verification
+// methods are only called once the algorithm has been checked against the key, and those for
+// ECDSA and EdDSA take the algorithm from the key.
+pub(crate) trait Backend {
+    type RsaPrivateKey: fmt::Debug + Clone + PartialEq;
+    type RsaPublicKey: fmt::Debug + Clone + PartialEq;
+    type EcPrivateKey: fmt::Debug + Clone + PartialEq;
+    type EcPublicKey: fmt::Debug + Clone + PartialEq;
+    type Ed25519PrivateKey: fmt::Debug + Clone + PartialEq;
+    type Ed25519PublicKey: fmt::Debug + Clone + PartialEq;
+
+    // Whether P-521 keys can be loaded.
+    #[cfg(test)]
+    const P521: bool;
+
+    fn hmac(hash: Hash, key: &[u8], signing_input: &[u8]) -> Vec<u8>;
+
+    fn rsa_private_key(der: PrivateKeyDer) -> err::Result<Self::RsaPrivateKey>;
+    fn rsa_public_key(der: PublicKeyDer) -> err::Result<Self::RsaPublicKey>;
+    fn rsa_public_key_of(key: &Self::RsaPrivateKey) -> Self::RsaPublicKey;
+    // Returns the size of the key's modulus, in bits.
+    fn rsa_public_key_bits(key: &Self::RsaPublicKey) -> usize;
+    // Returns the key's modulus and public exponent, as unsigned big-endian integers.
+    fn rsa_public_key_components(key: &Self::RsaPublicKey) -> (Vec<u8>, Vec<u8>);
+    // Generates a private key with a modulus of `bits` bits, as PKCS#8 DER.
+    fn rsa_generate(bits: usize) -> err::Result<Vec<u8>>;
+    fn rsa_sign(hash: Hash, key: &Self::RsaPrivateKey, signing_input: &[u8])
+        -> err::Result<Vec<u8>>;
+    fn verify_rsa(hash: Hash, key: &Self::RsaPublicKey, signing_input: &[u8], signature: &[u8])
+        -> bool;
+
+    fn ec_private_key(der: PrivateKeyDer) -> err::Result<Self::EcPrivateKey>;
+    fn ec_public_key(der: PublicKeyDer) -> err::Result<Self::EcPublicKey>;
+    fn ec_public_key_of(key: &Self::EcPrivateKey) -> Self::EcPublicKey;
+    fn ec_alg(key: &Self::EcPublicKey) -> Alg;
+    // Returns the key's point, uncompressed as SEC 1 section 2.3.3 specifies.
+    fn ec_public_key_point(key: &Self::EcPublicKey) -> Vec<u8>;
+    // Generates a private key on the curve the ECDSA algorithm `alg` signs on, as PKCS#8 DER.
+    fn ec_generate(alg: Alg) -> err::Result<Vec<u8>>;
+    fn ec_sign(key: &Self::EcPrivateKey, signing_input: &[u8]) -> err::Result<Vec<u8>>;
+    fn verify_ec(key: &Self::EcPublicKey, signing_input: &[u8], signature: &[u8]) -> bool;
+
+    fn ed25519_private_key(der: PrivateKeyDer) -> err::Result<Self::Ed25519PrivateKey>;
+    fn ed25519_private_key_from_seed(seed: &[u8; 32]) -> Self::Ed25519PrivateKey;
+    fn ed25519_public_key(der: PublicKeyDer) -> err::Result<Self::Ed25519PublicKey>;
+    fn ed25519_public_key_from_bytes(bytes: &[u8; 32]) -> err::Result<Self::Ed25519PublicKey>;
+    fn ed25519_public_key_of(key: &Self::Ed25519PrivateKey) -> Self::Ed25519PublicKey;
+    fn ed25519_public_key_bytes(key: &Self::Ed25519PublicKey) -> [u8; 32];
+    // Generates a private key, as PKCS#8 DER.
+    fn ed25519_generate() -> err::Result<Vec<u8>>;
+    fn eddsa_sign(key: &Self::Ed25519PrivateKey, signing_input: &[u8]) -> Vec<u8>;
+    fn verify_eddsa(key: &Self::Ed25519PublicKey, signing_input: &[u8], signature: &[u8])
+        -> bool;
+}
+
+// Returns the error for a key which is not of the kind `expected` describes.
+pub(crate) fn key_error(expected: &str) -> err::JWTError {
+    err::JWTError::ParseError(format!("expected {}", expected))
+}
+
+// Returns the error for a key which could not be generated, which only happens if the system's
+// random number generator fails.
+pub(crate) fn generate_error<E: fmt::Display>(e: E) -> err::JWTError {
+    err::JWTError::IoError(format!("could not generate a key: {}", e))
+}
+
+fn hmac_hash(alg: Alg) -> Option<Hash> {
+    match alg {
+        Alg::HS256 => Some(Hash::Sha256),
+        Alg::HS384 => Some(Hash::Sha384),
+        Alg::HS512 => Some(Hash::Sha512),
+        _ => None,
+    }
+}
+
+fn rsa_hash(alg: Alg) -> Option<Hash> {
+    match alg {
+        Alg::RS256 => Some(Hash::Sha256),
+        Alg::RS384 => Some(Hash::Sha384),
+        Alg::RS512 => Some(Hash::Sha512),
+        _ => None,
+    }
+}
+
+/// Computes the HMAC-SHA256 of `signing_input` under `key`: the signature of the `HS256`
+/// algorithm of RFC 7518 section 3.2.
+pub fn hs256(key: &[u8], signing_input: &[u8]) -> Vec<u8> {
+    Active::hmac(Hash::Sha256, key, signing_input)
+}
+
+/// Computes the signature of `signing_input` under `key` with the HMAC algorithm `alg` (`HS256`,
+/// `HS384` or `HS512`). Returns a `err::JWTError::NotImplementedError` for other algorithms.
+pub fn hmac(alg: Alg, key: &[u8], signing_input: &[u8]) -> err::Result<Vec<u8>> {
+    let hash = hmac_hash(alg).ok_or(err::JWTError::NotImplementedError)?;
+    Ok(Active::hmac(hash, key, signing_input))
+}
+
+/// Checks that `signature` is the signature of `signing_input` under `key` with the HMAC
+/// algorithm `alg`, comparing them in constant time (see `ct::eq`). Returns a
+/// `err::JWTError::SignatureError` if it is not, or if `alg` is not an HMAC algorithm.
+pub fn verify_hmac(alg: Alg, key: &[u8], signing_input: &[u8], signature: &[u8])
+    -> err::Result<()> {
+    let verified = hmac_hash(alg)
+        .is_some_and(|hash| ct::eq(&Active::hmac(hash, key, signing_input), signature));
+    if verified { Ok(()) } else { Err(err::JWTError::SignatureError) }
+}
+
+/// Computes the RSASSA-PKCS1-v1_5 signature of `signing_input` under `key` with the algorithm
+/// `alg` (`RS256`, `RS384` or `RS512`), as RFC 7518 section 3.3 specifies. Returns a
+/// `err::JWTError::NotImplementedError` for other algorithms.
+pub fn rsa_sign(alg: Alg, key: &RsaPrivateKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+    let hash = rsa_hash(alg).ok_or(err::JWTError::NotImplementedError)?;
+    Active::rsa_sign(hash, &key.key, signing_input)
+}
+
+/// Checks that `signature` is the RSASSA-PKCS1-v1_5 signature of `signing_input` under `key` with
+/// the algorithm `alg`. Returns a `err::JWTError::SignatureError` if it is not, or if `alg` is not
+/// an RSA algorithm.
+pub fn verify_rsa(alg: Alg, key: &RsaPublicKey, signing_input: &[u8], signature: &[u8])
+    -> err::Result<()> {
+    let verified = rsa_hash(alg)
+        .is_some_and(|hash| Active::verify_rsa(hash, &key.key, signing_input, signature));
+    if verified { Ok(()) } else { Err(err::JWTError::SignatureError) }
+}
+
+/// Returns the length of the signatures made with the ECDSA algorithm `alg`, which is twice the
+/// size of the curve's field, or `None` if `alg` is not an ECDSA algorithm.
+pub fn ec_signature_len(alg: Alg) -> Option<usize> {
+    match alg {
+        Alg::ES256 | Alg::ES256K => Some(64),
+        Alg::ES384 => Some(96),
+        Alg::ES512 => Some(132),
+        _ => None,
+    }
+}
+
+/// Computes the ECDSA signature of `signing_input` under `key` with the algorithm `alg`, which must
+/// be the `ES*` algorithm of the key's curve (`ES256` for P-256, `ES384` for P-384, `ES512` for
+/// P-521 and `ES256K` for secp256k1). The signature is encoded as the fixed-length concatenation
+/// of R and S which RFC 7518 section 3.4 requires, rather than as DER. Returns a
+/// `err::JWTError::NotImplementedError` for other algorithms.
+pub fn ec_sign(alg: Alg, key: &EcPrivateKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+    if alg != key.alg() {
+        return Err(err::JWTError::NotImplementedError)
+    }
+    Active::ec_sign(&key.key, signing_input)
+}
+
+/// Checks that `signature` is the R || S encoded ECDSA signature of `signing_input` under `key`
+/// with the algorithm `alg`. Returns a `err::JWTError::SignatureError` if it is not, if it is not
+/// the length signatures on the key's curve have, or if `alg` is not the `ES*` algorithm of the
+/// key's curve.
+pub fn verify_ec(alg: Alg, key: &EcPublicKey, signing_input: &[u8], signature: &[u8])
+    -> err::Result<()> {
+    let verified = alg == key.alg() && ec_signature_len(alg) == Some(signature.len()) &&
+        Active::verify_ec(&key.key, signing_input, signature);
+    if verified { Ok(()) } else { Err(err::JWTError::SignatureError) }
+}
+
+/// Computes the Ed25519 signature of `signing_input` under `key`, as RFC 8037 section 3.1 specifies
+/// for the `EdDSA` algorithm. Returns a `err::JWTError::NotImplementedError` if `alg` is not
+/// `EdDSA`.
+pub fn eddsa_sign(alg: Alg, key: &Ed25519PrivateKey, signing_input: &[u8])
+    -> err::Result<Vec<u8>> {
+    match alg {
+        Alg::EdDSA => Ok(Active::eddsa_sign(&key.key, signing_input)),
+        _ => Err(err::JWTError::NotImplementedError),
+    }
+}
+
+/// Checks that `signature` is the Ed25519 signature of `signing_input` under `key`. Returns a
+/// `err::JWTError::SignatureError` if it is not, or if `alg` is not `EdDSA`.
+pub fn verify_eddsa(alg: Alg, key: &Ed25519PublicKey, signing_input: &[u8], signature: &[u8])
+    -> err::Result<()> {
+    let verified = alg == Alg::EdDSA && Active::verify_eddsa(&key.key, signing_input, signature);
+    if verified { Ok(()) } else { Err(err::JWTError::SignatureError) }
+}
+
+/// Checks that `signature` is the signature of `signing_input` under `key` with the algorithm
+/// `alg`, with whichever of the functions above suits the kind of key. Returns a
+/// `err::JWTError::SignatureError` if it is not, or if `alg` is not an algorithm of that kind.
+pub fn verify(alg: Alg, key: VerificationKey, signing_input: &[u8], signature: &[u8])
+    -> err::Result<()> {
+    match key {
+        VerificationKey::Hmac(key) => verify_hmac(alg, key, signing_input, signature),
+        VerificationKey::Rsa(key) => verify_rsa(alg, key, signing_input, signature),
+        VerificationKey::Ec(key) => verify_ec(alg, key, signing_input, signature),
+        VerificationKey::Ed25519(key) => verify_eddsa(alg, key, signing_input, signature),
+    }
+}
+
+impl traits::Signer for EcPrivateKey {
+    fn alg(&self) -> Alg {
+        EcPrivateKey::alg(self)
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        ec_sign(EcPrivateKey::alg(self), self, signing_input)
+    }
+}
+
+impl traits::Verifier for EcPublicKey {
+    fn alg(&self) -> Alg {
+        EcPublicKey::alg(self)
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> bool {
+        verify_ec(EcPublicKey::alg(self), self, signing_input, signature).is_ok()
+    }
+}
+
+impl traits::Signer for Ed25519PrivateKey {
+    fn alg(&self) -> Alg {
+        Alg::EdDSA
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        eddsa_sign(Alg::EdDSA, self, signing_input)
+    }
+}
+
+impl traits::Verifier for Ed25519PublicKey {
+    fn alg(&self) -> Alg {
+        Alg::EdDSA
+    }
+
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> bool {
+        verify_eddsa(Alg::EdDSA, self, signing_input, signature).is_ok()
+    }
+}
+
+/// Encodes `input` as unpadded base64url, the encoding RFC 7515 section 2 uses for every
+/// component of a compact JWS.
+pub fn b64url(input: &[u8]) -> String {
+    base64::encode_config(input, base64::URL_SAFE_NO_PAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compliance;
+
+    #[test]
+    fn test_hs256_rfc7515() {
+        let vector = &compliance::RFC7515_A1;
+        let key = vector.key.secret().unwrap();
+        assert_eq!(b64url(&hs256(&key, vector.signing_input.as_bytes())), vector.signature);
+    }
+
+    #[test]
+    fn test_rs256_rfc7515() {
+        let vector = &compliance::RFC7515_A2;
+        let key = RsaPrivateKey::from_pem(vector.key.pem().unwrap()).unwrap();
+        let signing_input = vector.signing_input;
+        let signature = rsa_sign(Alg::RS256, &key, signing_input.as_bytes()).unwrap();
+        assert_eq!(b64url(&signature), vector.signature);
+        let public = key.public_key();
+        assert!(verify_rsa(Alg::RS256, &public, signing_input.as_bytes(), &signature).is_ok());
+        assert!(verify_rsa(Alg::RS256, &public, b"tampered", &signature).is_err());
+        assert!(verify_rsa(Alg::HS256, &public, signing_input.as_bytes(), &signature).is_err());
+        assert!(rsa_sign(Alg::HS256, &key, signing_input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_rs384_rs512() {
+        let key = include_str!("../testdata/rsa_private_pkcs8.pem");
+        let key = RsaPrivateKey::from_pem(key).unwrap();
+        // The payload of RFC 7515 appendix A.2, under {"alg":"RS384"} and {"alg":"RS512"}.
+        let payload = "eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAsDQogImh0dHA6Ly9leGFtcGxlLmNvbS9p\
+                       c19yb290Ijp0cnVlfQ";
+        for (alg, header, expected) in &[
+            (Alg::RS384, "eyJhbGciOiJSUzM4NCJ9",
+             "UqgNjrJOGhk4wfoSG6Uvrt9GcKu-TgPwInExALrMBadg1pol1uTw7mZADTddAWsC6ZzdFiTFUmIi7DuD\
+              38ftLAZoW4qezdAO7RYf1yZDsbT20bt8DJJN1I4VovL2PLg80B6x6ug-kaW8k5LaM5ce0dk1zgWhjafK\
+              C3Mb4UNLL8f9fqVMkHpdWYRjF6QjTz12Ap-gq-tPyUoWSdvzCIYOcZ9-08SQQdUTTgsNF1Qwu3TqeWPq\
+              zNJwmWHiHMmaV8I4ktMFEX-AiEBa55KsfYTx0jSbTHP-odqmnLQJ4n-oQJ2RSXy0HQP6BkdiwDHdoMUk\
+              4z_wAeOsfDTs_mLxTgOInQ"),
+            (Alg::RS512, "eyJhbGciOiJSUzUxMiJ9",
+             "ZatQfsb2gyCu3y9cDuz59a-IKm4bkqtT0HuT8BpNlPCmA3Y2eH91CVSI0TbkPqI9v2jaXuWvPcoJGNRt\
+              TpUXafTAbqzxWSMjqx8SkJRTuUz6imaHBctra42j2AvJ1t7qJwf2NN49y9PZbkYn3ejhU-iCmKJ3J-_G\
+              LsYp5VlximYm-o3sMul0tyCMvHUdmuWvadnVEaio-jix3pXYWfyFC8tp19zZrTaofxTAzCqlqundx22t\
+              fsuqchto_zVnZk_ZBr1R5lr29Qle5JgLmRkfDNbVSQZFdwg6mSlODL8BrOiM_vreMaPCO8U_JGezKUob\
+              0ONv7DA7XDfpbaXaFsHipQ"),
+        ] {
+            let signing_input = format!("{}.{}", header, payload);
+            let signature = rsa_sign(*alg, &key, signing_input.as_bytes()).unwrap();
+            assert_eq!(b64url(&signature), *expected);
+            let public = key.public_key();
+            assert!(verify_rsa(*alg, &public, signing_input.as_bytes(), &signature).is_ok());
+            assert!(verify_rsa(Alg::RS256, &public, signing_input.as_bytes(), &signature).is_err());
+        }
+    }
+
+    #[test]
+    fn test_es256_rfc7515() {
+        // ECDSA signatures are randomized, so only the appendix's signature can be checked, not
+        // reproduced.
+        let vector = &compliance::RFC7515_A3;
+        let key = EcPrivateKey::from_pem(vector.key.pem().unwrap()).unwrap();
+        let public = key.public_key();
+        let signing_input = vector.signing_input;
+        let signature = base64::decode_config(vector.signature, base64::URL_SAFE_NO_PAD).unwrap();
+        assert!(verify_ec(Alg::ES256, &public, signing_input.as_bytes(), &signature).is_ok());
+
+        let signature = ec_sign(Alg::ES256, &key, b"input").unwrap();
+        assert_eq!(signature.len(), 64);
+        assert!(verify_ec(Alg::ES256, &public, b"input", &signature).is_ok());
+        assert!(verify_ec(Alg::ES256, &public, b"other", &signature).is_err());
+        assert!(verify_ec(Alg::RS256, &public, b"input", &signature).is_err());
+        assert!(ec_sign(Alg::HS256, &key, b"input").is_err());
+    }
+
+    #[test]
+    fn test_es384_es512() {
+        // Signatures of "input" made with the test keys by another implementation.
+        for (private, alg, expected) in &[
+            (include_str!("../testdata/ec_p384_private_pkcs8.pem"), Alg::ES384,
+             "TQz3rXR02pbpeudUYNhwvZHs1-PxPPaO7GUmYcMiqcNH1hswvDdAZCE6hrXlu3Tc9_NItKZpJfjz2XwqAllD\
+              EjG2kfsTdrIOG3VYZ7kD1IG1iPc3_W_AEWbTyxyxrS4l"),
+            (include_str!("../testdata/ec_p521_private_pkcs8.pem"), Alg::ES512,
+             "AdhTqf76RI3SsZS_u1TFn7hthCiWqCZMP8LOedKw_9vJG_Zg3vN6xwh8QJma49KSrvXKehVR_cAtwGgKlJGI\
+              et-BABIQUdbLyKF0AIM6hygBloDOo662AHczGsrlVQR6AI4KXJK37T3-vD1aSzNy1ndzmGBVxBVV8BGIW72R\
+              u6m6lM_S"),
+        ] {
+            if !Active::P521 && *alg == Alg::ES512 {
+                continue
+            }
+            let key = EcPrivateKey::from_pem(private).unwrap();
+            let public = key.public_key();
+            let expected = base64::decode_config(expected, base64::URL_SAFE_NO_PAD).unwrap();
+            assert!(verify_ec(*alg, &public, b"input", &expected).is_ok());
+
+            let signature = ec_sign(*alg, &key, b"input").unwrap();
+            assert_eq!(Some(signature.len()), ec_signature_len(*alg));
+            assert!(verify_ec(*alg, &public, b"input", &signature).is_ok());
+            assert!(verify_ec(*alg, &public, b"input", &signature[1..]).is_err());
+            assert!(verify_ec(Alg::ES256, &public, b"input", &signature).is_err());
+            assert!(ec_sign(Alg::ES256, &key, b"input").is_err());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn test_es256k() {
+        let key = include_str!("../testdata/ec_secp256k1_private_pkcs8.pem");
+        let key = EcPrivateKey::from_pem(key).unwrap();
+        let public = key.public_key();
+        // A signature of "input" made with the test key by another implementation.
+        let expected = base64::decode_config(
+            "mviF9xxL4Mb5ZGosKyvo1wo29XE7q6xhPm1cCEo8-ZpxxICUD83Mgi4sZ4dtpEgPPs6CP0NccC6S6goEo\
+             gjw7g",
+            base64::URL_SAFE_NO_PAD,
+        ).unwrap();
+        assert!(verify_ec(Alg::ES256K, &public, b"input", &expected).is_ok());
+
+        let signature = ec_sign(Alg::ES256K, &key, b"input").unwrap();
+        assert!(verify_ec(Alg::ES256K, &public, b"input", &signature).is_ok());
+        assert!(verify_ec(Alg::ES256K, &public, b"other", &signature).is_err());
+        assert!(verify_ec(Alg::ES256, &public, b"input", &signature).is_err());
+        assert!(ec_sign(Alg::ES256, &key, b"input").is_err());
+    }
+
+    #[test]
+    fn test_eddsa_rfc8037() {
+        // Ed25519 signatures are deterministic, so the appendix's signature can be reproduced.
+        let vector = &compliance::RFC8037_A4;
+        let key = Ed25519PrivateKey::from_pem(vector.key.pem().unwrap()).unwrap();
+        let public = key.public_key();
+        let signing_input = vector.signing_input.as_bytes();
+        let signature = eddsa_sign(Alg::EdDSA, &key, signing_input).unwrap();
+        assert_eq!(b64url(&signature), vector.signature);
+        assert!(verify_eddsa(Alg::EdDSA, &public, signing_input, &signature).is_ok());
+        assert!(verify_eddsa(Alg::EdDSA, &public, b"other", &signature).is_err());
+        assert!(verify_eddsa(Alg::EdDSA, &public, signing_input, &signature[1..]).is_err());
+        assert!(verify_eddsa(Alg::ES256, &public, signing_input, &signature).is_err());
+        assert!(eddsa_sign(Alg::HS256, &key, signing_input).is_err());
+    }
+
+    #[test]
+    fn test_hmac_verify() {
+        for (alg, len) in &[(Alg::HS256, 32), (Alg::HS384, 48), (Alg::HS512, 64)] {
+            let signature = hmac(*alg, b"key", b"input").unwrap();
+            assert_eq!(signature.len(), *len);
+            assert!(verify_hmac(*alg, b"key", b"input", &signature).is_ok());
+            assert!(verify_hmac(*alg, b"other", b"input", &signature).is_err());
+            assert!(verify_hmac(*alg, b"key", b"input", &signature[1..]).is_err());
+            let signature = hmac(*alg, b"", b"input").unwrap();
+            assert!(verify_hmac(*alg, b"", b"input", &signature).is_ok());
+        }
+        assert!(hmac(Alg::None, b"key", b"input").is_err());
+        assert!(verify_hmac(Alg::None, b"key", b"input", b"").is_err());
+    }
+}
@@ -0,0 +1,340 @@
+//! The OpenSSL backend, which signs and verifies with the system's OpenSSL through the `openssl`
+//! crate.
+
+use std::fmt;
+
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{HasPublic, Id, PKey, Private, Public};
+use openssl::rsa::Rsa;
+use openssl::sign::{Signer, Verifier};
+
+use super::{ec_signature_len, generate_error, key_error, Backend, Hash};
+use crate::err;
+use crate::header::Alg;
+use crate::keys::{PrivateKeyDer, PublicKeyDer};
+
+pub(crate) struct OpenSsl;
+
+// An OpenSSL key. Keys compare by their public components, which determine private keys too.
+pub(crate) struct Key<T>(PKey<T>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EcSigningKey {
+    // A P-256, P-384 or P-521 key, with the algorithm of its curve.
+    Nist(Alg, Key<Private>),
+    #[cfg(feature = "secp256k1")]
+    K256(k256::SecretKey),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EcVerifyingKey {
+    // A P-256, P-384 or P-521 key, with the algorithm of its curve.
+    Nist(Alg, Key<Public>),
+    #[cfg(feature = "secp256k1")]
+    K256(k256::PublicKey),
+}
+
+// Derived implementations would require `T`, which only marks the kind of key, to implement the
+// traits too.
+impl<T> fmt::Debug for Key<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T> Clone for Key<T> {
+    fn clone(&self) -> Key<T> {
+        Key(self.0.clone())
+    }
+}
+
+impl<T: HasPublic> PartialEq for Key<T> {
+    fn eq(&self, other: &Key<T>) -> bool {
+        self.0.public_eq(&other.0)
+    }
+}
+
+fn openssl_error(e: openssl::error::ErrorStack) -> err::JWTError {
+    err::JWTError::ParseError(format!("{}", e))
+}
+
+fn digest(hash: Hash) -> MessageDigest {
+    match hash {
+        Hash::Sha256 => MessageDigest::sha256(),
+        Hash::Sha384 => MessageDigest::sha384(),
+        Hash::Sha512 => MessageDigest::sha512(),
+    }
+}
+
+// Returns the hash the ECDSA algorithm `alg` signs with.
+fn ecdsa_hash(alg: Alg) -> Hash {
+    match alg {
+        Alg::ES384 => Hash::Sha384,
+        Alg::ES512 => Hash::Sha512,
+        _ => Hash::Sha256,
+    }
+}
+
+// Returns the public half of `key`.
+fn public_key_of(key: &PKey<Private>) -> PKey<Public> {
+    // A key OpenSSL loaded can be encoded and decoded again.
+    PKey::public_key_from_der(&key.public_key_to_der().unwrap()).unwrap()
+}
+
+// Returns the algorithm of the curve of the elliptic curve key `key`, if it has one.
+fn curve_alg<T: HasPublic>(key: &PKey<T>) -> Option<Alg> {
+    match key.ec_key().ok()?.group().curve_name()? {
+        Nid::X9_62_PRIME256V1 => Some(Alg::ES256),
+        Nid::SECP384R1 => Some(Alg::ES384),
+        Nid::SECP521R1 => Some(Alg::ES512),
+        _ => None,
+    }
+}
+
+fn sign(key: &PKey<Private>, digest: Option<MessageDigest>, signing_input: &[u8])
+    -> err::Result<Vec<u8>> {
+    let mut signer = match digest {
+        Some(digest) => Signer::new(digest, key),
+        None => Signer::new_without_digest(key),
+    }.map_err(openssl_error)?;
+    signer.sign_oneshot_to_vec(signing_input).map_err(openssl_error)
+}
+
+fn verify(key: &PKey<Public>, digest: Option<MessageDigest>, signing_input: &[u8],
+    signature: &[u8]) -> bool {
+    let verifier = match digest {
+        Some(digest) => Verifier::new(digest, key),
+        None => Verifier::new_without_digest(key),
+    };
+    verifier.and_then(|mut verifier| verifier.verify_oneshot(signature, signing_input))
+        .unwrap_or(false)
+}
+
+impl Backend for OpenSsl {
+    type RsaPrivateKey = Key<Private>;
+    type RsaPublicKey = Key<Public>;
+    type EcPrivateKey = EcSigningKey;
+    type EcPublicKey = EcVerifyingKey;
+    type Ed25519PrivateKey = Key<Private>;
+    type Ed25519PublicKey = Key<Public>;
+
+    #[cfg(test)]
+    const P521: bool = true;
+
+    fn hmac(hash: Hash, key: &[u8], signing_input: &[u8]) -> Vec<u8> {
+        // OpenSSL rejects empty keys. HMAC pads keys with zeros to the hash's block size, so a
+        // single zero byte is equivalent.
+        let key = if key.is_empty() { &[0][..] } else { key };
+        // HMAC accepts keys of any other length, so this cannot fail.
+        let key = PKey::hmac(key).unwrap();
+        sign(&key, Some(digest(hash)), signing_input).unwrap()
+    }
+
+    fn rsa_private_key(der: PrivateKeyDer) -> err::Result<Key<Private>> {
+        match der {
+            PrivateKeyDer::Pkcs8(der) => PKey::private_key_from_pkcs8(der).ok(),
+            PrivateKeyDer::Pkcs1(der) => {
+                Rsa::private_key_from_der(der).and_then(PKey::from_rsa).ok()
+            }
+            PrivateKeyDer::Sec1(_) => None,
+        }.filter(|key| key.id() == Id::RSA).map(Key)
+            .ok_or_else(|| key_error("an RSA private key"))
+    }
+
+    fn rsa_public_key(der: PublicKeyDer) -> err::Result<Key<Public>> {
+        match der {
+            PublicKeyDer::Spki(der) => PKey::public_key_from_der(der).ok(),
+            PublicKeyDer::Pkcs1(der) => {
+                Rsa::public_key_from_der_pkcs1(der).and_then(PKey::from_rsa).ok()
+            }
+        }.filter(|key| key.id() == Id::RSA).map(Key)
+            .ok_or_else(|| key_error("an RSA public key"))
+    }
+
+    fn rsa_public_key_of(key: &Key<Private>) -> Key<Public> {
+        Key(public_key_of(&key.0))
+    }
+
+    fn rsa_public_key_bits(key: &Key<Public>) -> usize {
+        key.0.bits() as usize
+    }
+
+    fn rsa_generate(bits: usize) -> err::Result<Vec<u8>> {
+        let key = Rsa::generate(bits as u32).and_then(PKey::from_rsa).map_err(generate_error)?;
+        key.private_key_to_pkcs8().map_err(generate_error)
+    }
+
+    fn rsa_public_key_components(key: &Key<Public>) -> (Vec<u8>, Vec<u8>) {
+        // The key was loaded as an RSA key.
+        let key = key.0.rsa().unwrap();
+        (key.n().to_vec(), key.e().to_vec())
+    }
+
+    fn rsa_sign(hash: Hash, key: &Key<Private>, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        sign(&key.0, Some(digest(hash)), signing_input)
+    }
+
+    fn verify_rsa(hash: Hash, key: &Key<Public>, signing_input: &[u8], signature: &[u8])
+        -> bool {
+        verify(&key.0, Some(digest(hash)), signing_input, signature)
+    }
+
+    fn ec_private_key(der: PrivateKeyDer) -> err::Result<EcSigningKey> {
+        let key = match der {
+            PrivateKeyDer::Pkcs8(der) => PKey::private_key_from_pkcs8(der).ok(),
+            PrivateKeyDer::Sec1(der) => {
+                EcKey::private_key_from_der(der).and_then(PKey::from_ec_key).ok()
+            }
+            PrivateKeyDer::Pkcs1(_) => None,
+        }.and_then(|key| curve_alg(&key).map(|alg| EcSigningKey::Nist(alg, Key(key))));
+        #[cfg(feature = "secp256k1")]
+        let key = key.or_else(|| super::secp256k1::private_key(der).map(EcSigningKey::K256));
+        key.ok_or_else(|| key_error("an elliptic curve private key"))
+    }
+
+    fn ec_public_key(der: PublicKeyDer) -> err::Result<EcVerifyingKey> {
+        let key = match der {
+            PublicKeyDer::Spki(der) => PKey::public_key_from_der(der).ok(),
+            PublicKeyDer::Pkcs1(_) => None,
+        }.and_then(|key| curve_alg(&key).map(|alg| EcVerifyingKey::Nist(alg, Key(key))));
+        #[cfg(feature = "secp256k1")]
+        let key = key.or_else(|| super::secp256k1::public_key(der).map(EcVerifyingKey::K256));
+        key.ok_or_else(|| key_error("an elliptic curve public key"))
+    }
+
+    fn ec_public_key_of(key: &EcSigningKey) -> EcVerifyingKey {
+        match key {
+            EcSigningKey::Nist(alg, key) => {
+                EcVerifyingKey::Nist(*alg, Key(public_key_of(&key.0)))
+            }
+            #[cfg(feature = "secp256k1")]
+            EcSigningKey::K256(key) => EcVerifyingKey::K256(key.public_key()),
+        }
+    }
+
+    fn ec_alg(key: &EcVerifyingKey) -> Alg {
+        match key {
+            EcVerifyingKey::Nist(alg, _) => *alg,
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(_) => Alg::ES256K,
+        }
+    }
+
+    fn ec_public_key_point(key: &EcVerifyingKey) -> Vec<u8> {
+        match key {
+            EcVerifyingKey::Nist(_, key) => {
+                // The key was loaded as an elliptic curve key, on a named curve.
+                let key = key.0.ec_key().unwrap();
+                let mut context = BigNumContext::new().unwrap();
+                key.public_key()
+                    .to_bytes(key.group(), PointConversionForm::UNCOMPRESSED, &mut context)
+                    .unwrap()
+            }
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(key) => super::secp256k1::point(key),
+        }
+    }
+
+    fn ec_generate(alg: Alg) -> err::Result<Vec<u8>> {
+        let curve = match alg {
+            Alg::ES256 => Nid::X9_62_PRIME256V1,
+            Alg::ES384 => Nid::SECP384R1,
+            Alg::ES512 => Nid::SECP521R1,
+            #[cfg(feature = "secp256k1")]
+            Alg::ES256K => return super::secp256k1::generate(),
+            _ => return Err(err::JWTError::NotImplementedError),
+        };
+        let key = EcGroup::from_curve_name(curve)
+            .and_then(|group| EcKey::generate(&group))
+            .and_then(PKey::from_ec_key)
+            .map_err(generate_error)?;
+        key.private_key_to_pkcs8().map_err(generate_error)
+    }
+
+    fn ec_sign(key: &EcSigningKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        match key {
+            EcSigningKey::Nist(alg, key) => {
+                // OpenSSL encodes ECDSA signatures as DER, rather than as R || S.
+                let signature = sign(&key.0, Some(digest(ecdsa_hash(*alg))), signing_input)?;
+                let signature = EcdsaSig::from_der(&signature).map_err(openssl_error)?;
+                // The signature is as long as the algorithm's signatures.
+                let len = ec_signature_len(*alg).unwrap() as i32 / 2;
+                let mut r = signature.r().to_vec_padded(len).map_err(openssl_error)?;
+                r.extend(signature.s().to_vec_padded(len).map_err(openssl_error)?);
+                Ok(r)
+            }
+            #[cfg(feature = "secp256k1")]
+            EcSigningKey::K256(key) => Ok(super::secp256k1::sign(key, signing_input)),
+        }
+    }
+
+    fn verify_ec(key: &EcVerifyingKey, signing_input: &[u8], signature: &[u8]) -> bool {
+        match key {
+            EcVerifyingKey::Nist(alg, key) => {
+                let (r, s) = signature.split_at(signature.len() / 2);
+                let signature = BigNum::from_slice(r)
+                    .and_then(|r| Ok((r, BigNum::from_slice(s)?)))
+                    .and_then(|(r, s)| EcdsaSig::from_private_components(r, s))
+                    .and_then(|signature| signature.to_der());
+                signature.is_ok_and(|signature| {
+                    verify(&key.0, Some(digest(ecdsa_hash(*alg))), signing_input, &signature)
+                })
+            }
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(key) => super::secp256k1::verify(key, signing_input, signature),
+        }
+    }
+
+    fn ed25519_private_key(der: PrivateKeyDer) -> err::Result<Key<Private>> {
+        match der {
+            PrivateKeyDer::Pkcs8(der) => PKey::private_key_from_pkcs8(der).ok(),
+            _ => None,
+        }.filter(|key| key.id() == Id::ED25519).map(Key)
+            .ok_or_else(|| key_error("an Ed25519 private key"))
+    }
+
+    fn ed25519_private_key_from_seed(seed: &[u8; 32]) -> Key<Private> {
+        // Every 32-byte seed is a valid key.
+        Key(PKey::private_key_from_raw_bytes(seed, Id::ED25519).unwrap())
+    }
+
+    fn ed25519_public_key(der: PublicKeyDer) -> err::Result<Key<Public>> {
+        match der {
+            PublicKeyDer::Spki(der) => PKey::public_key_from_der(der).ok(),
+            PublicKeyDer::Pkcs1(_) => None,
+        }.filter(|key| key.id() == Id::ED25519).map(Key)
+            .ok_or_else(|| key_error("an Ed25519 public key"))
+    }
+
+    fn ed25519_public_key_from_bytes(bytes: &[u8; 32]) -> err::Result<Key<Public>> {
+        PKey::public_key_from_raw_bytes(bytes, Id::ED25519).map(Key).map_err(openssl_error)
+    }
+
+    fn ed25519_public_key_of(key: &Key<Private>) -> Key<Public> {
+        Key(public_key_of(&key.0))
+    }
+
+    fn ed25519_public_key_bytes(key: &Key<Public>) -> [u8; 32] {
+        // The key was loaded as an Ed25519 key, whose public keys are 32 bytes long.
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(&key.0.raw_public_key().unwrap());
+        bytes
+    }
+
+    fn ed25519_generate() -> err::Result<Vec<u8>> {
+        PKey::generate_ed25519().and_then(|key| key.private_key_to_pkcs8()).map_err(generate_error)
+    }
+
+    fn eddsa_sign(key: &Key<Private>, signing_input: &[u8]) -> Vec<u8> {
+        // Ed25519 signing cannot fail.
+        sign(&key.0, None, signing_input).unwrap()
+    }
+
+    fn verify_eddsa(key: &Key<Public>, signing_input: &[u8], signature: &[u8]) -> bool {
+        verify(&key.0, None, signing_input, signature)
+    }
+}
@@ -0,0 +1,359 @@
+//! The `ring` backend. `ring` has no P-521 support, so P-521 keys are rejected when they are
+//! loaded, and it only accepts RSA keys of 2048 bits or more.
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use ring::rand::SystemRandom;
+use ring::signature::{self, EcdsaKeyPair, Ed25519KeyPair, KeyPair, RsaKeyPair};
+use ring::signature::UnparsedPublicKey;
+use spki::der::asn1::{ObjectIdentifier, UintRef};
+use spki::der::{Decode, Reader, SliceReader};
+use spki::SubjectPublicKeyInfoRef;
+
+use super::{generate_error, key_error, Backend, Hash};
+use crate::err;
+use crate::header::Alg;
+use crate::keys::{PrivateKeyDer, PublicKeyDer};
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+pub(crate) struct Ring;
+
+#[derive(Debug, Clone)]
+pub(crate) struct RsaSigningKey(Arc<RsaKeyPair>);
+
+#[derive(Debug, Clone, PartialEq)]
+// The PKCS#1 `RSAPublicKey` DER which ring verifies with. It is only parsed when it is used.
+pub(crate) struct RsaVerifyingKey(Vec<u8>);
+
+#[derive(Debug, Clone)]
+pub(crate) enum EcSigningKey {
+    // A P-256 or P-384 key, with the algorithm of its curve.
+    Nist(Alg, Arc<EcdsaKeyPair>),
+    #[cfg(feature = "secp256k1")]
+    K256(k256::SecretKey),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EcVerifyingKey {
+    // A P-256 or P-384 key, with the algorithm of its curve, as an uncompressed point.
+    Nist(Alg, Vec<u8>),
+    #[cfg(feature = "secp256k1")]
+    K256(k256::PublicKey),
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Ed25519SigningKey(Arc<Ed25519KeyPair>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Ed25519VerifyingKey([u8; 32]);
+
+// ring's key pairs cannot be compared, so private keys compare by their public halves, which
+// determine them.
+impl PartialEq for RsaSigningKey {
+    fn eq(&self, other: &RsaSigningKey) -> bool {
+        self.0.public_key().as_ref() == other.0.public_key().as_ref()
+    }
+}
+
+impl PartialEq for EcSigningKey {
+    fn eq(&self, other: &EcSigningKey) -> bool {
+        Ring::ec_public_key_of(self) == Ring::ec_public_key_of(other)
+    }
+}
+
+impl PartialEq for Ed25519SigningKey {
+    fn eq(&self, other: &Ed25519SigningKey) -> bool {
+        self.0.public_key().as_ref() == other.0.public_key().as_ref()
+    }
+}
+
+fn hmac_algorithm(hash: Hash) -> ring::hmac::Algorithm {
+    match hash {
+        Hash::Sha256 => ring::hmac::HMAC_SHA256,
+        Hash::Sha384 => ring::hmac::HMAC_SHA384,
+        Hash::Sha512 => ring::hmac::HMAC_SHA512,
+    }
+}
+
+fn ecdsa_signing(alg: Alg) -> &'static signature::EcdsaSigningAlgorithm {
+    match alg {
+        Alg::ES384 => &signature::ECDSA_P384_SHA384_FIXED_SIGNING,
+        _ => &signature::ECDSA_P256_SHA256_FIXED_SIGNING,
+    }
+}
+
+fn ecdsa_verification(alg: Alg) -> &'static signature::EcdsaVerificationAlgorithm {
+    match alg {
+        Alg::ES384 => &signature::ECDSA_P384_SHA384_FIXED,
+        _ => &signature::ECDSA_P256_SHA256_FIXED,
+    }
+}
+
+// Returns the algorithm of the curve `oid` names, if ring supports it.
+fn curve_alg(oid: ObjectIdentifier) -> Option<Alg> {
+    match oid {
+        SECP256R1 => Some(Alg::ES256),
+        SECP384R1 => Some(Alg::ES384),
+        _ => None,
+    }
+}
+
+// Returns the algorithm OID, the parameters OID if there is one, and the key of a
+// SubjectPublicKeyInfo.
+fn spki(der: &[u8]) -> Option<(ObjectIdentifier, Option<ObjectIdentifier>, &[u8])> {
+    let spki = SubjectPublicKeyInfoRef::from_der(der).ok()?;
+    let key = spki.subject_public_key.as_bytes()?;
+    Some((spki.algorithm.oid, spki.algorithm.parameters_oid().ok(), key))
+}
+
+fn ec_key_pair(alg: Alg, der: PrivateKeyDer) -> Option<EcdsaKeyPair> {
+    let rng = SystemRandom::new();
+    match der {
+        PrivateKeyDer::Pkcs8(der) => EcdsaKeyPair::from_pkcs8(ecdsa_signing(alg), der, &rng).ok(),
+        PrivateKeyDer::Sec1(der) => {
+            let key = sec1::EcPrivateKey::try_from(der).ok()?;
+            let curve = key.parameters.and_then(|parameters| parameters.named_curve());
+            if curve.and_then(curve_alg) != Some(alg) {
+                return None
+            }
+            // ring needs the public key, which SEC1 makes optional.
+            EcdsaKeyPair::from_private_key_and_public_key(
+                ecdsa_signing(alg), key.private_key, key.public_key?, &rng
+            ).ok()
+        }
+        PrivateKeyDer::Pkcs1(_) => None,
+    }
+}
+
+impl Backend for Ring {
+    type RsaPrivateKey = RsaSigningKey;
+    type RsaPublicKey = RsaVerifyingKey;
+    type EcPrivateKey = EcSigningKey;
+    type EcPublicKey = EcVerifyingKey;
+    type Ed25519PrivateKey = Ed25519SigningKey;
+    type Ed25519PublicKey = Ed25519VerifyingKey;
+
+    #[cfg(test)]
+    const P521: bool = false;
+
+    fn hmac(hash: Hash, key: &[u8], signing_input: &[u8]) -> Vec<u8> {
+        let key = ring::hmac::Key::new(hmac_algorithm(hash), key);
+        ring::hmac::sign(&key, signing_input).as_ref().to_vec()
+    }
+
+    fn rsa_private_key(der: PrivateKeyDer) -> err::Result<RsaSigningKey> {
+        match der {
+            PrivateKeyDer::Pkcs8(der) => RsaKeyPair::from_pkcs8(der).ok(),
+            PrivateKeyDer::Pkcs1(der) => RsaKeyPair::from_der(der).ok(),
+            PrivateKeyDer::Sec1(_) => None,
+        }.map(|key| RsaSigningKey(Arc::new(key)))
+            .ok_or_else(|| key_error("an RSA private key of at least 2048 bits"))
+    }
+
+    fn rsa_public_key(der: PublicKeyDer) -> err::Result<RsaVerifyingKey> {
+        match der {
+            PublicKeyDer::Spki(der) => match spki(der) {
+                Some((RSA_ENCRYPTION, _, key)) => Some(key.to_vec()),
+                _ => None,
+            },
+            PublicKeyDer::Pkcs1(der) => Some(der.to_vec()),
+        }.map(RsaVerifyingKey).ok_or_else(|| key_error("an RSA public key"))
+    }
+
+    fn rsa_public_key_of(key: &RsaSigningKey) -> RsaVerifyingKey {
+        RsaVerifyingKey(key.0.public_key().as_ref().to_vec())
+    }
+
+    fn rsa_public_key_bits(key: &RsaVerifyingKey) -> usize {
+        let (modulus, _) = Ring::rsa_public_key_components(key);
+        // UintRef strips leading zero bytes, so the first byte is the most significant nonzero one.
+        match modulus.as_slice() {
+            [first, rest @ ..] => rest.len() * 8 + (8 - first.leading_zeros() as usize),
+            // A key which does not parse is rejected when it is used anyway.
+            _ => 0,
+        }
+    }
+
+    fn rsa_generate(_: usize) -> err::Result<Vec<u8>> {
+        // ring cannot generate RSA keys.
+        Err(err::JWTError::NotImplementedError)
+    }
+
+    fn rsa_public_key_components(key: &RsaVerifyingKey) -> (Vec<u8>, Vec<u8>) {
+        // The key is an RSAPublicKey: a SEQUENCE of the modulus and the public exponent.
+        let components = SliceReader::new(&key.0).and_then(|mut reader| {
+            reader.sequence(|reader| {
+                let modulus = UintRef::decode(reader)?.as_bytes().to_vec();
+                Ok((modulus, UintRef::decode(reader)?.as_bytes().to_vec()))
+            })
+        });
+        // A key which does not parse is rejected when it is used anyway.
+        components.unwrap_or_default()
+    }
+
+    fn rsa_sign(hash: Hash, key: &RsaSigningKey, signing_input: &[u8])
+        -> err::Result<Vec<u8>> {
+        let padding: &'static dyn signature::RsaEncoding = match hash {
+            Hash::Sha256 => &signature::RSA_PKCS1_SHA256,
+            Hash::Sha384 => &signature::RSA_PKCS1_SHA384,
+            Hash::Sha512 => &signature::RSA_PKCS1_SHA512,
+        };
+        let mut signature = vec![0; key.0.public().modulus_len()];
+        key.0.sign(padding, &SystemRandom::new(), signing_input, &mut signature)
+            .map_err(|_| { err::JWTError::ParseError(String::from("RSA signing failed")) })?;
+        Ok(signature)
+    }
+
+    fn verify_rsa(hash: Hash, key: &RsaVerifyingKey, signing_input: &[u8], signature: &[u8])
+        -> bool {
+        let parameters = match hash {
+            Hash::Sha256 => &signature::RSA_PKCS1_2048_8192_SHA256,
+            Hash::Sha384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+            Hash::Sha512 => &signature::RSA_PKCS1_2048_8192_SHA512,
+        };
+        UnparsedPublicKey::new(parameters, &key.0).verify(signing_input, signature).is_ok()
+    }
+
+    fn ec_private_key(der: PrivateKeyDer) -> err::Result<EcSigningKey> {
+        let key = [Alg::ES256, Alg::ES384].iter().find_map(|alg| {
+            ec_key_pair(*alg, der).map(|key| EcSigningKey::Nist(*alg, Arc::new(key)))
+        });
+        #[cfg(feature = "secp256k1")]
+        let key = key.or_else(|| super::secp256k1::private_key(der).map(EcSigningKey::K256));
+        key.ok_or_else(|| key_error("a P-256 or P-384 private key"))
+    }
+
+    fn ec_public_key(der: PublicKeyDer) -> err::Result<EcVerifyingKey> {
+        let key = match der {
+            PublicKeyDer::Spki(der) => match spki(der) {
+                Some((EC_PUBLIC_KEY, Some(curve), key)) => {
+                    curve_alg(curve).map(|alg| EcVerifyingKey::Nist(alg, key.to_vec()))
+                }
+                _ => None,
+            },
+            PublicKeyDer::Pkcs1(_) => None,
+        };
+        #[cfg(feature = "secp256k1")]
+        let key = key.or_else(|| super::secp256k1::public_key(der).map(EcVerifyingKey::K256));
+        key.ok_or_else(|| key_error("a P-256 or P-384 public key"))
+    }
+
+    fn ec_public_key_of(key: &EcSigningKey) -> EcVerifyingKey {
+        match key {
+            EcSigningKey::Nist(alg, key) => {
+                EcVerifyingKey::Nist(*alg, key.public_key().as_ref().to_vec())
+            }
+            #[cfg(feature = "secp256k1")]
+            EcSigningKey::K256(key) => EcVerifyingKey::K256(key.public_key()),
+        }
+    }
+
+    fn ec_alg(key: &EcVerifyingKey) -> Alg {
+        match key {
+            EcVerifyingKey::Nist(alg, _) => *alg,
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(_) => Alg::ES256K,
+        }
+    }
+
+    fn ec_public_key_point(key: &EcVerifyingKey) -> Vec<u8> {
+        match key {
+            EcVerifyingKey::Nist(_, key) => key.clone(),
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(key) => super::secp256k1::point(key),
+        }
+    }
+
+    fn ec_generate(alg: Alg) -> err::Result<Vec<u8>> {
+        match alg {
+            Alg::ES256 | Alg::ES384 => {
+                EcdsaKeyPair::generate_pkcs8(ecdsa_signing(alg), &SystemRandom::new())
+                    .map(|der| der.as_ref().to_vec())
+                    .map_err(generate_error)
+            }
+            #[cfg(feature = "secp256k1")]
+            Alg::ES256K => super::secp256k1::generate(),
+            _ => Err(err::JWTError::NotImplementedError),
+        }
+    }
+
+    fn ec_sign(key: &EcSigningKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        match key {
+            EcSigningKey::Nist(_, key) => key.sign(&SystemRandom::new(), signing_input)
+                .map(|signature| signature.as_ref().to_vec())
+                .map_err(|_| { err::JWTError::ParseError(String::from("ECDSA signing failed")) }),
+            #[cfg(feature = "secp256k1")]
+            EcSigningKey::K256(key) => Ok(super::secp256k1::sign(key, signing_input)),
+        }
+    }
+
+    fn verify_ec(key: &EcVerifyingKey, signing_input: &[u8], signature: &[u8]) -> bool {
+        match key {
+            EcVerifyingKey::Nist(alg, key) => UnparsedPublicKey::new(ecdsa_verification(*alg), key)
+                .verify(signing_input, signature).is_ok(),
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(key) => super::secp256k1::verify(key, signing_input, signature),
+        }
+    }
+
+    fn ed25519_private_key(der: PrivateKeyDer) -> err::Result<Ed25519SigningKey> {
+        match der {
+            // PKCS#8 v1 keys, which most tools write, do not include the public key.
+            PrivateKeyDer::Pkcs8(der) => Ed25519KeyPair::from_pkcs8_maybe_unchecked(der).ok(),
+            _ => None,
+        }.map(|key| Ed25519SigningKey(Arc::new(key)))
+            .ok_or_else(|| key_error("an Ed25519 private key"))
+    }
+
+    fn ed25519_private_key_from_seed(seed: &[u8; 32]) -> Ed25519SigningKey {
+        // Every 32-byte seed is a valid key.
+        Ed25519SigningKey(Arc::new(Ed25519KeyPair::from_seed_unchecked(seed).unwrap()))
+    }
+
+    fn ed25519_public_key(der: PublicKeyDer) -> err::Result<Ed25519VerifyingKey> {
+        let key = match der {
+            PublicKeyDer::Spki(der) => match spki(der) {
+                Some((ED25519, None, key)) => <[u8; 32]>::try_from(key).ok(),
+                _ => None,
+            },
+            PublicKeyDer::Pkcs1(_) => None,
+        };
+        key.map(Ed25519VerifyingKey).ok_or_else(|| key_error("an Ed25519 public key"))
+    }
+
+    fn ed25519_public_key_from_bytes(bytes: &[u8; 32]) -> err::Result<Ed25519VerifyingKey> {
+        // ring only checks that the bytes encode a point when verifying.
+        Ok(Ed25519VerifyingKey(*bytes))
+    }
+
+    fn ed25519_public_key_of(key: &Ed25519SigningKey) -> Ed25519VerifyingKey {
+        // Ed25519 public keys are 32 bytes long.
+        Ed25519VerifyingKey(<[u8; 32]>::try_from(key.0.public_key().as_ref()).unwrap())
+    }
+
+    fn ed25519_public_key_bytes(key: &Ed25519VerifyingKey) -> [u8; 32] {
+        key.0
+    }
+
+    fn ed25519_generate() -> err::Result<Vec<u8>> {
+        Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map(|der| der.as_ref().to_vec())
+            .map_err(generate_error)
+    }
+
+    fn eddsa_sign(key: &Ed25519SigningKey, signing_input: &[u8]) -> Vec<u8> {
+        key.0.sign(signing_input).as_ref().to_vec()
+    }
+
+    fn verify_eddsa(key: &Ed25519VerifyingKey, signing_input: &[u8], signature: &[u8])
+        -> bool {
+        UnparsedPublicKey::new(&signature::ED25519, &key.0)
+            .verify(signing_input, signature).is_ok()
+    }
+}
@@ -0,0 +1,291 @@
+//! The RustCrypto backend: pure Rust implementations of every algorithm.
+
+use hmac::{Hmac, Mac};
+use p256::ecdsa::signature::{Signer, Verifier};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey};
+use rand_core::{OsRng, RngCore};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::Pkcs1v15Sign;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use super::{generate_error, key_error, Backend, Hash};
+use crate::err;
+use crate::header::Alg;
+use crate::keys::{PrivateKeyDer, PublicKeyDer};
+
+pub(crate) struct RustCrypto;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EcSigningKey {
+    P256(p256::SecretKey),
+    P384(p384::SecretKey),
+    P521(p521::SecretKey),
+    #[cfg(feature = "secp256k1")]
+    K256(k256::SecretKey),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EcVerifyingKey {
+    P256(p256::PublicKey),
+    P384(p384::PublicKey),
+    P521(p521::PublicKey),
+    #[cfg(feature = "secp256k1")]
+    K256(k256::PublicKey),
+}
+
+// Computes the MAC of `signing_input` under `key`.
+fn sign<M: Mac + hmac::digest::KeyInit>(key: &[u8], signing_input: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length, so this cannot fail.
+    let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+    mac.update(signing_input);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// Returns the RSASSA-PKCS1-v1_5 padding scheme for `hash`, and the digest of `signing_input` it
+// signs.
+fn pkcs1v15(hash: Hash, signing_input: &[u8]) -> (Pkcs1v15Sign, Vec<u8>) {
+    match hash {
+        Hash::Sha256 => (Pkcs1v15Sign::new::<Sha256>(), Sha256::digest(signing_input).to_vec()),
+        Hash::Sha384 => (Pkcs1v15Sign::new::<Sha384>(), Sha384::digest(signing_input).to_vec()),
+        Hash::Sha512 => (Pkcs1v15Sign::new::<Sha512>(), Sha512::digest(signing_input).to_vec()),
+    }
+}
+
+impl Backend for RustCrypto {
+    type RsaPrivateKey = rsa::RsaPrivateKey;
+    type RsaPublicKey = rsa::RsaPublicKey;
+    type EcPrivateKey = EcSigningKey;
+    type EcPublicKey = EcVerifyingKey;
+    type Ed25519PrivateKey = ed25519_dalek::SigningKey;
+    type Ed25519PublicKey = ed25519_dalek::VerifyingKey;
+
+    #[cfg(test)]
+    const P521: bool = true;
+
+    fn hmac(hash: Hash, key: &[u8], signing_input: &[u8]) -> Vec<u8> {
+        match hash {
+            Hash::Sha256 => sign::<Hmac<Sha256>>(key, signing_input),
+            Hash::Sha384 => sign::<Hmac<Sha384>>(key, signing_input),
+            Hash::Sha512 => sign::<Hmac<Sha512>>(key, signing_input),
+        }
+    }
+
+    fn rsa_private_key(der: PrivateKeyDer) -> err::Result<rsa::RsaPrivateKey> {
+        match der {
+            PrivateKeyDer::Pkcs8(der) => rsa::RsaPrivateKey::from_pkcs8_der(der).ok(),
+            PrivateKeyDer::Pkcs1(der) => rsa::RsaPrivateKey::from_pkcs1_der(der).ok(),
+            PrivateKeyDer::Sec1(_) => None,
+        }.ok_or_else(|| key_error("an RSA private key"))
+    }
+
+    fn rsa_public_key(der: PublicKeyDer) -> err::Result<rsa::RsaPublicKey> {
+        match der {
+            PublicKeyDer::Spki(der) => rsa::RsaPublicKey::from_public_key_der(der).ok(),
+            PublicKeyDer::Pkcs1(der) => rsa::RsaPublicKey::from_pkcs1_der(der).ok(),
+        }.ok_or_else(|| key_error("an RSA public key"))
+    }
+
+    fn rsa_public_key_of(key: &rsa::RsaPrivateKey) -> rsa::RsaPublicKey {
+        key.to_public_key()
+    }
+
+    fn rsa_public_key_bits(key: &rsa::RsaPublicKey) -> usize {
+        key.n().bits()
+    }
+
+    fn rsa_public_key_components(key: &rsa::RsaPublicKey) -> (Vec<u8>, Vec<u8>) {
+        (key.n().to_bytes_be(), key.e().to_bytes_be())
+    }
+
+    fn rsa_generate(bits: usize) -> err::Result<Vec<u8>> {
+        let key = rsa::RsaPrivateKey::new(&mut OsRng, bits).map_err(generate_error)?;
+        Ok(key.to_pkcs8_der().map_err(generate_error)?.as_bytes().to_vec())
+    }
+
+    fn rsa_sign(hash: Hash, key: &rsa::RsaPrivateKey, signing_input: &[u8])
+        -> err::Result<Vec<u8>> {
+        let (scheme, digest) = pkcs1v15(hash, signing_input);
+        // Signing fails only if the key is too small for the digest, which is a property of the
+        // key.
+        key.sign(scheme, &digest).map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }
+
+    fn verify_rsa(hash: Hash, key: &rsa::RsaPublicKey, signing_input: &[u8], signature: &[u8])
+        -> bool {
+        let (scheme, digest) = pkcs1v15(hash, signing_input);
+        key.verify(scheme, &digest, signature).is_ok()
+    }
+
+    fn ec_private_key(der: PrivateKeyDer) -> err::Result<EcSigningKey> {
+        let key = match der {
+            PrivateKeyDer::Pkcs8(der) => p256::SecretKey::from_pkcs8_der(der)
+                .map(EcSigningKey::P256)
+                .or_else(|_| p384::SecretKey::from_pkcs8_der(der).map(EcSigningKey::P384))
+                .or_else(|_| p521::SecretKey::from_pkcs8_der(der).map(EcSigningKey::P521))
+                .ok(),
+            PrivateKeyDer::Sec1(der) => p256::SecretKey::from_sec1_der(der)
+                .map(EcSigningKey::P256)
+                .or_else(|_| p384::SecretKey::from_sec1_der(der).map(EcSigningKey::P384))
+                .or_else(|_| p521::SecretKey::from_sec1_der(der).map(EcSigningKey::P521))
+                .ok(),
+            PrivateKeyDer::Pkcs1(_) => None,
+        };
+        #[cfg(feature = "secp256k1")]
+        let key = key.or_else(|| super::secp256k1::private_key(der).map(EcSigningKey::K256));
+        key.ok_or_else(|| key_error("an elliptic curve private key"))
+    }
+
+    fn ec_public_key(der: PublicKeyDer) -> err::Result<EcVerifyingKey> {
+        let key = match der {
+            PublicKeyDer::Spki(der) => p256::PublicKey::from_public_key_der(der)
+                .map(EcVerifyingKey::P256)
+                .or_else(|_| p384::PublicKey::from_public_key_der(der).map(EcVerifyingKey::P384))
+                .or_else(|_| p521::PublicKey::from_public_key_der(der).map(EcVerifyingKey::P521))
+                .ok(),
+            PublicKeyDer::Pkcs1(_) => None,
+        };
+        #[cfg(feature = "secp256k1")]
+        let key = key.or_else(|| super::secp256k1::public_key(der).map(EcVerifyingKey::K256));
+        key.ok_or_else(|| key_error("an elliptic curve public key"))
+    }
+
+    fn ec_public_key_of(key: &EcSigningKey) -> EcVerifyingKey {
+        match key {
+            EcSigningKey::P256(key) => EcVerifyingKey::P256(key.public_key()),
+            EcSigningKey::P384(key) => EcVerifyingKey::P384(key.public_key()),
+            EcSigningKey::P521(key) => EcVerifyingKey::P521(key.public_key()),
+            #[cfg(feature = "secp256k1")]
+            EcSigningKey::K256(key) => EcVerifyingKey::K256(key.public_key()),
+        }
+    }
+
+    fn ec_alg(key: &EcVerifyingKey) -> Alg {
+        match key {
+            EcVerifyingKey::P256(_) => Alg::ES256,
+            EcVerifyingKey::P384(_) => Alg::ES384,
+            EcVerifyingKey::P521(_) => Alg::ES512,
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(_) => Alg::ES256K,
+        }
+    }
+
+    fn ec_public_key_point(key: &EcVerifyingKey) -> Vec<u8> {
+        match key {
+            EcVerifyingKey::P256(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+            EcVerifyingKey::P384(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+            EcVerifyingKey::P521(key) => key.to_encoded_point(false).as_bytes().to_vec(),
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(key) => super::secp256k1::point(key),
+        }
+    }
+
+    fn ec_generate(alg: Alg) -> err::Result<Vec<u8>> {
+        let der = match alg {
+            Alg::ES256 => p256::SecretKey::random(&mut OsRng).to_pkcs8_der(),
+            Alg::ES384 => p384::SecretKey::random(&mut OsRng).to_pkcs8_der(),
+            Alg::ES512 => p521::SecretKey::random(&mut OsRng).to_pkcs8_der(),
+            #[cfg(feature = "secp256k1")]
+            Alg::ES256K => return super::secp256k1::generate(),
+            _ => return Err(err::JWTError::NotImplementedError),
+        };
+        Ok(der.map_err(generate_error)?.as_bytes().to_vec())
+    }
+
+    fn ec_sign(key: &EcSigningKey, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        let signature = match key {
+            EcSigningKey::P256(key) => {
+                let signature: p256::ecdsa::Signature =
+                    p256::ecdsa::SigningKey::from(key).sign(signing_input);
+                signature.to_bytes().to_vec()
+            }
+            EcSigningKey::P384(key) => {
+                let signature: p384::ecdsa::Signature =
+                    p384::ecdsa::SigningKey::from(key).sign(signing_input);
+                signature.to_bytes().to_vec()
+            }
+            EcSigningKey::P521(key) => {
+                // A valid secret key is a valid signing key.
+                let signature: p521::ecdsa::Signature =
+                    p521::ecdsa::SigningKey::from_bytes(&key.to_bytes()).unwrap()
+                        .sign(signing_input);
+                signature.to_bytes().to_vec()
+            }
+            #[cfg(feature = "secp256k1")]
+            EcSigningKey::K256(key) => super::secp256k1::sign(key, signing_input),
+        };
+        Ok(signature)
+    }
+
+    fn verify_ec(key: &EcVerifyingKey, signing_input: &[u8], signature: &[u8]) -> bool {
+        match key {
+            EcVerifyingKey::P256(key) => p256::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| {
+                    p256::ecdsa::VerifyingKey::from(key).verify(signing_input, &signature).is_ok()
+                }),
+            EcVerifyingKey::P384(key) => p384::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| {
+                    p384::ecdsa::VerifyingKey::from(key).verify(signing_input, &signature).is_ok()
+                }),
+            EcVerifyingKey::P521(key) => p521::ecdsa::Signature::from_slice(signature)
+                .is_ok_and(|signature| {
+                    // A valid public key is a valid verifying key.
+                    p521::ecdsa::VerifyingKey::from_affine(*key.as_affine()).unwrap()
+                        .verify(signing_input, &signature).is_ok()
+                }),
+            #[cfg(feature = "secp256k1")]
+            EcVerifyingKey::K256(key) => super::secp256k1::verify(key, signing_input, signature),
+        }
+    }
+
+    fn ed25519_private_key(der: PrivateKeyDer) -> err::Result<ed25519_dalek::SigningKey> {
+        match der {
+            PrivateKeyDer::Pkcs8(der) => ed25519_dalek::SigningKey::from_pkcs8_der(der).ok(),
+            _ => None,
+        }.ok_or_else(|| key_error("an Ed25519 private key"))
+    }
+
+    fn ed25519_private_key_from_seed(seed: &[u8; 32]) -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::from_bytes(seed)
+    }
+
+    fn ed25519_public_key(der: PublicKeyDer) -> err::Result<ed25519_dalek::VerifyingKey> {
+        match der {
+            PublicKeyDer::Spki(der) => ed25519_dalek::VerifyingKey::from_public_key_der(der).ok(),
+            PublicKeyDer::Pkcs1(_) => None,
+        }.ok_or_else(|| key_error("an Ed25519 public key"))
+    }
+
+    fn ed25519_public_key_from_bytes(bytes: &[u8; 32])
+        -> err::Result<ed25519_dalek::VerifyingKey> {
+        ed25519_dalek::VerifyingKey::from_bytes(bytes)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }
+
+    fn ed25519_public_key_of(key: &ed25519_dalek::SigningKey) -> ed25519_dalek::VerifyingKey {
+        key.verifying_key()
+    }
+
+    fn ed25519_public_key_bytes(key: &ed25519_dalek::VerifyingKey) -> [u8; 32] {
+        key.to_bytes()
+    }
+
+    fn ed25519_generate() -> err::Result<Vec<u8>> {
+        let mut seed = [0; 32];
+        OsRng.try_fill_bytes(&mut seed).map_err(generate_error)?;
+        let der = ed25519_dalek::SigningKey::from_bytes(&seed).to_pkcs8_der()
+            .map_err(generate_error)?;
+        Ok(der.as_bytes().to_vec())
+    }
+
+    fn eddsa_sign(key: &ed25519_dalek::SigningKey, signing_input: &[u8]) -> Vec<u8> {
+        key.sign(signing_input).to_bytes().to_vec()
+    }
+
+    fn verify_eddsa(key: &ed25519_dalek::VerifyingKey, signing_input: &[u8], signature: &[u8])
+        -> bool {
+        ed25519_dalek::Signature::from_slice(signature)
+            .is_ok_and(|signature| key.verify_strict(signing_input, &signature).is_ok())
+    }
+}
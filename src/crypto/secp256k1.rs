@@ -0,0 +1,50 @@
+//! ES256K, which every backend implements with the k256 crate, as `ring` does not support
+//! secp256k1.
+
+use k256::ecdsa::signature::{Signer, Verifier};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::pkcs8::{DecodePrivateKey, DecodePublicKey, EncodePrivateKey};
+use rand_core::OsRng;
+
+use super::generate_error;
+use crate::err;
+
+use crate::keys::{PrivateKeyDer, PublicKeyDer};
+
+pub(crate) fn private_key(der: PrivateKeyDer) -> Option<k256::SecretKey> {
+    match der {
+        PrivateKeyDer::Pkcs8(der) => k256::SecretKey::from_pkcs8_der(der).ok(),
+        PrivateKeyDer::Sec1(der) => k256::SecretKey::from_sec1_der(der).ok(),
+        PrivateKeyDer::Pkcs1(_) => None,
+    }
+}
+
+pub(crate) fn public_key(der: PublicKeyDer) -> Option<k256::PublicKey> {
+    match der {
+        PublicKeyDer::Spki(der) => k256::PublicKey::from_public_key_der(der).ok(),
+        PublicKeyDer::Pkcs1(_) => None,
+    }
+}
+
+pub(crate) fn generate() -> err::Result<Vec<u8>> {
+    let der = k256::SecretKey::random(&mut OsRng).to_pkcs8_der().map_err(generate_error)?;
+    Ok(der.as_bytes().to_vec())
+}
+
+pub(crate) fn point(key: &k256::PublicKey) -> Vec<u8> {
+    key.to_encoded_point(false).as_bytes().to_vec()
+}
+
+pub(crate) fn sign(key: &k256::SecretKey, signing_input: &[u8]) -> Vec<u8> {
+    let signature: k256::ecdsa::Signature = k256::ecdsa::SigningKey::from(key).sign(signing_input);
+    signature.to_bytes().to_vec()
+}
+
+pub(crate) fn verify(key: &k256::PublicKey, signing_input: &[u8], signature: &[u8]) -> bool {
+    k256::ecdsa::Signature::from_slice(signature).is_ok_and(|signature| {
+        // k256 only accepts signatures with a low S, as Bitcoin requires, but RFC 8812 allows
+        // either.
+        let signature = signature.normalize_s().unwrap_or(signature);
+        k256::ecdsa::VerifyingKey::from(key).verify(signing_input, &signature).is_ok()
+    })
+}
@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::config::DuplicatePolicy;
+
+/// The header parameters registered by RFC 7515 section 4.1 (including `typ` and `cty`, whose use
+/// in JWTs RFC 7519 section 5 describes).
+pub const REGISTERED_HEADER_PARAMETERS: &[&str; 11] = &[
+    "alg", "jku", "jwk", "kid", "x5u", "x5c", "x5t", "x5t#S256", "typ", "cty", "crit",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+/// A **Diagnostic** is a non-fatal observation made while decoding a token: something which was
+/// accepted, but which suggests that the issuer is not producing tokens quite as specified. See
+/// `JWT::decode_b64_with_diagnostics`.
+pub enum Diagnostic {
+    /// Whitespace in the token was ignored under `Strictness::Lenient`.
+    WhitespaceIgnored,
+    /// The named component ("header" or "claims") was base64-encoded with padding, which RFC 7515
+    /// omits.
+    PaddedBase64(&'static str),
+    /// The header contains a parameter which is not registered by RFC 7515.
+    UnknownHeaderParameter(String),
+    /// The claim set contained the named claim more than once, and the duplicate was resolved by
+    /// the configured policy.
+    DuplicateClaim(String, DuplicatePolicy),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::WhitespaceIgnored => write!(f, "whitespace in the token was ignored"),
+            Diagnostic::PaddedBase64(component) => {
+                write!(f, "the {} component uses padded base64", component)
+            }
+            Diagnostic::UnknownHeaderParameter(name) => {
+                write!(f, "unknown header parameter \"{}\"", name)
+            }
+            Diagnostic::DuplicateClaim(name, policy) => {
+                let kept = if *policy == DuplicatePolicy::Last { "last" } else { "first" };
+                write!(f, "claim \"{}\" appears more than once; the {} was kept", name, kept)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Diagnostic::DuplicateClaim(String::from("sub"), DuplicatePolicy::Last).to_string(),
+            "claim \"sub\" appears more than once; the last was kept"
+        );
+        assert_eq!(
+            Diagnostic::PaddedBase64("claims").to_string(),
+            "the claims component uses padded base64"
+        );
+    }
+}
@@ -4,7 +4,38 @@ use std::{error::Error, fmt, result};
 pub enum JWTError {
     ParseError(String),
     SchemaError,
-    NotImplementedError
+    NotImplementedError,
+    HttpError(String),
+    IoError(String),
+    ClaimTypeError(String),
+    SignatureError,
+    /// The verification key is weaker than the verifier's policy allows.
+    KeyTooWeak(String),
+    /// The token's `kid` header parameter names no key the verifier holds. The issuer may have
+    /// rotated its keys, so fetching them again may find it.
+    UnknownKid(String),
+    ValidationError(ValidationError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The reason a claim set failed a rule of a `claims::Validator`.
+pub enum ValidationError {
+    /// A claim which a rule requires is absent.
+    MissingClaim(String),
+    /// A claim does not have the value a rule requires.
+    InvalidClaim(String),
+    /// The `exp` claim is in the past.
+    Expired,
+    /// The `nbf` claim is in the future.
+    NotYetValid,
+    /// The `iat` claim is further in the past than the validator's maximum token age.
+    TooOld,
+    /// The token's `jti` has been seen before, and it may only be used once.
+    Replayed,
+    /// A header parameter is absent, or does not have the value a rule requires.
+    InvalidHeader(String),
+    /// The encoded token is larger than the validator allows.
+    TooLarge,
 }
 
 // Cf https://stackoverflow.com/questions/42584368/how-do-you-define-custom-error-types-in-rust
@@ -21,9 +52,103 @@ impl fmt::Display for JWTError {
             JWTError::NotImplementedError => {
                 write!(f, "Not implemented.")
             }
+            JWTError::HttpError(e) => {
+                write!(f, "HTTP request failed with:\n{}", e)
+            }
+            JWTError::IoError(e) => {
+                write!(f, "I/O failed with:\n{}", e)
+            }
+            JWTError::ClaimTypeError(e) => {
+                write!(f, "Invalid registered claim value:\n{}", e)
+            }
+            JWTError::SignatureError => {
+                write!(f, "Signature verification failed.")
+            }
+            JWTError::KeyTooWeak(e) => {
+                write!(f, "The verification key is too weak: {}", e)
+            }
+            JWTError::UnknownKid(kid) => {
+                write!(f, "No verification key has the key ID \"{}\".", kid)
+            }
+            JWTError::ValidationError(e) => {
+                write!(f, "Claim validation failed: {}", e)
+            }
         }
     }
 }
+
+impl Error for ValidationError {}
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationError::MissingClaim(claim_name) => {
+                write!(f, "missing required claim \"{}\".", claim_name)
+            }
+            ValidationError::InvalidClaim(claim_name) => {
+                write!(f, "claim \"{}\" has an unacceptable value.", claim_name)
+            }
+            ValidationError::Expired => {
+                write!(f, "the token has expired.")
+            }
+            ValidationError::NotYetValid => {
+                write!(f, "the token is not valid yet.")
+            }
+            ValidationError::TooOld => {
+                write!(f, "the token was issued too long ago.")
+            }
+            ValidationError::Replayed => {
+                write!(f, "the token has already been used.")
+            }
+            ValidationError::InvalidHeader(name) => {
+                write!(f, "header parameter \"{}\" is missing or has an unacceptable value.", name)
+            }
+            ValidationError::TooLarge => {
+                write!(f, "the token is too large.")
+            }
+        }
+    }
+}
+
+impl From<ValidationError> for JWTError {
+    fn from(e: ValidationError) -> JWTError {
+        JWTError::ValidationError(e)
+    }
+}
+
+impl JWTError {
+    /// Returns a short, stable, machine-readable identifier for the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            JWTError::ParseError(_) => "parse-error",
+            JWTError::SchemaError => "schema-error",
+            JWTError::NotImplementedError => "not-implemented",
+            JWTError::HttpError(_) => "http-error",
+            JWTError::IoError(_) => "io-error",
+            JWTError::ClaimTypeError(_) => "claim-type-error",
+            JWTError::SignatureError => "signature-error",
+            JWTError::KeyTooWeak(_) => "key-too-weak",
+            JWTError::UnknownKid(_) => "unknown-kid",
+            JWTError::ValidationError(e) => e.code(),
+        }
+    }
+}
+
+impl ValidationError {
+    /// Returns a short, stable, machine-readable identifier for the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::MissingClaim(_) => "missing-claim",
+            ValidationError::InvalidClaim(_) => "invalid-claim",
+            ValidationError::Expired => "expired",
+            ValidationError::NotYetValid => "not-yet-valid",
+            ValidationError::TooOld => "too-old",
+            ValidationError::Replayed => "replayed",
+            ValidationError::InvalidHeader(_) => "invalid-header",
+            ValidationError::TooLarge => "too-large",
+        }
+    }
+}
+
 // Result aliasing is a common technique for managing the type of errors specific to your library.
 // Cf https://blog.burntsushi.net/rust-error-handling/#the-result-type-alias-idiom
 pub type Result<T> = result::Result<T, JWTError>;
\ No newline at end of file
@@ -0,0 +1,115 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::UNIX_EPOCH;
+
+use crate::traits::Clock;
+
+// The number of entries below which expired ones are never purged.
+pub(crate) const MIN_PURGE_LEN: usize = 1024;
+
+#[derive(Debug)]
+struct Entries<K, V> {
+    // Each entry, with the time in seconds since the epoch at which it expires.
+    map: HashMap<K, (f64, V)>,
+    // The number of entries at which expired ones are next purged.
+    purge_at: usize,
+}
+
+impl<K: Eq + Hash, V> Entries<K, V> {
+    fn insert(&mut self, now: f64, key: K, expiry: f64, value: V) {
+        self.map.insert(key, (expiry, value));
+        if self.map.len() >= self.purge_at {
+            self.map.retain(|_, (expiry, _)| *expiry > now);
+            self.purge_at = MIN_PURGE_LEN.max(self.map.len() * 2);
+        }
+    }
+}
+
+// A map whose entries each expire at a given time, told by a `Clock`, for the in-memory stores
+// which remember tokens until they expire. Expired entries are never returned, and are purged
+// whenever the map has doubled in size since the last purge.
+pub(crate) struct ExpiringMap<K, V> {
+    entries: Mutex<Entries<K, V>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<K: Eq + Hash, V> ExpiringMap<K, V> {
+    // Constructs an empty map, which tells expired entries by `clock`.
+    pub(crate) fn with_clock<C: Clock + 'static>(clock: C) -> ExpiringMap<K, V> {
+        ExpiringMap {
+            entries: Mutex::new(Entries { map: HashMap::new(), purge_at: MIN_PURGE_LEN }),
+            clock: Arc::new(clock),
+        }
+    }
+
+    // Returns the number of entries held, including expired ones not yet purged.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().map.len()
+    }
+
+    // Returns the current time, in seconds since the epoch.
+    pub(crate) fn now(&self) -> f64 {
+        self.clock.now().duration_since(UNIX_EPOCH).map(|d| d.as_secs_f64()).unwrap_or(0.0)
+    }
+
+    // Applies `f` to the value of `key`, unless it is absent or has expired.
+    pub(crate) fn get<Q, R, F>(&self, key: &Q, f: F) -> Option<R>
+        where K: Borrow<Q>, Q: Eq + Hash + ?Sized, F: FnOnce(&V) -> R {
+        let now = self.now();
+        self.lock().map.get(key).filter(|(expiry, _)| *expiry > now).map(|(_, value)| f(value))
+    }
+
+    // Inserts `value` for `key` until `expiry`, replacing any previous value.
+    pub(crate) fn insert(&self, key: K, expiry: f64, value: V) {
+        let now = self.now();
+        self.lock().insert(now, key, expiry, value);
+    }
+
+    // Inserts `value` for `key` until `expiry`, unless `key` holds a value which has not expired.
+    // Returns whether it did.
+    pub(crate) fn insert_new(&self, key: K, expiry: f64, value: V) -> bool {
+        let now = self.now();
+        let mut entries = self.lock();
+        if entries.map.get(&key).is_some_and(|(expiry, _)| *expiry > now) {
+            return false
+        }
+        entries.insert(now, key, expiry, value);
+        true
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries<K, V>> {
+        // The entries are consistent whenever the lock is released, even by a panicking thread.
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+// A clock which tests can move.
+#[cfg(test)]
+pub(crate) struct TestClock(pub(crate) Arc<Mutex<u64>>);
+
+#[cfg(test)]
+impl Clock for TestClock {
+    fn now(&self) -> std::time::SystemTime {
+        UNIX_EPOCH + std::time::Duration::from_secs(*self.0.lock().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry() {
+        let now = Arc::new(Mutex::new(100));
+        let map = ExpiringMap::with_clock(TestClock(now.clone()));
+        map.insert("a", 110.0, 1);
+        assert_eq!(map.get("a", |value| *value), Some(1));
+        assert!(!map.insert_new("a", 120.0, 2));
+        *now.lock().unwrap() = 110;
+        assert_eq!(map.get("a", |value| *value), None);
+        assert!(map.insert_new("a", 120.0, 2));
+        assert_eq!(map.get("a", |value| *value), Some(2));
+    }
+}
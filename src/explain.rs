@@ -0,0 +1,472 @@
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::claims::Validator;
+use crate::keys::VerificationKey;
+use crate::verify::VerifyOptions;
+use crate::{compression, err, header, ParseConfig, JWT};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// One of the three segments of a token, as it was encoded and, for the header and the claim
+/// set, as JSON. `decoded` is `None` for the signature, and for a segment which did not decode.
+pub struct Segment {
+    pub name: &'static str,
+    pub encoded: String,
+    pub decoded: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A header parameter, with a description of what it means and, if it calls for caution, a
+/// warning.
+pub struct HeaderParameter {
+    pub name: String,
+    pub value: Value,
+    pub meaning: String,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    /// The rule could not be checked, or does not apply to the token.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// The outcome of checking one rule against a token, and why.
+pub struct Rule {
+    pub name: &'static str,
+    pub outcome: Outcome,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// A structured, human-readable breakdown of a token; see `explain`.
+pub struct Explanation {
+    pub segments: Vec<Segment>,
+    pub header: Vec<HeaderParameter>,
+    pub rules: Vec<Rule>,
+    /// The irregularities tolerated while decoding; see `JWT::decode_b64_with_diagnostics`.
+    pub diagnostics: Vec<String>,
+}
+
+/// Explains the base64-encoded `token`, verifying its signature under `key` if one is given and
+/// checking it against the rules of `validator`, whose clock and leeway the time claims are
+/// evaluated with. Segments are decoded leniently and independently, so that a token which does
+/// not decode as a whole can still be explained; the `decode` rule reports whether it does, and
+/// the rules which need the decoded token are skipped if it does not.
+///
+/// The `signature` rule is skipped for a secured token if no `key` is given, and fails for an
+/// unsecured one, or if `key` is weaker than `verify::VerifyOptions` allows by default. The `jti`
+/// of the token is never recorded in the validator's replay store, so explaining a token does not
+/// use it up. Returns a `err::JWTError::SchemaError` only if `token` does not have three segments.
+///
+/// # Examples
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use jwt::{JsonSerializable, JWT};
+/// use jwt::claims::Validator;
+/// use jwt::explain::{self, Outcome};
+/// use jwt::header::Alg;
+/// use jwt::traits::FixedClock;
+///
+/// let mut jwt = JWT::from_plain_str("{\"exp\": 100, \"aud\": \"web\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// let key = [7; 32];
+/// let token = jwt.sign(&key).unwrap();
+///
+/// let validator = Validator::new()
+///     .audience("api")
+///     .clock(FixedClock(UNIX_EPOCH + Duration::from_secs(200)));
+/// let explanation = explain::explain(&token, Some((&key[..]).into()), &validator).unwrap();
+/// let outcome = |name| explanation.rules.iter().find(|rule| rule.name == name).unwrap().outcome;
+/// assert_eq!(outcome("signature"), Outcome::Pass);
+/// assert_eq!(outcome("exp"), Outcome::Fail);
+/// assert_eq!(outcome("claims"), Outcome::Fail);
+/// ```
+pub fn explain(token: &str, key: Option<VerificationKey>, validator: &Validator)
+    -> err::Result<Explanation> {
+    let components = JWT::split_into_components(token)?;
+    let config = ParseConfig::lenient();
+
+    let header = config.decode_base64(&components[0]).and_then(|header| {
+        serde_json::from_str::<Value>(&header)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }).ok();
+    let claims = decode_claims(&components[1], header.as_ref(), &config).ok();
+
+    let header_object = header.as_ref().and_then(Value::as_object).cloned().unwrap_or_default();
+    let claims_object = claims.as_ref().and_then(Value::as_object).cloned().unwrap_or_default();
+
+    let mut rules = Vec::<Rule>::new();
+    let mut diagnostics = Vec::<String>::new();
+    let jwt = match JWT::decode_b64_with_diagnostics(token, &config) {
+        Ok((jwt, found)) => {
+            rules.push(rule("decode", Outcome::Pass, String::from("the token decodes")));
+            diagnostics = found.iter().map(ToString::to_string).collect();
+            // Its signature is checked by the "signature" rule, and its claims only reported.
+            Some(jwt.dangerously_assume_verified())
+        }
+        Err(e) => {
+            rules.push(rule("decode", Outcome::Fail, e.to_string()));
+            None
+        }
+    };
+    rules.push(signature_rule(&header_object, jwt.as_ref(), key));
+    if let (Some(crit), Some(header)) = (header_object.get("crit"), &header) {
+        rules.push(crit_rule(crit, header));
+    }
+    let now = validator.clock.now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs());
+    rules.append(&mut time_rules(&claims_object, now, validator.leeway.as_secs()));
+    rules.append(&mut validator_rules(jwt.as_ref(), &components, validator));
+
+    let segments = vec![
+        Segment { name: "header", encoded: components[0].clone(), decoded: header },
+        Segment { name: "claims", encoded: components[1].clone(), decoded: claims },
+        Segment { name: "signature", encoded: components[2].clone(), decoded: None },
+    ];
+    Ok(Explanation { segments, header: header_parameters(&header_object), rules, diagnostics })
+}
+
+/// Interprets each parameter of the decoded JOSE header `header`.
+pub fn header_parameters(header: &Map<String, Value>) -> Vec<HeaderParameter> {
+    header.iter()
+        .map(|(name, value)| {
+            let (meaning, warning) = interpret(name, value);
+            HeaderParameter { name: name.clone(), value: value.clone(), meaning, warning }
+        })
+        .collect()
+}
+
+// Describes the header parameter `name`, warning about it if it calls for caution.
+fn interpret(name: &str, value: &Value) -> (String, Option<String>) {
+    let meaning = |meaning: &str| String::from(meaning);
+    match name {
+        "alg" => match value.as_str() {
+            Some(alg) if alg.eq_ignore_ascii_case("none") => (
+                meaning("the token is not signed"),
+                Some(format!(
+                    "alg is \"{}\": the token is unsecured and anyone could have produced it", alg
+                )),
+            ),
+            Some(alg) => (format!("the token is signed with {}", alg), None),
+            None => (meaning("the signing algorithm, which must be a string"), None),
+        },
+        "typ" => (meaning("the media type of the token"), None),
+        "cty" if value == "deflate" => (meaning("the claim set is DEFLATE-compressed"), None),
+        "cty" => (meaning("the media type of the payload"), None),
+        "kid" => (meaning("identifies the key the token was signed with"), None),
+        "jwk" => (
+            meaning("the public key the token was signed with"),
+            Some(String::from(
+                "embedded jwk: the token carries its own verification key, which must not be \
+                 trusted"
+            )),
+        ),
+        "jku" | "x5u" => (
+            if name == "jku" {
+                meaning("the URL of a JWK Set containing the signing key")
+            } else {
+                meaning("the URL of the X.509 certificate chain of the signing key")
+            },
+            Some(format!(
+                "{} present: never fetch keys from token-supplied URLs unless allowlisted", name
+            )),
+        ),
+        "x5c" => (meaning("the X.509 certificate chain of the signing key"), None),
+        "x5t" => (meaning("the SHA-1 thumbprint of the signing key's X.509 certificate"), None),
+        "x5t#S256" => {
+            (meaning("the SHA-256 thumbprint of the signing key's X.509 certificate"), None)
+        }
+        "crit" => (
+            meaning("extensions the recipient must understand to accept the token"),
+            match value.as_array() {
+                Some(crit) if !crit.is_empty() => {
+                    let unknown = crit.iter()
+                        .filter(|name| {
+                            !name.as_str()
+                                .is_some_and(|name| header::UNDERSTOOD_CRIT.contains(&name))
+                        })
+                        .cloned()
+                        .collect::<Vec<Value>>();
+                    if unknown.is_empty() {
+                        None
+                    } else {
+                        Some(format!(
+                            "unknown critical extension(s) {}: compliant verifiers must reject \
+                             this token", Value::from(unknown)
+                        ))
+                    }
+                }
+                _ => Some(String::from("crit is not a non-empty array of names")),
+            },
+        ),
+        _ => (meaning("a parameter not registered by RFC 7515"), None),
+    }
+}
+
+// Decodes the claims segment of a token with the decoded `header` into JSON. The segment is the
+// claim set as it is if the header has b64: false (RFC 7797), and is otherwise base64-encoded and
+// possibly compressed.
+fn decode_claims(segment: &str, header: Option<&Value>, config: &ParseConfig)
+    -> err::Result<Value> {
+    let param = |name: &str| header.and_then(|header| header.get(name));
+    let mut payload = if param("b64") == Some(&Value::Bool(false)) {
+        segment.as_bytes().to_vec()
+    } else {
+        config.decode_base64_bytes(segment)?
+    };
+    if param("cty") == Some(&json!("deflate")) {
+        payload = compression::inflate(&payload, config.max_decompressed_size)?;
+    }
+    serde_json::from_slice(&payload).map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+fn rule(name: &'static str, outcome: Outcome, reason: String) -> Rule {
+    Rule { name, outcome, reason }
+}
+
+// Checks the crit header parameter as decoding does, with no extensions marked as handled but
+// those the crate understands itself.
+fn crit_rule(crit: &Value, header: &Value) -> Rule {
+    match header::check_crit(header, &ParseConfig::lenient()) {
+        Ok(()) => rule("crit", Outcome::Pass, format!(
+            "every critical extension listed, {}, is understood", crit
+        )),
+        Err(err::JWTError::NotImplementedError) => rule("crit", Outcome::Fail, format!(
+            "the token lists critical extensions which are not understood: {}", crit
+        )),
+        Err(_) => rule("crit", Outcome::Fail, format!(
+            "crit is not a non-empty array of the names of extension parameters the header \
+             carries: {}", crit
+        )),
+    }
+}
+
+// Checks the signature of the decoded `jwt` under `key`, if both are at hand.
+fn signature_rule(header: &Map<String, Value>, jwt: Option<&JWT>, key: Option<VerificationKey>)
+    -> Rule {
+    match (header.get("alg").and_then(Value::as_str), jwt, key) {
+        (None, _, _) => {
+            rule("signature", Outcome::Fail, String::from("the header has no string alg"))
+        }
+        (Some(alg), _, _) if alg.eq_ignore_ascii_case("none") => rule(
+            "signature", Outcome::Fail, String::from("the token is unsecured")
+        ),
+        (Some(_), _, None) => rule(
+            "signature", Outcome::Skipped, String::from("no key was given to verify it with")
+        ),
+        (Some(_), None, Some(_)) => rule(
+            "signature", Outcome::Skipped, String::from("the token does not decode")
+        ),
+        (Some(_), Some(jwt), Some(key)) => {
+            // The token's own algorithm is allowed, but the key must meet the usual minimums.
+            match jwt.verify(key, &VerifyOptions::new(&[jwt.header.alg])) {
+                Ok(_) => rule("signature", Outcome::Pass, String::from("the signature is valid")),
+                Err(e) => rule("signature", Outcome::Fail, e.to_string()),
+            }
+        }
+    }
+}
+
+// Checks the exp, nbf and iat claims against `now`, allowing `leeway` seconds of clock skew as
+// claims::Validator does.
+fn time_rules(claims: &Map<String, Value>, now: u64, leeway: u64) -> Vec<Rule> {
+    let mut rules = Vec::<Rule>::new();
+    match claims.get("exp").map(Value::as_u64) {
+        None => rules.push(rule("exp", Outcome::Skipped, String::from(
+            "no exp claim: the token never expires"
+        ))),
+        Some(None) => rules.push(rule("exp", Outcome::Fail, String::from(
+            "exp is not a valid NumericDate"
+        ))),
+        Some(Some(exp)) if exp.saturating_add(leeway) <= now => {
+            rules.push(rule("exp", Outcome::Fail, format!(
+                "the token expired at {}, {}s before {}", exp, now - exp, now
+            )))
+        }
+        Some(Some(exp)) if exp <= now => rules.push(rule("exp", Outcome::Pass, format!(
+            "the token expired at {}, {}s before {}, within the leeway of {}s", exp, now - exp,
+            now, leeway
+        ))),
+        Some(Some(exp)) => rules.push(rule("exp", Outcome::Pass, format!(
+            "the token expires at {}, {}s after {}", exp, exp - now, now
+        ))),
+    }
+    match claims.get("nbf").map(Value::as_u64) {
+        None => (),
+        Some(None) => rules.push(rule("nbf", Outcome::Fail, String::from(
+            "nbf is not a valid NumericDate"
+        ))),
+        Some(Some(nbf)) if nbf > now.saturating_add(leeway) => {
+            rules.push(rule("nbf", Outcome::Fail, format!(
+                "the token is not valid until {}, {}s after {}", nbf, nbf - now, now
+            )))
+        }
+        Some(Some(nbf)) if nbf > now => rules.push(rule("nbf", Outcome::Pass, format!(
+            "the token is valid from {}, {}s after {}, within the leeway of {}s", nbf, nbf - now,
+            now, leeway
+        ))),
+        Some(Some(nbf)) => rules.push(rule("nbf", Outcome::Pass, format!(
+            "the token has been valid since {}", nbf
+        ))),
+    }
+    match claims.get("iat").map(Value::as_u64) {
+        None => (),
+        Some(None) => rules.push(rule("iat", Outcome::Fail, String::from(
+            "iat is not a valid NumericDate"
+        ))),
+        Some(Some(iat)) if iat > now.saturating_add(leeway) => {
+            rules.push(rule("iat", Outcome::Fail, format!(
+                "the token was issued in the future, {}s after {}", iat - now, now
+            )))
+        }
+        Some(Some(iat)) => rules.push(rule("iat", Outcome::Pass, format!(
+            "the token was issued at {}", iat
+        ))),
+    }
+    rules
+}
+
+// Checks the decoded `jwt`, whose segments are `components`, against `validator`: the size of
+// the token, its header, and every rule on its claim set but the replay check.
+fn validator_rules(jwt: Option<&JWT>, components: &[String], validator: &Validator)
+    -> Vec<Rule> {
+    let jwt = match jwt {
+        Some(jwt) => jwt,
+        None => return vec![
+            rule("header", Outcome::Skipped, String::from("the token does not decode")),
+            rule("claims", Outcome::Skipped, String::from("the token does not decode")),
+        ],
+    };
+    let mut rules = Vec::<Rule>::new();
+    if let Some(max_size) = validator.max_token_size {
+        let size = components.iter().map(String::len).sum::<usize>() + 2;
+        rules.push(if size > max_size {
+            rule("size", Outcome::Fail, format!(
+                "the token is {} bytes, over the limit of {}", size, max_size
+            ))
+        } else {
+            rule("size", Outcome::Pass, format!("the token is {} bytes", size))
+        });
+    }
+    rules.push(match validator.validate_header(&jwt.header) {
+        Ok(()) => rule("header", Outcome::Pass, String::from("the header passes every rule")),
+        Err(e) => rule("header", Outcome::Fail, e.to_string()),
+    });
+    let validator = Validator { replay_store: None, ..validator.clone() };
+    let report = validator.validate_all(&jwt.claim_set);
+    rules.push(if report.is_valid() {
+        rule("claims", Outcome::Pass, String::from("the claim set passes every rule"))
+    } else {
+        let failures = report.failures.iter().map(ToString::to_string).collect::<Vec<String>>();
+        rule("claims", Outcome::Fail, failures.join(" "))
+    });
+    rules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::claims::ClaimSet;
+    use crate::header::Alg;
+    use crate::traits::{FixedClock, JsonSerializable};
+
+    fn at(now: u64) -> Validator {
+        Validator::new().clock(FixedClock(UNIX_EPOCH + Duration::from_secs(now)))
+    }
+
+    fn outcome(explanation: &Explanation, name: &str) -> Option<Outcome> {
+        explanation.rules.iter().find(|rule| rule.name == name).map(|rule| rule.outcome)
+    }
+
+    #[test]
+    fn test_explain() {
+        let mut jwt = JWT::new();
+        jwt.claim_set = ClaimSet::decode_str("{\"exp\": 100, \"nbf\": 50}").unwrap();
+        let explanation = explain(&jwt.encode_b64(), None, &at(40)).unwrap();
+
+        assert_eq!(explanation.segments[0].decoded, Some(json!({"alg": "none"})));
+        assert_eq!(explanation.segments[1].decoded, Some(json!({"exp": 100, "nbf": 50})));
+        assert_eq!(explanation.header.len(), 1);
+        assert!(explanation.header[0].warning.is_some());
+        assert_eq!(outcome(&explanation, "decode"), Some(Outcome::Pass));
+        assert_eq!(outcome(&explanation, "signature"), Some(Outcome::Fail));
+        assert_eq!(outcome(&explanation, "exp"), Some(Outcome::Pass));
+        assert_eq!(outcome(&explanation, "nbf"), Some(Outcome::Fail));
+        assert_eq!(outcome(&explanation, "iat"), None);
+        assert_eq!(explanation.diagnostics[0], "whitespace in the token was ignored");
+    }
+
+    #[test]
+    fn test_explain_undecodable() {
+        // {"alg": "RS1", "crit": ["b64"]} . {"iat": 10}
+        let token = "eyJhbGciOiAiUlMxIiwgImNyaXQiOiBbImI2NCJdfQ.eyJpYXQiOiAxMH0.c2ln";
+        let explanation = explain(token, None, &at(5)).unwrap();
+        assert_eq!(outcome(&explanation, "decode"), Some(Outcome::Fail));
+        assert_eq!(outcome(&explanation, "signature"), Some(Outcome::Skipped));
+        assert_eq!(outcome(&explanation, "crit"), Some(Outcome::Fail));
+        assert_eq!(outcome(&explanation, "iat"), Some(Outcome::Fail));
+        assert_eq!(outcome(&explanation, "claims"), Some(Outcome::Skipped));
+        assert_eq!(explanation.segments[1].decoded, Some(json!({"iat": 10})));
+        assert!(explain("a.b", None, &at(0)).is_err());
+    }
+
+    #[test]
+    fn test_explain_unencoded_payload() {
+        let mut jwt = JWT::from_plain_str("{\"exp\":100}").unwrap();
+        jwt.header = crate::header::JWTHeader::builder()
+            .alg(crate::header::Alg::HS256)
+            .unencoded_payload()
+            .build()
+            .unwrap();
+        let explanation = explain(&jwt.sign(b"key").unwrap(), None, &at(40)).unwrap();
+        assert_eq!(outcome(&explanation, "decode"), Some(Outcome::Pass));
+        assert_eq!(outcome(&explanation, "crit"), Some(Outcome::Pass));
+        assert_eq!(explanation.segments[1].decoded, Some(json!({"exp": 100})));
+        assert!(explanation.header.iter().all(|parameter| parameter.warning.is_none()));
+    }
+
+    #[test]
+    fn test_explain_with_key_and_validator() {
+        let mut jwt = JWT::from_plain_str("{\"exp\": 100, \"aud\": \"web\"}").unwrap();
+        jwt.header.alg = Alg::HS256;
+        let token = jwt.sign(&[7; 32]).unwrap();
+
+        let validator = at(105).leeway(Duration::from_secs(10)).audience("web");
+        let explanation = explain(&token, Some((&[7; 32][..]).into()), &validator).unwrap();
+        assert_eq!(outcome(&explanation, "signature"), Some(Outcome::Pass));
+        assert_eq!(outcome(&explanation, "exp"), Some(Outcome::Pass));
+        assert_eq!(outcome(&explanation, "header"), Some(Outcome::Pass));
+        assert_eq!(outcome(&explanation, "claims"), Some(Outcome::Pass));
+
+        let validator = Validator::strict().audience("api").clock(FixedClock(UNIX_EPOCH));
+        let explanation = explain(&token, Some((&[8; 32][..]).into()), &validator).unwrap();
+        assert_eq!(outcome(&explanation, "signature"), Some(Outcome::Fail));
+        assert_eq!(outcome(&explanation, "size"), Some(Outcome::Pass));
+        let explanation = explain(&token, Some((&[7; 16][..]).into()), &validator).unwrap();
+        let signature = explanation.rules.iter().find(|rule| rule.name == "signature").unwrap();
+        assert_eq!(signature.outcome, Outcome::Fail);
+        assert!(signature.reason.contains("at least 32 bytes"));
+        assert_eq!(outcome(&explanation, "claims"), Some(Outcome::Fail));
+        let claims = explanation.rules.iter().find(|rule| rule.name == "claims").unwrap();
+        assert_eq!(claims.reason, "claim \"aud\" has an unacceptable value.");
+    }
+
+    #[test]
+    fn test_header_parameters() {
+        let header = json!({"alg": "HS256", "jku": "https://x", "kid": "k1", "zip": "DEF"});
+        let parameters = header_parameters(header.as_object().unwrap());
+        let warnings = parameters.iter()
+            .filter_map(|parameter| parameter.warning.as_deref())
+            .collect::<Vec<&str>>();
+        assert_eq!(parameters[0].meaning, "the token is signed with HS256");
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("jku present"));
+    }
+}
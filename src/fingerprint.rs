@@ -0,0 +1,102 @@
+use std::fmt;
+use sha2::{Digest, Sha256};
+
+// Number of leading digest bytes kept in a fingerprint. 64 bits is plenty to tell tokens apart in
+// logs, while being far too short to be of any use in recovering the token.
+const FINGERPRINT_BYTES: usize = 8;
+
+/// Returns a short, stable identifier for a token: the first 64 bits of the SHA-256 digest of the
+/// token, hex-encoded. Fingerprints allow log lines about the same token to be correlated without
+/// ever logging the token itself.
+///
+/// Optional whitespace (space, CR, LF) is ignored, so the compact and the line-broken forms of a
+/// token share a fingerprint.
+///
+/// # Examples
+/// ```
+/// use jwt::token_fingerprint;
+///
+/// let fp = token_fingerprint("eyJhbGciOiAibm9uZSJ9.e30=.");
+/// assert_eq!(fp.len(), 16);
+/// assert_eq!(fp, token_fingerprint("eyJhbGciOiAibm9uZSJ9\n.\ne30=\n.\n"));
+/// ```
+pub fn token_fingerprint(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    for c in token.chars().filter(|c| !matches!(c, '\u{0020}' | '\u{000A}' | '\u{000D}')) {
+        let mut buf = [0; 4];
+        hasher.update(c.encode_utf8(&mut buf).as_bytes());
+    }
+    hasher.finalize()[..FINGERPRINT_BYTES]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// A wrapper around a token string whose `Display` and `Debug` implementations print only the
+/// token's fingerprint, never the token itself. Wrap tokens in a `RedactedToken` before they go
+/// anywhere near a logger or an error message.
+///
+/// # Examples
+/// ```
+/// use jwt::RedactedToken;
+///
+/// let t = RedactedToken::new("eyJhbGciOiAibm9uZSJ9.e30=.");
+/// assert!(!format!("{}", t).contains("eyJ"));
+/// assert!(!format!("{:?}", t).contains("eyJ"));
+/// ```
+pub struct RedactedToken<'a> {
+    token: &'a str,
+}
+
+impl<'a> RedactedToken<'a> {
+    /// Wraps `token`.
+    pub fn new(token: &'a str) -> RedactedToken<'a> {
+        RedactedToken { token }
+    }
+
+    /// Returns the fingerprint of the wrapped token.
+    pub fn fingerprint(&self) -> String {
+        token_fingerprint(self.token)
+    }
+
+    /// Returns the wrapped token. Take care not to log the result.
+    pub fn expose(&self) -> &'a str {
+        self.token
+    }
+}
+
+impl<'a> fmt::Display for RedactedToken<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "token:{}", self.fingerprint())
+    }
+}
+
+impl<'a> fmt::Debug for RedactedToken<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RedactedToken({})", self.fingerprint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_known_value() {
+        // The first 8 bytes of sha256("abc").
+        assert_eq!(token_fingerprint("abc"), "ba7816bf8f01cfea");
+    }
+
+    #[test]
+    fn test_fingerprint_distinguishes_tokens() {
+        assert_ne!(token_fingerprint("a.b.c"), token_fingerprint("a.b.d"));
+    }
+
+    #[test]
+    fn test_redacted_token_display() {
+        let t = RedactedToken::new("abc");
+        assert_eq!(format!("{}", t), "token:ba7816bf8f01cfea");
+        assert_eq!(format!("{:?}", t), "RedactedToken(ba7816bf8f01cfea)");
+        assert_eq!(t.expose(), "abc");
+    }
+}
@@ -0,0 +1,297 @@
+//! Generators of realistic headers, claims and claim sets, for property testing and fuzzing code
+//! which handles tokens.
+//!
+//! With the `arbitrary` feature, `JWTHeader`, `Claim` and `ClaimSet` implement
+//! `arbitrary::Arbitrary`, for use with `cargo fuzz` and similar tools. With the `proptest`
+//! feature, `fuzz::strategies` provides proptest strategies for the same types.
+//!
+//! Generated registered claims always have the types RFC 7519 gives them (so that they are
+//! accepted by `Claim::parse`), while other claims have arbitrary names and JSON values.
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impls {
+    use arbitrary::{Arbitrary, Result, Unstructured};
+    use serde_json::{Map, Value};
+    use url::Url;
+
+    use crate::claims::{Claim, ClaimSet, REGISTERED_CLAIMS};
+    use crate::header::{Alg, Cty, JWTHeader, Typ, ALGORITHMS};
+
+    impl<'a> Arbitrary<'a> for Typ {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Typ> {
+            Ok(u.choose(&[Typ::None, Typ::JWT])?.clone())
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Alg {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Alg> {
+            Ok(*u.choose(ALGORITHMS)?)
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for Cty {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Cty> {
+            Ok(u.choose(&[Cty::None, Cty::JWT, Cty::Deflate])?.clone())
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for JWTHeader {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<JWTHeader> {
+            Ok(JWTHeader {
+                typ: u.arbitrary()?,
+                cty: u.arbitrary()?,
+                alg: u.arbitrary()?,
+                kid: u.arbitrary()?,
+                jku: optional_url(u)?,
+                x5u: optional_url(u)?,
+                x5c: u.arbitrary()?,
+                x5t: u.arbitrary()?,
+                x5t_s256: u.arbitrary()?,
+                // An unencoded payload must be listed in crit, and cannot hold every claim set.
+                b64: true,
+                crit: None,
+                params: extension_params(u)?,
+            })
+        }
+    }
+
+    // A string which is not a URI (because it has no colon).
+    fn plain_string(u: &mut Unstructured) -> Result<String> {
+        Ok(String::arbitrary(u)?.replace(':', ""))
+    }
+
+    // A string which is a valid URI.
+    fn uri(u: &mut Unstructured) -> Result<String> {
+        let path = String::arbitrary(u)?.chars().filter(char::is_ascii_alphanumeric)
+            .collect::<String>();
+        Ok(u.choose(&["https://example.com/", "urn:example:"])?.to_string() + &path)
+    }
+
+    // Header parameters without a field of their own. Their names are prefixed, so that they are
+    // neither registered parameters nor crit.
+    fn extension_params(u: &mut Unstructured) -> Result<Map<String, Value>> {
+        let mut params = Map::new();
+        while u.arbitrary()? {
+            params.insert(String::from("ext_") + &String::arbitrary(u)?, json_value(u, 2)?);
+        }
+        Ok(params)
+    }
+
+    // An absolute URL, or None.
+    fn optional_url(u: &mut Unstructured) -> Result<Option<Url>> {
+        if !bool::arbitrary(u)? {
+            return Ok(None)
+        }
+        let path = String::arbitrary(u)?.chars().filter(char::is_ascii_alphanumeric)
+            .collect::<String>();
+        Ok(Url::parse(&(String::from("https://example.com/") + &path)).ok())
+    }
+
+    fn string_or_uri(u: &mut Unstructured) -> Result<String> {
+        if u.arbitrary()? { uri(u) } else { plain_string(u) }
+    }
+
+    fn json_value(u: &mut Unstructured, depth: u32) -> Result<Value> {
+        let kinds = if depth == 0 { 4 } else { 6 };
+        Ok(match u.choose_index(kinds)? {
+            0 => Value::Null,
+            1 => Value::from(bool::arbitrary(u)?),
+            // Integers only: floats do not reliably survive a round trip through JSON text.
+            2 => Value::from(i64::arbitrary(u)?),
+            3 => Value::from(String::arbitrary(u)?),
+            4 => {
+                let mut values = Vec::<Value>::new();
+                while u.arbitrary()? {
+                    values.push(json_value(u, depth - 1)?);
+                }
+                Value::Array(values)
+            }
+            _ => {
+                let mut members = Map::new();
+                while u.arbitrary()? {
+                    members.insert(String::arbitrary(u)?, json_value(u, depth - 1)?);
+                }
+                Value::Object(members)
+            }
+        })
+    }
+
+    fn registered_value(claim_name: &str, u: &mut Unstructured) -> Result<Value> {
+        Ok(match claim_name {
+            // Dates between 1970 and 2100.
+            "exp" | "nbf" | "iat" => Value::from(u.int_in_range(0..=4102444800u64)?),
+            "iss" | "sub" => Value::from(string_or_uri(u)?),
+            "aud" if u.arbitrary()? => {
+                let mut audiences = Vec::<String>::new();
+                while u.arbitrary()? {
+                    audiences.push(string_or_uri(u)?);
+                }
+                Value::from(audiences)
+            }
+            "aud" => Value::from(string_or_uri(u)?),
+            _ => Value::from(String::arbitrary(u)?),
+        })
+    }
+
+    impl<'a> Arbitrary<'a> for Claim {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<Claim> {
+            let (claim_name, claim_value) = match u.choose_index(3)? {
+                0 => {
+                    let claim_name = *u.choose(REGISTERED_CLAIMS)?;
+                    (String::from(claim_name), registered_value(claim_name, u)?)
+                }
+                1 => (uri(u)?, json_value(u, 3)?),
+                _ => (plain_string(u)?, json_value(u, 3)?),
+            };
+            Claim::parse(claim_name, claim_value).map_err(|_| arbitrary::Error::IncorrectFormat)
+        }
+    }
+
+    impl<'a> Arbitrary<'a> for ClaimSet {
+        fn arbitrary(u: &mut Unstructured<'a>) -> Result<ClaimSet> {
+            let mut claim_set = ClaimSet::new();
+            for claim in u.arbitrary_iter::<Claim>()? {
+                // Claims whose names are already taken are dropped.
+                let _ = claim_set.insert(claim?);
+            }
+            Ok(claim_set)
+        }
+    }
+}
+
+/// Proptest strategies generating headers, claims and claim sets.
+#[cfg(feature = "proptest")]
+pub mod strategies {
+    use proptest::collection::{btree_map, vec};
+    use proptest::prelude::*;
+    use serde_json::Value;
+    use url::Url;
+
+    use crate::claims::{Claim, ClaimSet};
+    use crate::header::{Cty, JWTHeader, Typ, ALGORITHMS};
+
+    /// Generates headers.
+    pub fn header() -> impl Strategy<Value = JWTHeader> {
+        (
+            prop_oneof![Just(Typ::None), Just(Typ::JWT)],
+            prop_oneof![Just(Cty::None), Just(Cty::JWT), Just(Cty::Deflate)],
+            proptest::sample::select(&ALGORITHMS[..]),
+            proptest::option::of(any::<String>()),
+            proptest::option::of(url()),
+            proptest::option::of(url()),
+            vec(vec(any::<u8>(), 0..64), 0..3),
+            (proptest::option::of(any::<[u8; 20]>()), proptest::option::of(any::<[u8; 32]>())),
+            // Prefixed names are neither registered parameters nor crit.
+            btree_map("ext_[a-z0-9]{0,8}", json_value(), 0..3),
+        ).prop_map(|(typ, cty, alg, kid, jku, x5u, x5c, (x5t, x5t_s256), params)| {
+            let params = params.into_iter().collect();
+            // An unencoded payload must be listed in crit, and cannot hold every claim set.
+            JWTHeader {
+                typ, cty, alg, kid, jku, x5u, x5c, x5t, x5t_s256, b64: true, crit: None, params
+            }
+        })
+    }
+
+    fn url() -> impl Strategy<Value = Url> {
+        "https://example\\.com/[a-z0-9]{0,8}".prop_map(|url| Url::parse(&url).unwrap())
+    }
+
+    /// Generates JSON values nested up to three deep. Numbers are integers, since floats do not
+    /// reliably survive a round trip through JSON text.
+    pub fn json_value() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            any::<String>().prop_map(Value::from),
+        ];
+        leaf.prop_recursive(3, 32, 4, |inner| prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(Value::Array),
+            btree_map(any::<String>(), inner, 0..4)
+                .prop_map(|members| Value::Object(members.into_iter().collect())),
+        ])
+    }
+
+    fn string_or_uri() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "[^:]{0,16}", "https://example\\.com/[a-z0-9]{0,8}", "urn:example:[a-z0-9]{1,8}"
+        ]
+    }
+
+    fn registered() -> impl Strategy<Value = (String, Value)> {
+        let name = |names: &'static [&'static str]| {
+            proptest::sample::select(names).prop_map(String::from)
+        };
+        prop_oneof![
+            (name(&["exp", "nbf", "iat"]), 0..=4102444800u64)
+                .prop_map(|(claim_name, date)| (claim_name, Value::from(date))),
+            (name(&["iss", "sub"]), string_or_uri())
+                .prop_map(|(claim_name, value)| (claim_name, Value::from(value))),
+            (name(&["aud"]), vec(string_or_uri(), 0..4))
+                .prop_map(|(claim_name, values)| (claim_name, Value::from(values))),
+            (name(&["jti"]), any::<String>())
+                .prop_map(|(claim_name, value)| (claim_name, Value::from(value))),
+        ]
+    }
+
+    /// Generates registered, public and private claims.
+    pub fn claim() -> impl Strategy<Value = Claim> {
+        prop_oneof![
+            registered(),
+            ("https://example\\.com/[a-z0-9]{1,8}", json_value()),
+            ("[^:]{0,16}", json_value()),
+        ].prop_map(|(claim_name, claim_value)| {
+            // Every generated claim is valid.
+            Claim::parse(claim_name, claim_value).unwrap()
+        })
+    }
+
+    /// Generates claim sets of up to eight claims.
+    pub fn claim_set() -> impl Strategy<Value = ClaimSet> {
+        vec(claim(), 0..8).prop_map(|claims| {
+            let mut claim_set = ClaimSet::new();
+            for claim in claims {
+                // Claims whose names are already taken are dropped.
+                let _ = claim_set.insert(claim);
+            }
+            claim_set
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::claims::ClaimSet;
+    use crate::traits::JsonSerializable;
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn test_arbitrary_roundtrip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        // A fixed pseudo-random byte stream, so the test is deterministic.
+        let mut state: u32 = 0x2545f491;
+        let bytes = (0..1 << 16).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        }).collect::<Vec<u8>>();
+
+        let mut u = Unstructured::new(&bytes);
+        for _ in 0..32 {
+            let claim_set = ClaimSet::arbitrary(&mut u).unwrap();
+            assert_eq!(ClaimSet::decode_str(&claim_set.encode_str()).unwrap(), claim_set);
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn test_proptest_roundtrip(claim_set in super::strategies::claim_set()) {
+            proptest::prop_assert_eq!(
+                ClaimSet::decode_str(&claim_set.encode_str()).unwrap(), claim_set
+            );
+        }
+    }
+}
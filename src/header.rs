@@ -1,42 +1,541 @@
-use serde_json::Value;
-use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::ser::Formatter;
+use serde_json::{Map, Value};
+use std::convert::TryFrom;
+use std::{fmt, io};
+use url::Url;
 
+use crate::config::ParseConfig;
 use crate::err;
+use crate::keys::PublicKey;
 use crate::traits::JsonSerializable;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+/// The `typ` header parameter, which declares the media type of the token. Token profiles
+/// distinguish their tokens by it, so that a token issued for one purpose cannot be used for
+/// another: OAuth access tokens carry `at+jwt` (RFC 9068), DPoP proofs `dpop+jwt` (RFC 9449), and
+/// logout tokens `logout+jwt`.
 pub enum Typ {
+    #[default]
     None,
     JWT,
+    /// Any other media type, lowercased and without an `application/` prefix.
+    Other(String),
 }
 
-#[derive(Debug)]
+impl Typ {
+    /// Parses a `typ` value. Media types are case-insensitive, and RFC 7515 recommends omitting
+    /// their `application/` prefix, so `"JWT"`, `"jwt"` and `"application/jwt"` all parse to
+    /// `Typ::JWT`.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::header::Typ;
+    ///
+    /// assert_eq!(Typ::parse("application/JWT"), Typ::JWT);
+    /// assert_eq!(Typ::parse("AT+JWT"), Typ::Other(String::from("at+jwt")));
+    /// ```
+    pub fn parse(typ: &str) -> Typ {
+        let typ = typ.to_ascii_lowercase();
+        // The prefix may only be omitted from media types without another slash.
+        let typ = match typ.strip_prefix("application/") {
+            Some(subtype) if !subtype.contains('/') => String::from(subtype),
+            _ => typ,
+        };
+        if typ == "jwt" { Typ::JWT } else { Typ::Other(typ) }
+    }
+
+    /// Returns the value of the `typ` header parameter, or `None` if it is absent.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Typ::None => None,
+            Typ::JWT => Some("JWT"),
+            Typ::Other(typ) => Some(typ),
+        }
+    }
+
+    fn is_none(&self) -> bool {
+        *self == Typ::None
+    }
+}
+
+// typ is written as Typ::as_str returns it, and read with Typ::parse. A null typ reads as an
+// absent one.
+impl Serialize for Typ {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Typ {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Typ, D::Error> {
+        let typ = Option::<String>::deserialize(deserializer)?;
+        Ok(typ.map_or(Typ::None, |typ| Typ::parse(&typ)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Alg {
+    #[serde(rename = "none")]
     None,
     HS256,
+    HS384,
+    HS512,
+    RS256,
+    RS384,
+    RS512,
+    ES256,
+    ES384,
+    ES512,
+    ES256K,
+    EdDSA,
 }
 
-#[derive(Debug)]
+// Every algorithm, for generating headers in property tests.
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub(crate) const ALGORITHMS: &[Alg; 12] = &[
+    Alg::None, Alg::HS256, Alg::HS384, Alg::HS512, Alg::RS256, Alg::RS384, Alg::RS512, Alg::ES256,
+    Alg::ES384, Alg::ES512, Alg::ES256K, Alg::EdDSA,
+];
+
+impl Alg {
+    /// Returns the name of the algorithm, as it appears in the `alg` header parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Alg::None => "none",
+            Alg::HS256 => "HS256",
+            Alg::HS384 => "HS384",
+            Alg::HS512 => "HS512",
+            Alg::RS256 => "RS256",
+            Alg::RS384 => "RS384",
+            Alg::RS512 => "RS512",
+            Alg::ES256 => "ES256",
+            Alg::ES384 => "ES384",
+            Alg::ES512 => "ES512",
+            Alg::ES256K => "ES256K",
+            Alg::EdDSA => "EdDSA",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub enum Cty {
+    #[default]
     None,
     JWT,
+    /// The payload is the claim set compressed with DEFLATE (RFC 1951), which this crate marks
+    /// with `"cty": "deflate"`. See `jwt::compression`.
+    Deflate,
+    /// Any other content type, lowercased and without an `application/` prefix.
+    Other(String),
 }
 
-#[derive(Debug)]
+impl Cty {
+    /// Parses a `cty` value. Content types are case-insensitive, and may omit their
+    /// `application/` prefix as `typ` values do (see `Typ::parse`).
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::header::Cty;
+    ///
+    /// assert_eq!(Cty::parse("application/jwt"), Cty::JWT);
+    /// assert_eq!(Cty::parse("Text/Plain"), Cty::Other(String::from("text/plain")));
+    /// ```
+    pub fn parse(cty: &str) -> Cty {
+        match Typ::parse(cty) {
+            Typ::JWT => Cty::JWT,
+            Typ::Other(cty) if cty == "deflate" => Cty::Deflate,
+            Typ::Other(cty) => Cty::Other(cty),
+            Typ::None => Cty::None,
+        }
+    }
+
+    /// Returns the value of the `cty` header parameter, or `None` if it is absent.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Cty::None => None,
+            Cty::JWT => Some("JWT"),
+            Cty::Deflate => Some("deflate"),
+            Cty::Other(cty) => Some(cty),
+        }
+    }
+
+    fn is_none(&self) -> bool {
+        *self == Cty::None
+    }
+}
+
+impl Serialize for Cty {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_str().serialize(serializer)
+    }
+}
+
+// cty is read with Cty::parse. A null cty reads as an absent one.
+impl<'de> Deserialize<'de> for Cty {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Cty, D::Error> {
+        let cty = Option::<String>::deserialize(deserializer)?;
+        Ok(cty.map_or(Cty::None, |cty| Cty::parse(&cty)))
+    }
+}
+
+// The header parameters which JWS and JWA define, which a crit header parameter may not list.
+const REGISTERED_PARAMS: &[&str; 11] = &[
+    "alg", "jku", "jwk", "kid", "x5u", "x5c", "x5t", "x5t#S256", "typ", "cty", "crit",
+];
+
+// The extensions this crate understands itself, which a crit header parameter may always list.
+pub(crate) const UNDERSTOOD_CRIT: &[&str; 1] = &["b64"];
+
+// Checks the crit header parameter of `header` against RFC 7515 section 4.1.11: if present, it
+// must be a non-empty array of the distinct names of extension parameters the header carries.
+// Returns a err::JWTError::SchemaError if it is malformed, or a err::JWTError::NotImplementedError
+// if it lists a parameter the caller has not marked as handled in `config` and the crate does not
+// understand itself.
+pub(crate) fn check_crit(header: &Value, config: &ParseConfig) -> err::Result<()> {
+    let crit = match &header["crit"] {
+        Value::Null => return Ok(()),
+        Value::Array(crit) if !crit.is_empty() => crit,
+        _ => return Err(err::JWTError::SchemaError),
+    };
+    let mut names = Vec::<&str>::new();
+    for name in crit {
+        let name = name.as_str().ok_or(err::JWTError::SchemaError)?;
+        let listable = !REGISTERED_PARAMS.contains(&name) && header.get(name).is_some();
+        if !listable || names.contains(&name) {
+            return Err(err::JWTError::SchemaError)
+        }
+        names.push(name);
+    }
+    let handled = |name: &&str| {
+        UNDERSTOOD_CRIT.contains(name) || config.handled_crit.iter().any(|handled| handled == name)
+    };
+    if names.iter().all(handled) {
+        Ok(())
+    } else {
+        Err(err::JWTError::NotImplementedError)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// The `JWTHeader` struct represents a JWT header, known in the spec as a JOSE header. Although
 /// you may construct with `JWTHeader` structs directly, it is usually better to use the public
 /// `JWT` struct and its accompanying methods instead.
 pub struct JWTHeader {
+    pub alg: Alg,
+    #[serde(default, skip_serializing_if = "Typ::is_none")]
     pub typ: Typ,
+    #[serde(default, skip_serializing_if = "Cty::is_none")]
     pub cty: Cty,
-    pub alg: Alg,
+    /// The `kid` header parameter, which identifies the key the token was signed with, so that a
+    /// verifier holding several keys knows which to verify it with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    /// The `jku` header parameter, the URL of a JWK Set holding the key the token was signed
+    /// with. It is only represented here: keys must never be fetched from a URL the token names
+    /// unless the URL is trusted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jku: Option<Url>,
+    /// The `x5u` header parameter, the URL of the X.509 certificate chain of the key the token
+    /// was signed with. Like `jku`, it is only represented here.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x5u: Option<Url>,
+    /// The `x5c` header parameter: the DER-encoded X.509 certificate chain of the key the token
+    /// was signed with, leaf first. Empty if it is absent.
+    #[serde(default, skip_serializing_if = "Vec::is_empty", with = "x5c")]
+    pub x5c: Vec<Vec<u8>>,
+    /// The `x5t` header parameter, the SHA-1 thumbprint of the certificate of the key the token
+    /// was signed with. See `keys::x5t`.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "thumbprint")]
+    pub x5t: Option<[u8; 20]>,
+    /// The `x5t#S256` header parameter, the SHA-256 thumbprint of the certificate of the key the
+    /// token was signed with. See `keys::x5t_s256`.
+    #[serde(rename = "x5t#S256")]
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "thumbprint")]
+    pub x5t_s256: Option<[u8; 32]>,
+    /// The `b64` header parameter of RFC 7797. If `false`, the payload is not base64url-encoded:
+    /// it appears in the token, and is signed, as it is. `b64` must then be listed in the `crit`
+    /// header parameter. Only `false` is written.
+    #[serde(default = "default_b64", skip_serializing_if = "is_true")]
+    pub b64: bool,
+    /// The `crit` header parameter: the names of the extension parameters which a recipient must
+    /// understand to accept the token. Decoding checks it against `ParseConfig::handle_crit`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crit: Option<Vec<String>>,
+    /// Every other header parameter, such as the `nonce` of Azure AD tokens or vendor extensions,
+    /// kept so that they survive a round trip. Parameters with a field of their own are not held
+    /// here.
+    #[serde(flatten, serialize_with = "serialize_params")]
+    pub params: Map<String, Value>,
+}
+
+// The header parameters with a field of their own in JWTHeader.
+const FIELD_PARAMS: &[&str; 11] = &[
+    "alg", "typ", "cty", "kid", "jku", "x5u", "x5c", "x5t", "x5t#S256", "b64", "crit",
+];
+
+impl JWTHeader {
+    /// Constructs a header naming `alg`, with no other parameters.
+    pub fn new(alg: Alg) -> JWTHeader {
+        JWTHeader {
+            typ: Typ::None,
+            cty: Cty::None,
+            alg,
+            kid: None,
+            jku: None,
+            x5u: None,
+            x5c: Vec::new(),
+            x5t: None,
+            x5t_s256: None,
+            b64: true,
+            crit: None,
+            params: Map::new(),
+        }
+    }
+
+    /// Returns the names the `crit` header parameter lists, which are none if it is absent.
+    pub fn critical(&self) -> &[String] {
+        self.crit.as_deref().unwrap_or(&[])
+    }
+
+    /// Returns whether the `crit` header parameter lists `name`.
+    pub fn is_critical(&self, name: &str) -> bool {
+        self.critical().iter().any(|critical| critical == name)
+    }
+
+    /// Returns a builder for a header, with every parameter absent.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::header::{Alg, JWTHeader, Typ};
+    ///
+    /// let header = JWTHeader::builder().alg(Alg::HS256).typ(Typ::JWT).kid("key-1").build()
+    ///     .unwrap();
+    /// assert_eq!(header.kid.as_deref(), Some("key-1"));
+    /// assert!(JWTHeader::builder().typ(Typ::JWT).build().is_err());
+    /// ```
+    pub fn builder() -> JWTHeaderBuilder {
+        JWTHeaderBuilder { alg: None, header: JWTHeader::new(Alg::None) }
+    }
+
+    /// Returns the public key of the leaf certificate of the `x5c` header parameter, or `None` if
+    /// it is absent. See `keys::PublicKey::from_certificate`.
+    ///
+    /// The chain is not validated. Before verifying a token with this key, check that the chain
+    /// leads to a certificate the application trusts; otherwise anyone can sign tokens which
+    /// verify, by including a certificate of their own.
+    pub fn x5c_public_key(&self) -> err::Result<Option<PublicKey>> {
+        self.x5c.first().map(|leaf| PublicKey::from_certificate(leaf)).transpose()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A builder for a `JWTHeader`, returned by `JWTHeader::builder`.
+pub struct JWTHeaderBuilder {
+    // The alg header parameter, which must be set before the header is built.
+    alg: Option<Alg>,
+    // Every other header parameter.
+    header: JWTHeader,
+}
+
+impl JWTHeaderBuilder {
+    /// Sets the `alg` header parameter, which every header must have.
+    pub fn alg(mut self, alg: Alg) -> JWTHeaderBuilder {
+        self.alg = Some(alg);
+        self
+    }
+
+    /// Sets the `typ` header parameter.
+    pub fn typ(mut self, typ: Typ) -> JWTHeaderBuilder {
+        self.header.typ = typ;
+        self
+    }
+
+    /// Sets the `cty` header parameter.
+    pub fn cty(mut self, cty: Cty) -> JWTHeaderBuilder {
+        self.header.cty = cty;
+        self
+    }
+
+    /// Sets the `kid` header parameter.
+    pub fn kid(mut self, kid: &str) -> JWTHeaderBuilder {
+        self.header.kid = Some(String::from(kid));
+        self
+    }
+
+    /// Sets the `jku` header parameter.
+    pub fn jku(mut self, jku: Url) -> JWTHeaderBuilder {
+        self.header.jku = Some(jku);
+        self
+    }
+
+    /// Sets the `x5u` header parameter.
+    pub fn x5u(mut self, x5u: Url) -> JWTHeaderBuilder {
+        self.header.x5u = Some(x5u);
+        self
+    }
+
+    /// Sets the `x5c` header parameter to the DER-encoded certificate `chain`, leaf first.
+    pub fn x5c(mut self, chain: Vec<Vec<u8>>) -> JWTHeaderBuilder {
+        self.header.x5c = chain;
+        self
+    }
+
+    /// Sets the `x5t` header parameter. See `keys::x5t`.
+    pub fn x5t(mut self, x5t: [u8; 20]) -> JWTHeaderBuilder {
+        self.header.x5t = Some(x5t);
+        self
+    }
+
+    /// Sets the `x5t#S256` header parameter. See `keys::x5t_s256`.
+    pub fn x5t_s256(mut self, x5t_s256: [u8; 32]) -> JWTHeaderBuilder {
+        self.header.x5t_s256 = Some(x5t_s256);
+        self
+    }
+
+    /// Sets the `b64` header parameter to `false`, so that the payload is not base64url-encoded
+    /// (RFC 7797), and lists it in the `crit` header parameter.
+    pub fn unencoded_payload(mut self) -> JWTHeaderBuilder {
+        self.header.b64 = false;
+        self.crit("b64")
+    }
+
+    /// Lists `name` in the `crit` header parameter, unless it is already.
+    pub fn crit(mut self, name: &str) -> JWTHeaderBuilder {
+        if !self.header.is_critical(name) {
+            self.header.crit.get_or_insert_with(Vec::new).push(String::from(name));
+        }
+        self
+    }
+
+    /// Sets the header parameter `name`, which has no field of its own, to `value`.
+    pub fn param(mut self, name: &str, value: Value) -> JWTHeaderBuilder {
+        self.header.params.insert(String::from(name), value);
+        self
+    }
+
+    /// Builds the header. Returns a `err::JWTError::SchemaError` if `alg` was not set, or if a
+    /// parameter set with `param` has a field of its own.
+    pub fn build(self) -> err::Result<JWTHeader> {
+        let alg = self.alg.ok_or(err::JWTError::SchemaError)?;
+        if self.header.params.keys().any(|name| FIELD_PARAMS.contains(&name.as_str())) {
+            return Err(err::JWTError::SchemaError)
+        }
+        Ok(JWTHeader { alg, ..self.header })
+    }
+}
+
+fn default_b64() -> bool {
+    true
+}
+
+fn is_true(b64: &bool) -> bool {
+    *b64
+}
+
+// Serializes the header parameters without a field of their own. Any which have one are skipped,
+// so that the header does not name them twice.
+fn serialize_params<S>(params: &Map<String, Value>, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+    serializer.collect_map(params.iter().filter(|(name, _)| !FIELD_PARAMS.contains(&name.as_str())))
+}
+
+// The x5c header parameter, whose certificates are base64-encoded with the standard alphabet (RFC
+// 7515 section 4.1.6). If present, it must be a non-empty array.
+mod x5c {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(x5c: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(x5c.iter().map(base64::encode))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+        where D: Deserializer<'de> {
+        match Option::<Vec<String>>::deserialize(deserializer)? {
+            None => Ok(Vec::new()),
+            Some(x5c) if x5c.is_empty() => Err(D::Error::invalid_length(0, &"a certificate")),
+            Some(x5c) => x5c.iter()
+                .map(|certificate| base64::decode(certificate).map_err(D::Error::custom))
+                .collect(),
+        }
+    }
+}
+
+// The x5t and x5t#S256 header parameters, certificate thumbprints which are base64url-encoded
+// without padding.
+mod thumbprint {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::convert::TryFrom;
+
+    pub fn serialize<T, S>(thumbprint: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: AsRef<[u8]>, S: Serializer {
+        thumbprint.as_ref()
+            .map(|thumbprint| base64::encode_config(thumbprint, base64::URL_SAFE_NO_PAD))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+        where T: for<'a> TryFrom<&'a [u8]>, D: Deserializer<'de> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|thumbprint| super::parse_thumbprint(&thumbprint).map_err(D::Error::custom))
+            .transpose()
+    }
+}
+
+// Decodes a certificate thumbprint, returning a err::JWTError::SchemaError if it is not a
+// base64url-encoded digest of the right length.
+pub(crate) fn parse_thumbprint<T>(thumbprint: &str) -> err::Result<T>
+    where T: for<'a> TryFrom<&'a [u8]> {
+    let thumbprint = base64::decode_config(thumbprint, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| err::JWTError::SchemaError)?;
+    T::try_from(&thumbprint).map_err(|_| err::JWTError::SchemaError)
+}
+
+// Formats a header as this crate always has: its parameters separated by ", " and their names from
+// their values by ": ", with nested values written compactly.
+#[derive(Default)]
+struct HeaderFormatter {
+    depth: usize,
+}
+
+impl Formatter for HeaderFormatter {
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth += 1;
+        writer.write_all(b"{")
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth -= 1;
+        writer.write_all(b"}")
+    }
+
+    fn begin_object_key<W>(&mut self, writer: &mut W, first: bool) -> io::Result<()>
+        where W: ?Sized + io::Write {
+        match (first, self.depth) {
+            (true, _) => Ok(()),
+            (false, 1) => writer.write_all(b", "),
+            (false, _) => writer.write_all(b","),
+        }
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(if self.depth == 1 { b": " } else { b":" })
+    }
 }
 
 impl JsonSerializable for JWTHeader {
 
-    /// Encodes self into a plaintext JOSE Header suitable for display.
+    /// Encodes self into a plaintext JOSE Header suitable for display. Absent parameters are
+    /// omitted.
     fn encode_str(&self) -> String {
-        String::from("{\"alg\": ") + "\"none\"" + "}"
+        let mut header = Vec::new();
+        let mut serializer =
+            serde_json::Serializer::with_formatter(&mut header, HeaderFormatter::default());
+        // Every header parameter serializes to JSON, and serde_json only writes UTF-8.
+        self.serialize(&mut serializer).unwrap();
+        String::from_utf8(header).unwrap()
     }
 
     /// Encodes self into a valid JOSE Header.
@@ -49,52 +548,40 @@ impl JsonSerializable for JWTHeader {
 
     /// Decodes an `input` `String` into a JOSE header. `input` must be a valid encoded JWT
     /// payload, elsewise a `JWTError` will be thrown.
-    fn decode_b64(input: &str) -> err::Result<JWTHeader> {
-        let header: err::Result<String> =
-            // (1) String of b64 chars -> Vec<u8>, a sequence of octets. A DecodeError is thrown
-            // if a byte is found to be out of range.
-            base64::decode(&input)
-            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
-            // (2) Vec<u8> -> String. Recall that Strings are utf-8.
-            .and_then(|inner| {
-                String::from_utf8(inner)
-                .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
-            });
-
-        // Early return to unpack the non-error header.
-            let header: String = match header {
-            Ok(header) => header,
-            Err(e) => return Err(e),
-        };
+    fn decode_b64_with(input: &str, config: &ParseConfig) -> err::Result<JWTHeader> {
+        config.check_size(input)?;
+
+        // String of b64 chars -> Vec<u8>, a sequence of octets -> String. A ParseError is thrown
+        // if a byte is found to be out of range, or if the octets are not valid utf-8.
+        let header: String = config.decode_base64(input)?;
 
         // Pass it through to decode_str.
-        JWTHeader::decode_str(&header)
+        JWTHeader::decode_str_with(&header, config)
     }
 
-    fn decode_str(input: &str) -> err::Result<JWTHeader> {
+    fn decode_str_with(input: &str, config: &ParseConfig) -> err::Result<JWTHeader> {
+        config.check_size(input)?;
+
         // String -> JSON.
         let header = serde_json::from_str(input)
             .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) });
 
-        // Early return to unpack the non-error header.
-        let header: Value = match header {
-            Ok(header) => header,
-            Err(e) => return Err(e)
-        };
+        let header: Value = header?;
 
+        check_crit(&header, config)?;
+
+        // Algorithms this crate does not implement are told apart from malformed headers.
         let alg = &header["alg"];
-        if alg.is_null() {
+        if alg.is_string() && Alg::deserialize(alg).is_err() {
+            return Err(err::JWTError::NotImplementedError)
+        }
+        let header: JWTHeader =
+            serde_json::from_value(header).map_err(|_| err::JWTError::SchemaError)?;
+        // RFC 7797 section 6: b64 must be understood by the recipient, so it must be critical.
+        if !header.b64 && !header.is_critical("b64") {
             return Err(err::JWTError::SchemaError)
         }
-        let alg = alg.as_str().unwrap();
-        let alg = match alg {
-            "none" => Alg::None,
-            "HS256" => Alg::HS256,
-            _ => return Err(err::JWTError::NotImplementedError)
-        };
-        Ok(JWTHeader {
-            alg, cty: Cty::None, typ: Typ::None
-        })
+        Ok(header)
     }
 }
 
@@ -118,6 +605,239 @@ mod tests {
         assert_eq!(h.encode_b64(), h_str);
     }
 
+    #[test]
+    fn test_header_alg() {
+        let h = JWTHeader::new(Alg::HS256);
+        assert_eq!(h.encode_str(), "{\"alg\": \"HS256\"}");
+        for alg in &[Alg::HS256, Alg::RS256, Alg::ES256, Alg::ES512, Alg::EdDSA] {
+            let h = JWTHeader { alg: *alg, ..h.clone() };
+            assert_eq!(JWTHeader::decode_str(&h.encode_str()).unwrap(), h);
+        }
+    }
+
+    #[test]
+    fn test_header_cty() {
+        let h = JWTHeader { cty: Cty::Deflate, ..JWTHeader::new(Alg::None) };
+        assert_eq!(h.encode_str(), "{\"alg\": \"none\", \"cty\": \"deflate\"}");
+        assert_eq!(JWTHeader::decode_str(&h.encode_str()).unwrap(), h);
+        let h = JWTHeader::decode_str("{\"alg\": \"none\", \"cty\": \"Text/Plain\"}").unwrap();
+        assert_eq!(h.cty, Cty::Other(String::from("text/plain")));
+        assert_eq!(h.encode_str(), "{\"alg\": \"none\", \"cty\": \"text/plain\"}");
+        assert!(matches!(
+            JWTHeader::decode_str("{\"alg\": \"none\", \"cty\": 1}"),
+            Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_header_nested_cty() {
+        for cty in &["JWT", "jwt", "application/JWT"] {
+            let h = JWTHeader::decode_str(&format!("{{\"alg\": \"none\", \"cty\": \"{}\"}}", cty))
+                .unwrap();
+            assert_eq!(h.cty, Cty::JWT, "{}", cty);
+            assert_eq!(h.encode_str(), "{\"alg\": \"none\", \"cty\": \"JWT\"}");
+        }
+        let h = JWTHeader { typ: Typ::JWT, cty: Cty::JWT, ..JWTHeader::new(Alg::HS256) };
+        assert_eq!(h.encode_str(), "{\"alg\": \"HS256\", \"typ\": \"JWT\", \"cty\": \"JWT\"}");
+        assert_eq!(JWTHeader::decode_b64(&h.encode_b64()).unwrap(), h);
+    }
+
+    #[test]
+    fn test_header_typ() {
+        let h = JWTHeader::decode_str("{\"alg\": \"none\", \"typ\": \"JWT\"}").unwrap();
+        assert_eq!(h.typ, Typ::JWT);
+        let h = JWTHeader::decode_str("{\"alg\": \"none\", \"typ\": \"application/at+jwt\"}")
+            .unwrap();
+        assert_eq!(h.typ.as_str(), Some("at+jwt"));
+        assert_eq!(h.encode_str(), "{\"alg\": \"none\", \"typ\": \"at+jwt\"}");
+        let h = JWTHeader { typ: Typ::Other(String::from("a\"b")), ..h };
+        assert_eq!(JWTHeader::decode_str(&h.encode_str()).unwrap(), h);
+        assert_eq!(Typ::parse("application/a/b"), Typ::Other(String::from("application/a/b")));
+        assert!(matches!(
+            JWTHeader::decode_str("{\"alg\": \"none\", \"typ\": 1}"),
+            Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_header_kid() {
+        let h = JWTHeader::decode_str("{\"alg\": \"RS256\", \"kid\": \"2024-01\"}").unwrap();
+        assert_eq!(h.kid.as_deref(), Some("2024-01"));
+        assert_eq!(h.encode_str(), "{\"alg\": \"RS256\", \"kid\": \"2024-01\"}");
+        let h = JWTHeader { kid: Some(String::from("a\"b")), ..h };
+        assert_eq!(JWTHeader::decode_b64(&h.encode_b64()).unwrap(), h);
+        assert!(matches!(
+            JWTHeader::decode_str("{\"alg\": \"none\", \"kid\": 1}"),
+            Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_header_urls() {
+        let h = JWTHeader::decode_str(
+            "{\"alg\": \"RS256\", \"jku\": \"https://idp/jwks\", \"x5u\": \"https://idp/x5\"}"
+        ).unwrap();
+        assert_eq!(h.jku.as_ref().map(Url::as_str), Some("https://idp/jwks"));
+        assert_eq!(h.x5u.as_ref().map(Url::as_str), Some("https://idp/x5"));
+        assert_eq!(JWTHeader::decode_b64(&h.encode_b64()).unwrap(), h);
+        for malformed in &[
+            "{\"alg\": \"none\", \"jku\": \"/jwks\"}",
+            "{\"alg\": \"none\", \"x5u\": 1}",
+        ] {
+            assert!(matches!(JWTHeader::decode_str(malformed), Err(err::JWTError::SchemaError)));
+        }
+    }
+
+    #[test]
+    fn test_header_x5c() {
+        let certificate = include_str!("testdata/ec_p256_cert.pem").lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+        let h = JWTHeader::decode_str(&format!(
+            "{{\"alg\": \"ES256\", \"x5c\": [\"{}\", \"AQI=\"]}}", certificate
+        )).unwrap();
+        assert_eq!(h.x5c.len(), 2);
+        assert_eq!(h.x5c[1], [1, 2]);
+        assert_eq!(JWTHeader::decode_b64(&h.encode_b64()).unwrap(), h);
+        let public = PublicKey::Ec(
+            crate::keys::EcPublicKey::from_pem(include_str!("testdata/ec_p256_public.pem")).unwrap()
+        );
+        assert_eq!(h.x5c_public_key().unwrap(), Some(public));
+        assert_eq!(JWTHeader::new(Alg::ES256).x5c_public_key().unwrap(), None);
+
+        // Certificates are base64-encoded with the standard alphabet, not base64url.
+        for malformed in &[
+            "{\"alg\": \"none\", \"x5c\": []}",
+            "{\"alg\": \"none\", \"x5c\": \"AQI=\"}",
+            "{\"alg\": \"none\", \"x5c\": [\"-_8=\"]}",
+        ] {
+            assert!(matches!(JWTHeader::decode_str(malformed), Err(err::JWTError::SchemaError)));
+        }
+    }
+
+    #[test]
+    fn test_header_x5t() {
+        let certificate = b"certificate";
+        let h = JWTHeader {
+            x5t: Some(crate::keys::x5t(certificate)),
+            x5t_s256: Some(crate::keys::x5t_s256(certificate)),
+            ..JWTHeader::new(Alg::RS256)
+        };
+        assert_eq!(h.encode_str(), "{\"alg\": \"RS256\", \
+            \"x5t\": \"c1rVccGJ17qERkv0qfHSKAF1sSg\", \
+            \"x5t#S256\": \"A9Zt0Ig1wco_EozOrNHzGslBYwlrIPRFroQoW8CDLXI\"}");
+        assert_eq!(JWTHeader::decode_b64(&h.encode_b64()).unwrap(), h);
+
+        // Each must be a digest of the right length.
+        for malformed in &[
+            "{\"alg\": \"none\", \"x5t\": \"A9Zt0Ig1wco_EozOrNHzGslBYwlrIPRFroQoW8CDLXI\"}",
+            "{\"alg\": \"none\", \"x5t#S256\": \"AQI\"}",
+            "{\"alg\": \"none\", \"x5t\": \"+/8=\"}",
+        ] {
+            assert!(matches!(JWTHeader::decode_str(malformed), Err(err::JWTError::SchemaError)));
+        }
+    }
+
+    #[test]
+    fn test_header_params() {
+        let h = JWTHeader::decode_str(
+            "{\"alg\": \"RS256\", \"kid\": \"k1\", \"nonce\": \"n-0S6\", \"x-ext\": {\"a\": [1]}}"
+        ).unwrap();
+        assert_eq!(h.kid.as_deref(), Some("k1"));
+        assert_eq!(h.params.len(), 2);
+        assert_eq!(h.params["nonce"], "n-0S6");
+        assert_eq!(
+            h.encode_str(),
+            "{\"alg\": \"RS256\", \"kid\": \"k1\", \"nonce\": \"n-0S6\", \"x-ext\": {\"a\":[1]}}"
+        );
+        assert_eq!(JWTHeader::decode_b64(&h.encode_b64()).unwrap(), h);
+
+        // Parameters with a field of their own are only written once.
+        let mut h = JWTHeader::new(Alg::None);
+        h.params.insert(String::from("alg"), Value::from("HS256"));
+        assert_eq!(h.encode_str(), "{\"alg\": \"none\"}");
+        assert!(JWTHeader::decode_str("[]").is_err());
+    }
+
+    #[test]
+    fn test_header_null_params() {
+        // Null parameters read as absent ones, and are not written back.
+        let h = JWTHeader::decode_str(
+            "{\"alg\": \"none\", \"typ\": null, \"kid\": null, \"x5c\": null, \"cty\": null}"
+        ).unwrap();
+        assert_eq!(h, JWTHeader::new(Alg::None));
+        assert_eq!(h.encode_str(), "{\"alg\": \"none\"}");
+        assert!(matches!(
+            JWTHeader::decode_str("{\"alg\": null}"),
+            Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_header_builder() {
+        let h = JWTHeader::builder()
+            .alg(Alg::RS256)
+            .typ(Typ::JWT)
+            .kid("key-1")
+            .x5t([1; 20])
+            .param("nonce", Value::from("n-0S6"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            h.encode_str(),
+            "{\"alg\": \"RS256\", \"typ\": \"JWT\", \"kid\": \"key-1\", \
+             \"x5t\": \"AQEBAQEBAQEBAQEBAQEBAQEBAQE\", \"nonce\": \"n-0S6\"}"
+        );
+        assert!(matches!(JWTHeader::builder().build(), Err(err::JWTError::SchemaError)));
+        assert!(matches!(
+            JWTHeader::builder().alg(Alg::None).param("kid", Value::from("k")).build(),
+            Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_header_b64() {
+        let h = JWTHeader::builder().alg(Alg::HS256).unencoded_payload().build().unwrap();
+        assert_eq!(h.encode_str(), "{\"alg\": \"HS256\", \"b64\": false, \"crit\": [\"b64\"]}");
+        assert_eq!(JWTHeader::decode_str(&h.encode_str()).unwrap(), h);
+        let h = JWTHeader::decode_str("{\"alg\": \"HS256\", \"b64\": true}").unwrap();
+        assert!(h.b64);
+        assert_eq!(h.encode_str(), "{\"alg\": \"HS256\"}");
+        for malformed in &[
+            "{\"alg\": \"none\", \"b64\": false}",
+            "{\"alg\": \"none\", \"b64\": \"false\", \"crit\": [\"b64\"]}",
+        ] {
+            assert!(matches!(JWTHeader::decode_str(malformed), Err(err::JWTError::SchemaError)));
+        }
+    }
+
+    #[test]
+    fn test_header_crit() {
+        let config = ParseConfig::new().handle_crit("b64");
+        let decode = |header: &str| JWTHeader::decode_str_with(header, &config);
+        let h = decode("{\"alg\": \"none\", \"crit\": [\"b64\"], \"b64\": false}").unwrap();
+        assert_eq!(h.critical(), ["b64"]);
+        assert!(h.params.is_empty());
+        assert!(JWTHeader::new(Alg::None).critical().is_empty());
+        let h = JWTHeader::builder().alg(Alg::None).param("x", Value::from(1)).crit("x").build()
+            .unwrap();
+        assert_eq!(h.encode_str(), "{\"alg\": \"none\", \"crit\": [\"x\"], \"x\": 1}");
+        assert!(matches!(decode(&h.encode_str()), Err(err::JWTError::NotImplementedError)));
+        assert!(matches!(
+            decode("{\"alg\": \"none\", \"crit\": [\"x\"], \"x\": 1}"),
+            Err(err::JWTError::NotImplementedError)
+        ));
+        for malformed in &[
+            "{\"alg\": \"none\", \"crit\": []}",
+            "{\"alg\": \"none\", \"crit\": \"b64\", \"b64\": false}",
+            "{\"alg\": \"none\", \"crit\": [\"b64\"]}",
+            "{\"alg\": \"none\", \"crit\": [\"b64\", \"b64\"], \"b64\": false}",
+            "{\"alg\": \"none\", \"crit\": [\"alg\"]}",
+        ] {
+            assert!(matches!(decode(malformed), Err(err::JWTError::SchemaError)), "{}", malformed);
+        }
+    }
+
     #[test]
     fn test_header_roundtrip_str() {
         let h_str = "{\"alg\": \"none\"}";
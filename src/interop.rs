@@ -0,0 +1,11 @@
+// Adapters to and from the types of other JWT libraries, each behind a cargo feature named after
+// the library in question.
+
+/// Conversions to and from the `jsonwebtoken` crate's types. Requires the `jsonwebtoken` feature.
+///
+/// `ClaimSet` implements `Serialize` and `Deserialize`, so it may be used directly as the claims
+/// type of `jsonwebtoken::encode` and `jsonwebtoken::decode`. Headers and whole `JWT`s convert
+/// via `TryFrom`, and a `claims::Validator` with its `verify::VerifyOptions` converts to and from a
+/// `jsonwebtoken::Validation` via `to_validation` and `from_validation`.
+#[cfg(feature = "jsonwebtoken")]
+pub mod jsonwebtoken;
@@ -0,0 +1,394 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use ::jsonwebtoken::{Algorithm, Header, Validation};
+
+use crate::claims::{ClaimSet, Comparison, Validator};
+use crate::err;
+use crate::header::{parse_thumbprint, Alg, Cty, JWTHeader, Typ};
+use crate::verify::VerifyOptions;
+use crate::JWT;
+use serde_json::Value;
+use url::Url;
+
+// jsonwebtoken has no notion of unsecured JWTs, so Alg::None has no counterpart.
+fn alg_to_algorithm(alg: &Alg) -> err::Result<Algorithm> {
+    match alg {
+        Alg::HS256 => Ok(Algorithm::HS256),
+        Alg::HS384 => Ok(Algorithm::HS384),
+        Alg::HS512 => Ok(Algorithm::HS512),
+        Alg::RS256 => Ok(Algorithm::RS256),
+        Alg::RS384 => Ok(Algorithm::RS384),
+        Alg::RS512 => Ok(Algorithm::RS512),
+        Alg::ES256 => Ok(Algorithm::ES256),
+        Alg::ES384 => Ok(Algorithm::ES384),
+        Alg::EdDSA => Ok(Algorithm::EdDSA),
+        // jsonwebtoken does not support P-521 or secp256k1.
+        Alg::ES512 | Alg::ES256K => Err(err::JWTError::NotImplementedError),
+        Alg::None => Err(err::JWTError::NotImplementedError),
+    }
+}
+
+fn algorithm_to_alg(algorithm: Algorithm) -> err::Result<Alg> {
+    match algorithm {
+        Algorithm::HS256 => Ok(Alg::HS256),
+        Algorithm::HS384 => Ok(Alg::HS384),
+        Algorithm::HS512 => Ok(Alg::HS512),
+        Algorithm::RS256 => Ok(Alg::RS256),
+        Algorithm::RS384 => Ok(Alg::RS384),
+        Algorithm::RS512 => Ok(Alg::RS512),
+        Algorithm::ES256 => Ok(Alg::ES256),
+        Algorithm::ES384 => Ok(Alg::ES384),
+        Algorithm::EdDSA => Ok(Alg::EdDSA),
+        _ => Err(err::JWTError::NotImplementedError),
+    }
+}
+
+/// Converts a `JWTHeader` into a `jsonwebtoken::Header`. Returns a
+/// `err::JWTError::NotImplementedError` for unsecured (`alg: none`) headers, which jsonwebtoken
+/// does not support, and for headers with an unencoded payload (`b64: false`), which it cannot
+/// sign or verify. Of the other header parameters, jsonwebtoken only keeps those with string
+/// values, so any others are dropped.
+impl TryFrom<&JWTHeader> for Header {
+    type Error = err::JWTError;
+
+    fn try_from(header: &JWTHeader) -> err::Result<Header> {
+        if !header.b64 {
+            return Err(err::JWTError::NotImplementedError)
+        }
+        let mut out = Header::new(alg_to_algorithm(&header.alg)?);
+        out.typ = header.typ.as_str().map(String::from);
+        out.kid = header.kid.clone();
+        out.jku = header.jku.as_ref().map(Url::to_string);
+        out.x5u = header.x5u.as_ref().map(Url::to_string);
+        if !header.x5c.is_empty() {
+            out.x5c = Some(header.x5c.iter().map(base64::encode).collect());
+        }
+        let thumbprint = |t: &[u8]| base64::encode_config(t, base64::URL_SAFE_NO_PAD);
+        out.x5t = header.x5t.as_ref().map(|x5t| thumbprint(x5t));
+        out.x5t_s256 = header.x5t_s256.as_ref().map(|x5t_s256| thumbprint(x5t_s256));
+        out.crit = header.crit.clone();
+        // jsonwebtoken would not compress the payload to match.
+        if header.cty == Cty::Deflate {
+            return Err(err::JWTError::NotImplementedError)
+        }
+        out.cty = header.cty.as_str().map(String::from);
+        for (name, value) in &header.params {
+            let value = match value.as_str() {
+                Some(value) => String::from(value),
+                None => continue,
+            };
+            match name.as_str() {
+                "nonce" => out.nonce = Some(value),
+                "url" => out.url = Some(value),
+                // jsonwebtoken gives these structured fields of their own.
+                "crit" | "jwk" | "enc" | "zip" => {}
+                _ => {
+                    out.extras.insert(name.clone(), value);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Converts a `jsonwebtoken::Header` into a `JWTHeader`. Returns a
+/// `err::JWTError::NotImplementedError` if the header uses an algorithm which `JWTHeader` cannot
+/// yet represent or has `"cty": "deflate"`, whose payload jsonwebtoken would not have compressed,
+/// or a `err::JWTError::SchemaError` if its `jku` or `x5u` is not
+/// a URL, its `x5c` is not base64, or its `x5t` or `x5t#S256` is not a thumbprint. Header
+/// parameters which `JWTHeader` has no field for (`jwk`, ...) are dropped.
+impl TryFrom<&Header> for JWTHeader {
+    type Error = err::JWTError;
+
+    fn try_from(header: &Header) -> err::Result<JWTHeader> {
+        let typ = header.typ.as_deref().map_or(Typ::None, Typ::parse);
+        let cty = header.cty.as_deref().map_or(Cty::None, Cty::parse);
+        if cty == Cty::Deflate {
+            return Err(err::JWTError::NotImplementedError)
+        }
+        let url = |url: &Option<String>| {
+            url.as_deref().map(Url::parse).transpose().map_err(|_| err::JWTError::SchemaError)
+        };
+        Ok(JWTHeader {
+            typ,
+            cty,
+            kid: header.kid.clone(),
+            jku: url(&header.jku)?,
+            x5u: url(&header.x5u)?,
+            x5c: header.x5c.iter().flatten()
+                .map(base64::decode)
+                .collect::<Result<_, _>>()
+                .map_err(|_| err::JWTError::SchemaError)?,
+            x5t: header.x5t.as_deref().map(parse_thumbprint).transpose()?,
+            x5t_s256: header.x5t_s256.as_deref().map(parse_thumbprint).transpose()?,
+            crit: header.crit.clone(),
+            params: header.nonce.iter().map(|nonce| ("nonce", nonce))
+                .chain(header.url.iter().map(|url| ("url", url)))
+                .chain(header.extras.iter().map(|(name, value)| (name.as_str(), value)))
+                .map(|(name, value)| (String::from(name), Value::from(value.as_str())))
+                .collect(),
+            ..JWTHeader::new(algorithm_to_alg(header.alg)?)
+        })
+    }
+}
+
+/// Splits a `JWT` into the header and claims arguments expected by `jsonwebtoken::encode`.
+impl TryFrom<JWT> for (Header, ClaimSet) {
+    type Error = err::JWTError;
+
+    fn try_from(jwt: JWT) -> err::Result<(Header, ClaimSet)> {
+        Ok((Header::try_from(&jwt.header)?, jwt.claim_set))
+    }
+}
+
+/// Assembles a `JWT` from the header and claims returned by `jsonwebtoken::decode`, e.g.
+/// `(token_data.header, token_data.claims)`.
+impl TryFrom<(Header, ClaimSet)> for JWT {
+    type Error = err::JWTError;
+
+    fn try_from(parts: (Header, ClaimSet)) -> err::Result<JWT> {
+        let (header, claim_set) = parts;
+        Ok(JWT {
+            header: JWTHeader::try_from(&header)?,
+            claim_set,
+            signature: Vec::new(),
+            raw: None,
+        })
+    }
+}
+
+// The claims jsonwebtoken's Validation can require; it ignores any others.
+const SPEC_CLAIMS: &[&str; 5] = &["exp", "nbf", "aud", "iss", "sub"];
+
+/// Converts a `claims::Validator` and the `VerifyOptions` naming the algorithms tokens may be
+/// signed with into a `jsonwebtoken::Validation` checking the same `iss`, `aud` and `sub`, the
+/// same required claims, and `exp` and `nbf` with the same leeway, rounded down to whole seconds.
+/// jsonwebtoken does not check that `iat` is not in the future, and always reads the system clock.
+///
+/// Returns a `err::JWTError::NotImplementedError` if `validator` or `options` sets anything
+/// jsonwebtoken cannot check: a maximum age, an authorized party, a replay store or chain, a
+/// `typ`, a maximum token size, custom rules, `Comparison::Normalized`, audiences per issuer,
+/// required claims other than `exp`, `nbf`, `aud`, `iss` and `sub`, unsecured tokens, or an
+/// algorithm jsonwebtoken does not support.
+///
+/// # Examples
+/// ```
+/// use jwt::claims::Validator;
+/// use jwt::header::Alg;
+/// use jwt::verify::VerifyOptions;
+///
+/// let validator = Validator::new().issuer("https://idp").audience("api");
+/// let options = VerifyOptions::new(&[Alg::RS256]);
+/// let validation = jwt::interop::jsonwebtoken::to_validation(&validator, &options).unwrap();
+/// assert_eq!(validation.algorithms, [jsonwebtoken::Algorithm::RS256]);
+/// assert!(validation.aud.unwrap().contains("api"));
+/// ```
+pub fn to_validation(validator: &Validator, options: &VerifyOptions) -> err::Result<Validation> {
+    let unsupported = validator.max_age.is_some()
+        || validator.authorized_party.is_some()
+        || validator.replay_store.is_some()
+        || validator.require_chain
+        || validator.typ.is_some()
+        || validator.max_token_size.is_some()
+        || !validator.custom_rules.is_empty()
+        || validator.comparison != Comparison::Exact
+        || validator.issuers.iter().any(|issuer| !issuer.audiences.is_empty())
+        || validator.required_claims.iter()
+            .any(|claim_name| !SPEC_CLAIMS.contains(&claim_name.as_str()))
+        || options.allow_unsecured;
+    if unsupported {
+        return Err(err::JWTError::NotImplementedError)
+    }
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.algorithms = options.algorithms.iter()
+        .map(alg_to_algorithm)
+        .collect::<err::Result<_>>()?;
+    validation.leeway = validator.leeway.as_secs();
+    validation.validate_nbf = true;
+    // jsonwebtoken only checks iss, sub and aud when they are present, where a Validator
+    // requires them.
+    let mut required_claims = validator.required_claims.clone();
+    if !validator.issuers.is_empty() {
+        validation.set_issuer(&validator.issuers.iter()
+            .map(|issuer| issuer.issuer.as_str())
+            .collect::<Vec<&str>>());
+        required_claims.push(String::from("iss"));
+    }
+    if let Some(subject) = &validator.subject {
+        validation.sub = Some(subject.clone());
+        required_claims.push(String::from("sub"));
+    }
+    if validator.audiences.is_empty() && !validator.require_audience {
+        validation.validate_aud = false;
+    } else {
+        // An empty set of audiences rejects every token, as require_audience does.
+        validation.set_audience(&validator.audiences);
+        required_claims.push(String::from("aud"));
+    }
+    validation.set_required_spec_claims(&required_claims);
+    Ok(validation)
+}
+
+/// Converts a `jsonwebtoken::Validation` into a `claims::Validator` checking the same `iss`,
+/// `aud` and `sub` with the same leeway and required claims, and `VerifyOptions` allowing the
+/// same algorithms. The result is stricter than `validation` in places: `exp`, `nbf` and `iat` are
+/// checked whenever present, whatever `validate_exp` and `validate_nbf` say, and `iss`, `sub`
+/// and `aud` are required if they are checked. A `Validation` which checks `aud` but names no
+/// audiences rejects tokens with an `aud` claim, as jsonwebtoken does, through a custom rule.
+///
+/// Returns a `err::JWTError::NotImplementedError` if `validation` rejects tokens expiring soon,
+/// or allows an algorithm `Alg` cannot represent.
+pub fn from_validation(validation: &Validation) -> err::Result<(Validator, VerifyOptions)> {
+    if validation.reject_tokens_expiring_in_less_than > 0 {
+        return Err(err::JWTError::NotImplementedError)
+    }
+    let algorithms = validation.algorithms.iter()
+        .map(|algorithm| algorithm_to_alg(*algorithm))
+        .collect::<err::Result<Vec<Alg>>>()?;
+
+    let required_claims = validation.required_spec_claims.iter()
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+    let mut validator = Validator::new()
+        .require_claims(&required_claims)
+        .leeway(Duration::from_secs(validation.leeway));
+    for issuer in validation.iss.iter().flatten() {
+        validator = validator.issuer(issuer);
+    }
+    if let Some(subject) = &validation.sub {
+        validator = validator.subject(subject);
+    }
+    match &validation.aud {
+        Some(audiences) if validation.validate_aud => {
+            for audience in audiences {
+                validator = validator.audience(audience);
+            }
+            validator = validator.require_audience();
+        }
+        None if validation.validate_aud => {
+            validator = validator.rule(|claim_set| match claim_set.get("aud") {
+                Ok(_) => Err(err::ValidationError::InvalidClaim(String::from("aud"))),
+                Err(_) => Ok(()),
+            });
+        }
+        _ => {}
+    }
+    Ok((validator, VerifyOptions::new(&algorithms)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::JsonSerializable;
+
+    #[test]
+    fn test_header_roundtrip() {
+        let kid = Some(String::from("k1"));
+        let h = JWTHeader {
+            typ: Typ::JWT,
+            kid: kid.clone(),
+            x5c: vec![vec![1, 2]],
+            x5t_s256: Some([7; 32]),
+            crit: Some(vec![String::from("ext")]),
+            params: serde_json::json!({"nonce": "n", "ext": "e", "num": 1})
+                .as_object().unwrap().clone(),
+            ..JWTHeader::new(Alg::HS256)
+        };
+        let jh = Header::try_from(&h).unwrap();
+        assert_eq!(jh.alg, Algorithm::HS256);
+        assert_eq!(jh.typ.as_deref(), Some("JWT"));
+        assert!(jh.cty.is_none());
+        assert_eq!(jh.kid, kid);
+        assert_eq!(jh.nonce.as_deref(), Some("n"));
+        assert_eq!(jh.x5c, Some(vec![String::from("AQI=")]));
+
+        let h = JWTHeader::try_from(&jh).unwrap();
+        assert!(matches!(h.alg, Alg::HS256));
+        assert!(matches!(h.typ, Typ::JWT));
+        assert_eq!(h.kid, kid);
+        assert_eq!(h.x5c, [[1, 2]]);
+        assert_eq!(h.x5t_s256, Some([7; 32]));
+        assert_eq!(h.critical(), ["ext"]);
+        assert_eq!(Value::from(h.params), serde_json::json!({"nonce": "n", "ext": "e"}));
+    }
+
+    #[test]
+    fn test_header_unsupported() {
+        let h = JWTHeader::new(Alg::None);
+        assert!(matches!(Header::try_from(&h), Err(err::JWTError::NotImplementedError)));
+        assert!(JWTHeader::try_from(&Header::new(Algorithm::PS256)).is_err());
+    }
+
+    #[test]
+    fn test_jwt_roundtrip() {
+        let mut jwt = JWT::new();
+        jwt.header.alg = Alg::HS256;
+        jwt.claim_set = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+
+        let (header, claims) = <(Header, ClaimSet)>::try_from(jwt).unwrap();
+        assert_eq!(header.alg, Algorithm::HS256);
+        let jwt = JWT::try_from((header, claims)).unwrap();
+        assert_eq!(jwt.claim_set.get("sub").unwrap().claim_value, "u1");
+    }
+
+    #[test]
+    fn test_validation_roundtrip() {
+        let validator = Validator::new()
+            .issuer("https://idp")
+            .audience("api")
+            .subject("u1")
+            .leeway(Duration::from_secs(30))
+            .require_claims(&["exp"]);
+        let options = VerifyOptions::new(&[Alg::ES256, Alg::EdDSA]);
+        let validation = to_validation(&validator, &options).unwrap();
+        assert_eq!(validation.algorithms, [Algorithm::ES256, Algorithm::EdDSA]);
+        assert_eq!(validation.leeway, 30);
+        assert!(validation.validate_nbf && validation.validate_aud);
+        assert_eq!(validation.sub.as_deref(), Some("u1"));
+        let mut required_claims = validation.required_spec_claims.iter().collect::<Vec<_>>();
+        required_claims.sort();
+        assert_eq!(required_claims, ["aud", "exp", "iss", "sub"]);
+
+        let (validator, options) = from_validation(&validation).unwrap();
+        assert_eq!(options.algorithms, [Alg::ES256, Alg::EdDSA]);
+        assert_eq!(validator.leeway, Duration::from_secs(30));
+        assert_eq!(validator.issuers[0].issuer, "https://idp");
+        assert_eq!(validator.audiences, ["api"]);
+        assert_eq!(validator.subject.as_deref(), Some("u1"));
+        let cs = ClaimSet::decode_str(
+            "{\"iss\": \"https://idp\", \"aud\": \"api\", \"sub\": \"u1\", \"exp\": 1e10}"
+        ).unwrap();
+        assert!(validator.validate(&cs).is_ok());
+        let cs = ClaimSet::decode_str("{\"iss\": \"https://idp\", \"sub\": \"u1\"}").unwrap();
+        assert!(validator.validate(&cs).is_err());
+    }
+
+    #[test]
+    fn test_validation_unsupported() {
+        let options = VerifyOptions::new(&[Alg::HS256]);
+        for validator in [
+            Validator::new().max_age(Duration::from_secs(60)),
+            Validator::new().require_claims(&["jti"]),
+            Validator::new().comparison(Comparison::Normalized),
+            Validator::strict(),
+        ] {
+            assert!(matches!(
+                to_validation(&validator, &options),
+                Err(err::JWTError::NotImplementedError)
+            ));
+        }
+        let options = VerifyOptions::new(&[Alg::ES256K]);
+        assert!(to_validation(&Validator::new(), &options).is_err());
+        let validation = Validation::new(Algorithm::PS256);
+        assert!(from_validation(&validation).is_err());
+
+        // jsonwebtoken rejects tokens with an aud claim when it is given no audiences.
+        let validation = Validation::new(Algorithm::HS256);
+        let (validator, _) = from_validation(&validation).unwrap();
+        let cs = ClaimSet::decode_str("{\"aud\": \"api\", \"exp\": 1e10}").unwrap();
+        assert!(validator.validate(&cs).is_err());
+        let cs = ClaimSet::decode_str("{\"exp\": 1e10}").unwrap();
+        assert!(validator.validate(&cs).is_ok());
+    }
+}
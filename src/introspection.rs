@@ -0,0 +1,257 @@
+#[cfg(feature = "introspection")]
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::claims::{Claim, ClaimSet};
+use crate::err;
+use crate::header::{Alg, JWTHeader};
+use crate::keys::VerificationKey;
+use crate::traits::Introspector;
+use crate::verify::{VerifiedJWT, VerifyOptions};
+use crate::JWT;
+
+#[derive(Debug)]
+/// The response to an RFC 7662 token introspection request. The authorization server reports
+/// whether the token is `active`, and, if it is, the claims associated with it.
+///
+/// Every top-level member of the response other than `active` is mapped onto a `Claim`. The
+/// members defined by RFC 7662 deliberately reuse the JWT registered claim names (`iss`, `sub`,
+/// `exp`, ...), so the resulting `ClaimSet` has the same shape as the payload of a local JWT and
+/// may be handled by the same code.
+///
+/// # Examples
+/// ```
+/// use jwt::introspection::IntrospectionResponse;
+///
+/// let r = IntrospectionResponse::parse("{\"active\": true, \"sub\": \"Z5O3upPC88QrAjx00dis\"}")
+///     .unwrap();
+/// assert!(r.active);
+/// assert_eq!(r.claim_set.get("sub").unwrap().claim_value, "Z5O3upPC88QrAjx00dis");
+/// ```
+pub struct IntrospectionResponse {
+    pub active: bool,
+    pub claim_set: ClaimSet,
+}
+
+impl IntrospectionResponse {
+    /// Parses an introspection response body. Returns a `err::JWTError::ParseError` if the body
+    /// is not valid JSON, or a `err::JWTError::SchemaError` if it is missing the required boolean
+    /// `active` member.
+    pub fn parse(body: &str) -> err::Result<IntrospectionResponse> {
+        let mut members: Map<String, Value> = serde_json::from_str(body)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+
+        let active = members.remove("active")
+            .and_then(|active| active.as_bool())
+            .ok_or(err::JWTError::SchemaError)?;
+
+        let mut claim_set = ClaimSet::new();
+        // Inactive tokens carry no meaningful claims; RFC 7662 section 2.2 asks servers not to
+        // return any, and we should not trust them if they do.
+        if active {
+            for (claim_name, claim_value) in members {
+                claim_set.insert(Claim::parse(claim_name, claim_value)?)?;
+            }
+        }
+        Ok(IntrospectionResponse { active, claim_set })
+    }
+
+    /// Returns the `ClaimSet` of an active token, or `None` if the token is inactive (expired,
+    /// revoked, unknown to the server, ...).
+    pub fn into_claim_set(self) -> Option<ClaimSet> {
+        if self.active { Some(self.claim_set) } else { None }
+    }
+}
+
+#[cfg(feature = "introspection")]
+/// A client for an RFC 7662 token introspection endpoint, authenticating with HTTP Basic client
+/// credentials. Requires the `introspection` feature.
+///
+/// This is intended as a fallback for tokens which cannot be checked locally, such as opaque
+/// (non-JWT) access tokens.
+pub struct IntrospectionClient {
+    pub endpoint: url::Url,
+    client_id: String,
+    client_secret: String,
+}
+
+#[cfg(feature = "introspection")]
+impl IntrospectionClient {
+    /// Constructs a new client for the given `endpoint`. Returns a `err::JWTError::ParseError` if
+    /// the endpoint is not a valid URL.
+    pub fn new(endpoint: &str, client_id: &str, client_secret: &str)
+        -> err::Result<IntrospectionClient> {
+        let endpoint = url::Url::parse(endpoint)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        Ok(IntrospectionClient {
+            endpoint,
+            client_id: String::from(client_id),
+            client_secret: String::from(client_secret),
+        })
+    }
+
+    /// Asks the introspection endpoint about `token`. Returns the token's `ClaimSet` if it is
+    /// active, `None` if it is not, or a `err::JWTError::HttpError` if the request fails.
+    pub fn introspect(&self, token: &str) -> err::Result<Option<ClaimSet>> {
+        let body = ureq::post(self.endpoint.as_str())
+            .set("Authorization", &self.authorization())
+            .set("Accept", "application/json")
+            .send_form(&[("token", token)])
+            .map_err(|e| { err::JWTError::HttpError(format!("{}", e)) })?
+            .into_string()
+            .map_err(|e| { err::JWTError::HttpError(format!("{}", e)) })?;
+
+        IntrospectionResponse::parse(&body).map(|r| r.into_claim_set())
+    }
+
+    // Returns the Basic Authorization header value for the client's credentials, each of which
+    // is form-urlencoded first (RFC 6749 section 2.3.1).
+    fn authorization(&self) -> String {
+        let encode = |value: &str| {
+            url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>()
+        };
+        let credentials = format!("{}:{}", encode(&self.client_id), encode(&self.client_secret));
+        String::from("Basic ") + &base64::encode(credentials)
+    }
+}
+
+// The client secret is a credential, so it is left out.
+#[cfg(feature = "introspection")]
+impl fmt::Debug for IntrospectionClient {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntrospectionClient")
+            .field("endpoint", &self.endpoint)
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "introspection")]
+impl Introspector for IntrospectionClient {
+    fn introspect(&self, token: &str) -> err::Result<Option<ClaimSet>> {
+        IntrospectionClient::introspect(self, token)
+    }
+}
+
+/// Verifies `token` locally under `key` if it can, and otherwise asks `introspector` about it,
+/// so that callers handle self-contained JWTs and opaque tokens along one code path.
+///
+/// A token which decodes as a JWT is verified under `key` against `options`, as
+/// `verify::UnverifiedJWT::verify` does, and rejected if that fails. A token which does not
+/// decode, or for which no `key` is given, such as one whose `kid` names no known key, is
+/// introspected instead: it is accepted with the claims the server reports if the server reports
+/// it active, and rejected with a `err::JWTError::SignatureError` if not. The header of an
+/// introspected token is never verified, so the `VerifiedJWT` returned for it has an unsecured
+/// header and no signature, and its claims should be checked with `claims::Validator::validate`
+/// rather than `validate_jwt`.
+///
+/// # Examples
+/// ```
+/// use jwt::{JsonSerializable, JWT};
+/// use jwt::claims::ClaimSet;
+/// use jwt::err;
+/// use jwt::header::Alg;
+/// use jwt::introspection;
+/// use jwt::verify::VerifyOptions;
+///
+/// let introspector = |token: &str| -> err::Result<Option<ClaimSet>> {
+///     let response = format!("{{\"active\": {}, \"sub\": \"u2\"}}", token == "opaque");
+///     introspection::IntrospectionResponse::parse(&response).map(|r| r.into_claim_set())
+/// };
+///
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// let key = [7; 32];
+/// let token = jwt.sign(&key).unwrap();
+/// let key = Some((&key[..]).into());
+/// let options = VerifyOptions::new(&[Alg::HS256]);
+/// let verified = introspection::verify_or_introspect(&token, key, &options, &introspector);
+/// assert_eq!(verified.unwrap().claim_set.get("sub").unwrap().claim_value, "u1");
+///
+/// let verified = introspection::verify_or_introspect("opaque", None, &options, &introspector);
+/// assert_eq!(verified.unwrap().claim_set.get("sub").unwrap().claim_value, "u2");
+/// assert!(introspection::verify_or_introspect("revoked", None, &options, &introspector).is_err());
+/// ```
+pub fn verify_or_introspect<I>(token: &str, key: Option<VerificationKey>, options: &VerifyOptions,
+    introspector: &I) -> err::Result<VerifiedJWT>
+    where I: Introspector + ?Sized {
+    if let (Ok(jwt), Some(key)) = (JWT::decode_unverified(token), key) {
+        return jwt.verify(key, options)
+    }
+    let claim_set = introspector.introspect(token)?.ok_or(err::JWTError::SignatureError)?;
+    let mut jwt = JWT::new();
+    jwt.header = JWTHeader::new(Alg::None);
+    jwt.claim_set = claim_set;
+    Ok(VerifiedJWT::new(jwt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::ClaimType;
+    use crate::traits::JsonSerializable;
+
+    #[test]
+    fn test_parse_active() {
+        let r = IntrospectionResponse::parse(r#"{
+            "active": true,
+            "client_id": "l238j323ds-23ij4",
+            "scope": "read write",
+            "iss": "https://server.example.com/",
+            "exp": 1419356238
+        }"#).unwrap();
+        assert!(r.active);
+        assert!(r.claim_set.get("active").is_err());
+        assert_eq!(r.claim_set.get("exp").unwrap().claim_value, 1419356238);
+        assert!(matches!(r.claim_set.get("iss").unwrap().claim_type, ClaimType::Registered));
+        assert!(matches!(r.claim_set.get("scope").unwrap().claim_type, ClaimType::Private));
+    }
+
+    #[test]
+    fn test_parse_inactive() {
+        let r = IntrospectionResponse::parse(r#"{"active": false, "sub": "ignored"}"#).unwrap();
+        assert!(!r.active);
+        assert!(r.into_claim_set().is_none());
+    }
+
+    #[test]
+    fn test_parse_missing_active() {
+        let r = IntrospectionResponse::parse(r#"{"sub": "foo"}"#);
+        assert!(matches!(r, Err(err::JWTError::SchemaError)));
+    }
+
+    #[test]
+    fn test_verify_or_introspect() {
+        let introspector = |_: &str| -> err::Result<Option<ClaimSet>> {
+            Ok(Some(ClaimSet::decode_str("{\"sub\": \"introspected\"}").unwrap()))
+        };
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"local\"}").unwrap();
+        jwt.header.alg = Alg::HS256;
+        let token = jwt.sign(&[7; 32]).unwrap();
+
+        let options = VerifyOptions::new(&[Alg::HS256]);
+        let key = Some((&[7; 32][..]).into());
+        let verified = verify_or_introspect(&token, key, &options, &introspector);
+        assert_eq!(verified.unwrap().claim_set.get("sub").unwrap().claim_value, "local");
+        // A bad signature is a decision, not a reason to ask the server, and so is an algorithm
+        // the options do not allow.
+        let other = Some((&[8; 32][..]).into());
+        assert!(verify_or_introspect(&token, other, &options, &introspector).is_err());
+        let options = VerifyOptions::new(&[Alg::HS512]);
+        assert!(verify_or_introspect(&token, key, &options, &introspector).is_err());
+        let verified = verify_or_introspect(&token, None, &options, &introspector).unwrap();
+        assert_eq!(verified.claim_set.get("sub").unwrap().claim_value, "introspected");
+        assert_eq!(verified.header.alg, Alg::None);
+    }
+
+    #[cfg(feature = "introspection")]
+    #[test]
+    fn test_authorization() {
+        let client = IntrospectionClient::new("https://as.example", "my client", "s:cr+t%")
+            .unwrap();
+        let credentials = base64::decode(&client.authorization()["Basic ".len()..]).unwrap();
+        assert_eq!(credentials, b"my+client:s%3Acr%2Bt%25");
+        assert!(!format!("{:?}", client).contains("s:cr+t%"));
+    }
+}
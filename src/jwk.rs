@@ -0,0 +1,455 @@
+//! JSON Web Keys (RFC 7517): keys in the JSON form identity providers publish them in, and JWK
+//! Sets of them, from which each token is verified under the key its `kid` header parameter names.
+
+use std::fmt;
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::crypto::b64url;
+use crate::err;
+use crate::header::{Alg, JWTHeader};
+use crate::keys::{EcPublicKey, Ed25519PublicKey, PrivateKey, PublicKey, RsaPublicKey};
+use crate::keys::VerificationKey;
+
+// The curves of RFC 7518 section 6.2.1.1 and RFC 8812 section 3.1, by their `crv` names, with the
+// algorithm which signs on each.
+const CURVES: [(&str, Alg); 4] = [
+    ("P-256", Alg::ES256), ("P-384", Alg::ES384), ("P-521", Alg::ES512), ("secp256k1", Alg::ES256K),
+];
+
+#[derive(Clone, PartialEq)]
+/// The key material of a `Jwk`: an HMAC secret (`"kty": "oct"`) or a public key. Only the public
+/// members of a private key are read, so a private JWK loads as its public half.
+pub enum JwkKey {
+    Oct(Vec<u8>),
+    Rsa(RsaPublicKey),
+    Ec(EcPublicKey),
+    Ed25519(Ed25519PublicKey),
+}
+
+impl JwkKey {
+    /// Returns whether this is a key for tokens signed with `alg`.
+    pub fn suits(&self, alg: Alg) -> bool {
+        match self {
+            JwkKey::Oct(_) => matches!(alg, Alg::HS256 | Alg::HS384 | Alg::HS512),
+            JwkKey::Rsa(_) => matches!(alg, Alg::RS256 | Alg::RS384 | Alg::RS512),
+            JwkKey::Ec(key) => key.alg() == alg,
+            JwkKey::Ed25519(_) => alg == Alg::EdDSA,
+        }
+    }
+
+    // Returns the members which hold the key, in lexicographic order as RFC 7638 section 3.2
+    // lists them for its thumbprint.
+    fn members(&self) -> Vec<(&'static str, String)> {
+        match self {
+            JwkKey::Oct(key) => vec![("k", b64url(key)), ("kty", String::from("oct"))],
+            JwkKey::Rsa(key) => {
+                let (n, e) = key.components();
+                vec![("e", b64url(&e)), ("kty", String::from("RSA")), ("n", b64url(&n))]
+            }
+            JwkKey::Ec(key) => {
+                let (x, y) = key.coordinates();
+                // Every curve an EcPublicKey may be on is named.
+                let (crv, _) = CURVES.iter().find(|(_, alg)| *alg == key.alg()).unwrap();
+                vec![
+                    ("crv", String::from(*crv)), ("kty", String::from("EC")),
+                    ("x", b64url(&x)), ("y", b64url(&y)),
+                ]
+            }
+            JwkKey::Ed25519(key) => vec![
+                ("crv", String::from("Ed25519")), ("kty", String::from("OKP")),
+                ("x", b64url(&key.to_bytes())),
+            ],
+        }
+    }
+}
+
+impl From<PublicKey> for JwkKey {
+    fn from(key: PublicKey) -> JwkKey {
+        match key {
+            PublicKey::Rsa(key) => JwkKey::Rsa(key),
+            PublicKey::Ec(key) => JwkKey::Ec(key),
+            PublicKey::Ed25519(key) => JwkKey::Ed25519(key),
+        }
+    }
+}
+
+// The secret of an HMAC key is a credential, so it is left out.
+impl fmt::Debug for JwkKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JwkKey::Oct(_) => f.debug_tuple("Oct").finish_non_exhaustive(),
+            JwkKey::Rsa(key) => f.debug_tuple("Rsa").field(key).finish(),
+            JwkKey::Ec(key) => f.debug_tuple("Ec").field(key).finish(),
+            JwkKey::Ed25519(key) => f.debug_tuple("Ed25519").field(key).finish(),
+        }
+    }
+}
+
+impl<'a> From<&'a JwkKey> for VerificationKey<'a> {
+    fn from(key: &'a JwkKey) -> VerificationKey<'a> {
+        match key {
+            JwkKey::Oct(key) => VerificationKey::Hmac(key),
+            JwkKey::Rsa(key) => VerificationKey::Rsa(key),
+            JwkKey::Ec(key) => VerificationKey::Ec(key),
+            JwkKey::Ed25519(key) => VerificationKey::Ed25519(key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A JSON Web Key, with the members of RFC 7517 section 4 which say what it may be used for.
+///
+/// # Examples
+/// ```
+/// use jwt::header::Alg;
+/// use jwt::jwk::{Jwk, JwkKey};
+///
+/// // The key of RFC 8037 appendix A.2.
+/// let jwk = Jwk::from_json(
+///     "{\"kty\": \"OKP\", \"crv\": \"Ed25519\", \"kid\": \"k1\",
+///       \"x\": \"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo\"}"
+/// ).unwrap();
+/// assert_eq!(jwk.kid.as_deref(), Some("k1"));
+/// assert!(matches!(jwk.key, JwkKey::Ed25519(_)));
+/// assert!(jwk.allows(Alg::EdDSA));
+/// assert!(!jwk.allows(Alg::ES256));
+/// ```
+pub struct Jwk {
+    /// The `kid` member, which identifies the key among those of a `JwkSet`.
+    pub kid: Option<String>,
+    /// The `alg` member, the one algorithm the key may be used with, if it is restricted to one.
+    pub alg: Option<Alg>,
+    /// The `use` member: `"sig"` for a key which verifies signatures, `"enc"` for one which
+    /// encrypts.
+    pub key_use: Option<String>,
+    pub key: JwkKey,
+}
+
+#[derive(Deserialize)]
+// The members of a JWK this crate reads.
+struct Members {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    #[serde(rename = "use")]
+    key_use: Option<String>,
+    crv: Option<String>,
+    k: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+// Decodes the base64url-encoded member `name` of a JWK, which the key type requires.
+fn member(name: &str, value: Option<&String>) -> err::Result<Vec<u8>> {
+    let value = value.ok_or_else(|| {
+        err::JWTError::ParseError(format!("missing JWK member \"{}\"", name))
+    })?;
+    base64::decode_config(value, base64::URL_SAFE_NO_PAD).map_err(|e| {
+        err::JWTError::ParseError(format!("JWK member \"{}\": {}", name, e))
+    })
+}
+
+impl Jwk {
+    /// Parses a JWK from its JSON. Returns a `err::JWTError::ParseError` if it is not a JWK, if
+    /// it names an algorithm, key type or curve this crate does not support, or if its key is
+    /// invalid.
+    pub fn from_json(json: &str) -> err::Result<Jwk> {
+        let value = serde_json::from_str(json)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        Jwk::from_value(value)
+    }
+
+    fn from_value(value: Value) -> err::Result<Jwk> {
+        let members: Members = serde_json::from_value(value)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        let alg = match &members.alg {
+            Some(alg) => Some(serde_json::from_value(Value::String(alg.clone())).map_err(|_| {
+                err::JWTError::ParseError(format!("unsupported algorithm \"{}\"", alg))
+            })?),
+            None => None,
+        };
+        let crv = members.crv.as_deref();
+        let key = match (members.kty.as_str(), crv) {
+            ("oct", _) => JwkKey::Oct(member("k", members.k.as_ref())?),
+            ("RSA", _) => JwkKey::Rsa(RsaPublicKey::from_components(
+                &member("n", members.n.as_ref())?, &member("e", members.e.as_ref())?
+            )?),
+            ("EC", Some(crv)) => {
+                let (_, curve) = CURVES.iter().find(|(name, _)| *name == crv).ok_or_else(|| {
+                    err::JWTError::ParseError(format!("unsupported curve \"{}\"", crv))
+                })?;
+                JwkKey::Ec(EcPublicKey::from_coordinates(
+                    *curve, &member("x", members.x.as_ref())?, &member("y", members.y.as_ref())?
+                )?)
+            }
+            ("OKP", Some("Ed25519")) => {
+                JwkKey::Ed25519(Ed25519PublicKey::from_bytes(&member("x", members.x.as_ref())?)?)
+            }
+            (kty, crv) => {
+                return Err(err::JWTError::ParseError(format!(
+                    "unsupported key type \"{}\"{}", kty,
+                    crv.map(|crv| format!(" on curve \"{}\"", crv)).unwrap_or_default()
+                )))
+            }
+        };
+        Ok(Jwk { kid: members.kid, alg, key_use: members.key_use, key })
+    }
+
+    /// Serializes the JWK to JSON. The secret of an HMAC key is written out, so only publish the
+    /// JWKs of public keys.
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    fn to_value(&self) -> Value {
+        let mut members: Map<String, Value> = self.key.members().into_iter()
+            .map(|(name, value)| (String::from(name), Value::String(value)))
+            .collect();
+        if let Some(kid) = &self.kid {
+            members.insert(String::from("kid"), Value::from(kid.as_str()));
+        }
+        if let Some(alg) = self.alg {
+            members.insert(String::from("alg"), Value::from(alg.as_str()));
+        }
+        if let Some(key_use) = &self.key_use {
+            members.insert(String::from("use"), Value::from(key_use.as_str()));
+        }
+        Value::Object(members)
+    }
+
+    /// Returns the JWK's SHA-256 thumbprint, as RFC 7638 specifies: the digest of the members
+    /// which hold the key, but not of its `kid`, `alg` or `use`. Base64url-encoded, it serves as a
+    /// `kid` which stays the same for as long as the key does.
+    pub fn thumbprint(&self) -> [u8; 32] {
+        let members: Vec<String> = self.key.members().iter()
+            .map(|(name, value)| format!("\"{}\":\"{}\"", name, value))
+            .collect();
+        Sha256::digest(format!("{{{}}}", members.join(",")).as_bytes()).into()
+    }
+
+    /// Returns whether tokens signed with `alg` may be verified under this key: whether the key
+    /// suits `alg`, and its `alg` and `use` members, where present, allow it.
+    pub fn allows(&self, alg: Alg) -> bool {
+        self.key.suits(alg) && self.alg.is_none_or(|only| only == alg) &&
+            self.key_use.as_deref().is_none_or(|key_use| key_use == "sig")
+    }
+}
+
+impl<'a> From<&'a Jwk> for VerificationKey<'a> {
+    fn from(jwk: &'a Jwk) -> VerificationKey<'a> {
+        VerificationKey::from(&jwk.key)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// A JWK Set: the keys an issuer may sign with, as published at its `jwks_uri`. Tokens are
+/// verified against a set with `verify::UnverifiedJWT::verify_with_keys`.
+///
+/// # Examples
+/// ```
+/// use jwt::JWT;
+/// use jwt::err::JWTError;
+/// use jwt::header::Alg;
+/// use jwt::jwk::JwkSet;
+/// use jwt::verify::VerifyOptions;
+///
+/// let keys = JwkSet::from_json(
+///     "{\"keys\": [{\"kty\": \"oct\", \"kid\": \"2024\",
+///                 \"k\": \"BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc\"}]}"
+/// ).unwrap();
+/// let options = VerifyOptions::new(&[Alg::HS256]);
+///
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// jwt.header.kid = Some(String::from("2024"));
+/// let token = jwt.sign(&[7; 32]).unwrap();
+/// assert!(JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options).is_ok());
+///
+/// // The issuer has rotated its keys since the set was fetched.
+/// jwt.header.kid = Some(String::from("2025"));
+/// let token = jwt.sign(&[8; 32]).unwrap();
+/// assert!(matches!(
+///     JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options),
+///     Err(JWTError::UnknownKid(kid)) if kid == "2025"
+/// ));
+/// ```
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Constructs the JWK Set to publish for the private keys `keys`: the JWKs of their public
+    /// halves, each with the algorithm it signs with (see `keys::PrivateKey::alg`), a `use` of
+    /// `"sig"`, and its thumbprint (see `Jwk::thumbprint`) as its `kid`. `JWT::sign_key` signs
+    /// tokens these keys verify, once the header's `kid` is set to the signing key's.
+    pub fn from_keys(keys: &[&PrivateKey]) -> JwkSet {
+        let keys = keys.iter().map(|key| {
+            let mut jwk = Jwk {
+                kid: None,
+                alg: Some(key.alg()),
+                key_use: Some(String::from("sig")),
+                key: JwkKey::from(key.public_key()),
+            };
+            jwk.kid = Some(b64url(&jwk.thumbprint()));
+            jwk
+        }).collect();
+        JwkSet { keys }
+    }
+
+    /// Parses a JWK Set from its JSON. Keys this crate cannot use, such as those of other key
+    /// types or on other curves, are skipped, as RFC 7517 section 5 asks. Returns a
+    /// `err::JWTError::ParseError` if the JSON is not a JWK Set.
+    pub fn from_json(json: &str) -> err::Result<JwkSet> {
+        #[derive(Deserialize)]
+        struct Keys {
+            keys: Vec<Value>,
+        }
+        let set: Keys = serde_json::from_str(json)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        let keys = set.keys.into_iter().filter_map(|key| Jwk::from_value(key).ok()).collect();
+        Ok(JwkSet { keys })
+    }
+
+    /// Returns the key whose `kid` is `kid`, if there is one.
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|jwk| jwk.kid.as_deref() == Some(kid))
+    }
+
+    /// Returns the keys a token with `header` may be verified under: those whose `kid` is the
+    /// header's or, if it has none, every key. Keys which do not allow the header's algorithm
+    /// (see `Jwk::allows`) are left out. Returns a `err::JWTError::UnknownKid` if the header
+    /// names a `kid` no key has, which may mean that the issuer has rotated its keys since the set
+    /// was fetched.
+    pub fn select(&self, header: &JWTHeader) -> err::Result<Vec<&Jwk>> {
+        let keys: Vec<&Jwk> = match &header.kid {
+            Some(kid) => {
+                let keys: Vec<&Jwk> =
+                    self.keys.iter().filter(|jwk| jwk.kid.as_ref() == Some(kid)).collect();
+                if keys.is_empty() {
+                    return Err(err::JWTError::UnknownKid(kid.clone()))
+                }
+                keys
+            }
+            None => self.keys.iter().collect(),
+        };
+        Ok(keys.into_iter().filter(|jwk| jwk.allows(header.alg)).collect())
+    }
+
+    /// Serializes the set to JSON, as `{"keys": [...]}`, to publish at a `jwks_uri`. HMAC keys
+    /// are secrets, so they are left out.
+    pub fn to_json(&self) -> String {
+        let keys: Vec<Value> = self.keys.iter()
+            .filter(|jwk| !matches!(jwk.key, JwkKey::Oct(_)))
+            .map(Jwk::to_value)
+            .collect();
+        serde_json::json!({ "keys": keys }).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The public keys of RFC 7515 appendices A.2 and A.3, which the test keys are.
+    const RSA: &str = "{\"kty\": \"RSA\", \"kid\": \"rsa\", \"e\": \"AQAB\", \"n\": \
+        \"ofgWCuLjybRlzo0tZWJjNiuSfb4p4fAkd_wWJcyQoTbji9k0l8W26mPddxHmfHQp-Vaw-4qPCJrcS2mJPMEzP1P\
+        t0Bm4d4QlL-yRT-SFd2lZS-pCgNMsD1W_YpRPEwOWvG6b32690r2jZ47soMZo9wGzjb_7OMg0LOL-bSf63kpaSHSXnd\
+        S5z5rexMdbBYUsLA9e-KXBdQOS-UTo7WTBEMa2R2CapHg665xsmtdVMTBQY4uDZlxvb3qCo5ZwKh9kG4LT6_I5IhlJH\
+        7aGhyxXFvUK-DWNmoudF8NAco9_h9iaGNj8q2ethFkMLs91kzk2PAcDTW9gb54h4FRWyuXpoQ\"}";
+    const EC: &str = "{\"kty\": \"EC\", \"kid\": \"ec\", \"crv\": \"P-256\", \"use\": \"sig\", \
+        \"x\": \"f83OJ3D2xF1Bg8vub9tLe1gHMzV76e8Tus9uPHvRVEU\", \
+        \"y\": \"x_FEzRu9m36HLN_tue659LNpXW6pCyStikYjKIWI5a0\"}";
+
+    #[test]
+    fn test_from_json() {
+        let rsa = Jwk::from_json(RSA).unwrap();
+        let public = RsaPublicKey::from_pem(include_str!("testdata/rsa_public.pem")).unwrap();
+        assert_eq!(rsa.key, JwkKey::Rsa(public));
+        assert!(rsa.allows(Alg::RS256) && !rsa.allows(Alg::ES256));
+        let ec = Jwk::from_json(EC).unwrap();
+        let public = EcPublicKey::from_pem(include_str!("testdata/ec_p256_public.pem")).unwrap();
+        assert_eq!(ec.key, JwkKey::Ec(public));
+        assert_eq!(ec.key_use.as_deref(), Some("sig"));
+        assert!(ec.allows(Alg::ES256) && !ec.allows(Alg::ES384));
+
+        let oct = Jwk::from_json("{\"kty\": \"oct\", \"alg\": \"HS384\", \"k\": \"a2V5\"}")
+            .unwrap();
+        assert_eq!(oct.key, JwkKey::Oct(b"key".to_vec()));
+        assert!(oct.allows(Alg::HS384) && !oct.allows(Alg::HS256));
+        assert_eq!(format!("{:?}", oct.key), "Oct(..)");
+        let enc = Jwk::from_json("{\"kty\": \"oct\", \"use\": \"enc\", \"k\": \"a2V5\"}").unwrap();
+        assert!(!enc.allows(Alg::HS256));
+
+        assert!(Jwk::from_json("{\"kty\": \"oct\"}").is_err());
+        assert!(Jwk::from_json("{\"kty\": \"oct\", \"alg\": \"A128KW\", \"k\": \"\"}").is_err());
+        assert!(Jwk::from_json("{\"kty\": \"OKP\", \"crv\": \"X25519\", \"x\": \"\"}").is_err());
+        assert!(Jwk::from_json(&EC.replace("P-256", "P-384")).is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let json = format!(
+            "{{\"keys\": [{}, {}, {{\"kty\": \"EC\", \"crv\": \"Brainpool\"}}, \
+             {{\"kty\": \"oct\", \"k\": \"a2V5\"}}]}}", RSA, EC
+        );
+        let keys = JwkSet::from_json(&json).unwrap();
+        assert_eq!(keys.keys.len(), 3);
+        assert_eq!(keys.find("ec"), Some(&keys.keys[1]));
+        assert!(keys.find("oct").is_none());
+
+        let header = |alg, kid: Option<&str>| {
+            JWTHeader { kid: kid.map(String::from), ..JWTHeader::new(alg) }
+        };
+        assert_eq!(keys.select(&header(Alg::RS256, Some("rsa"))).unwrap(), vec![&keys.keys[0]]);
+        assert!(keys.select(&header(Alg::ES256, Some("rsa"))).unwrap().is_empty());
+        assert!(matches!(
+            keys.select(&header(Alg::RS256, Some("old"))), Err(err::JWTError::UnknownKid(_))
+        ));
+        // Without a kid, every key suited to the algorithm is a candidate.
+        assert_eq!(keys.select(&header(Alg::HS256, None)).unwrap(), vec![&keys.keys[2]]);
+        assert_eq!(keys.select(&header(Alg::ES256, None)).unwrap(), vec![&keys.keys[1]]);
+
+        assert!(JwkSet::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn test_thumbprint() {
+        // The key and thumbprint of RFC 7638 section 3.1.
+        let jwk = Jwk::from_json(
+            "{\"kty\": \"RSA\", \"e\": \"AQAB\", \"kid\": \"2011-04-29\", \
+            \"alg\": \"RS256\", \"n\": \"\
+            0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc\
+            _BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQ\
+            R0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bF\
+            TWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw\"}"
+        ).unwrap();
+        assert_eq!(b64url(&jwk.thumbprint()), "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+    }
+
+    #[test]
+    fn test_from_keys() {
+        let rsa = PrivateKey::from_pem(include_str!("testdata/rsa_private_pkcs8.pem")).unwrap();
+        let ec = PrivateKey::from_pem(include_str!("testdata/ec_p384_private_pkcs8.pem")).unwrap();
+        let ed25519 = PrivateKey::from_pem(include_str!("testdata/ed25519_private.pem")).unwrap();
+        let mut keys = JwkSet::from_keys(&[&rsa, &ec, &ed25519]);
+        assert_eq!(
+            keys.keys.iter().map(|jwk| jwk.alg).collect::<Vec<_>>(),
+            vec![Some(Alg::RS256), Some(Alg::ES384), Some(Alg::EdDSA)]
+        );
+        assert_eq!(keys.keys[0].kid, Some(b64url(&keys.keys[0].thumbprint())));
+
+        // The public keys read back as they were written, and the HMAC secret is left out.
+        keys.keys.push(Jwk::from_json("{\"kty\": \"oct\", \"k\": \"a2V5\"}").unwrap());
+        let json = keys.to_json();
+        assert!(!json.contains("a2V5"));
+        keys.keys.pop();
+        assert_eq!(JwkSet::from_json(&json).unwrap(), keys);
+        assert_eq!(keys.keys[1].key, JwkKey::from(ec.public_key()));
+        assert_eq!(Jwk::from_json(&keys.keys[2].to_json()).unwrap(), keys.keys[2]);
+    }
+}
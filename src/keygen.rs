@@ -0,0 +1,75 @@
+//! Generation of new signing keys, for issuers which hold their keys themselves rather than in a
+//! key management service (see `signers`).
+
+use crate::crypto::{Active, Backend};
+use crate::err;
+use crate::header::Alg;
+use crate::keys::PrivateKey;
+
+/// The size in bits of the RSA keys `generate_pem` generates: the smallest RFC 7518 section 3.3
+/// allows, and the one most issuers use.
+pub const RSA_BITS: usize = 2048;
+
+/// Generates a new private key to sign with `alg`, as PKCS#8 (`PRIVATE KEY`) PEM. RSA keys are
+/// `RSA_BITS` bits long. Returns a `err::JWTError::NotImplementedError` for the HMAC algorithms,
+/// whose keys are random byte strings, for `none`, and for keys the backend cannot generate: RSA
+/// and P-521 keys with the `ring` backend.
+///
+/// # Examples
+/// ```
+/// use jwt::header::Alg;
+/// use jwt::keygen;
+/// use jwt::keys::PrivateKey;
+///
+/// let pem = keygen::generate_pem(Alg::EdDSA).unwrap();
+/// assert_eq!(PrivateKey::from_pem(&pem).unwrap().alg(), Alg::EdDSA);
+/// assert!(keygen::generate_pem(Alg::HS256).is_err());
+/// ```
+pub fn generate_pem(alg: Alg) -> err::Result<String> {
+    let der = match alg {
+        Alg::RS256 | Alg::RS384 | Alg::RS512 => Active::rsa_generate(RSA_BITS)?,
+        Alg::ES256 | Alg::ES384 | Alg::ES512 | Alg::ES256K => Active::ec_generate(alg)?,
+        Alg::EdDSA => Active::ed25519_generate()?,
+        Alg::None | Alg::HS256 | Alg::HS384 | Alg::HS512 => {
+            return Err(err::JWTError::NotImplementedError)
+        }
+    };
+    pem_rfc7468::encode_string("PRIVATE KEY", pem_rfc7468::LineEnding::LF, &der)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+/// Generates a new private key to sign with `alg`, as `generate_pem` does.
+pub fn generate(alg: Alg) -> err::Result<PrivateKey> {
+    PrivateKey::from_pem(&generate_pem(alg)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keys::PublicKey;
+    use crate::verify::VerifyOptions;
+    use crate::JWT;
+
+    #[test]
+    fn test_generate() {
+        // ring generates neither RSA nor P-521 keys.
+        let ring = cfg!(all(feature = "ring", not(feature = "openssl")));
+        let algs = [Alg::RS256, Alg::ES256, Alg::ES384, Alg::ES512, Alg::EdDSA];
+        for alg in algs.iter().filter(|alg| !ring || !matches!(alg, Alg::RS256 | Alg::ES512)) {
+            let key = generate(*alg).unwrap();
+            assert_eq!(key.alg(), *alg);
+            let token = JWT::new().sign_key(&key).unwrap();
+            let public = key.public_key();
+            assert_eq!(PublicKey::from_pem(&public.to_pem()).unwrap(), public);
+            let unverified = JWT::decode_unverified(&token).unwrap();
+            assert!(unverified.verify(&public, &VerifyOptions::new(&[*alg])).is_ok());
+        }
+        // Each key is new.
+        assert_ne!(generate(Alg::EdDSA).unwrap(), generate(Alg::EdDSA).unwrap());
+        assert!(matches!(generate_pem(Alg::HS256), Err(err::JWTError::NotImplementedError)));
+        assert!(matches!(generate_pem(Alg::None), Err(err::JWTError::NotImplementedError)));
+        if ring {
+            assert!(matches!(generate_pem(Alg::RS256), Err(err::JWTError::NotImplementedError)));
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! Keys for the asymmetric signing algorithms. HMAC keys are plain byte strings, and need no type
+//! of their own.
+
+use std::convert::TryFrom;
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use x509_cert::der::asn1::{Any, BitString, UintRef};
+use x509_cert::der::{Decode, Encode};
+use x509_cert::spki::{AlgorithmIdentifierOwned, ObjectIdentifier, SubjectPublicKeyInfoOwned};
+use x509_cert::Certificate;
+
+use crate::crypto::{Active, Backend};
+use crate::err;
+use crate::header::Alg;
+
+#[derive(Debug, Clone, PartialEq)]
+/// An RSA private key, for signing with the `RS*` algorithms.
+pub struct RsaPrivateKey {
+    pub(crate) key: <Active as Backend>::RsaPrivateKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An RSA public key, for verifying signatures made with the `RS*` algorithms.
+pub struct RsaPublicKey {
+    pub(crate) key: <Active as Backend>::RsaPublicKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An elliptic curve private key, for signing with the `ES*` algorithm of its curve.
+pub struct EcPrivateKey {
+    pub(crate) key: <Active as Backend>::EcPrivateKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An elliptic curve public key, for verifying signatures made with the `ES*` algorithm of its
+/// curve.
+pub struct EcPublicKey {
+    pub(crate) key: <Active as Backend>::EcPublicKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An Ed25519 private key, for signing with the `EdDSA` algorithm.
+pub struct Ed25519PrivateKey {
+    pub(crate) key: <Active as Backend>::Ed25519PrivateKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// An Ed25519 public key, for verifying signatures made with the `EdDSA` algorithm.
+pub struct Ed25519PublicKey {
+    pub(crate) key: <Active as Backend>::Ed25519PublicKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A public key of any of the kinds above, for keys whose kind is only known once they are
+/// loaded, such as those of X.509 certificates.
+pub enum PublicKey {
+    Rsa(RsaPublicKey),
+    Ec(EcPublicKey),
+    Ed25519(Ed25519PublicKey),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A private key of any of the kinds above, for keys whose kind is only known once they are
+/// loaded, such as those read from a PEM file given on the command line. `JWT::sign_key` signs
+/// with one under the algorithm it infers from the kind of key.
+pub enum PrivateKey {
+    Rsa(RsaPrivateKey),
+    Ec(EcPrivateKey),
+    Ed25519(Ed25519PrivateKey),
+}
+
+// The algorithm identifiers of the public keys certificates may hold, from RFC 3279 and RFC 8410.
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+// The named curves of RFC 5480 and SEC 2.
+const SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const SECP384R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+const SECP521R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.35");
+const SECP256K1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.10");
+
+// Encodes the SubjectPublicKeyInfo of a key of the algorithm `oid`.
+fn spki_der(oid: ObjectIdentifier, parameters: Option<Any>, key: &[u8])
+    -> x509_cert::der::Result<Vec<u8>> {
+    SubjectPublicKeyInfoOwned {
+        algorithm: AlgorithmIdentifierOwned { oid, parameters },
+        subject_public_key: BitString::from_bytes(key)?,
+    }.to_der()
+}
+
+impl PublicKey {
+    /// Loads a public key of any kind from PEM, in any of the forms the `from_pem` functions of
+    /// the kinds of key accept. Returns a `err::JWTError::ParseError` if `pem` holds none of them.
+    pub fn from_pem(pem: &str) -> err::Result<PublicKey> {
+        RsaPublicKey::from_pem(pem).map(PublicKey::Rsa)
+            .or_else(|_| EcPublicKey::from_pem(pem).map(PublicKey::Ec))
+            .or_else(|_| Ed25519PublicKey::from_pem(pem).map(PublicKey::Ed25519))
+            .map_err(|_| {
+                err::JWTError::ParseError(String::from(
+                    "expected an RSA, elliptic curve or Ed25519 public key"
+                ))
+            })
+    }
+
+    /// Loads the public key of the DER-encoded X.509 certificate `der`. Returns a
+    /// `err::JWTError::ParseError` if `der` is not a certificate, or if its key is of a kind this
+    /// crate does not support. Only the key is read: the certificate's signature, validity period
+    /// and chain of trust are not checked.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::keys::PublicKey;
+    ///
+    /// assert!(PublicKey::from_certificate(b"not a certificate").is_err());
+    /// ```
+    pub fn from_certificate(der: &[u8]) -> err::Result<PublicKey> {
+        let parse_error = |e: x509_cert::der::Error| err::JWTError::ParseError(format!("{}", e));
+        let certificate = Certificate::from_der(der).map_err(parse_error)?;
+        let spki = &certificate.tbs_certificate.subject_public_key_info;
+        let spki_der = spki.to_der().map_err(parse_error)?;
+        let der = PublicKeyDer::Spki(&spki_der);
+        match spki.algorithm.oid {
+            RSA_ENCRYPTION => {
+                Active::rsa_public_key(der).map(|key| PublicKey::Rsa(RsaPublicKey { key }))
+            }
+            EC_PUBLIC_KEY => {
+                Active::ec_public_key(der).map(|key| PublicKey::Ec(EcPublicKey { key }))
+            }
+            ED25519 => {
+                Active::ed25519_public_key(der)
+                    .map(|key| PublicKey::Ed25519(Ed25519PublicKey { key }))
+            }
+            oid => Err(err::JWTError::ParseError(format!("unsupported key algorithm {}", oid))),
+        }
+    }
+
+    /// Encodes the key as SubjectPublicKeyInfo (`PUBLIC KEY`) PEM, the form the `from_pem`
+    /// functions of the kinds of key read back.
+    pub fn to_pem(&self) -> String {
+        let der = match self {
+            PublicKey::Rsa(key) => {
+                // The key of an RSA SubjectPublicKeyInfo is its PKCS#1 RSAPublicKey.
+                let (n, e) = key.components();
+                UintRef::new(&n).and_then(|n| [n, UintRef::new(&e)?].to_der())
+                    .and_then(|key| spki_der(RSA_ENCRYPTION, Some(Any::null()), &key))
+            }
+            PublicKey::Ec(key) => {
+                let curve = match key.alg() {
+                    Alg::ES384 => SECP384R1,
+                    Alg::ES512 => SECP521R1,
+                    Alg::ES256K => SECP256K1,
+                    _ => SECP256R1,
+                };
+                let (x, y) = key.coordinates();
+                let point = [&[4], &x[..], &y].concat();
+                Any::encode_from(&curve)
+                    .and_then(|curve| spki_der(EC_PUBLIC_KEY, Some(curve), &point))
+            }
+            PublicKey::Ed25519(key) => spki_der(ED25519, None, &key.to_bytes()),
+        };
+        // The components of a loaded key always encode, and the PEM of DER always does.
+        pem_rfc7468::encode_string("PUBLIC KEY", pem_rfc7468::LineEnding::LF, &der.unwrap())
+            .unwrap()
+    }
+}
+
+impl PrivateKey {
+    /// Loads a private key of any kind from PEM, in any of the forms the `from_pem` functions of
+    /// the kinds of key accept. Returns a `err::JWTError::ParseError` if `pem` holds none of them.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::header::Alg;
+    /// use jwt::keys::PrivateKey;
+    /// # let pem = include_str!("testdata/ec_p256_private_pkcs8.pem");
+    ///
+    /// let key = PrivateKey::from_pem(pem).unwrap();
+    /// assert!(matches!(key, PrivateKey::Ec(_)));
+    /// assert_eq!(key.alg(), Alg::ES256);
+    /// ```
+    pub fn from_pem(pem: &str) -> err::Result<PrivateKey> {
+        RsaPrivateKey::from_pem(pem).map(PrivateKey::Rsa)
+            .or_else(|_| EcPrivateKey::from_pem(pem).map(PrivateKey::Ec))
+            .or_else(|_| Ed25519PrivateKey::from_pem(pem).map(PrivateKey::Ed25519))
+            .map_err(|_| {
+                err::JWTError::ParseError(String::from(
+                    "expected an RSA, elliptic curve or Ed25519 private key"
+                ))
+            })
+    }
+
+    /// Returns the algorithm to sign with under this key, unless another is chosen: `RS256` for
+    /// RSA keys, the `ES*` algorithm of their curve for elliptic curve keys, and `EdDSA` for
+    /// Ed25519 keys. RSA keys could sign with `RS384` or `RS512` too, but `RS256` is the one every
+    /// verifier implements.
+    pub fn alg(&self) -> Alg {
+        match self {
+            PrivateKey::Rsa(_) => Alg::RS256,
+            PrivateKey::Ec(key) => key.alg(),
+            PrivateKey::Ed25519(_) => Alg::EdDSA,
+        }
+    }
+
+    /// Returns whether this key can sign with `alg`.
+    pub fn suits(&self, alg: Alg) -> bool {
+        match self {
+            PrivateKey::Rsa(_) => matches!(alg, Alg::RS256 | Alg::RS384 | Alg::RS512),
+            PrivateKey::Ec(key) => key.alg() == alg,
+            PrivateKey::Ed25519(_) => alg == Alg::EdDSA,
+        }
+    }
+
+    /// Returns the public half of this key.
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            PrivateKey::Rsa(key) => PublicKey::Rsa(key.public_key()),
+            PrivateKey::Ec(key) => PublicKey::Ec(key.public_key()),
+            PrivateKey::Ed25519(key) => PublicKey::Ed25519(key.public_key()),
+        }
+    }
+}
+
+/// Returns the SHA-1 thumbprint of the DER-encoded X.509 certificate `certificate`, as the `x5t`
+/// header parameter holds. SHA-1 is no longer collision resistant, so prefer `x5t_s256` for
+/// pinning certificates.
+pub fn x5t(certificate: &[u8]) -> [u8; 20] {
+    Sha1::digest(certificate).into()
+}
+
+/// Returns the SHA-256 thumbprint of the DER-encoded X.509 certificate `certificate`, as the
+/// `x5t#S256` header parameter holds.
+///
+/// # Examples
+/// ```
+/// use jwt::JsonSerializable;
+/// use jwt::header::JWTHeader;
+/// use jwt::keys;
+///
+/// // The thumbprint of the certificate the application pins.
+/// let pinned = keys::x5t_s256(b"certificate");
+/// let header = JWTHeader::decode_str(
+///     "{\"alg\": \"RS256\", \"x5t#S256\": \"A9Zt0Ig1wco_EozOrNHzGslBYwlrIPRFroQoW8CDLXI\"}"
+/// ).unwrap();
+/// assert_eq!(header.x5t_s256, Some(pinned));
+/// ```
+pub fn x5t_s256(certificate: &[u8]) -> [u8; 32] {
+    Sha256::digest(certificate).into()
+}
+
+#[derive(Debug, Clone, Copy)]
+/// A key to verify a signature with, of whichever kind the token's algorithm needs. It converts
+/// from an HMAC key or a reference to any of the public keys above, so `JWT::verify` may be passed
+/// either directly.
+pub enum VerificationKey<'a> {
+    Hmac(&'a [u8]),
+    Rsa(&'a RsaPublicKey),
+    Ec(&'a EcPublicKey),
+    Ed25519(&'a Ed25519PublicKey),
+}
+
+impl<'a> From<&'a [u8]> for VerificationKey<'a> {
+    fn from(key: &'a [u8]) -> VerificationKey<'a> {
+        VerificationKey::Hmac(key)
+    }
+}
+
+impl<'a, const N: usize> From<&'a [u8; N]> for VerificationKey<'a> {
+    fn from(key: &'a [u8; N]) -> VerificationKey<'a> {
+        VerificationKey::Hmac(key)
+    }
+}
+
+impl<'a> From<&'a RsaPublicKey> for VerificationKey<'a> {
+    fn from(key: &'a RsaPublicKey) -> VerificationKey<'a> {
+        VerificationKey::Rsa(key)
+    }
+}
+
+impl<'a> From<&'a EcPublicKey> for VerificationKey<'a> {
+    fn from(key: &'a EcPublicKey) -> VerificationKey<'a> {
+        VerificationKey::Ec(key)
+    }
+}
+
+impl<'a> From<&'a Ed25519PublicKey> for VerificationKey<'a> {
+    fn from(key: &'a Ed25519PublicKey) -> VerificationKey<'a> {
+        VerificationKey::Ed25519(key)
+    }
+}
+
+impl<'a> From<&'a PublicKey> for VerificationKey<'a> {
+    fn from(key: &'a PublicKey) -> VerificationKey<'a> {
+        match key {
+            PublicKey::Rsa(key) => VerificationKey::Rsa(key),
+            PublicKey::Ec(key) => VerificationKey::Ec(key),
+            PublicKey::Ed25519(key) => VerificationKey::Ed25519(key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+// The DER encoding of a private key, in the format named by the label of the PEM it came from.
+pub(crate) enum PrivateKeyDer<'a> {
+    Pkcs8(&'a [u8]),
+    Pkcs1(&'a [u8]),
+    Sec1(&'a [u8]),
+}
+
+#[derive(Debug, Clone, Copy)]
+// The DER encoding of a public key, in the format named by the label of the PEM it came from.
+pub(crate) enum PublicKeyDer<'a> {
+    Spki(&'a [u8]),
+    Pkcs1(&'a [u8]),
+}
+
+// Decodes a PEM document into its label and DER contents.
+fn decode_pem(pem: &str) -> err::Result<(String, Vec<u8>)> {
+    pem_rfc7468::decode_vec(pem.trim().as_bytes())
+        .map(|(label, der)| (String::from(label), der))
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+// Decodes private key PEM, passing its DER contents to `parse`.
+fn from_private_pem<K, F>(pem: &str, parse: F) -> err::Result<K>
+    where F: FnOnce(PrivateKeyDer) -> err::Result<K> {
+    let (label, der) = decode_pem(pem)?;
+    match label.as_str() {
+        "PRIVATE KEY" => parse(PrivateKeyDer::Pkcs8(&der)),
+        "RSA PRIVATE KEY" => parse(PrivateKeyDer::Pkcs1(&der)),
+        "EC PRIVATE KEY" => parse(PrivateKeyDer::Sec1(&der)),
+        _ => Err(err::JWTError::ParseError(format!("unexpected PEM label \"{}\"", label))),
+    }
+}
+
+// Decodes public key PEM, passing its DER contents to `parse`.
+fn from_public_pem<K, F>(pem: &str, parse: F) -> err::Result<K>
+    where F: FnOnce(PublicKeyDer) -> err::Result<K> {
+    let (label, der) = decode_pem(pem)?;
+    match label.as_str() {
+        "PUBLIC KEY" => parse(PublicKeyDer::Spki(&der)),
+        "RSA PUBLIC KEY" => parse(PublicKeyDer::Pkcs1(&der)),
+        _ => Err(err::JWTError::ParseError(format!("unexpected PEM label \"{}\"", label))),
+    }
+}
+
+impl RsaPrivateKey {
+    /// Loads an RSA private key from PEM, in either PKCS#8 (`PRIVATE KEY`) or PKCS#1 (`RSA
+    /// PRIVATE KEY`) form. Returns a `err::JWTError::ParseError` if `pem` holds neither.
+    pub fn from_pem(pem: &str) -> err::Result<RsaPrivateKey> {
+        from_private_pem(pem, Active::rsa_private_key).map(|key| RsaPrivateKey { key })
+    }
+
+    /// Returns the public half of this key.
+    pub fn public_key(&self) -> RsaPublicKey {
+        RsaPublicKey { key: Active::rsa_public_key_of(&self.key) }
+    }
+}
+
+impl RsaPublicKey {
+    /// Loads an RSA public key from PEM, in either SubjectPublicKeyInfo (`PUBLIC KEY`) or PKCS#1
+    /// (`RSA PUBLIC KEY`) form. Returns a `err::JWTError::ParseError` if `pem` holds neither.
+    pub fn from_pem(pem: &str) -> err::Result<RsaPublicKey> {
+        from_public_pem(pem, Active::rsa_public_key).map(|key| RsaPublicKey { key })
+    }
+
+    /// Loads an RSA public key from its modulus `n` and public exponent `e`, as unsigned
+    /// big-endian integers (the `n` and `e` of a JWK). Returns a `err::JWTError::ParseError` if
+    /// they are not those of a valid key.
+    pub fn from_components(n: &[u8], e: &[u8]) -> err::Result<RsaPublicKey> {
+        // An RSAPublicKey of PKCS#1 is a SEQUENCE of the modulus and the public exponent.
+        let der = UintRef::new(n).and_then(|n| [n, UintRef::new(e)?].to_der())
+            .map_err(|e| err::JWTError::ParseError(format!("{}", e)))?;
+        Active::rsa_public_key(PublicKeyDer::Pkcs1(&der)).map(|key| RsaPublicKey { key })
+    }
+
+    /// Returns the size of the key's modulus, in bits.
+    pub fn bits(&self) -> usize {
+        Active::rsa_public_key_bits(&self.key)
+    }
+
+    /// Returns the key's modulus and public exponent, as unsigned big-endian integers without
+    /// leading zero bytes: the inverse of `from_components`.
+    pub fn components(&self) -> (Vec<u8>, Vec<u8>) {
+        Active::rsa_public_key_components(&self.key)
+    }
+}
+
+impl EcPrivateKey {
+    /// Loads an elliptic curve private key on P-256, P-384 or P-521 (or secp256k1, with the
+    /// `secp256k1` feature) from PEM, in either PKCS#8 (`PRIVATE KEY`) or SEC1 (`EC PRIVATE KEY`)
+    /// form. Returns a `err::JWTError::ParseError` if `pem` holds neither, or holds a P-521 key
+    /// and the crate was built with the `ring` backend.
+    pub fn from_pem(pem: &str) -> err::Result<EcPrivateKey> {
+        from_private_pem(pem, Active::ec_private_key).map(|key| EcPrivateKey { key })
+    }
+
+    /// Returns the public half of this key.
+    pub fn public_key(&self) -> EcPublicKey {
+        EcPublicKey { key: Active::ec_public_key_of(&self.key) }
+    }
+
+    /// Returns the algorithm this key signs with: the `ES*` algorithm of its curve.
+    pub fn alg(&self) -> Alg {
+        self.public_key().alg()
+    }
+}
+
+impl EcPublicKey {
+    /// Loads an elliptic curve public key on P-256, P-384 or P-521 (or secp256k1, with the
+    /// `secp256k1` feature) from SubjectPublicKeyInfo (`PUBLIC KEY`) PEM. Returns a
+    /// `err::JWTError::ParseError` if `pem` does not hold one, or holds a P-521 key and the crate
+    /// was built with the `ring` backend.
+    pub fn from_pem(pem: &str) -> err::Result<EcPublicKey> {
+        from_public_pem(pem, Active::ec_public_key).map(|key| EcPublicKey { key })
+    }
+
+    /// Loads an elliptic curve public key from the coordinates `x` and `y` of its point, as
+    /// big-endian integers padded to the size of the curve's field (the `x` and `y` of a JWK). The
+    /// curve is the one `alg`, an `ES*` algorithm, signs on. Returns a `err::JWTError::ParseError`
+    /// if `alg` is not an `ES*` algorithm, or if the point is not on its curve.
+    pub fn from_coordinates(alg: Alg, x: &[u8], y: &[u8]) -> err::Result<EcPublicKey> {
+        let (curve, len) = match alg {
+            Alg::ES256 => (SECP256R1, 32),
+            Alg::ES384 => (SECP384R1, 48),
+            Alg::ES512 => (SECP521R1, 66),
+            Alg::ES256K => (SECP256K1, 32),
+            _ => {
+                return Err(err::JWTError::ParseError(format!(
+                    "{} is not an ECDSA algorithm", alg.as_str()
+                )))
+            }
+        };
+        if x.len() != len || y.len() != len {
+            return Err(err::JWTError::ParseError(format!(
+                "expected {}-byte coordinates for {}", len, alg.as_str()
+            )))
+        }
+        // The point is encoded uncompressed, as SEC 1 section 2.3.3 specifies.
+        let point = [&[4], x, y].concat();
+        let der = Any::encode_from(&curve)
+            .and_then(|curve| spki_der(EC_PUBLIC_KEY, Some(curve), &point))
+            .map_err(|e| err::JWTError::ParseError(format!("{}", e)))?;
+        Active::ec_public_key(PublicKeyDer::Spki(&der)).map(|key| EcPublicKey { key })
+    }
+
+    /// Returns the algorithm this key verifies: the `ES*` algorithm of its curve.
+    pub fn alg(&self) -> Alg {
+        Active::ec_alg(&self.key)
+    }
+
+    /// Returns the coordinates `x` and `y` of the key's point, padded to the size of the curve's
+    /// field: the inverse of `from_coordinates`.
+    pub fn coordinates(&self) -> (Vec<u8>, Vec<u8>) {
+        // The point is uncompressed: a 4 byte, then the coordinates.
+        let point = Active::ec_public_key_point(&self.key);
+        let (x, y) = point[1..].split_at(point.len() / 2);
+        (x.to_vec(), y.to_vec())
+    }
+}
+
+impl Ed25519PrivateKey {
+    /// Loads an Ed25519 private key from PKCS#8 (`PRIVATE KEY`) PEM. Returns a
+    /// `err::JWTError::ParseError` if `pem` does not hold one.
+    pub fn from_pem(pem: &str) -> err::Result<Ed25519PrivateKey> {
+        from_private_pem(pem, Active::ed25519_private_key).map(|key| Ed25519PrivateKey { key })
+    }
+
+    /// Loads an Ed25519 private key from its raw 32-byte seed, the `d` of RFC 8037. Returns a
+    /// `err::JWTError::ParseError` if `seed` is not 32 bytes long.
+    pub fn from_seed(seed: &[u8]) -> err::Result<Ed25519PrivateKey> {
+        let seed = <&[u8; 32]>::try_from(seed).map_err(|_| {
+            err::JWTError::ParseError(format!("expected a 32-byte seed, got {} bytes", seed.len()))
+        })?;
+        Ok(Ed25519PrivateKey { key: Active::ed25519_private_key_from_seed(seed) })
+    }
+
+    /// Returns the public half of this key.
+    pub fn public_key(&self) -> Ed25519PublicKey {
+        Ed25519PublicKey { key: Active::ed25519_public_key_of(&self.key) }
+    }
+}
+
+impl Ed25519PublicKey {
+    /// Loads an Ed25519 public key from SubjectPublicKeyInfo (`PUBLIC KEY`) PEM. Returns a
+    /// `err::JWTError::ParseError` if `pem` does not hold one.
+    pub fn from_pem(pem: &str) -> err::Result<Ed25519PublicKey> {
+        from_public_pem(pem, Active::ed25519_public_key).map(|key| Ed25519PublicKey { key })
+    }
+
+    /// Loads an Ed25519 public key from its raw 32-byte encoding, the `x` of RFC 8037. Returns a
+    /// `err::JWTError::ParseError` if `bytes` is not 32 bytes long or, with the `rust-crypto`
+    /// backend, is not the encoding of a point on the curve. The `ring` backend only finds that
+    /// out when verifying.
+    pub fn from_bytes(bytes: &[u8]) -> err::Result<Ed25519PublicKey> {
+        let bytes = <&[u8; 32]>::try_from(bytes).map_err(|_| {
+            err::JWTError::ParseError(format!("expected 32 bytes, got {}", bytes.len()))
+        })?;
+        Active::ed25519_public_key_from_bytes(bytes).map(|key| Ed25519PublicKey { key })
+    }
+
+    /// Returns the key's raw 32-byte encoding: the inverse of `from_bytes`.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        Active::ed25519_public_key_bytes(&self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsa_from_pem() {
+        let pkcs8 = include_str!("testdata/rsa_private_pkcs8.pem");
+        let pkcs8 = RsaPrivateKey::from_pem(pkcs8).unwrap();
+        let pkcs1 = include_str!("testdata/rsa_private_pkcs1.pem");
+        let pkcs1 = RsaPrivateKey::from_pem(pkcs1).unwrap();
+        assert_eq!(pkcs8, pkcs1);
+        let public = RsaPublicKey::from_pem(include_str!("testdata/rsa_public.pem")).unwrap();
+        assert_eq!(pkcs8.public_key(), public);
+
+        assert!(RsaPrivateKey::from_pem(include_str!("testdata/rsa_public.pem")).is_err());
+        assert!(RsaPublicKey::from_pem("not a key").is_err());
+    }
+
+    #[test]
+    fn test_ec_from_pem() {
+        let pkcs8 = EcPrivateKey::from_pem(include_str!("testdata/ec_p256_private_pkcs8.pem"))
+            .unwrap();
+        let sec1 = EcPrivateKey::from_pem(include_str!("testdata/ec_p256_private_sec1.pem"))
+            .unwrap();
+        assert_eq!(pkcs8, sec1);
+        assert_eq!(pkcs8.alg(), Alg::ES256);
+        let public = EcPublicKey::from_pem(include_str!("testdata/ec_p256_public.pem")).unwrap();
+        assert_eq!(pkcs8.public_key(), public);
+
+        for (private, public, alg) in &[
+            (include_str!("testdata/ec_p384_private_pkcs8.pem"),
+             include_str!("testdata/ec_p384_public.pem"), Alg::ES384),
+            (include_str!("testdata/ec_p521_private_pkcs8.pem"),
+             include_str!("testdata/ec_p521_public.pem"), Alg::ES512),
+        ] {
+            if !Active::P521 && *alg == Alg::ES512 {
+                assert!(EcPrivateKey::from_pem(private).is_err());
+                continue
+            }
+            let key = EcPrivateKey::from_pem(private).unwrap();
+            assert_eq!(key.alg(), *alg);
+            assert_eq!(key.public_key(), EcPublicKey::from_pem(public).unwrap());
+        }
+
+        #[cfg(feature = "secp256k1")]
+        {
+            let key = include_str!("testdata/ec_secp256k1_private_pkcs8.pem");
+            let key = EcPrivateKey::from_pem(key).unwrap();
+            assert_eq!(key.alg(), Alg::ES256K);
+            let public = include_str!("testdata/ec_secp256k1_public.pem");
+            assert_eq!(key.public_key(), EcPublicKey::from_pem(public).unwrap());
+        }
+        #[cfg(not(feature = "secp256k1"))]
+        assert!(EcPublicKey::from_pem(include_str!("testdata/ec_secp256k1_public.pem")).is_err());
+
+        assert!(EcPrivateKey::from_pem(include_str!("testdata/rsa_private_pkcs8.pem")).is_err());
+        assert!(EcPublicKey::from_pem(include_str!("testdata/rsa_public.pem")).is_err());
+    }
+
+    #[test]
+    fn test_ed25519() {
+        // The key of RFC 8037 appendix A.1.
+        let d = base64::decode_config(
+            "nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A", base64::URL_SAFE_NO_PAD
+        ).unwrap();
+        let x = base64::decode_config(
+            "11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo", base64::URL_SAFE_NO_PAD
+        ).unwrap();
+        let key = Ed25519PrivateKey::from_seed(&d).unwrap();
+        assert_eq!(key, Ed25519PrivateKey::from_pem(include_str!("testdata/ed25519_private.pem"))
+            .unwrap());
+        let public = Ed25519PublicKey::from_pem(include_str!("testdata/ed25519_public.pem"))
+            .unwrap();
+        assert_eq!(key.public_key(), public);
+        assert_eq!(Ed25519PublicKey::from_bytes(&x).unwrap(), public);
+
+        assert!(Ed25519PrivateKey::from_seed(&d[1..]).is_err());
+        assert!(Ed25519PrivateKey::from_pem(include_str!("testdata/ec_p256_private_pkcs8.pem"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_private_key() {
+        for (pem, alg) in &[
+            (include_str!("testdata/rsa_private_pkcs1.pem"), Alg::RS256),
+            (include_str!("testdata/ec_p256_private_sec1.pem"), Alg::ES256),
+            (include_str!("testdata/ec_p384_private_pkcs8.pem"), Alg::ES384),
+            (include_str!("testdata/ed25519_private.pem"), Alg::EdDSA),
+        ] {
+            let key = PrivateKey::from_pem(pem).unwrap();
+            assert_eq!(key.alg(), *alg);
+            assert!(key.suits(*alg));
+            assert!(!key.suits(Alg::HS256) && !key.suits(Alg::None));
+        }
+        let key = PrivateKey::from_pem(include_str!("testdata/ec_p256_private_pkcs8.pem")).unwrap();
+        assert!(!key.suits(Alg::ES384));
+        let public = EcPublicKey::from_pem(include_str!("testdata/ec_p256_public.pem")).unwrap();
+        assert_eq!(key.public_key(), PublicKey::Ec(public));
+        assert!(PrivateKey::from_pem(include_str!("testdata/rsa_public.pem")).is_err());
+    }
+
+    #[test]
+    fn test_public_key_from_certificate() {
+        let der = |pem| decode_pem(pem).unwrap().1;
+        let rsa = PublicKey::from_certificate(&der(include_str!("testdata/rsa_cert.pem"))).unwrap();
+        let public = RsaPublicKey::from_pem(include_str!("testdata/rsa_public.pem")).unwrap();
+        assert_eq!(rsa, PublicKey::Rsa(public));
+        let ec = PublicKey::from_certificate(&der(include_str!("testdata/ec_p256_cert.pem")))
+            .unwrap();
+        let public = EcPublicKey::from_pem(include_str!("testdata/ec_p256_public.pem")).unwrap();
+        assert_eq!(ec, PublicKey::Ec(public));
+
+        let spki = der(include_str!("testdata/rsa_public.pem"));
+        assert!(PublicKey::from_certificate(&spki).is_err());
+    }
+}
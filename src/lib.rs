@@ -7,22 +7,67 @@
 //! Also, we only currently use (encode into and decode from) the compact JWS format.
 
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 // "[pub] mod NAME;" in lib.rs tells Rust to import a namespace from a file in the same crate.
 // In other files, this is a bit different: this will import from the such-named _directory_;
 // you want "use crate::err;" to import from other files in the same directory.
 pub mod err;
+pub mod audience;
+pub mod cache;
+pub mod chain;
+#[cfg(any(test, feature = "compliance"))]
+pub mod compliance;
 pub mod header;
 pub mod claims;
+pub mod compression;
+pub mod config;
+pub mod crypto;
+pub mod diagnostics;
+pub mod explain;
+mod expiring;
+pub mod fingerprint;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub mod fuzz;
+pub mod introspection;
+pub mod problem;
+pub mod redact;
+pub mod replay;
+pub mod signers;
+pub mod interop;
+pub mod jwk;
+pub mod keygen;
+pub mod keys;
+pub mod multi_issuer;
+pub mod template;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod token_pair;
+#[cfg(feature = "otel")]
+pub mod trace_context;
 pub mod traits;
+pub mod typed;
+pub mod vc;
+pub mod verify;
 
 pub use traits::JsonSerializable;
+pub use config::ParseConfig;
+pub use fingerprint::{token_fingerprint, RedactedToken};
+#[cfg(feature = "macros")]
+pub use jwt_macros::{claims, jwt};
 
-#[derive(Debug)]
+// Lets the macros, which expand to paths starting with `::jwt`, be used inside this crate.
+#[cfg(feature = "macros")]
+extern crate self as jwt;
+
+#[derive(Debug, Clone)]
 /// The `JWT` struct represents a JWT of any of three valid types: an unencrypted JWT, a JWS (JSON
 /// Web Signature), or a JWE (JSON Web Encryption). This struct and the methods that interact with
 /// it form the bulk of the public-facing API.
 ///
+/// `JWT`s compare and hash semantically: two are equal if their headers, their claim sets (see
+/// `claims::ClaimSet`) and their signatures are, however their encodings order claims.
+///
 /// # Examples
 ///
 /// ```
@@ -38,108 +83,614 @@ pub use traits::JsonSerializable;
 /// .
 /// "#, jwt_as_plaintext);
 ///
-/// // Encode and decode to and from an unencrypted base64 `String`.
+/// // Encode to an unencrypted base64 `String`.
 /// let jwt_encoded: String = jwt.encode_b64();
 /// assert_eq!(r#"eyJhbGciOiAibm9uZSJ9
 /// .
 /// eyJmb28iOiJiYXIifQ==
 /// .
 /// "#, jwt_encoded);
-/// let jwt: JWT = JWT::decode_b64(&jwt_encoded).unwrap();
+///
+/// // Compact tokens decode unverified, and their claim sets are read once they are verified.
+/// let token = jwt_encoded.replace(&['\n', '='][..], "");
+/// let options = jwt::verify::VerifyOptions::new(&[]).allow_unsecured();
+/// let decoded = JWT::decode_unverified(&token).unwrap().verify(b"", &options);
+/// assert_eq!(*decoded.unwrap(), jwt);
 /// ```
 pub struct JWT {
     pub header: header::JWTHeader,
     pub claim_set: claims::ClaimSet,
+    /// The signature of a decoded token, which is empty for unsecured tokens and for `JWT`s which
+    /// were not decoded. See `JWT::verify`.
+    pub signature: Vec<u8>,
+    /// The encoded header and payload of a decoded token, which its signature covers, or `None`
+    /// for `JWT`s which were not decoded from base64.
+    pub raw: Option<RawSegments>,
 }
 
-impl traits::JsonSerializable for JWT {
-    /// Encodes self into a plaintext string suitable for display.
-    fn encode_str(&self) -> String {
-        // Otherwise this is a JWS.
-        match self.header.alg {
-            header::Alg::None =>
-                self.header.encode_str() + "\n.\n" + &self.claim_set.encode_str() + "\n.\n",
-            header::Alg::HS256 => {
-                let signature_plaintext: String =
-                    self.header.encode_b64() + "." + &self.claim_set.encode_b64();
-                // TODO: apply the encoding here.
-                let signature_plaintext = "HELLO";
-
-                self.header.encode_str() + "\n.\n" + &self.claim_set.encode_str() + "\n.\n" +
-                &signature_plaintext
-            },
-        }
-    }
-
-    /// Encodes self into a base64-encoded JWT string suitable for transport.
-    fn encode_b64(&self) -> String {
-        self.header.encode_b64() + "\n.\n" +
-        &base64::encode(self.claim_set.encode_str().into_bytes()) +
-        "\n.\n"
-    }
-
-    /// Decodes an `input` base64-encoded `String` into a JWT. `input` must be a valid encoded JWT
-    /// payload, otherwise a `JWTError` will be returned.
-    fn decode_b64(input: &str) -> err::Result<JWT> {
-        let components = JWT::split_into_components(input);
-        let components = match components {
-            Ok(components) => components,
-            Err(e) => return Err(e),
-        };
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// The header and payload components of a decoded token, exactly as they appeared in it. A
+/// signature is computed over these octets, which re-encoding the decoded header and claim set
+/// need not reproduce: the signer may have ordered or spaced its JSON differently.
+pub struct RawSegments {
+    pub header: String,
+    pub payload: String,
+}
 
-        let header = header::JWTHeader::decode_b64(&components[0]);
-        let header: header::JWTHeader = match header {
-            Ok(header) => header,
-            Err(e) => return Err(e),
-        };
+impl RawSegments {
+    /// Returns the signing input of the token: the header and payload, separated by a period.
+    pub fn signing_input(&self) -> String {
+        self.header.clone() + "." + &self.payload
+    }
+}
+
+impl PartialEq for JWT {
+    fn eq(&self, other: &JWT) -> bool {
+        self.header == other.header && self.claim_set == other.claim_set &&
+            self.signature == other.signature
+    }
+}
+
+impl Eq for JWT {}
+
+impl Hash for JWT {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.header.hash(state);
+        self.claim_set.hash(state);
+        self.signature.hash(state);
+    }
+}
+
+impl JWT {
+    /// Encodes self into a plaintext string suitable for display. The signature is not shown; see
+    /// `JWT::sign` for producing a signed token.
+    pub fn encode_str(&self) -> String {
+        self.header.encode_str() + "\n.\n" + &self.claim_set.encode_str() + "\n.\n"
+    }
 
-        let claim_set = claims::ClaimSet::decode_b64(&components[1]);
-        let claim_set: claims::ClaimSet = match claim_set {
-            Ok(claim_set) => claim_set,
-            Err(e) => return Err(e),
+    /// Encodes self into a base64-encoded JWT string suitable for transport. If the header has
+    /// `header::Cty::Deflate`, the claim set is compressed; see `jwt::compression`. If it has
+    /// `b64: false`, the claim set is not encoded.
+    pub fn encode_b64(&self) -> String {
+        let payload = if self.header.b64 {
+            base64::encode(self.payload())
+        } else {
+            self.claim_set.encode_str()
         };
+        self.header.encode_b64() + "\n.\n" + &payload + "\n.\n"
+    }
+
+    // Decodes an `input` base64-encoded `String` into a JWT with the default configuration.
+    #[cfg(test)]
+    pub(crate) fn decode_b64(input: &str) -> err::Result<JWT> {
+        JWT::decode_b64_with(input, &ParseConfig::default())
+    }
+
+    // Decodes an `input` base64-encoded `String` into a JWT. Its claim set has not been verified,
+    // so this is private to the crate; tokens are decoded with `JWT::decode_unverified` outside
+    // it.
+    pub(crate) fn decode_b64_with(input: &str, config: &ParseConfig) -> err::Result<JWT> {
+        config.check_size(input)?;
+        let components = JWT::split_into_components_with(input, config)?;
+        JWT::decode_components(&components[0], &components[1], &components[2], config)
+    }
+
+    /// Decodes an `input` plaintext JWT `String`, as produced by `JWT::encode_str`, into a `JWT`
+    /// with the default configuration. See `JWT::decode_str_with`.
+    pub fn decode_str(input: &str) -> err::Result<JWT> {
+        JWT::decode_str_with(input, &ParseConfig::default())
+    }
+
+    /// Decodes an `input` plaintext JWT `String`, as produced by `JWT::encode_str`, into a `JWT`.
+    /// `input` must be a valid JWT payload, otherwise a `JWTError` will be returned. The plaintext
+    /// form carries no signature, so the result is no more trustworthy than `JWT::from_plain_str`.
+    pub fn decode_str_with(input: &str, config: &ParseConfig) -> err::Result<JWT> {
+        config.check_size(input)?;
+        let components = JWT::split_into_components_with(input, config)?;
+
+        let header: header::JWTHeader =
+            header::JWTHeader::decode_str_with(&components[0], config)?;
+
+        let claim_set: claims::ClaimSet =
+            claims::ClaimSet::decode_str_with(&components[1], config)?;
 
         let mut jwt = JWT::new();
         jwt.header = header;
         jwt.claim_set = claim_set;
         Ok(jwt)
     }
+}
 
-    /// Decodes an `input` plaintext JWT `String` into a `JWT`. `input` must be a valid JWT
-    /// payload, otherwise a `JWTError` will be returned.
-    fn decode_str(input: &str) -> err::Result<JWT> {
-        let components = JWT::split_into_components(input);
-        let components = match components {
-            Ok(components) => components,
-            Err(e) => return Err(e),
-        };
+impl JWT {
+    // Returns the octets of the payload: the claim set, compressed if the header says so.
+    fn payload(&self) -> Vec<u8> {
+        let payload = self.claim_set.encode_str().into_bytes();
+        if self.header.cty == header::Cty::Deflate {
+            compression::deflate(&payload)
+        } else {
+            payload
+        }
+    }
 
-        let header = header::JWTHeader::decode_str(&components[0]);
-        let header: header::JWTHeader = match header {
-            Ok(header) => header,
-            Err(e) => return Err(e),
-        };
+    /// Signs this `JWT` under `key` with the HMAC algorithm named by its header (`HS256`, `HS384`
+    /// or `HS512`), returning it in the compact serialization of RFC 7515 section 7.1: the
+    /// base64url-encoded header, payload and signature, separated by periods. Returns a
+    /// `err::JWTError::NotImplementedError` if the header names any other algorithm.
+    pub fn sign(&self, key: &[u8]) -> err::Result<String> {
+        self.sign_using(|alg, signing_input| crypto::hmac(alg, key, signing_input))
+    }
+
+    /// Signs this `JWT` under the RSA private key `key` with the RSA algorithm named by its
+    /// header (`RS256`, `RS384` or `RS512`), returning it in compact serialization as `JWT::sign`
+    /// does. Returns a `err::JWTError::NotImplementedError` if the header names any other
+    /// algorithm.
+    pub fn sign_rsa(&self, key: &keys::RsaPrivateKey) -> err::Result<String> {
+        self.sign_using(|alg, signing_input| crypto::rsa_sign(alg, key, signing_input))
+    }
+
+    /// Signs this `JWT` under the elliptic curve private key `key` with the ECDSA algorithm named
+    /// by its header, which must be the one for the key's curve (see `keys::EcPrivateKey::alg`),
+    /// returning it in compact serialization as `JWT::sign` does. Returns a
+    /// `err::JWTError::NotImplementedError` if the header names any other algorithm.
+    pub fn sign_ec(&self, key: &keys::EcPrivateKey) -> err::Result<String> {
+        self.sign_using(|alg, signing_input| crypto::ec_sign(alg, key, signing_input))
+    }
+
+    /// Signs this `JWT` under the Ed25519 private key `key`, returning it in compact serialization
+    /// as `JWT::sign` does. Returns a `err::JWTError::NotImplementedError` unless the header names
+    /// `EdDSA`.
+    pub fn sign_eddsa(&self, key: &keys::Ed25519PrivateKey) -> err::Result<String> {
+        self.sign_using(|alg, signing_input| crypto::eddsa_sign(alg, key, signing_input))
+    }
+
+    /// Signs this `JWT` under `key`, returning it in compact serialization as `JWT::sign` does.
+    /// If the header names no algorithm (`none`), the token is signed with the one `key` infers
+    /// from its kind (see `keys::PrivateKey::alg`), which the header then names. Returns a
+    /// `err::JWTError::NotImplementedError` if the header names an algorithm `key` cannot sign
+    /// with.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::header::Alg;
+    /// use jwt::keys::{Ed25519PrivateKey, PrivateKey};
+    ///
+    /// let key = PrivateKey::Ed25519(Ed25519PrivateKey::from_seed(&[7; 32]).unwrap());
+    /// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// let token = jwt.sign_key(&key).unwrap();
+    /// let decoded = JWT::decode_unverified(&token).unwrap();
+    /// assert_eq!(decoded.header().alg, Alg::EdDSA);
+    ///
+    /// jwt.header.alg = Alg::RS256;
+    /// assert!(jwt.sign_key(&key).is_err());
+    /// ```
+    pub fn sign_key(&self, key: &keys::PrivateKey) -> err::Result<String> {
+        if self.header.alg == header::Alg::None {
+            let mut header = self.header.clone();
+            header.alg = key.alg();
+            return JWT { header, ..self.clone() }.sign_key(key)
+        }
+        match key {
+            keys::PrivateKey::Rsa(key) => self.sign_rsa(key),
+            keys::PrivateKey::Ec(key) => self.sign_ec(key),
+            keys::PrivateKey::Ed25519(key) => self.sign_eddsa(key),
+        }
+    }
 
-        let claim_set = claims::ClaimSet::decode_str(&components[1]);
-        let claim_set: claims::ClaimSet = match claim_set {
-            Ok(claim_set) => claim_set,
-            Err(e) => return Err(e),
+    /// Signs this `JWT` with `signer`, returning it in compact serialization as `JWT::sign` does.
+    /// Returns a `err::JWTError::NotImplementedError` unless the header names the algorithm
+    /// `signer` signs with, or any error `signer` returns.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::keys::Ed25519PrivateKey;
+    /// use jwt::traits::Signer;
+    ///
+    /// let key = Ed25519PrivateKey::from_seed(&[7; 32]).unwrap();
+    /// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// jwt.header.alg = key.alg();
+    /// let token = jwt.sign_with(&key).unwrap();
+    /// let options = jwt::verify::VerifyOptions::new(&[key.alg()]);
+    /// let verified = JWT::verify_with(&token, &key.public_key(), &options).unwrap();
+    /// assert_eq!(verified.claim_set, jwt.claim_set);
+    /// ```
+    pub fn sign_with(&self, signer: &dyn traits::Signer) -> err::Result<String> {
+        self.sign_using(|alg, signing_input| {
+            if alg != signer.alg() {
+                return Err(err::JWTError::NotImplementedError)
+            }
+            signer.sign(signing_input)
+        })
+    }
+
+    /// Signs this `JWT` with `signer` without blocking, returning it in compact serialization as
+    /// `JWT::sign` does. If `signer` has a key ID, it is signed with that as its `kid` header
+    /// parameter. Returns a `err::JWTError::SchemaError` unless the header names the algorithm
+    /// `signer` signs with, or if it names a `kid` other than the signer's, or any error `signer`
+    /// returns.
+    #[cfg(feature = "async")]
+    pub async fn encode_b64_async(&self, signer: &dyn traits::AsyncSigner)
+        -> err::Result<String> {
+        if self.header.alg != signer.alg() {
+            return Err(err::JWTError::SchemaError)
+        }
+        let kid = match (signer.kid(), self.header.kid.as_deref()) {
+            (Some(kid), Some(header_kid)) if kid != header_kid => {
+                return Err(err::JWTError::SchemaError)
+            }
+            (Some(kid), _) => Some(String::from(kid)),
+            (None, kid) => kid.map(String::from),
         };
+        let header = header::JWTHeader { kid, ..self.header.clone() };
+        let signing_input = JWT { header, ..self.clone() }.signing_input()?;
+        let signature = signer.sign(signing_input.as_bytes()).await?;
+        Ok(signing_input + "." + &crypto::b64url(&signature))
+    }
+
+    // Signs this `JWT` with `sign`, which computes the signature of the given signing input with
+    // the given algorithm.
+    fn sign_using<F>(&self, sign: F) -> err::Result<String>
+        where F: FnOnce(header::Alg, &[u8]) -> err::Result<Vec<u8>> {
+        let signing_input = self.signing_input()?;
+        let signature = sign(self.header.alg, signing_input.as_bytes())?;
+        Ok(signing_input + "." + &crypto::b64url(&signature))
+    }
 
+    // Returns the signing input of this `JWT`: its base64url-encoded header and payload, separated
+    // by a period. If the header has b64: false, the payload is the claim set as it is (RFC 7797),
+    // which cannot be compressed, and b64 must be critical.
+    fn signing_input(&self) -> err::Result<String> {
+        let header = crypto::b64url(self.header.encode_str().as_bytes());
+        if self.header.b64 {
+            return Ok(header + "." + &crypto::b64url(&self.payload()))
+        }
+        if self.header.cty == header::Cty::Deflate || !self.header.is_critical("b64") {
+            return Err(err::JWTError::SchemaError)
+        }
+        Ok(header + "." + &self.claim_set.encode_str())
+    }
+
+    /// Splits the compact `token` into the same token with its payload detached (RFC 7515
+    /// appendix F), whose payload component is empty, and the payload, to be sent separately. A
+    /// token with an unencoded payload (`b64: false`) can only be sent whole if its claim set
+    /// contains no periods or whitespace (RFC 7797 section 5.2); otherwise, detach its payload. See
+    /// `JWT::decode_detached`.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::{JWT, ParseConfig};
+    /// use jwt::config::Base64Alphabet;
+    /// use jwt::header::{Alg, JWTHeader};
+    /// use jwt::verify::VerifyOptions;
+    ///
+    /// let key = [7; 32];
+    /// let mut jwt = JWT::from_plain_str("{\"iss\": \"https://idp.example.com\"}").unwrap();
+    /// jwt.header = JWTHeader::builder().alg(Alg::HS256).unencoded_payload().build().unwrap();
+    /// let (token, payload) = JWT::detach(&jwt.sign(&key).unwrap()).unwrap();
+    /// assert_eq!(payload, "{\"iss\":\"https://idp.example.com\"}");
+    ///
+    /// let config = ParseConfig { base64: Base64Alphabet::UrlSafe, ..ParseConfig::hardened() };
+    /// let decoded = JWT::decode_detached(&token, &payload, &config).unwrap();
+    /// let verified = decoded.verify(&key, &VerifyOptions::new(&[Alg::HS256])).unwrap();
+    /// assert_eq!(verified.claim_set, jwt.claim_set);
+    /// ```
+    pub fn detach(token: &str) -> err::Result<(String, String)> {
+        // Neither the header nor the signature component may contain a period.
+        let (header, rest) = token.split_once('.').ok_or(err::JWTError::SchemaError)?;
+        let (payload, signature) = rest.rsplit_once('.').ok_or(err::JWTError::SchemaError)?;
+        Ok((format!("{}..{}", header, signature), String::from(payload)))
+    }
+
+    /// Decodes a base64-encoded `token` whose payload is detached (RFC 7515 appendix F) under
+    /// `config`, taking its payload from `payload`. `payload` is base64-encoded
+    /// unless the header has `b64: false`, and whitespace in it is never ignored. Returns a
+    /// `err::JWTError::SchemaError` if the payload component of `token` is not empty. The
+    /// claim set of the returned `verify::UnverifiedJWT` is only reachable once it has been
+    /// verified.
+    pub fn decode_detached(token: &str, payload: &str, config: &ParseConfig)
+        -> err::Result<verify::UnverifiedJWT> {
+        config.check_size(token)?;
+        config.check_segment_size(payload)?;
+        let components = JWT::split_into_components_with(token, config)?;
+        if !components[1].is_empty() {
+            return Err(err::JWTError::SchemaError)
+        }
+        JWT::decode_components(&components[0], payload, &components[2], config)
+            .map(verify::UnverifiedJWT::new)
+    }
+
+    // Decodes the header, payload and signature components of a token.
+    fn decode_components(header: &str, payload: &str, signature: &str, config: &ParseConfig)
+        -> err::Result<JWT> {
         let mut jwt = JWT::new();
-        jwt.header = header;
-        jwt.claim_set = claim_set;
+        jwt.header = header::JWTHeader::decode_b64_with(header, config)?;
+        let claim_set = JWT::decode_payload(&jwt.header, payload, config)?;
+        jwt.claim_set = claims::ClaimSet::decode_str_with(&claim_set, config)?;
+        jwt.signature = JWT::decode_signature(signature, config)?;
+        jwt.raw = Some(RawSegments {
+            header: String::from(header),
+            payload: String::from(payload),
+        });
         Ok(jwt)
     }
-}
 
-impl JWT {
-    // Splits a base64-encoded or plaintext JWT into its three components, removing optional
-    // characters (space, CR, LF) in the process.
-    fn split_into_components(input: &str) -> err::Result<Vec<String>> {
-        let filter = |c: &char| -> bool { 
-            c != &'\u{0020}' && c != &'\u{000A}' && c != &'\u{000D}'
+    /// Signs this `JWT` with HMAC-SHA256 under `key`, as `JWT::sign` does. The header is encoded
+    /// with `"alg": "HS256"`, whatever its `alg`.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    ///
+    /// let key = [7; 32];
+    /// let jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// let token = jwt.sign_hs256(&key);
+    /// let options = jwt::verify::VerifyOptions::new(&[jwt::header::Alg::HS256]);
+    /// assert_eq!(JWT::decode_hmac(&token, &key, &options).unwrap().claim_set, jwt.claim_set);
+    /// ```
+    pub fn sign_hs256(&self, key: &[u8]) -> String {
+        let mut jwt = self.clone();
+        jwt.header.alg = header::Alg::HS256;
+        // HS256 is an HMAC algorithm, so this cannot fail.
+        jwt.sign(key).unwrap()
+    }
+
+    /// Decodes a compact, base64url-encoded `token` signed with an HMAC algorithm, verifying its
+    /// signature under `key`. The token is decoded with `ParseConfig::hardened`. Returns a
+    /// `err::JWTError::SignatureError` if the header names an algorithm `options` does not allow
+    /// or which is not an HMAC algorithm (including `none`), or if the signature does not match,
+    /// and a `err::JWTError::KeyTooWeak` if `key` falls short of the strength `options` requires.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::header::Alg;
+    /// use jwt::verify::VerifyOptions;
+    ///
+    /// let key = [7; 64];
+    /// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// jwt.header.alg = Alg::HS512;
+    /// let token = jwt.sign(&key).unwrap();
+    /// let options = VerifyOptions::new(&[Alg::HS512]);
+    /// assert_eq!(JWT::decode_hmac(&token, &key, &options).unwrap().claim_set, jwt.claim_set);
+    /// assert!(JWT::decode_hmac(&token, &[8; 64], &options).is_err());
+    /// assert!(JWT::decode_hmac(&token, &key, &VerifyOptions::new(&[Alg::HS256])).is_err());
+    /// ```
+    pub fn decode_hmac(token: &str, key: &[u8], options: &verify::VerifyOptions)
+        -> err::Result<verify::VerifiedJWT> {
+        JWT::decode_keyed(token, key.into(), options)
+    }
+
+    /// Decodes a compact, base64url-encoded `token` signed with an RSA algorithm, verifying its
+    /// signature under the RSA public key `key`, as `JWT::decode_hmac` does.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::header::Alg;
+    /// use jwt::keys::{RsaPrivateKey, RsaPublicKey};
+    /// use jwt::verify::VerifyOptions;
+    /// # let private_pem = include_str!("testdata/rsa_private_pkcs8.pem");
+    /// # let public_pem = include_str!("testdata/rsa_public.pem");
+    ///
+    /// let private_key = RsaPrivateKey::from_pem(private_pem).unwrap();
+    /// let public_key = RsaPublicKey::from_pem(public_pem).unwrap();
+    /// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// jwt.header.alg = Alg::RS256;
+    /// let token = jwt.sign_rsa(&private_key).unwrap();
+    /// let options = VerifyOptions::new(&[Alg::RS256]);
+    /// let verified = JWT::decode_rsa(&token, &public_key, &options).unwrap();
+    /// assert_eq!(verified.claim_set, jwt.claim_set);
+    /// ```
+    pub fn decode_rsa(token: &str, key: &keys::RsaPublicKey, options: &verify::VerifyOptions)
+        -> err::Result<verify::VerifiedJWT> {
+        JWT::decode_keyed(token, key.into(), options)
+    }
+
+    /// Decodes a compact, base64url-encoded `token` signed with an ECDSA algorithm, verifying its
+    /// signature under the elliptic curve public key `key`, as `JWT::decode_hmac` does.
+    pub fn decode_ec(token: &str, key: &keys::EcPublicKey, options: &verify::VerifyOptions)
+        -> err::Result<verify::VerifiedJWT> {
+        JWT::decode_keyed(token, key.into(), options)
+    }
+
+    /// Decodes a compact, base64url-encoded `token` signed with `EdDSA`, verifying its signature
+    /// under the Ed25519 public key `key`, as `JWT::decode_hmac` does.
+    pub fn decode_eddsa(token: &str, key: &keys::Ed25519PublicKey,
+        options: &verify::VerifyOptions) -> err::Result<verify::VerifiedJWT> {
+        JWT::decode_keyed(token, key.into(), options)
+    }
+
+    /// Decodes a compact, base64url-encoded `token`, verifying its signature with `verifier`, as
+    /// `JWT::decode_hmac` does. Returns a `err::JWTError::SignatureError` if the header names any
+    /// algorithm but the one `verifier` checks, or one `options` does not allow.
+    pub fn verify_with(token: &str, verifier: &dyn traits::Verifier,
+        options: &verify::VerifyOptions) -> err::Result<verify::VerifiedJWT> {
+        JWT::decode_verified_with(token, options, |alg, signing_input, signature| {
+            if alg == verifier.alg() && verifier.verify(signing_input, signature) {
+                Ok(())
+            } else {
+                Err(err::JWTError::SignatureError)
+            }
+        })
+    }
+
+    /// Decodes a compact, base64url-encoded `token` with `ParseConfig::hardened`, without
+    /// verifying its signature. The claim set of the returned `verify::UnverifiedJWT` is only
+    /// reachable once it has been verified.
+    pub fn decode_unverified(token: &str) -> err::Result<verify::UnverifiedJWT> {
+        JWT::decode_b64_with(token, &JWT::hardened_config()).map(verify::UnverifiedJWT::new)
+    }
+
+    // The configuration tokens are decoded with before their signatures are verified.
+    fn hardened_config() -> ParseConfig {
+        ParseConfig { base64: config::Base64Alphabet::UrlSafe, ..ParseConfig::hardened() }
+    }
+
+    // Decodes a compact, base64url-encoded `token` with `ParseConfig::hardened`, verifying its
+    // signature under `key` once `key` has passed `options`. Unlike `UnverifiedJWT::verify`, it
+    // rejects unsecured tokens, which carry no signature for `key` to check.
+    fn decode_keyed(token: &str, key: keys::VerificationKey, options: &verify::VerifyOptions)
+        -> err::Result<verify::VerifiedJWT> {
+        JWT::decode_verified_with(token, options, |alg, signing_input, signature| {
+            options.check_key(alg, &key)?;
+            crypto::verify(alg, key, signing_input, signature)
+        })
+    }
+
+    // Decodes a compact, base64url-encoded `token` with `ParseConfig::hardened`, checking its
+    // signature with `verify`, which is given the algorithm, the signing input and the signature.
+    // Tokens whose header names an algorithm `options` does not allow are rejected first.
+    fn decode_verified_with<F>(token: &str, options: &verify::VerifyOptions, verify: F)
+        -> err::Result<verify::VerifiedJWT>
+        where F: FnOnce(header::Alg, &[u8], &[u8]) -> err::Result<()> {
+        let jwt = JWT::decode_b64_with(token, &JWT::hardened_config())?;
+        if !options.allows(jwt.header.alg) {
+            return Err(err::JWTError::SignatureError)
+        }
+        // The token decoded, so it has three components.
+        let (signing_input, _) = token.rsplit_once('.').unwrap();
+        verify(jwt.header.alg, signing_input.as_bytes(), &jwt.signature)?;
+        Ok(verify::VerifiedJWT::new(jwt))
+    }
+
+    // Verifies the signature of this decoded `JWT` under `key` with the algorithm its header
+    // names, once its algorithm and `key` have passed `options`. The signature is checked against
+    // the raw segments it was decoded from or, if it has none, against its re-encoded header and
+    // claim set. See `verify::UnverifiedJWT::verify`.
+    pub(crate) fn verify<'a, K>(&self, key: K, options: &verify::VerifyOptions)
+        -> err::Result<verify::VerifiedJWT>
+        where K: Into<keys::VerificationKey<'a>> {
+        if !options.allows(self.header.alg) {
+            return Err(err::JWTError::SignatureError)
+        }
+        if self.header.alg == header::Alg::None {
+            return if self.signature.is_empty() {
+                Ok(verify::VerifiedJWT::new(self.clone()))
+            } else {
+                Err(err::JWTError::SignatureError)
+            }
+        }
+        let key = key.into();
+        options.check_key(self.header.alg, &key)?;
+        let signing_input = match &self.raw {
+            Some(raw) if !self.matches(raw)? => return Err(err::JWTError::SignatureError),
+            Some(raw) => raw.signing_input(),
+            None => self.signing_input()?,
+        };
+        crypto::verify(self.header.alg, key, signing_input.as_bytes(), &self.signature)?;
+        Ok(verify::VerifiedJWT::new(self.clone()))
+    }
+
+    /// Decodes each compact, base64url-encoded token in `tokens`, verifying its signature under
+    /// `key` against `options`, as `verify::UnverifiedJWT::verify` does, and checking it against
+    /// `validator`, and returns a result for each token in order. With the `rayon` feature, tokens
+    /// are verified in parallel on rayon's global thread pool.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::claims::Validator;
+    /// use jwt::header::Alg;
+    /// use jwt::verify::VerifyOptions;
+    ///
+    /// let key = [7; 32];
+    /// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// jwt.header.alg = Alg::HS256;
+    /// let token = jwt.sign(&key).unwrap();
+    ///
+    /// let options = VerifyOptions::new(&[Alg::HS256]);
+    /// let tokens = [&token[..], "e30.e30.", &token];
+    /// let results = JWT::verify_batch(&tokens, &key, &options, &Validator::new());
+    /// assert!(results[0].is_ok() && results[1].is_err() && results[2].is_ok());
+    /// ```
+    pub fn verify_batch<'a, K>(tokens: &[&str], key: K, options: &verify::VerifyOptions,
+        validator: &claims::Validator) -> Vec<err::Result<verify::VerifiedJWT>>
+        where K: Into<keys::VerificationKey<'a>> {
+        let key = key.into();
+        let verify = |token: &&str| {
+            let jwt = JWT::decode_unverified(token)?.verify(key, options)?;
+            validator.validate_jwt(&jwt)?;
+            Ok(jwt)
         };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            tokens.par_iter().map(verify).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            tokens.iter().map(verify).collect()
+        }
+    }
+
+    // Returns whether `raw` holds this `JWT`'s header and claim set, which it does unless they
+    // were changed after decoding. The segments decoded once, so they decode leniently.
+    fn matches(&self, raw: &RawSegments) -> err::Result<bool> {
+        let config = ParseConfig::lenient();
+        let header = header::JWTHeader::decode_b64_with(&raw.header, &config)?;
+        let claim_set = JWT::decode_payload(&header, &raw.payload, &config)?;
+        let claim_set = claims::ClaimSet::decode_str_with(&claim_set, &config)?;
+        Ok(header == self.header && claim_set == self.claim_set)
+    }
+
+    // Decodes the signature component of a token. Signatures are base64url-encoded whatever
+    // alphabet the rest of a token uses, so the alphabet is detected unless `config` is hardened.
+    fn decode_signature(signature: &str, config: &ParseConfig) -> err::Result<Vec<u8>> {
+        if config.strictness == config::Strictness::Hardened {
+            config.decode_base64_bytes(signature)
+        } else {
+            let config = ParseConfig { base64: config::Base64Alphabet::Auto, ..config.clone() };
+            config.decode_base64_bytes(signature)
+        }
+    }
+
+    // Decodes the base64-encoded payload of a token with the given header into the plaintext of
+    // its claim set, decompressing it if the header says it is compressed. If the header has b64:
+    // false, the payload is the claim set as it is, which cannot be compressed.
+    fn decode_payload(header: &header::JWTHeader, payload: &str, config: &ParseConfig)
+        -> err::Result<String> {
+        if !header.b64 {
+            return match header.cty {
+                header::Cty::Deflate => Err(err::JWTError::SchemaError),
+                _ => Ok(String::from(payload)),
+            }
+        }
+        let mut payload = config.decode_base64_bytes(payload)?;
+        if header.cty == header::Cty::Deflate {
+            payload = compression::inflate(&payload, config.max_decompressed_size)?;
+        }
+        String::from_utf8(payload).map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }
+
+    /// Splits a base64-encoded or plaintext JWT into its three components, removing optional
+    /// characters (space, CR, LF) in the process. Returns a `err::JWTError::SchemaError` if the
+    /// input does not have exactly three components.
+    pub fn split_into_components(input: &str) -> err::Result<Vec<String>> {
+        JWT::split_into_components_with(input, &ParseConfig::default())
+    }
+
+    /// Splits a JWT into its three components, as `JWT::split_into_components` does. Under
+    /// `Strictness::Strict` and `Strictness::Hardened`, optional characters are not removed but
+    /// rejected with a `err::JWTError::SchemaError`. Under `Strictness::Hardened`, any other
+    /// control character is rejected with a `err::JWTError::ParseError`. Components larger than
+    /// the configured `max_segment_size` are rejected with a `err::JWTError::ParseError`.
+    pub fn split_into_components_with(input: &str, config: &ParseConfig)
+        -> err::Result<Vec<String>> {
+        let optional = |c: &char| -> bool {
+            c == &'\u{0020}' || c == &'\u{000A}' || c == &'\u{000D}'
+        };
+        if config.strictness != config::Strictness::Lenient && input.chars().any(|c| optional(&c)) {
+            return Err(err::JWTError::SchemaError)
+        }
+        if config.strictness == config::Strictness::Hardened {
+            if let Some(c) = input.chars().find(|c| c.is_control()) {
+                return Err(err::JWTError::ParseError(format!(
+                    "control character {:?} in token", c
+                )))
+            }
+        }
+        let filter = |c: &char| -> bool { !optional(c) };
         let components = input
             .split(".")
             .map(|s: &str| s.chars().filter(filter).collect::<String>())
@@ -147,39 +698,119 @@ impl JWT {
         if components.len() != 3 {
             return Err(err::JWTError::SchemaError)
         }
+        for component in &components {
+            config.check_segment_size(component)?;
+        }
         Ok(components)
     }
 
+    /// Decodes an `input` base64-encoded `String` into a `verify::UnverifiedJWT` under `config`,
+    /// also returning a `diagnostics::Diagnostic` for each irregularity which was tolerated:
+    /// ignored whitespace, padded base64, unregistered header parameters, and duplicate claims
+    /// resolved by the configured `DuplicatePolicy`.
+    pub fn decode_b64_with_diagnostics(input: &str, config: &ParseConfig)
+        -> err::Result<(verify::UnverifiedJWT, Vec<diagnostics::Diagnostic>)> {
+        use diagnostics::Diagnostic;
+
+        config.check_size(input)?;
+        let components = JWT::split_into_components_with(input, config)?;
+        let mut found = Vec::<Diagnostic>::new();
+        if input.contains([' ', '\n', '\r']) {
+            found.push(Diagnostic::WhitespaceIgnored);
+        }
+        for (component, name) in components.iter().zip(&["header", "claims"]) {
+            if component.ends_with('=') {
+                found.push(Diagnostic::PaddedBase64(name));
+            }
+        }
+
+        let header = config.decode_base64(&components[0])?;
+        let mut jwt = JWT::new();
+        jwt.header = header::JWTHeader::decode_str_with(&header, config)?;
+        // The header was parsed as a JSON object above.
+        let header: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&header).unwrap();
+        let mut unknown = header.keys()
+            .filter(|name| !diagnostics::REGISTERED_HEADER_PARAMETERS.contains(&name.as_str()))
+            .map(|name| Diagnostic::UnknownHeaderParameter(name.clone()))
+            .collect::<Vec<Diagnostic>>();
+        found.append(&mut unknown);
+
+        let claim_set = JWT::decode_payload(&jwt.header, &components[1], config)?;
+        let (claim_set, duplicates) = claims::ClaimSet::decode_str_reporting(&claim_set, config)?;
+        jwt.claim_set = claim_set;
+        jwt.signature = JWT::decode_signature(&components[2], config)?;
+        jwt.raw = Some(RawSegments {
+            header: components[0].clone(),
+            payload: components[1].clone(),
+        });
+        for claim_name in duplicates {
+            found.push(Diagnostic::DuplicateClaim(claim_name, config.duplicate_claims));
+        }
+        Ok((verify::UnverifiedJWT::new(jwt), found))
+    }
+
+    /// Returns a structured breakdown of the base64-encoded `token`: its decoded segments, an
+    /// interpretation of each header parameter, and the outcome of each rule checked against it,
+    /// verifying its signature under `key` if one is given and checking it against `validator`.
+    /// See `jwt::explain::explain`.
+    pub fn explain(token: &str, key: Option<keys::VerificationKey>, validator: &claims::Validator)
+        -> err::Result<explain::Explanation> {
+        explain::explain(token, key, validator)
+    }
+
     /// Outputs an unsecured `JWT` containing the given `claims_set`, or a `JWTError` if the
     /// `claims_set` is invalid. Takes a plaintext `JWT` string as input.
     pub fn from_plain_str(claims_set: &str) -> err::Result<JWT> {
         claims::ClaimSet::decode_str(claims_set)
             .map(|claims_set| { 
                 JWT {
-                    header: header::JWTHeader {
-                        typ: header::Typ::None,
-                        alg: header::Alg::None,
-                        cty: header::Cty::None
-                    },
-                    claim_set: claims_set
+                    header: header::JWTHeader::new(header::Alg::None),
+                    claim_set: claims_set,
+                    signature: Vec::new(),
+                    raw: None,
                 }
             })
             .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
     }
 
+    /// Returns a short fingerprint of this `JWT`, suitable for correlating log lines. A decoded
+    /// `JWT` has the fingerprint of the token it was decoded from, signature included, provided
+    /// that token encoded its signature in canonical base64url; any other has the fingerprint of
+    /// its base64-encoded form. See `jwt::token_fingerprint`.
+    pub fn fingerprint(&self) -> String {
+        match &self.raw {
+            Some(raw) => {
+                token_fingerprint(&(raw.signing_input() + "." + &crypto::b64url(&self.signature)))
+            }
+            None => token_fingerprint(&self.encode_b64()),
+        }
+    }
+
+    /// Returns a view of this `JWT` whose `Display` redacts the signature and all claim values
+    /// other than those of registered claims, as well as the values of the registered claims
+    /// named in `sensitive_claims`. See `jwt::redact::RedactedJWT`.
+    pub fn redacted(&self, sensitive_claims: &[&str]) -> redact::RedactedJWT<'_> {
+        redact::RedactedJWT::new(self, sensitive_claims)
+    }
+
     /// Constructor. Outputs an empty unsecured JWT.
     pub fn new() -> JWT {
         JWT {
-            header: header::JWTHeader {
-                typ: header::Typ::None,
-                alg: header::Alg::None,
-                cty: header::Cty::None
-            },
-            claim_set: claims::ClaimSet::new()
+            header: header::JWTHeader::new(header::Alg::None),
+            claim_set: claims::ClaimSet::new(),
+            signature: Vec::new(),
+            raw: None,
         }
     }
 }
 
+impl Default for JWT {
+    fn default() -> JWT {
+        JWT::new()
+    }
+}
+
 impl fmt::Display for JWT {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}", self.encode_str())
@@ -193,6 +824,7 @@ mod tests {
     // Useful trick for importing everything from the current file's context, which for the tests,
     // is the parent context, is to use super.
     use super::*;
+    use verify::VerifyOptions;
 
     #[test]
     fn test_encode_empty() {
@@ -215,6 +847,475 @@ eyJmb28iOiJiYXIifQ==
 "#, jwt.encode_b64());
     }
 
+    #[test]
+    fn test_sign_hs256() {
+        let jwt = JWT::from_plain_str("{\"iss\": \"joe\", \"exp\": 1300819380}").unwrap();
+        let token = jwt.sign_hs256(b"secret");
+        let components = token.split('.').collect::<Vec<&str>>();
+        assert_eq!(components[0], crypto::b64url(b"{\"alg\": \"HS256\"}"));
+        let signing_input = format!("{}.{}", components[0], components[1]);
+        assert_eq!(
+            components[2], crypto::b64url(&crypto::hs256(b"secret", signing_input.as_bytes()))
+        );
+        assert_ne!(token, jwt.sign_hs256(b"other secret"));
+
+        let config = ParseConfig { base64: config::Base64Alphabet::UrlSafe, ..ParseConfig::new() };
+        let decoded = JWT::decode_b64_with(&token, &config).unwrap();
+        assert_eq!(decoded.header.alg, header::Alg::HS256);
+        assert_eq!(decoded.claim_set, jwt.claim_set);
+    }
+
+    #[test]
+    fn test_decode_hmac() {
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(matches!(jwt.sign(b"key"), Err(err::JWTError::NotImplementedError)));
+        for alg in &[header::Alg::HS256, header::Alg::HS384, header::Alg::HS512] {
+            jwt.header.alg = *alg;
+            let token = jwt.sign(&[7; 64]).unwrap();
+            let options = VerifyOptions::new(&[*alg]);
+            let verified = JWT::decode_hmac(&token, &[7; 64], &options).unwrap();
+            assert_eq!(verified.claim_set, jwt.claim_set);
+            assert!(matches!(
+                JWT::decode_hmac(&token, &[8; 64], &options), Err(err::JWTError::SignatureError)
+            ));
+            // Only the algorithms the options list are accepted.
+            let rs256 = VerifyOptions::new(&[header::Alg::RS256]);
+            assert!(matches!(
+                JWT::decode_hmac(&token, &[7; 64], &rs256), Err(err::JWTError::SignatureError)
+            ));
+
+            // Keys shorter than the hash are rejected unless the options allow them.
+            let token = jwt.sign(b"key").unwrap();
+            assert!(matches!(
+                JWT::decode_hmac(&token, b"key", &options), Err(err::JWTError::KeyTooWeak(_))
+            ));
+            let options = options.allow_short_hmac_keys();
+            assert!(JWT::decode_hmac(&token, b"key", &options).is_ok());
+        }
+
+        // A token signed with HS256, then relabelled as unsecured.
+        let token = jwt.sign_hs256(b"key");
+        let (_, rest) = token.split_once('.').unwrap();
+        let unsecured = crypto::b64url(b"{\"alg\": \"none\"}") + "." + rest;
+        let options = VerifyOptions::new(&[header::Alg::HS256]).allow_unsecured();
+        assert!(matches!(
+            JWT::decode_hmac(&unsecured, b"key", &options), Err(err::JWTError::SignatureError)
+        ));
+    }
+
+    #[test]
+    fn test_decode_rsa() {
+        let key = keys::RsaPrivateKey::from_pem(include_str!("testdata/rsa_private_pkcs1.pem"))
+            .unwrap();
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(jwt.sign_rsa(&key).is_err());
+        jwt.header.alg = header::Alg::RS256;
+        let token = jwt.sign_rsa(&key).unwrap();
+        let options = VerifyOptions::new(&[header::Alg::RS256, header::Alg::HS256]);
+        let verified = JWT::decode_rsa(&token, &key.public_key(), &options).unwrap();
+        assert_eq!(verified.claim_set, jwt.claim_set);
+        assert!(matches!(
+            JWT::decode_hmac(&token, b"key", &options), Err(err::JWTError::SignatureError)
+        ));
+
+        let mut tampered = token.clone();
+        tampered.pop();
+        tampered.push(if token.ends_with('A') { 'B' } else { 'A' });
+        assert!(JWT::decode_rsa(&tampered, &key.public_key(), &options).is_err());
+
+        let options = options.min_rsa_bits(3072);
+        assert!(matches!(
+            JWT::decode_rsa(&token, &key.public_key(), &options), Err(err::JWTError::KeyTooWeak(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_ec() {
+        let key = keys::EcPrivateKey::from_pem(include_str!("testdata/ec_p256_private_sec1.pem"))
+            .unwrap();
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(jwt.sign_ec(&key).is_err());
+        jwt.header.alg = key.alg();
+        let token = jwt.sign_ec(&key).unwrap();
+        let options = VerifyOptions::new(&[key.alg()]);
+        let verified = JWT::decode_ec(&token, &key.public_key(), &options).unwrap();
+        assert_eq!(verified.claim_set, jwt.claim_set);
+
+        let mut other = jwt.clone();
+        other.claim_set = claims::ClaimSet::decode_str("{\"sub\": \"u2\"}").unwrap();
+        let (_, signature) = token.rsplit_once('.').unwrap();
+        let other = other.sign_ec(&key).unwrap();
+        let (signing_input, _) = other.rsplit_once('.').unwrap();
+        let forged = format!("{}.{}", signing_input, signature);
+        assert!(JWT::decode_ec(&forged, &key.public_key(), &options).is_err());
+    }
+
+    #[test]
+    fn test_decode_eddsa() {
+        let key = keys::Ed25519PrivateKey::from_seed(&[7; 32]).unwrap();
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(jwt.sign_eddsa(&key).is_err());
+        jwt.header.alg = header::Alg::EdDSA;
+        let token = jwt.sign_eddsa(&key).unwrap();
+        let options = VerifyOptions::new(&[header::Alg::EdDSA]);
+        let verified = JWT::decode_eddsa(&token, &key.public_key(), &options).unwrap();
+        assert_eq!(verified.claim_set, jwt.claim_set);
+
+        let other = keys::Ed25519PrivateKey::from_seed(&[8; 32]).unwrap();
+        assert!(matches!(
+            JWT::decode_eddsa(&token, &other.public_key(), &options),
+            Err(err::JWTError::SignatureError)
+        ));
+    }
+
+    #[test]
+    fn test_sign_key() {
+        let pem = include_str!("testdata/ec_p384_private_pkcs8.pem");
+        let key = keys::PrivateKey::from_pem(pem).unwrap();
+        let public = keys::EcPrivateKey::from_pem(pem).unwrap().public_key();
+        let options = VerifyOptions::new(&[header::Alg::ES384]);
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        let token = jwt.sign_key(&key).unwrap();
+        let verified = JWT::decode_ec(&token, &public, &options).unwrap();
+        assert_eq!(verified.header.alg, header::Alg::ES384);
+
+        jwt.header.alg = header::Alg::ES384;
+        assert!(JWT::decode_ec(&jwt.sign_key(&key).unwrap(), &public, &options).is_ok());
+        for alg in &[header::Alg::ES256, header::Alg::RS256, header::Alg::HS384] {
+            jwt.header.alg = *alg;
+            assert!(matches!(jwt.sign_key(&key), Err(err::JWTError::NotImplementedError)));
+        }
+    }
+
+    #[test]
+    fn test_sign_with() {
+        // A signer which signs with HS256, as a user might wrap an external HMAC implementation.
+        struct Hs256<'a>(&'a [u8]);
+        impl traits::Signer for Hs256<'_> {
+            fn alg(&self) -> header::Alg {
+                header::Alg::HS256
+            }
+            fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+                Ok(crypto::hs256(self.0, signing_input))
+            }
+        }
+        impl traits::Verifier for Hs256<'_> {
+            fn alg(&self) -> header::Alg {
+                header::Alg::HS256
+            }
+            fn verify(&self, signing_input: &[u8], signature: &[u8]) -> bool {
+                crypto::verify_hmac(header::Alg::HS256, self.0, signing_input, signature).is_ok()
+            }
+        }
+
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(jwt.sign_with(&Hs256(b"key")).is_err());
+        jwt.header.alg = header::Alg::HS256;
+        let token = jwt.sign_with(&Hs256(b"key")).unwrap();
+        assert_eq!(token, jwt.sign(b"key").unwrap());
+        let options = VerifyOptions::new(&[header::Alg::HS256, header::Alg::ES256]);
+        let verified = JWT::verify_with(&token, &Hs256(b"key"), &options).unwrap();
+        assert_eq!(verified.claim_set, jwt.claim_set);
+        assert!(JWT::verify_with(&token, &Hs256(b"other"), &options).is_err());
+        assert!(JWT::verify_with(&token, &Hs256(b"key"), &VerifyOptions::new(&[])).is_err());
+
+        // A verifier for another algorithm rejects the token, whatever its signature.
+        let key = keys::EcPrivateKey::from_pem(include_str!("testdata/ec_p256_private_sec1.pem"))
+            .unwrap();
+        assert!(matches!(
+            JWT::verify_with(&token, &key.public_key(), &options),
+            Err(err::JWTError::SignatureError)
+        ));
+        jwt.header.alg = header::Alg::ES256;
+        let token = jwt.sign_with(&key).unwrap();
+        let verified = JWT::verify_with(&token, &key.public_key(), &options).unwrap();
+        assert_eq!(verified.claim_set, jwt.claim_set);
+    }
+
+    #[test]
+    fn test_unencoded_payload() {
+        let mut jwt = JWT::from_plain_str("{\"sub\":\"u1\"}").unwrap();
+        jwt.header = header::JWTHeader::builder()
+            .alg(header::Alg::HS256)
+            .unencoded_payload()
+            .build()
+            .unwrap();
+        let token = jwt.sign(b"secret").unwrap();
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        assert_eq!(
+            signing_input,
+            crypto::b64url(b"{\"alg\": \"HS256\", \"b64\": false, \"crit\": [\"b64\"]}") +
+                ".{\"sub\":\"u1\"}"
+        );
+        assert_eq!(signature, crypto::b64url(&crypto::hs256(b"secret", signing_input.as_bytes())));
+        let options = VerifyOptions::new(&[header::Alg::HS256]).allow_short_hmac_keys();
+        assert_eq!(JWT::decode_hmac(&token, b"secret", &options).unwrap().claim_set, jwt.claim_set);
+
+        // The payload may be detached, and then hold periods.
+        let (detached, payload) = JWT::detach(&token).unwrap();
+        assert_eq!(payload, "{\"sub\":\"u1\"}");
+        let decoded = JWT::decode_detached(&detached, &payload, &JWT::hardened_config()).unwrap();
+        assert!(decoded.verify(b"secret", &options).is_ok());
+        assert!(JWT::decode_detached(&token, &payload, &ParseConfig::default()).is_err());
+        jwt.claim_set = claims::ClaimSet::decode_str("{\"iss\":\"a.b\"}").unwrap();
+        let (detached, payload) = JWT::detach(&jwt.sign(b"secret").unwrap()).unwrap();
+        let decoded = JWT::decode_detached(&detached, &payload, &ParseConfig::default()).unwrap();
+        assert_eq!(decoded.verify(b"secret", &options).unwrap().claim_set, jwt.claim_set);
+
+        // An unencoded payload cannot be compressed, and must be critical.
+        jwt.header.cty = header::Cty::Deflate;
+        assert!(matches!(jwt.sign(b"secret"), Err(err::JWTError::SchemaError)));
+        jwt.header.cty = header::Cty::None;
+        jwt.header.crit = None;
+        assert!(matches!(jwt.sign(b"secret"), Err(err::JWTError::SchemaError)));
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        jwt.header.alg = header::Alg::HS256;
+        let token = jwt.sign(b"key").unwrap();
+        let decoded = JWT::decode_b64_with(&token, &JWT::hardened_config()).unwrap();
+        let algorithms = [header::Alg::HS256, header::Alg::RS384];
+        let options = VerifyOptions::new(&algorithms).allow_short_hmac_keys();
+        // The signature is retained, and takes part in comparisons.
+        let (signing_input, _) = token.rsplit_once('.').unwrap();
+        assert_eq!(decoded.signature, crypto::hs256(b"key", signing_input.as_bytes()));
+        assert_ne!(decoded, jwt);
+        assert_eq!(decoded, JWT { signature: decoded.signature.clone(), ..jwt.clone() });
+        assert_eq!(decoded.verify(b"key", &options).unwrap().into_inner(), decoded);
+        assert!(matches!(decoded.verify(b"other", &options), Err(err::JWTError::SignatureError)));
+        assert!(jwt.verify(b"key", &options).is_err());
+        // Short HMAC keys are only accepted if the options allow them.
+        assert!(matches!(
+            decoded.verify(b"key", &VerifyOptions::new(&algorithms)),
+            Err(err::JWTError::KeyTooWeak(_))
+        ));
+
+        // The key must be of the kind the header's algorithm needs.
+        let public = keys::RsaPublicKey::from_pem(include_str!("testdata/rsa_public.pem")).unwrap();
+        assert!(matches!(decoded.verify(&public, &options), Err(err::JWTError::SignatureError)));
+        let private = keys::RsaPrivateKey::from_pem(include_str!("testdata/rsa_private_pkcs1.pem"))
+            .unwrap();
+        jwt.header.alg = header::Alg::RS384;
+        let token = jwt.sign_rsa(&private).unwrap();
+        let decoded = JWT::decode_b64_with(&token, &JWT::hardened_config()).unwrap();
+        assert!(decoded.verify(&public, &options).is_ok());
+        assert!(decoded.verify(b"key", &options).is_err());
+
+        // The signature covers the octets of the token, however it encodes its JSON (RFC 7515
+        // appendix A.1).
+        let key = base64::decode_config(
+            "AyM1SysPpbyDfgZld3umj1qzKObwVMkoqQ-EstJQLr_T-1qS0gZH75aKtMN3Yj0iPS4hcgUuTwjAzZr1Z9C\
+             Aow",
+            base64::URL_SAFE_NO_PAD,
+        ).unwrap();
+        let token =
+            "eyJ0eXAiOiJKV1QiLA0KICJhbGciOiJIUzI1NiJ9.eyJpc3MiOiJqb2UiLA0KICJleHAiOjEzMDA4MTkzODAs\
+             DQogImh0dHA6Ly9leGFtcGxlLmNvbS9pc19yb290Ijp0cnVlfQ.dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1g\
+             FWFOEjXk";
+        let mut decoded = JWT::decode_b64_with(token, &ParseConfig::lenient()).unwrap();
+        assert_ne!(decoded.raw.as_ref().unwrap().signing_input(), decoded.signing_input().unwrap());
+        assert!(decoded.verify(&key[..], &options).is_ok());
+        decoded.claim_set.claims.get_mut("iss").unwrap().claim_value = serde_json::json!("eve");
+        assert!(matches!(decoded.verify(&key[..], &options), Err(err::JWTError::SignatureError)));
+        decoded.raw = None;
+        assert!(decoded.verify(&key[..], &options).is_err());
+
+        // Signed tokens are base64url-encoded, which the standard alphabet cannot decode.
+        let jwt = JWT::from_plain_str("{\"sub\": \"u0??>>\"}").unwrap();
+        let token = JWT { header: header::JWTHeader::new(header::Alg::HS256), ..jwt }
+            .sign(b"key").unwrap();
+        assert!(token.contains(['-', '_']));
+        assert!(JWT::decode_b64(&token).is_err());
+        assert!(JWT::decode_unverified(&token).unwrap().verify(b"key", &options).is_ok());
+
+        // Unsecured tokens only verify if the options allow them.
+        let decoded = JWT::decode_b64("eyJhbGciOiAibm9uZSJ9.e30=.").unwrap();
+        assert!(decoded.verify(b"", &options).is_err());
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let key = keys::Ed25519PrivateKey::from_seed(&[7; 32]).unwrap();
+        let sign = |claims: &str| {
+            let mut jwt = JWT::from_plain_str(claims).unwrap();
+            jwt.header.alg = header::Alg::EdDSA;
+            jwt.sign_eddsa(&key).unwrap()
+        };
+        // Tokens with odd subjects have expired.
+        let tokens = (0..64u64)
+            .map(|i| format!("{{\"sub\": \"{}\", \"exp\": {}}}", i, (1 - i % 2) * 10_000_000_000))
+            .map(|claims| sign(&claims))
+            .collect::<Vec<String>>();
+        let tokens = tokens.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let options = VerifyOptions::new(&[header::Alg::EdDSA]);
+        let validator = claims::Validator::new();
+        let results = JWT::verify_batch(&tokens, &key.public_key(), &options, &validator);
+        assert_eq!(results.len(), 64);
+        for (i, result) in results.iter().enumerate() {
+            if i % 2 == 0 {
+                let sub = &result.as_ref().unwrap().claim_set.get("sub").unwrap().claim_value;
+                assert_eq!(*sub, i.to_string());
+            } else {
+                let expired = err::JWTError::ValidationError(err::ValidationError::Expired);
+                assert_eq!(result.as_ref().unwrap_err().to_string(), expired.to_string());
+            }
+        }
+        let results = JWT::verify_batch(&tokens, b"key", &options, &validator);
+        assert!(results.iter().all(|result| matches!(result, Err(err::JWTError::SignatureError))));
+        let options = VerifyOptions::new(&[header::Alg::ES256]);
+        let results = JWT::verify_batch(&tokens, &key.public_key(), &options, &validator);
+        assert!(results.iter().all(|result| matches!(result, Err(err::JWTError::SignatureError))));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_encode_b64_async() {
+        use std::future::Future;
+        use std::task::{Context, Poll, Waker};
+
+        struct Hs256(Option<&'static str>);
+        #[async_trait::async_trait]
+        impl traits::AsyncSigner for Hs256 {
+            fn alg(&self) -> header::Alg {
+                header::Alg::HS256
+            }
+            fn kid(&self) -> Option<&str> {
+                self.0
+            }
+            async fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+                Ok(crypto::hs256(b"key", signing_input))
+            }
+        }
+
+        // The signer never waits, so polling once completes the future.
+        fn block_on<F: Future>(future: F) -> F::Output {
+            match Box::pin(future).as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+                Poll::Ready(output) => output,
+                Poll::Pending => panic!("future did not complete"),
+            }
+        }
+
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        assert!(matches!(
+            block_on(jwt.encode_b64_async(&Hs256(None))), Err(err::JWTError::SchemaError)
+        ));
+        jwt.header.alg = header::Alg::HS256;
+        let token = block_on(jwt.encode_b64_async(&Hs256(None))).unwrap();
+        assert_eq!(token, jwt.sign(b"key").unwrap());
+
+        // The signer's key ID is put in the header, which may not name another.
+        let token = block_on(jwt.encode_b64_async(&Hs256(Some("k1")))).unwrap();
+        let decoded = JWT::decode_unverified(&token).unwrap();
+        assert_eq!(decoded.header().kid.as_deref(), Some("k1"));
+        jwt.header.kid = Some(String::from("k1"));
+        assert_eq!(block_on(jwt.encode_b64_async(&Hs256(Some("k1")))).unwrap(), token);
+        jwt.header.kid = Some(String::from("k2"));
+        assert!(matches!(
+            block_on(jwt.encode_b64_async(&Hs256(Some("k1")))), Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let jwt = JWT::new();
+        assert_eq!(jwt.fingerprint(), token_fingerprint("eyJhbGciOiAibm9uZSJ9.e30=."));
+
+        // Decoded tokens keep the fingerprint of their encoding, which covers their signature.
+        let jwt = JWT { header: header::JWTHeader::new(header::Alg::HS256), ..JWT::new() };
+        let token = jwt.sign(b"key").unwrap();
+        let decoded = JWT::decode_unverified(&token).unwrap().dangerously_assume_verified();
+        assert_eq!(decoded.fingerprint(), token_fingerprint(&token));
+        let other = JWT::decode_unverified(&jwt.sign(b"other").unwrap()).unwrap()
+            .dangerously_assume_verified();
+        assert_ne!(other.fingerprint(), decoded.fingerprint());
+    }
+
+    #[test]
+    fn test_decode_with_config() {
+        let strict = ParseConfig { strictness: config::Strictness::Strict, ..ParseConfig::new() };
+        assert!(JWT::decode_b64_with("eyJhbGciOiAibm9uZSJ9.e30=.", &strict).is_ok());
+        assert!(matches!(
+            JWT::decode_b64_with("eyJhbGciOiAibm9uZSJ9\n.\ne30=\n.\n", &strict),
+            Err(err::JWTError::SchemaError)
+        ));
+
+        let small = ParseConfig { max_size: Some(10), ..ParseConfig::new() };
+        assert!(JWT::decode_b64_with("eyJhbGciOiAibm9uZSJ9.e30=.", &small).is_err());
+    }
+
+    #[test]
+    fn test_decode_with_diagnostics() {
+        use diagnostics::Diagnostic;
+        use config::DuplicatePolicy;
+
+        // {"alg": "none", "zip": "DEF"} . {"a": 1, "a": 2}
+        let token = "eyJhbGciOiAibm9uZSIsICJ6aXAiOiAiREVGIn0\n.\neyJhIjogMSwgImEiOiAyfQ==\n.\n";
+        let config = ParseConfig { duplicate_claims: DuplicatePolicy::Last, ..ParseConfig::new() };
+        let (jwt, found) = JWT::decode_b64_with_diagnostics(token, &config).unwrap();
+        assert_eq!(jwt.dangerously_assume_verified().claim_set.get("a").unwrap().claim_value, 2);
+        assert_eq!(found, vec![
+            Diagnostic::WhitespaceIgnored,
+            Diagnostic::PaddedBase64("claims"),
+            Diagnostic::UnknownHeaderParameter(String::from("zip")),
+            Diagnostic::DuplicateClaim(String::from("a"), DuplicatePolicy::Last),
+        ]);
+
+        let (_, found) =
+            JWT::decode_b64_with_diagnostics("eyJhbGciOiAibm9uZSJ9.e30.", &config).unwrap();
+        assert!(found.is_empty());
+        assert!(JWT::decode_b64_with_diagnostics(token, &ParseConfig::new()).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "macros")]
+    fn test_macros() {
+        let token =
+            jwt!({"alg": "none"}, {"sub": "test", "iss": "https://idp.example.com", "n": -1});
+        assert_eq!(token.claim_set.get("iss").unwrap().claim_value, "https://idp.example.com");
+        assert_eq!(token.claim_set.get("n").unwrap().claim_value, -1);
+        assert_eq!(claims!({"sub": "test", "aud": ["a", "b"]}).claims.len(), 2);
+    }
+
+    #[test]
+    fn test_jwt_eq() {
+        let a = JWT::from_plain_str("{\"a\": 1, \"b\": 2}").unwrap();
+        let b = JWT::decode_b64(&JWT::from_plain_str("{\"b\": 2, \"a\": 1}").unwrap().encode_b64())
+            .unwrap();
+        assert_eq!(a, b);
+        let mut c = a.clone();
+        c.header.alg = header::Alg::HS256;
+        assert_ne!(a, c);
+
+        // Tokens differing only in their signatures are different tokens.
+        let mut d = c.clone();
+        d.signature = vec![0; 32];
+        assert_ne!(c, d);
+        let hash = |jwt: &JWT| {
+            let mut hasher = std::collections::hash_map::DefaultHasher::new();
+            jwt.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_ne!(hash(&c), hash(&d));
+    }
+
+    #[test]
+    fn test_decode_hardened() {
+        let hardened = ParseConfig::hardened();
+        assert!(JWT::decode_b64_with("eyJhbGciOiAibm9uZSJ9.e30.", &hardened).is_ok());
+        for token in &["eyJhbGciOiAibm9uZSJ9.e30\u{0}.", "eyJhbGciOiAibm9uZSJ9.e30\t.",
+                       "eyJhbGciOiAibm9uZSJ9.e31=."] {
+            assert!(matches!(
+                JWT::decode_b64_with(token, &hardened), Err(err::JWTError::ParseError(_))
+            ));
+        }
+        let long = format!("eyJhbGciOiAibm9uZSJ9.e30.{}", "A".repeat(64 * 1024));
+        assert!(JWT::decode_b64_with(&long, &hardened).is_err());
+        assert!(JWT::decode_b64_with(&long, &ParseConfig::default()).is_ok());
+    }
+
     #[test]
     fn test_encode_str_empty() {
         let jwt = JWT::new();
@@ -1,10 +1,55 @@
-use jwt::{JWT,JsonSerializable};
+use clap::{Parser, Subcommand};
+
+mod cli;
+
+#[derive(Parser)]
+#[command(
+    name = "jwt", version, about = "Decode, inspect, and work with JSON Web Tokens.",
+    after_help = cli::EXIT_CODE_HELP
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Decode a token and print its header and claims. Does NOT verify the signature.
+    Decode(cli::decode::DecodeArgs),
+    /// Convert a token between the compact, JSON, and decoded forms, preserving signatures.
+    Convert(cli::convert::ConvertArgs),
+    /// Lint a token: classify its claims, flag suspicious headers, and report its validity window.
+    Inspect(cli::inspect::InspectArgs),
+    /// Generate a key to sign tokens with, and write out its public half as PEM or a JWK.
+    Keygen(cli::keygen::KeygenArgs),
+    /// Sign a claim set, writing the token in compact serialization to stdout.
+    Sign(cli::sign::SignArgs),
+    /// Verify a token's signature and validate its claims, reporting each check.
+    Verify(cli::verify::VerifyArgs),
+}
 
 fn main() {
-    // println!("{:?}", JWT::decode_b64("eyJhbGciOiAibm9uZSJ9\n.\neyJmb28iOiJiYXIifQ==\n.\n"));
-    let mut jwt = JWT::decode_str(
-        "{\"alg\": \"none\"}\n.\n{\"foo\":\"bar\"}\n.\nHELLO"
-    ).unwrap();
-    jwt.header.alg = jwt::header::Alg::HS256;
-    println!("{}", jwt.encode_str());
+    let parsed = match Cli::try_parse() {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            // clap exits with 2 on usage errors, which would collide with EXIT_EXPIRED.
+            let _ = e.print();
+            std::process::exit(if e.use_stderr() { cli::EXIT_FAILURE } else { cli::EXIT_OK });
+        },
+    };
+    let result = match parsed.command {
+        Command::Decode(args) => cli::decode::run(&args),
+        Command::Convert(args) => cli::convert::run(&args),
+        Command::Inspect(args) => cli::inspect::run(&args),
+        Command::Keygen(args) => cli::keygen::run(&args),
+        Command::Sign(args) => cli::sign::run(&args),
+        Command::Verify(args) => cli::verify::run(&args),
+    };
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(cli::exit_code(&e));
+        },
+    }
 }
@@ -0,0 +1,157 @@
+//! Verification of tokens from several issuers, each with keys and rules of its own, for services
+//! which accept tokens from more than one identity provider.
+
+use std::collections::HashMap;
+
+use crate::claims::Validator;
+use crate::err::{self, ValidationError};
+use crate::jwk::JwkSet;
+use crate::verify::{VerifiedJWT, VerifyOptions};
+use crate::JWT;
+
+#[derive(Debug)]
+/// The keys one issuer signs with, and the rules its tokens are checked against.
+pub struct IssuerConfig {
+    /// The issuer's JWK Set, from which each token is verified under the key its `kid` names.
+    pub keys: JwkSet,
+    /// The algorithms the issuer signs with, and the key strength it is held to.
+    pub options: VerifyOptions,
+    /// The rules the issuer's tokens must pass once verified. Their `iss` claim has already
+    /// selected this configuration, so it need not check it.
+    pub validator: Validator,
+}
+
+#[derive(Debug, Default)]
+/// A verifier of tokens from several issuers. Each token is verified and validated with the
+/// configuration of the issuer its `iss` claim names, read before the signature is checked:
+/// the key which then verifies the token is what vouches for the claim.
+///
+/// Issuers are matched exactly, and tokens of any issuer without a configuration are rejected
+/// with a `err::ValidationError::InvalidClaim` for `iss`, before their signature is checked.
+///
+/// # Examples
+/// ```
+/// use jwt::JWT;
+/// use jwt::claims::Validator;
+/// use jwt::err::{JWTError, ValidationError};
+/// use jwt::header::Alg;
+/// use jwt::jwk::JwkSet;
+/// use jwt::multi_issuer::MultiIssuerVerifier;
+/// use jwt::verify::VerifyOptions;
+///
+/// let keys = |k| JwkSet::from_json(&format!(
+///     "{{\"keys\": [{{\"kty\": \"oct\", \"k\": \"{}\"}}]}}", k
+/// )).unwrap();
+/// let verifier = MultiIssuerVerifier::new()
+///     .issuer("https://a", keys("BwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwcHBwc"),
+///         VerifyOptions::new(&[Alg::HS256]), Validator::new().audience("api"))
+///     .issuer("https://b", keys("CAgICAgICAgICAgICAgICAgICAgICAgICAgICAgICAg"),
+///         VerifyOptions::new(&[Alg::HS256]), Validator::new().audience("app"));
+///
+/// let mut jwt = JWT::from_plain_str("{\"iss\": \"https://b\", \"aud\": \"app\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// assert!(verifier.verify(&jwt.sign(&[8; 32]).unwrap()).is_ok());
+/// // Each issuer's tokens are only verified under its own keys.
+/// assert!(verifier.verify(&jwt.sign(&[7; 32]).unwrap()).is_err());
+///
+/// let mut jwt = JWT::from_plain_str("{\"iss\": \"https://c\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// assert!(matches!(
+///     verifier.verify(&jwt.sign(&[7; 32]).unwrap()),
+///     Err(JWTError::ValidationError(ValidationError::InvalidClaim(claim))) if claim == "iss"
+/// ));
+/// ```
+pub struct MultiIssuerVerifier {
+    issuers: HashMap<String, IssuerConfig>,
+}
+
+impl MultiIssuerVerifier {
+    /// Constructs a verifier which accepts tokens from no issuer.
+    pub fn new() -> MultiIssuerVerifier {
+        MultiIssuerVerifier { issuers: HashMap::new() }
+    }
+
+    /// Accepts tokens from `issuer`, verified under `keys` with `options` and then checked
+    /// against `validator`. Replaces any configuration `issuer` had.
+    pub fn issuer(mut self, issuer: &str, keys: JwkSet, options: VerifyOptions,
+        validator: Validator) -> MultiIssuerVerifier {
+        self.issuers.insert(String::from(issuer), IssuerConfig { keys, options, validator });
+        self
+    }
+
+    /// Returns the configuration of `issuer`, if it has one.
+    pub fn config(&self, issuer: &str) -> Option<&IssuerConfig> {
+        self.issuers.get(issuer)
+    }
+
+    /// Decodes `token`, verifies its signature under the keys of the issuer its `iss` claim
+    /// names (see `verify::UnverifiedJWT::verify_with_keys`), and checks it against that issuer's
+    /// validator. Returns a `err::ValidationError::MissingClaim` or
+    /// `err::ValidationError::InvalidClaim` for `iss` if the token names no issuer, or one without
+    /// a configuration.
+    pub fn verify(&self, token: &str) -> err::Result<VerifiedJWT> {
+        let unverified = JWT::decode_unverified(token)?;
+        let issuer = unverified.issuer()?;
+        let config = self.issuers.get(issuer).ok_or_else(|| {
+            err::JWTError::ValidationError(ValidationError::InvalidClaim(String::from("iss")))
+        })?;
+        let jwt = unverified.verify_with_keys(&config.keys, &config.options)?;
+        config.validator.validate_jwt(&jwt)?;
+        Ok(jwt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Alg;
+    use crate::jwk::{Jwk, JwkKey};
+
+    fn hmac_keys(kid: &str, key: [u8; 32]) -> JwkSet {
+        let key = JwkKey::Oct(key.to_vec());
+        JwkSet { keys: vec![Jwk { kid: Some(String::from(kid)), alg: None, key_use: None, key }] }
+    }
+
+    fn token(claims: &str, kid: &str, key: [u8; 32]) -> String {
+        let mut jwt = JWT::from_plain_str(claims).unwrap();
+        jwt.header.alg = Alg::HS256;
+        jwt.header.kid = Some(String::from(kid));
+        jwt.sign(&key).unwrap()
+    }
+
+    #[test]
+    fn test_verify() {
+        let options = || VerifyOptions::new(&[Alg::HS256]);
+        let verifier = MultiIssuerVerifier::new()
+            .issuer("https://a", hmac_keys("a1", [1; 32]), options(), Validator::new())
+            .issuer("https://b", hmac_keys("b1", [2; 32]), options(),
+                Validator::new().require_claims(&["sub"]));
+        assert_eq!(verifier.config("https://b").unwrap().keys.keys[0].kid.as_deref(), Some("b1"));
+
+        let a = token("{\"iss\": \"https://a\"}", "a1", [1; 32]);
+        assert!(verifier.verify(&a).is_ok());
+        // b requires a sub claim, which a does not.
+        let b = token("{\"iss\": \"https://b\"}", "b1", [2; 32]);
+        assert!(matches!(
+            verifier.verify(&b),
+            Err(err::JWTError::ValidationError(ValidationError::MissingClaim(claim)))
+                if claim == "sub"
+        ));
+        assert!(verifier.verify(&token("{\"iss\": \"https://b\", \"sub\": \"u1\"}", "b1", [2; 32]))
+            .is_ok());
+
+        // A token claiming to be from b, signed with a's key, names a key b does not have.
+        let forged = token("{\"iss\": \"https://b\", \"sub\": \"u1\"}", "a1", [1; 32]);
+        assert!(matches!(verifier.verify(&forged),
+            Err(err::JWTError::UnknownKid(kid)) if kid == "a1"));
+        let forged = token("{\"iss\": \"https://b\", \"sub\": \"u1\"}", "b1", [1; 32]);
+        assert!(matches!(verifier.verify(&forged), Err(err::JWTError::SignatureError)));
+
+        let anonymous = token("{}", "a1", [1; 32]);
+        assert!(matches!(
+            verifier.verify(&anonymous),
+            Err(err::JWTError::ValidationError(ValidationError::MissingClaim(claim)))
+                if claim == "iss"
+        ));
+    }
+}
@@ -0,0 +1,124 @@
+use serde::Serialize;
+
+use crate::err::{JWTError, ValidationError};
+
+/// The media type of an RFC 7807 problem details body.
+pub const CONTENT_TYPE: &str = "application/problem+json";
+/// The prefix used to build the `type` URI of a problem from its code, unless another is given
+/// to `ProblemDetails::with_type_base`.
+pub const DEFAULT_TYPE_BASE: &str = "urn:jwt:problem:";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// An RFC 7807 problem details object describing a `JWTError`, suitable for returning as the
+/// `application/problem+json` body of an HTTP error response.
+///
+/// The `detail` member is left empty by default, since the underlying error message may describe
+/// the token being rejected; set it explicitly if that is acceptable.
+///
+/// # Examples
+/// ```
+/// use jwt::err::JWTError;
+/// use jwt::problem::ProblemDetails;
+///
+/// let problem = ProblemDetails::from(&JWTError::SchemaError);
+/// assert_eq!(problem.status, 401);
+/// assert_eq!(
+///     problem.to_json(),
+///     "{\"type\":\"urn:jwt:problem:schema-error\",\"title\":\"Invalid token\",\"status\":401,\
+///       \"code\":\"schema-error\"}"
+/// );
+/// ```
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    pub title: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// An extension member carrying `JWTError::code`.
+    pub code: String,
+}
+
+// The HTTP status and title of each kind of error.
+fn status_and_title(error: &JWTError) -> (u16, &'static str) {
+    match error {
+        JWTError::ParseError(_) | JWTError::SchemaError | JWTError::ClaimTypeError(_) |
+        JWTError::SignatureError | JWTError::UnknownKid(_) | JWTError::ValidationError(_) => {
+            (401, "Invalid token")
+        }
+        JWTError::NotImplementedError => (501, "Not implemented"),
+        JWTError::HttpError(_) => (502, "Upstream request failed"),
+        // The key is the verifier's own, so this is not the token's fault.
+        JWTError::IoError(_) | JWTError::KeyTooWeak(_) => (500, "Internal error"),
+    }
+}
+
+impl From<&JWTError> for ProblemDetails {
+    fn from(error: &JWTError) -> ProblemDetails {
+        ProblemDetails::with_type_base(error, DEFAULT_TYPE_BASE)
+    }
+}
+
+impl From<&ValidationError> for ProblemDetails {
+    fn from(error: &ValidationError) -> ProblemDetails {
+        ProblemDetails::from(&JWTError::ValidationError(error.clone()))
+    }
+}
+
+impl ProblemDetails {
+    /// Describes `error`, building its `type` URI by appending its code to `type_base` (e.g.
+    /// `"https://example.com/problems/"`).
+    pub fn with_type_base(error: &JWTError, type_base: &str) -> ProblemDetails {
+        let (status, title) = status_and_title(error);
+        ProblemDetails {
+            type_uri: String::from(type_base) + error.code(),
+            title: String::from(title),
+            status,
+            detail: None,
+            code: String::from(error.code()),
+        }
+    }
+
+    /// Returns the problem as an `application/problem+json` body.
+    pub fn to_json(&self) -> String {
+        // Serializing a struct of strings and integers cannot fail.
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_error() {
+        let problem = ProblemDetails::from(&JWTError::ParseError(String::from("secret")));
+        assert_eq!(problem.status, 401);
+        assert_eq!(problem.type_uri, "urn:jwt:problem:parse-error");
+        assert!(!problem.to_json().contains("secret"));
+
+        assert_eq!(ProblemDetails::from(&JWTError::HttpError(String::new())).status, 502);
+        assert_eq!(ProblemDetails::from(&JWTError::NotImplementedError).status, 501);
+
+        let problem = ProblemDetails::from(&ValidationError::MissingClaim(String::from("aud")));
+        assert_eq!((problem.status, problem.code.as_str()), (401, "missing-claim"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let mut problem =
+            ProblemDetails::with_type_base(&JWTError::SchemaError, "https://example.com/p/");
+        problem.detail = Some(String::from("missing alg"));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&problem.to_json()).unwrap(),
+            json!({
+                "type": "https://example.com/p/schema-error",
+                "title": "Invalid token",
+                "status": 401,
+                "detail": "missing alg",
+                "code": "schema-error",
+            })
+        );
+    }
+}
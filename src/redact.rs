@@ -0,0 +1,96 @@
+use std::fmt;
+use serde_json::{Map, Value};
+
+use crate::claims::ClaimType;
+use crate::header::Alg;
+use crate::traits::JsonSerializable;
+use crate::JWT;
+
+/// The placeholder shown in place of redacted values.
+pub const REDACTED: &str = "«redacted»";
+
+/// A view of a `JWT` whose `Display` implementation is safe to include in error messages, logs,
+/// and support tickets. It prints the header and the values of registered claims, but replaces
+/// the signature, the values of all public and private claims, and the values of any registered
+/// claims configured as sensitive with `«redacted»`. Claim names are always shown.
+///
+/// Construct one with `JWT::redacted`.
+///
+/// # Examples
+/// ```
+/// use jwt::JWT;
+///
+/// let jwt = JWT::from_plain_str("{\"iss\": \"idp\", \"sub\": \"alice\", \"email\": \"a@b.c\"}")
+///     .unwrap();
+/// let shown = format!("{}", jwt.redacted(&["sub"]));
+/// assert!(shown.contains("\"iss\":\"idp\""));
+/// assert!(shown.contains("\"sub\":\"«redacted»\""));
+/// assert!(shown.contains("\"email\":\"«redacted»\""));
+/// ```
+pub struct RedactedJWT<'a> {
+    jwt: &'a JWT,
+    sensitive_claims: Vec<String>,
+}
+
+impl<'a> RedactedJWT<'a> {
+    /// Constructs a redacted view of `jwt`, additionally redacting the registered claims named
+    /// in `sensitive_claims`.
+    pub fn new(jwt: &'a JWT, sensitive_claims: &[&str]) -> RedactedJWT<'a> {
+        RedactedJWT {
+            jwt,
+            sensitive_claims: sensitive_claims.iter().map(|name| String::from(*name)).collect(),
+        }
+    }
+
+    /// Returns the claim set as a JSON object, with sensitive values redacted. Object keys are
+    /// sorted, so that output is stable.
+    pub fn claims(&self) -> Value {
+        let mut claims = Map::new();
+        for (claim_name, claim) in &self.jwt.claim_set.claims {
+            let shown = matches!(claim.claim_type, ClaimType::Registered) &&
+                !self.sensitive_claims.contains(claim_name);
+            let value = if shown { claim.claim_value.clone() } else { Value::from(REDACTED) };
+            claims.insert(claim_name.clone(), value);
+        }
+        Value::Object(claims)
+    }
+}
+
+impl<'a> fmt::Display for RedactedJWT<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Unsecured JWTs have an empty signature, so there is nothing to redact.
+        let signature = match self.jwt.header.alg {
+            Alg::None => "",
+            _ => REDACTED,
+        };
+        write!(f, "{}\n.\n{}\n.\n{}", self.jwt.header.encode_str(), self.claims(), signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacted_claims() {
+        let jwt = JWT::from_plain_str(
+            "{\"iss\": \"idp\", \"sub\": \"alice\", \"https://example.com/email\": \"a@b.c\"}"
+        ).unwrap();
+        let claims = jwt.redacted(&["sub"]).claims();
+        assert_eq!(claims["iss"], "idp");
+        assert_eq!(claims["sub"], REDACTED);
+        assert_eq!(claims["https://example.com/email"], REDACTED);
+    }
+
+    #[test]
+    fn test_redacted_display() {
+        let mut jwt = JWT::from_plain_str("{\"exp\": 1, \"secret\": \"s\"}").unwrap();
+        assert_eq!(
+            format!("{}", jwt.redacted(&[])),
+            "{\"alg\": \"none\"}\n.\n{\"exp\":1,\"secret\":\"«redacted»\"}\n.\n"
+        );
+
+        jwt.header.alg = Alg::HS256;
+        assert!(format!("{}", jwt.redacted(&[])).ends_with("\n.\n«redacted»"));
+    }
+}
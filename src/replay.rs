@@ -0,0 +1,115 @@
+use std::fmt;
+
+use crate::expiring::ExpiringMap;
+use crate::traits::{Clock, ReplayStore, SystemClock};
+
+#[cfg(feature = "redis")]
+pub mod redis;
+
+/// An in-memory `ReplayStore`, which forgets each `jti` once the token carrying it has expired.
+/// Expired entries are purged whenever the store has doubled in size since the last purge.
+///
+/// # Examples
+/// ```
+/// use jwt::replay::MemoryReplayStore;
+/// use jwt::traits::ReplayStore;
+///
+/// let store = MemoryReplayStore::new();
+/// assert!(!store.seen("a", u64::MAX));
+/// assert!(store.seen("a", u64::MAX));
+/// assert!(!store.seen("b", u64::MAX));
+/// ```
+pub struct MemoryReplayStore {
+    // Each jti seen.
+    seen: ExpiringMap<String, ()>,
+}
+
+impl Default for MemoryReplayStore {
+    fn default() -> MemoryReplayStore {
+        MemoryReplayStore::new()
+    }
+}
+
+impl MemoryReplayStore {
+    /// Constructs an empty store, which tells expired entries by the system clock.
+    pub fn new() -> MemoryReplayStore {
+        MemoryReplayStore::with_clock(SystemClock)
+    }
+
+    /// Constructs an empty store, which tells expired entries by `clock`.
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> MemoryReplayStore {
+        MemoryReplayStore { seen: ExpiringMap::with_clock(clock) }
+    }
+
+    /// Returns the number of entries held, including expired ones not yet purged.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Returns whether the store holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ReplayStore for MemoryReplayStore {
+    fn seen(&self, jti: &str, exp: u64) -> bool {
+        !self.seen.insert_new(String::from(jti), exp as f64, ())
+    }
+
+    fn recorded(&self, jti: &str) -> bool {
+        self.seen.get(jti, |_| ()).is_some()
+    }
+}
+
+// The clock is a trait object, which need not implement Debug.
+impl fmt::Debug for MemoryReplayStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MemoryReplayStore")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::expiring::{TestClock, MIN_PURGE_LEN};
+
+    #[test]
+    fn test_seen_expires() {
+        let now = Arc::new(Mutex::new(100));
+        let store = MemoryReplayStore::with_clock(TestClock(now.clone()));
+        assert!(!store.seen("a", 110));
+        assert!(store.seen("a", 110));
+        *now.lock().unwrap() = 110;
+        assert!(!store.seen("a", 120));
+        assert!(store.seen("a", 120));
+    }
+
+    #[test]
+    fn test_recorded() {
+        let now = Arc::new(Mutex::new(100));
+        let store = MemoryReplayStore::with_clock(TestClock(now.clone()));
+        assert!(!store.recorded("a"));
+        assert!(!store.seen("a", 110));
+        assert!(store.recorded("a"));
+        *now.lock().unwrap() = 110;
+        assert!(!store.recorded("a"));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_purge() {
+        let now = Arc::new(Mutex::new(100));
+        let store = MemoryReplayStore::with_clock(TestClock(now.clone()));
+        for i in 0..MIN_PURGE_LEN - 1 {
+            store.seen(&i.to_string(), 110);
+        }
+        assert_eq!(store.len(), MIN_PURGE_LEN - 1);
+        *now.lock().unwrap() = 110;
+        assert!(!store.seen("last", 120));
+        assert_eq!(store.len(), 1);
+    }
+}
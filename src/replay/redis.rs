@@ -0,0 +1,123 @@
+use std::fmt;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::traits::ReplayStore;
+
+/// The prefix of the keys `RedisReplayStore` writes, unless another is set.
+pub const DEFAULT_PREFIX: &str = "jwt:jti:";
+
+/// A `ReplayStore` held in Redis, so that every instance of a service sharing the database
+/// accepts each token only once. Each `jti` is stored under its own key, which Redis expires at
+/// the token's `exp`; this uses the `EXAT` option of `SET`, added in Redis 6.2.
+///
+/// If Redis cannot be reached, every token is reported as seen, and so rejected, and none as
+/// recorded: replay protection and chain checks fail closed.
+///
+/// # Examples
+/// ```no_run
+/// use std::sync::Arc;
+/// use jwt::claims::Validator;
+/// use jwt::replay::redis::RedisReplayStore;
+///
+/// let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// let validator = Validator::new().replay_store(Arc::new(RedisReplayStore::new(client)));
+/// ```
+pub struct RedisReplayStore {
+    pub client: ::redis::Client,
+    /// The prefix of the keys written, which keeps them apart from other data in the database.
+    pub prefix: String,
+    // The connection commands are sent over, opened on first use and after errors.
+    connection: Mutex<Option<::redis::Connection>>,
+}
+
+impl RedisReplayStore {
+    /// Constructs a store writing keys prefixed with `DEFAULT_PREFIX` through `client`. No
+    /// connection is made until the store is first used.
+    pub fn new(client: ::redis::Client) -> RedisReplayStore {
+        RedisReplayStore {
+            client,
+            prefix: String::from(DEFAULT_PREFIX),
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Sets the prefix of the keys written.
+    pub fn with_prefix(mut self, prefix: &str) -> RedisReplayStore {
+        self.prefix = String::from(prefix);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<::redis::Connection>> {
+        // A connection left behind by a panicking thread is dropped, in case it is mid-command.
+        self.connection.lock().unwrap_or_else(|e| {
+            let mut connection = e.into_inner();
+            *connection = None;
+            connection
+        })
+    }
+
+    // Records `jti`, returning whether its key already existed.
+    fn try_seen(&self, connection: &mut Option<::redis::Connection>, jti: &str, exp: u64)
+        -> ::redis::RedisResult<bool> {
+        let connection = match connection {
+            Some(connection) => connection,
+            None => connection.insert(self.client.get_connection()?),
+        };
+        let set: Option<String> = ::redis::cmd("SET").arg(self.prefix.clone() + jti).arg(1)
+            .arg("NX").arg("EXAT").arg(exp).query(connection)?;
+        Ok(set.is_none())
+    }
+
+    // Returns whether the key of `jti` exists.
+    fn try_recorded(&self, connection: &mut Option<::redis::Connection>, jti: &str)
+        -> ::redis::RedisResult<bool> {
+        let connection = match connection {
+            Some(connection) => connection,
+            None => connection.insert(self.client.get_connection()?),
+        };
+        ::redis::cmd("EXISTS").arg(self.prefix.clone() + jti).query(connection)
+    }
+}
+
+impl ReplayStore for RedisReplayStore {
+    fn seen(&self, jti: &str, exp: u64) -> bool {
+        let mut connection = self.lock();
+        self.try_seen(&mut connection, jti, exp).unwrap_or_else(|_| {
+            *connection = None;
+            true
+        })
+    }
+
+    fn recorded(&self, jti: &str) -> bool {
+        let mut connection = self.lock();
+        self.try_recorded(&mut connection, jti).unwrap_or_else(|_| {
+            *connection = None;
+            false
+        })
+    }
+}
+
+// The client's connection info may hold a password, so it is left out.
+impl fmt::Debug for RedisReplayStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RedisReplayStore")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unreachable_fails_closed() {
+        // Nothing listens on port 1.
+        let client = ::redis::Client::open("redis://127.0.0.1:1/").unwrap();
+        let store = RedisReplayStore::new(client).with_prefix("test:");
+        assert_eq!(store.prefix, "test:");
+        assert!(store.seen("a", u64::MAX));
+        assert!(!store.recorded("a"));
+        assert!(store.lock().is_none());
+    }
+}
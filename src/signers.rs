@@ -0,0 +1,125 @@
+// Signers backed by remote key management services, each behind a cargo feature named after the
+// service. They implement `traits::AsyncSigner`, so tokens are signed with
+// `JWT::encode_b64_async`.
+
+/// Signing with asymmetric keys held in AWS KMS. Requires the `aws-kms` feature.
+#[cfg(feature = "aws-kms")]
+pub mod aws_kms;
+/// Signing with keys held in Azure Key Vault. Requires the `azure-key-vault` feature.
+#[cfg(feature = "azure-key-vault")]
+pub mod azure_key_vault;
+/// Signing with asymmetric keys held in Google Cloud KMS. Requires the `gcp-kms` feature.
+#[cfg(feature = "gcp-kms")]
+pub mod gcp_kms;
+
+// Returns the digest of `input` under the hash function of `alg`, which must be an RSA or ECDSA
+// algorithm. Key management services sign digests, so the signing input need not be sent to them.
+#[cfg(any(feature = "aws-kms", feature = "azure-key-vault", feature = "gcp-kms"))]
+fn digest(alg: crate::header::Alg, input: &[u8]) -> Vec<u8> {
+    use crate::header::Alg;
+    use sha2::{Digest, Sha256, Sha384, Sha512};
+    match alg {
+        Alg::RS384 | Alg::ES384 => Sha384::digest(input).to_vec(),
+        Alg::RS512 | Alg::ES512 => Sha512::digest(input).to_vec(),
+        _ => Sha256::digest(input).to_vec(),
+    }
+}
+
+// Sends `request` with the bearer token `access_token`, returning the JSON body of the response.
+// Returns a `err::JWTError::HttpError` if the request fails or the response is not a success.
+#[cfg(any(feature = "azure-key-vault", feature = "gcp-kms"))]
+async fn send(request: reqwest::RequestBuilder, access_token: &str)
+    -> crate::err::Result<serde_json::Value> {
+    use crate::err;
+    let response = request.bearer_auth(access_token)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| { err::JWTError::HttpError(format!("{}", e)) })?;
+    let status = response.status();
+    let body = response.text().await
+        .map_err(|e| { err::JWTError::HttpError(format!("{}", e)) })?;
+    if !status.is_success() {
+        return Err(err::JWTError::HttpError(format!("{}: {}", status, body)))
+    }
+    serde_json::from_str(&body).map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+// Converts a DER-encoded ECDSA signature, which is what key management services return, into the
+// concatenation of R and S which RFC 7518 section 3.4 requires, each padded to `len / 2` octets.
+#[cfg(any(feature = "aws-kms", feature = "gcp-kms"))]
+fn ecdsa_der_to_raw(der: &[u8], len: usize) -> Option<Vec<u8>> {
+    // Reads a DER value with the given tag from the front of `input`, returning its contents.
+    fn read<'a>(input: &mut &'a [u8], tag: u8) -> Option<&'a [u8]> {
+        let (&actual, rest) = input.split_first()?;
+        let (&first, mut rest) = rest.split_first()?;
+        if actual != tag {
+            return None
+        }
+        // ECDSA signatures are at most 139 octets long, so one length octet always suffices.
+        let length = match first {
+            0..=0x7f => usize::from(first),
+            0x81 => {
+                let (&length, remainder) = rest.split_first()?;
+                rest = remainder;
+                usize::from(length)
+            }
+            _ => return None,
+        };
+        if rest.len() < length {
+            return None
+        }
+        let (value, rest) = rest.split_at(length);
+        *input = rest;
+        Some(value)
+    }
+
+    let mut input = der;
+    let mut sequence = read(&mut input, 0x30)?;
+    let mut raw = Vec::with_capacity(len);
+    for _ in 0..2 {
+        let integer = read(&mut sequence, 0x02)?;
+        let start = integer.iter().position(|&octet| octet != 0).unwrap_or(integer.len());
+        let integer = &integer[start..];
+        if integer.len() > len / 2 {
+            return None
+        }
+        raw.resize(raw.len() + len / 2 - integer.len(), 0);
+        raw.extend_from_slice(integer);
+    }
+    if input.is_empty() && sequence.is_empty() {
+        Some(raw)
+    } else {
+        None
+    }
+}
+
+#[cfg(all(test, any(feature = "aws-kms", feature = "gcp-kms")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ecdsa_der_to_raw() {
+        // R needs a leading zero octet to stay positive; S is short.
+        let mut der = vec![0x30, 0x26, 0x02, 0x21, 0x00];
+        der.extend_from_slice(&[0x80; 32]);
+        der.extend_from_slice(&[0x02, 0x01, 0x01]);
+        let mut raw = vec![0x80; 32];
+        raw.extend_from_slice(&[0; 31]);
+        raw.push(0x01);
+        assert_eq!(ecdsa_der_to_raw(&der, 64), Some(raw));
+        assert_eq!(ecdsa_der_to_raw(&der, 62), None);
+        assert_eq!(ecdsa_der_to_raw(&der[..der.len() - 1], 64), None);
+        der.push(0);
+        assert_eq!(ecdsa_der_to_raw(&der, 64), None);
+
+        // A P-521 signature is long enough that the sequence has a two-octet length.
+        let mut der = vec![0x30, 0x81, 0x88];
+        for _ in 0..2 {
+            der.extend_from_slice(&[0x02, 0x42, 0x01]);
+            der.extend_from_slice(&[0xff; 65]);
+        }
+        assert_eq!(ecdsa_der_to_raw(&der, 132).map(|raw| raw.len()), Some(132));
+        assert_eq!(ecdsa_der_to_raw(&der, 132).unwrap()[..2], [0x01, 0xff]);
+    }
+}
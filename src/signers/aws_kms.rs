@@ -0,0 +1,140 @@
+use aws_sdk_kms::error::DisplayErrorContext;
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::{KeySpec, MessageType, SigningAlgorithmSpec};
+use aws_sdk_kms::Client;
+
+use crate::crypto;
+use crate::err;
+use crate::header::Alg;
+use crate::traits::AsyncSigner;
+
+/// Returns the algorithm which tokens signed with a KMS key of the given key spec are signed with,
+/// or `None` if the key spec is not one of an asymmetric signing key this crate supports. RSA keys
+/// map to `RS256`, since their key spec does not fix a hash function.
+pub fn alg_for_key_spec(key_spec: &KeySpec) -> Option<Alg> {
+    match key_spec {
+        KeySpec::Rsa2048 | KeySpec::Rsa3072 | KeySpec::Rsa4096 => Some(Alg::RS256),
+        KeySpec::EccNistP256 => Some(Alg::ES256),
+        KeySpec::EccNistP384 => Some(Alg::ES384),
+        KeySpec::EccNistP521 => Some(Alg::ES512),
+        KeySpec::EccSecgP256K1 => Some(Alg::ES256K),
+        _ => None,
+    }
+}
+
+// Returns the KMS signing algorithm which computes signatures for `alg`.
+fn signing_algorithm(alg: Alg) -> Option<SigningAlgorithmSpec> {
+    match alg {
+        Alg::RS256 => Some(SigningAlgorithmSpec::RsassaPkcs1V15Sha256),
+        Alg::RS384 => Some(SigningAlgorithmSpec::RsassaPkcs1V15Sha384),
+        Alg::RS512 => Some(SigningAlgorithmSpec::RsassaPkcs1V15Sha512),
+        Alg::ES256 | Alg::ES256K => Some(SigningAlgorithmSpec::EcdsaSha256),
+        Alg::ES384 => Some(SigningAlgorithmSpec::EcdsaSha384),
+        Alg::ES512 => Some(SigningAlgorithmSpec::EcdsaSha512),
+        _ => None,
+    }
+}
+
+fn kms_error<E: std::error::Error>(e: E) -> err::JWTError {
+    err::JWTError::HttpError(format!("{}", DisplayErrorContext(e)))
+}
+
+#[derive(Debug, Clone)]
+/// Signs tokens with an asymmetric key held in AWS KMS, using the KMS `Sign` API. The signing
+/// input is hashed locally, so only its digest is sent to KMS.
+///
+/// # Examples
+/// ```no_run
+/// # async fn example(client: aws_sdk_kms::Client) -> jwt::err::Result<()> {
+/// use jwt::JWT;
+/// use jwt::signers::aws_kms::KmsSigner;
+/// use jwt::traits::AsyncSigner;
+///
+/// let signer = KmsSigner::new(client, "alias/token-signing").await?;
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}")?;
+/// jwt.header.alg = signer.alg();
+/// let token = jwt.encode_b64_async(&signer).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct KmsSigner {
+    pub client: Client,
+    /// The ARN of the key, which identifies it unambiguously and so serves as the `kid` of the
+    /// tokens it signs.
+    pub kid: String,
+    /// The algorithm to sign with. `KmsSigner::new` picks it from the key spec; for RSA keys it
+    /// may be changed to `RS384` or `RS512`.
+    pub alg: Alg,
+}
+
+impl KmsSigner {
+    /// Looks up the KMS key `key_id`, which may be a key ID, a key ARN, an alias name or an alias
+    /// ARN, returning a signer for it. Returns a `err::JWTError::HttpError` if the key cannot be
+    /// described, or a `err::JWTError::NotImplementedError` if its key spec has no algorithm (see
+    /// `alg_for_key_spec`).
+    pub async fn new(client: Client, key_id: &str) -> err::Result<KmsSigner> {
+        let output = client.describe_key().key_id(key_id).send().await.map_err(kms_error)?;
+        let metadata = output.key_metadata().ok_or_else(|| {
+            err::JWTError::HttpError(String::from("KMS returned no key metadata"))
+        })?;
+        let alg = metadata.key_spec().and_then(alg_for_key_spec)
+            .ok_or(err::JWTError::NotImplementedError)?;
+        let kid = metadata.arn().unwrap_or(key_id).to_string();
+        Ok(KmsSigner { client, kid, alg })
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncSigner for KmsSigner {
+    fn alg(&self) -> Alg {
+        self.alg
+    }
+
+    fn kid(&self) -> Option<&str> {
+        Some(&self.kid)
+    }
+
+    async fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        let signing_algorithm =
+            signing_algorithm(self.alg).ok_or(err::JWTError::NotImplementedError)?;
+        let output = self.client.sign()
+            .key_id(&self.kid)
+            .message(Blob::new(super::digest(self.alg, signing_input)))
+            .message_type(MessageType::Digest)
+            .signing_algorithm(signing_algorithm)
+            .send()
+            .await
+            .map_err(kms_error)?;
+        let signature = output.signature().ok_or_else(|| {
+            err::JWTError::HttpError(String::from("KMS returned no signature"))
+        })?;
+        match crypto::ec_signature_len(self.alg) {
+            // KMS returns ECDSA signatures DER-encoded.
+            Some(len) => super::ecdsa_der_to_raw(signature.as_ref(), len).ok_or_else(|| {
+                err::JWTError::ParseError(String::from("KMS returned a malformed signature"))
+            }),
+            None => Ok(signature.as_ref().to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alg_for_key_spec() {
+        assert_eq!(alg_for_key_spec(&KeySpec::Rsa3072), Some(Alg::RS256));
+        assert_eq!(alg_for_key_spec(&KeySpec::EccNistP521), Some(Alg::ES512));
+        assert_eq!(alg_for_key_spec(&KeySpec::EccSecgP256K1), Some(Alg::ES256K));
+        assert_eq!(alg_for_key_spec(&KeySpec::Hmac256), None);
+        assert_eq!(alg_for_key_spec(&KeySpec::SymmetricDefault), None);
+
+        // Every algorithm a key spec maps to, and the others RSA keys may use, can be signed with.
+        for alg in &[Alg::RS256, Alg::RS384, Alg::RS512, Alg::ES256, Alg::ES384, Alg::ES512] {
+            assert!(signing_algorithm(*alg).is_some());
+        }
+        assert_eq!(signing_algorithm(Alg::ES256K), Some(SigningAlgorithmSpec::EcdsaSha256));
+        assert_eq!(signing_algorithm(Alg::EdDSA), None);
+    }
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::crypto;
+use crate::err;
+use crate::header::Alg;
+use crate::traits::AsyncSigner;
+
+const API_VERSION: &str = "api-version=7.4";
+
+/// Returns the algorithm which tokens signed with the Key Vault key described by the JSON Web Key
+/// `key` (the `key` member of a Key Vault key bundle) are signed with, or `None` if it is not a
+/// signing key this crate supports. RSA keys map to `RS256`, since a key does not fix a hash
+/// function.
+pub fn alg_for_key(key: &Value) -> Option<Alg> {
+    match key.get("kty").and_then(Value::as_str)? {
+        "RSA" | "RSA-HSM" => Some(Alg::RS256),
+        "EC" | "EC-HSM" => match key.get("crv").and_then(Value::as_str)? {
+            "P-256" => Some(Alg::ES256),
+            "P-384" => Some(Alg::ES384),
+            "P-521" => Some(Alg::ES512),
+            "P-256K" => Some(Alg::ES256K),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Returns the body of a `sign` request for `signing_input`. Key Vault names its signing algorithms
+// as JWA does.
+fn sign_request(alg: Alg, signing_input: &[u8]) -> Value {
+    json!({"alg": alg.as_str(), "value": crypto::b64url(&super::digest(alg, signing_input))})
+}
+
+// Returns the signature in the body of a `sign` response, which Key Vault already encodes as JWS
+// does.
+fn parse_sign_response(response: &Value) -> err::Result<Vec<u8>> {
+    let signature = response.get("value").and_then(Value::as_str)
+        .ok_or(err::JWTError::SchemaError)?;
+    base64::decode_config(signature, base64::URL_SAFE_NO_PAD)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+}
+
+#[derive(Clone)]
+/// Signs tokens with a key held in Azure Key Vault (or a Managed HSM), using the `sign` API. The
+/// signing input is hashed locally, so only its digest is sent to Key Vault.
+///
+/// Requests are authenticated with a Microsoft Entra ID access token for the Key Vault resource,
+/// which the caller obtains (for example from a managed identity) and refreshes with
+/// `AzureKeyVaultSigner::set_access_token`.
+///
+/// # Examples
+/// ```no_run
+/// # async fn example(access_token: &str) -> jwt::err::Result<()> {
+/// use jwt::JWT;
+/// use jwt::signers::azure_key_vault::AzureKeyVaultSigner;
+/// use jwt::traits::AsyncSigner;
+///
+/// let key = "https://my-vault.vault.azure.net/keys/token-signing";
+/// let signer = AzureKeyVaultSigner::new(reqwest::Client::new(), key, access_token).await?;
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}")?;
+/// jwt.header.alg = signer.alg();
+/// let token = jwt.encode_b64_async(&signer).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AzureKeyVaultSigner {
+    pub client: reqwest::Client,
+    /// The identifier of the key version, a URL which identifies it unambiguously and so serves
+    /// as the `kid` of the tokens it signs.
+    pub kid: String,
+    /// The algorithm to sign with. `AzureKeyVaultSigner::new` picks it from the key; for RSA keys
+    /// it may be changed to `RS384` or `RS512`.
+    pub alg: Alg,
+    access_token: String,
+}
+
+impl AzureKeyVaultSigner {
+    /// Looks up the key with the identifier `key_id`, a URL of the form
+    /// `https://{vault}.vault.azure.net/keys/{name}`, optionally followed by `/{version}`,
+    /// returning a signer for it. Without a version, the signer uses the key's current version.
+    /// Returns a `err::JWTError::HttpError` if the key cannot be retrieved, or a
+    /// `err::JWTError::NotImplementedError` if it is not a signing key (see `alg_for_key`).
+    pub async fn new(client: reqwest::Client, key_id: &str, access_token: &str)
+        -> err::Result<AzureKeyVaultSigner> {
+        let request = client.get(String::from(key_id.trim_end_matches('/')) + "?" + API_VERSION);
+        let bundle = super::send(request, access_token).await?;
+        let key = bundle.get("key").ok_or(err::JWTError::SchemaError)?;
+        let alg = alg_for_key(key).ok_or(err::JWTError::NotImplementedError)?;
+        let kid = key.get("kid").and_then(Value::as_str).ok_or(err::JWTError::SchemaError)?;
+        Ok(AzureKeyVaultSigner {
+            client,
+            kid: String::from(kid),
+            alg,
+            access_token: String::from(access_token),
+        })
+    }
+
+    /// Replaces the access token requests are authenticated with, which expires.
+    pub fn set_access_token(&mut self, access_token: &str) {
+        self.access_token = String::from(access_token);
+    }
+}
+
+// The access token is a credential, so it is left out.
+impl fmt::Debug for AzureKeyVaultSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AzureKeyVaultSigner")
+            .field("kid", &self.kid)
+            .field("alg", &self.alg)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncSigner for AzureKeyVaultSigner {
+    fn alg(&self) -> Alg {
+        self.alg
+    }
+
+    fn kid(&self) -> Option<&str> {
+        Some(&self.kid)
+    }
+
+    async fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        let supported = matches!(self.alg, Alg::RS256 | Alg::RS384 | Alg::RS512) ||
+            crypto::ec_signature_len(self.alg).is_some();
+        if !supported {
+            return Err(err::JWTError::NotImplementedError)
+        }
+        let request = self.client.post(self.kid.clone() + "/sign?" + API_VERSION)
+            .header("Content-Type", "application/json")
+            .body(sign_request(self.alg, signing_input).to_string());
+        let response = super::send(request, &self.access_token).await?;
+        parse_sign_response(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alg_for_key() {
+        assert_eq!(alg_for_key(&json!({"kty": "EC-HSM", "crv": "P-256K"})), Some(Alg::ES256K));
+        assert_eq!(alg_for_key(&json!({"kty": "RSA", "n": "AQAB"})), Some(Alg::RS256));
+        assert_eq!(alg_for_key(&json!({"kty": "EC", "crv": "P-192"})), None);
+        assert_eq!(alg_for_key(&json!({"kty": "oct-HSM"})), None);
+        assert_eq!(alg_for_key(&json!({})), None);
+    }
+
+    #[test]
+    fn test_sign_request_and_response() {
+        let request = sign_request(Alg::RS512, b"input");
+        assert_eq!(request["alg"], "RS512");
+        let digest = request["value"].as_str().unwrap();
+        assert_eq!(base64::decode_config(digest, base64::URL_SAFE_NO_PAD).unwrap().len(), 64);
+
+        assert_eq!(parse_sign_response(&json!({"kid": "k", "value": "AQI"})).unwrap(), [1, 2]);
+        assert!(matches!(parse_sign_response(&json!({})), Err(err::JWTError::SchemaError)));
+    }
+}
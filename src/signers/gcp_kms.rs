@@ -0,0 +1,170 @@
+use std::fmt;
+
+use serde_json::{json, Value};
+
+use crate::crypto;
+use crate::err;
+use crate::header::Alg;
+use crate::traits::AsyncSigner;
+
+const ENDPOINT: &str = "https://cloudkms.googleapis.com/v1/";
+
+/// Returns the algorithm which tokens signed with a Cloud KMS key version of the given algorithm
+/// (such as `EC_SIGN_P256_SHA256`) are signed with, or `None` if it is not a signing algorithm
+/// this crate supports.
+pub fn alg_for_algorithm(algorithm: &str) -> Option<Alg> {
+    match algorithm {
+        "RSA_SIGN_PKCS1_2048_SHA256" | "RSA_SIGN_PKCS1_3072_SHA256" |
+        "RSA_SIGN_PKCS1_4096_SHA256" => Some(Alg::RS256),
+        "RSA_SIGN_PKCS1_4096_SHA512" => Some(Alg::RS512),
+        "EC_SIGN_P256_SHA256" => Some(Alg::ES256),
+        "EC_SIGN_P384_SHA384" => Some(Alg::ES384),
+        "EC_SIGN_SECP256K1_SHA256" => Some(Alg::ES256K),
+        _ => None,
+    }
+}
+
+// Returns the body of an `asymmetricSign` request for `signing_input`.
+fn sign_request(alg: Alg, signing_input: &[u8]) -> Value {
+    let hash = match alg {
+        Alg::RS384 | Alg::ES384 => "sha384",
+        Alg::RS512 | Alg::ES512 => "sha512",
+        _ => "sha256",
+    };
+    json!({"digest": {hash: base64::encode(super::digest(alg, signing_input))}})
+}
+
+// Returns the signature in the body of an `asymmetricSign` response, as JWS encodes it.
+fn parse_sign_response(alg: Alg, response: &Value) -> err::Result<Vec<u8>> {
+    let signature = response.get("signature").and_then(Value::as_str)
+        .ok_or(err::JWTError::SchemaError)?;
+    let signature = base64::decode(signature)
+        .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+    match crypto::ec_signature_len(alg) {
+        // Cloud KMS returns ECDSA signatures DER-encoded.
+        Some(len) => super::ecdsa_der_to_raw(&signature, len).ok_or_else(|| {
+            err::JWTError::ParseError(String::from("Cloud KMS returned a malformed signature"))
+        }),
+        None => Ok(signature),
+    }
+}
+
+#[derive(Clone)]
+/// Signs tokens with an asymmetric key version held in Google Cloud KMS, using the
+/// `asymmetricSign` API. The signing input is hashed locally, so only its digest is sent to Cloud
+/// KMS.
+///
+/// Requests are authenticated with an OAuth 2.0 access token, which the caller obtains (for
+/// example from the metadata server or a service account key) and refreshes with
+/// `GcpKmsSigner::set_access_token`.
+///
+/// # Examples
+/// ```no_run
+/// # async fn example(access_token: &str) -> jwt::err::Result<()> {
+/// use jwt::JWT;
+/// use jwt::signers::gcp_kms::GcpKmsSigner;
+/// use jwt::traits::AsyncSigner;
+///
+/// let name = "projects/p/locations/global/keyRings/r/cryptoKeys/k/cryptoKeyVersions/1";
+/// let signer = GcpKmsSigner::new(reqwest::Client::new(), name, access_token).await?;
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}")?;
+/// jwt.header.alg = signer.alg();
+/// let token = jwt.encode_b64_async(&signer).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct GcpKmsSigner {
+    pub client: reqwest::Client,
+    /// The resource name of the key version, which identifies it unambiguously and so serves as
+    /// the `kid` of the tokens it signs.
+    pub kid: String,
+    /// The algorithm to sign with, which Cloud KMS fixes for each key version.
+    pub alg: Alg,
+    access_token: String,
+}
+
+impl GcpKmsSigner {
+    /// Looks up the key version with the resource name `name`, of the form
+    /// `projects/*/locations/*/keyRings/*/cryptoKeys/*/cryptoKeyVersions/*`, returning a signer
+    /// for it. Returns a `err::JWTError::HttpError` if the key version cannot be retrieved, or a
+    /// `err::JWTError::NotImplementedError` if its algorithm has no counterpart (see
+    /// `alg_for_algorithm`).
+    pub async fn new(client: reqwest::Client, name: &str, access_token: &str)
+        -> err::Result<GcpKmsSigner> {
+        let request = client.get(String::from(ENDPOINT) + name);
+        let version = super::send(request, access_token).await?;
+        let alg = version.get("algorithm").and_then(Value::as_str)
+            .ok_or(err::JWTError::SchemaError)?;
+        let alg = alg_for_algorithm(alg).ok_or(err::JWTError::NotImplementedError)?;
+        Ok(GcpKmsSigner {
+            client,
+            kid: String::from(name),
+            alg,
+            access_token: String::from(access_token),
+        })
+    }
+
+    /// Replaces the access token requests are authenticated with, which expires.
+    pub fn set_access_token(&mut self, access_token: &str) {
+        self.access_token = String::from(access_token);
+    }
+}
+
+// The access token is a credential, so it is left out.
+impl fmt::Debug for GcpKmsSigner {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GcpKmsSigner")
+            .field("kid", &self.kid)
+            .field("alg", &self.alg)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl AsyncSigner for GcpKmsSigner {
+    fn alg(&self) -> Alg {
+        self.alg
+    }
+
+    fn kid(&self) -> Option<&str> {
+        Some(&self.kid)
+    }
+
+    async fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>> {
+        let request = self.client.post(String::from(ENDPOINT) + &self.kid + ":asymmetricSign")
+            .header("Content-Type", "application/json")
+            .body(sign_request(self.alg, signing_input).to_string());
+        let response = super::send(request, &self.access_token).await?;
+        parse_sign_response(self.alg, &response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alg_for_algorithm() {
+        assert_eq!(alg_for_algorithm("EC_SIGN_P384_SHA384"), Some(Alg::ES384));
+        assert_eq!(alg_for_algorithm("RSA_SIGN_PKCS1_4096_SHA512"), Some(Alg::RS512));
+        assert_eq!(alg_for_algorithm("RSA_SIGN_PSS_2048_SHA256"), None);
+        assert_eq!(alg_for_algorithm("GOOGLE_SYMMETRIC_ENCRYPTION"), None);
+    }
+
+    #[test]
+    fn test_sign_request_and_response() {
+        let request = sign_request(Alg::ES384, b"input");
+        let digest = request["digest"]["sha384"].as_str().unwrap();
+        assert_eq!(base64::decode(digest).unwrap().len(), 48);
+
+        // The DER encoding of R = 1 and S = 2.
+        let der = [0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x02];
+        let response = json!({"signature": base64::encode(der)});
+        let signature = parse_sign_response(Alg::ES256, &response).unwrap();
+        assert_eq!((signature.len(), signature[31], signature[63]), (64, 1, 2));
+        assert_eq!(parse_sign_response(Alg::RS256, &response).unwrap().len(), 8);
+        assert!(matches!(
+            parse_sign_response(Alg::RS256, &json!({})), Err(err::JWTError::SchemaError)
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use serde_json::{Map, Value};
+
+use crate::claims::{Claim, ClaimSet};
+use crate::err;
+
+#[derive(Debug, Clone)]
+/// A **ClaimTemplate** is a claim set whose (string) claim values may contain placeholders, which
+/// are filled in by `render` to produce a `ClaimSet` per token. This lets services which mint
+/// tokens keep the shape of their claims in configuration rather than in code.
+///
+/// The base claims are held as plain JSON, since a placeholder may stand in for a value (such as
+/// a NumericDate) which is only valid once rendered. Claims are checked when they are rendered.
+///
+/// Placeholders are written in braces, and are either:
+///
+/// * `{name}`, replaced with the value bound to `name` when rendering, or
+/// * `{now}`, `{now+<duration>}` or `{now-<duration>}`, replaced with a NumericDate relative to
+///   the time the template is rendered at. Durations are a whole number followed by a unit of
+///   `s`, `m`, `h` or `d`, e.g. `{now+15m}`.
+///
+/// A claim value consisting of exactly one `{now...}` placeholder renders as a number; otherwise
+/// placeholders are interpolated into the surrounding string. Placeholders are expanded inside
+/// arrays and objects, but not in claim names. Literal braces are written `{{` and `}}`.
+///
+/// # Examples
+/// ```
+/// use std::collections::HashMap;
+/// use jwt::template::ClaimTemplate;
+///
+/// let template = ClaimTemplate::parse(
+///     "{\"sub\": \"{sub}\", \"iss\": \"https://{tenant}.example.com\", \"exp\": \"{now+15m}\"}"
+/// ).unwrap();
+/// let vars = HashMap::from([("sub", "u1"), ("tenant", "acme")]);
+/// let claim_set = template.render(&vars, 1600000000).unwrap();
+/// assert_eq!(claim_set.get("iss").unwrap().claim_value, "https://acme.example.com");
+/// assert_eq!(claim_set.get("exp").unwrap().claim_value, 1600000900);
+/// ```
+pub struct ClaimTemplate {
+    pub base: Map<String, Value>,
+}
+
+impl ClaimTemplate {
+    /// Constructs a template from its base claims.
+    pub fn new(base: Map<String, Value>) -> ClaimTemplate {
+        ClaimTemplate { base }
+    }
+
+    /// Constructs a template from a JSON object of its base claims.
+    pub fn parse(input: &str) -> err::Result<ClaimTemplate> {
+        serde_json::from_str(input)
+            .map(ClaimTemplate::new)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }
+
+    /// Renders the template into a `ClaimSet`, binding placeholders to `vars` and `{now...}`
+    /// placeholders relative to `now` (seconds since the epoch). Returns a
+    /// `err::JWTError::ParseError` naming the offending placeholder if one is unbound or
+    /// malformed, or any error `Claim::parse` returns for the rendered claims.
+    pub fn render(&self, vars: &HashMap<&str, &str>, now: u64) -> err::Result<ClaimSet> {
+        let mut claim_set = ClaimSet::new();
+        for (claim_name, claim_value) in &self.base {
+            let claim_value = render_value(claim_value, vars, now)?;
+            claim_set.insert(Claim::parse(claim_name.clone(), claim_value)?)?;
+        }
+        Ok(claim_set)
+    }
+}
+
+fn render_value(value: &Value, vars: &HashMap<&str, &str>, now: u64) -> err::Result<Value> {
+    match value {
+        Value::String(s) => render_str(s, vars, now),
+        Value::Array(values) => values.iter()
+            .map(|value| render_value(value, vars, now))
+            .collect::<err::Result<Vec<Value>>>()
+            .map(Value::Array),
+        Value::Object(members) => {
+            let mut rendered = Map::new();
+            for (name, value) in members {
+                rendered.insert(name.clone(), render_value(value, vars, now)?);
+            }
+            Ok(Value::Object(rendered))
+        }
+        _ => Ok(value.clone()),
+    }
+}
+
+fn render_str(s: &str, vars: &HashMap<&str, &str>, now: u64) -> err::Result<Value> {
+    // A value which is just a time placeholder renders as a NumericDate, not a string.
+    if let Some(placeholder) = s.strip_prefix("{now").and_then(|rest| rest.strip_suffix('}')) {
+        if !placeholder.contains(['{', '}']) {
+            return resolve_now(placeholder, now).map(Value::from);
+        }
+    }
+
+    let mut out = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => { chars.next(); out.push('{'); }
+            '}' if chars.peek() == Some(&'}') => { chars.next(); out.push('}'); }
+            '{' => {
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                out += &resolve(&name, vars, now)?;
+            }
+            '}' => return Err(err::JWTError::ParseError(format!(
+                "unmatched '}}' in template value \"{}\"", s
+            ))),
+            c => out.push(c),
+        }
+    }
+    Ok(Value::String(out))
+}
+
+fn resolve(name: &str, vars: &HashMap<&str, &str>, now: u64) -> err::Result<String> {
+    if let Some(offset) = name.strip_prefix("now") {
+        return resolve_now(offset, now).map(|date| date.to_string());
+    }
+    vars.get(name)
+        .map(|value| String::from(*value))
+        .ok_or_else(|| err::JWTError::ParseError(format!("unbound placeholder {{{}}}", name)))
+}
+
+// Resolves the part of a time placeholder following "now", e.g. "" or "+15m".
+fn resolve_now(offset: &str, now: u64) -> err::Result<u64> {
+    let invalid = || err::JWTError::ParseError(format!("invalid placeholder {{now{}}}", offset));
+    if offset.is_empty() {
+        return Ok(now);
+    }
+    if let Some(duration) = offset.strip_prefix('+') {
+        parse_duration(duration).and_then(|seconds| now.checked_add(seconds)).ok_or_else(invalid)
+    } else if let Some(duration) = offset.strip_prefix('-') {
+        parse_duration(duration).and_then(|seconds| now.checked_sub(seconds)).ok_or_else(invalid)
+    } else {
+        Err(invalid())
+    }
+}
+
+// Parses a duration such as "15m" into seconds. Durations are a whole number followed by a unit
+// of s, m, h or d.
+pub(crate) fn parse_duration(duration: &str) -> Option<u64> {
+    if duration.len() < 2 || !duration.is_char_boundary(duration.len() - 1) {
+        return None;
+    }
+    let (amount, unit) = duration.split_at(duration.len() - 1);
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "s" => Some(amount),
+        "m" => amount.checked_mul(60),
+        "h" => amount.checked_mul(60 * 60),
+        "d" => amount.checked_mul(24 * 60 * 60),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render() {
+        let template = ClaimTemplate::parse(
+            "{\"sub\": \"{sub}\", \"nbf\": \"{now-1h}\", \"iat\": \"{now}\", \
+              \"roles\": [\"{tenant}:admin\"], \"ctx\": {\"at\": \"t={now+1d}\"}, \"n\": 1}"
+        ).unwrap();
+        let vars = HashMap::from([("sub", "u1"), ("tenant", "acme")]);
+        let claim_set = template.render(&vars, 100000).unwrap();
+        assert_eq!(claim_set.get("sub").unwrap().claim_value, "u1");
+        assert_eq!(claim_set.get("nbf").unwrap().claim_value, 96400);
+        assert_eq!(claim_set.get("iat").unwrap().claim_value, 100000);
+        assert_eq!(claim_set.get("roles").unwrap().claim_value, json!(["acme:admin"]));
+        assert_eq!(claim_set.get("ctx").unwrap().claim_value, json!({"at": "t=186400"}));
+        assert_eq!(claim_set.get("n").unwrap().claim_value, 1);
+    }
+
+    #[test]
+    fn test_render_escapes() {
+        let template = ClaimTemplate::parse("{\"x\": \"{{literal}} {v}\"}").unwrap();
+        let claim_set = template.render(&HashMap::from([("v", "ok")]), 0).unwrap();
+        assert_eq!(claim_set.get("x").unwrap().claim_value, "{literal} ok");
+    }
+
+    #[test]
+    fn test_render_checks_claims() {
+        let template = ClaimTemplate::parse("{\"exp\": \"{when}\"}").unwrap();
+        assert!(matches!(
+            template.render(&HashMap::from([("when", "tomorrow")]), 0),
+            Err(err::JWTError::ClaimTypeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_render_invalid() {
+        let vars = HashMap::new();
+        for value in &["{missing}", "{now+15y}", "{now-1s}", "{now+}", "{now€1m}", "oops}"] {
+            let template = ClaimTemplate::parse(&json!({"x": value}).to_string()).unwrap();
+            assert!(matches!(template.render(&vars, 0), Err(err::JWTError::ParseError(_))));
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! Helpers for testing code which consumes this crate, enabled by the `test-util` feature. Only
+//! enable it in `[dev-dependencies]`.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde_json::Value;
+
+use crate::claims::{Claim, ClaimSet};
+use crate::header::Alg;
+use crate::jwk::JwkSet;
+use crate::keygen;
+use crate::keys::PrivateKey;
+use crate::template::parse_duration;
+use crate::JWT;
+
+/// Asserts that a `JWT`'s claims satisfy a list of conditions, panicking with a message naming
+/// the failing claim otherwise. Conditions are one of:
+///
+/// * `name == value` and `name != value`, comparing the claim value to anything convertible into
+///   a `serde_json::Value`;
+/// * `name within "duration"`, requiring the claim to be a NumericDate within the given duration
+///   (e.g. `"30s"` or `"1h"`) of the current time.
+///
+/// Claim names must be Rust identifiers; use `assert_claim_eq` for other names.
+///
+/// # Examples
+/// ```
+/// use jwt::{assert_claims, test_util};
+/// use jwt::claims::ClaimSet;
+/// use jwt::JsonSerializable;
+///
+/// let jwt = test_util::valid(&ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap());
+/// assert_claims!(jwt, sub == "u1", exp within "1h", iat within "1m");
+/// ```
+#[macro_export]
+macro_rules! assert_claims {
+    (@check $claim_set:ident $(,)?) => {};
+    (@check $claim_set:ident, $name:ident == $value:expr $(, $($rest:tt)*)?) => {
+        $crate::test_util::assert_claim_eq($claim_set, stringify!($name), $value);
+        $( $crate::assert_claims!(@check $claim_set, $($rest)*); )?
+    };
+    (@check $claim_set:ident, $name:ident != $value:expr $(, $($rest:tt)*)?) => {
+        $crate::test_util::assert_claim_ne($claim_set, stringify!($name), $value);
+        $( $crate::assert_claims!(@check $claim_set, $($rest)*); )?
+    };
+    (@check $claim_set:ident, $name:ident within $duration:expr $(, $($rest:tt)*)?) => {
+        $crate::test_util::assert_claim_within($claim_set, stringify!($name), $duration);
+        $( $crate::assert_claims!(@check $claim_set, $($rest)*); )?
+    };
+    ($jwt:expr, $($conditions:tt)+) => {{
+        let claim_set = &$jwt.claim_set;
+        $crate::assert_claims!(@check claim_set, $($conditions)+);
+    }};
+}
+
+/// Returns the current time, in seconds since the epoch.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn claim_value<'a>(claim_set: &'a ClaimSet, claim_name: &str) -> &'a Value {
+    match claim_set.get(claim_name) {
+        Ok(claim) => &claim.claim_value,
+        Err(_) => panic!("expected a \"{}\" claim, but the claim set has none", claim_name),
+    }
+}
+
+/// Asserts that the claim `claim_name` is present and equal to `expected`.
+pub fn assert_claim_eq<T: Into<Value>>(claim_set: &ClaimSet, claim_name: &str, expected: T) {
+    let (actual, expected) = (claim_value(claim_set, claim_name), expected.into());
+    assert!(
+        *actual == expected,
+        "expected claim \"{}\" to be {}, but it is {}", claim_name, expected, actual
+    );
+}
+
+/// Asserts that the claim `claim_name` is present and not equal to `unexpected`.
+pub fn assert_claim_ne<T: Into<Value>>(claim_set: &ClaimSet, claim_name: &str, unexpected: T) {
+    let (actual, unexpected) = (claim_value(claim_set, claim_name), unexpected.into());
+    assert!(
+        *actual != unexpected,
+        "expected claim \"{}\" not to be {}", claim_name, unexpected
+    );
+}
+
+/// Asserts that the claim `claim_name` is a NumericDate within `duration` (e.g. `"1h"`) of the
+/// current time, in either direction.
+pub fn assert_claim_within(claim_set: &ClaimSet, claim_name: &str, duration: &str) {
+    let seconds = parse_duration(duration)
+        .unwrap_or_else(|| panic!("invalid duration \"{}\"", duration));
+    let actual = claim_value(claim_set, claim_name);
+    let date = actual.as_u64()
+        .unwrap_or_else(|| panic!("expected claim \"{}\" to be a NumericDate, but it is {}",
+                                  claim_name, actual));
+    let now = now();
+    assert!(
+        date.max(now) - date.min(now) <= seconds,
+        "expected claim \"{}\" to be within {} of {}, but it is {}",
+        claim_name, duration, now, date
+    );
+}
+
+// Returns a JWT with the given claims, overwriting any of the same names in `claims`.
+fn with_claims(claims: &ClaimSet, overrides: &[(&str, u64)]) -> JWT {
+    let mut jwt = JWT::new();
+    jwt.claim_set = claims.clone();
+    for (claim_name, claim_value) in overrides {
+        // Registered claim names always parse.
+        let claim = Claim::parse(String::from(*claim_name), Value::from(*claim_value)).unwrap();
+        jwt.claim_set.claims.insert(String::from(*claim_name), claim);
+    }
+    jwt
+}
+
+/// Returns a token carrying `claims` which was issued a minute ago and expires in an hour.
+pub fn valid(claims: &ClaimSet) -> JWT {
+    let now = now();
+    with_claims(claims, &[("iat", now - 60), ("nbf", now - 60), ("exp", now + 60 * 60)])
+}
+
+/// Returns a token carrying `claims` which was issued two hours ago and expired an hour ago.
+pub fn expired(claims: &ClaimSet) -> JWT {
+    let now = now();
+    with_claims(claims, &[("iat", now - 2 * 60 * 60), ("exp", now - 60 * 60)])
+}
+
+/// Returns a token carrying `claims` which is not valid until an hour from now (and expires in
+/// two hours).
+pub fn not_yet_valid(claims: &ClaimSet) -> JWT {
+    let now = now();
+    with_claims(claims, &[("iat", now), ("nbf", now + 60 * 60), ("exp", now + 2 * 60 * 60)])
+}
+
+/// The `aud` of the tokens `MockIssuer` mints with `Defect::WrongAudience`.
+pub const WRONG_AUDIENCE: &str = "urn:mock-issuer:wrong-audience";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A deliberate defect in a token minted by `MockIssuer::defective_token`.
+pub enum Defect {
+    /// The token expired an hour ago, as `expired` has.
+    Expired,
+    /// The token is not valid until an hour from now, as `not_yet_valid` is.
+    NotYetValid,
+    /// The token's `aud` is `WRONG_AUDIENCE` rather than the issuer's audience.
+    WrongAudience,
+    /// The token is signed by a key other than the one its `kid` names.
+    BadSignature,
+}
+
+/// A token issuer for integration tests of services which verify tokens, holding a freshly
+/// generated ES256 key. Its tokens carry its `iss` and `aud`, and the `kid` of its key, which its
+/// JWK Set (see `jwks`) publishes, either directly or over HTTP (see `serve`).
+///
+/// # Examples
+/// ```
+/// use jwt::claims::{ClaimSet, Validator};
+/// use jwt::header::Alg;
+/// use jwt::test_util::{Defect, MockIssuer};
+/// use jwt::verify::VerifyOptions;
+/// use jwt::{JsonSerializable, JWT};
+///
+/// let issuer = MockIssuer::new("https://issuer.example", "api");
+/// let validator = Validator::new().issuer(issuer.issuer()).audience("api");
+/// let verify = |token: &str| {
+///     let jwt = JWT::decode_unverified(token)?
+///         .verify_with_keys(issuer.jwks(), &VerifyOptions::new(&[Alg::ES256]))?;
+///     validator.validate_jwt(&jwt).map_err(jwt::err::JWTError::ValidationError)
+/// };
+///
+/// let claims = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+/// assert!(verify(&issuer.token(&claims)).is_ok());
+/// assert!(verify(&issuer.defective_token(&claims, Defect::WrongAudience)).is_err());
+/// ```
+pub struct MockIssuer {
+    issuer: String,
+    audience: String,
+    kid: String,
+    key: PrivateKey,
+    // A key of the same algorithm which the JWK Set does not publish, for `Defect::BadSignature`.
+    other_key: PrivateKey,
+    jwks: JwkSet,
+}
+
+impl MockIssuer {
+    /// Constructs an issuer named `issuer` whose tokens are intended for `audience`, generating
+    /// its key. Panics if the crypto backend cannot generate ES256 keys.
+    pub fn new(issuer: &str, audience: &str) -> MockIssuer {
+        let generate = || keygen::generate(Alg::ES256)
+            .unwrap_or_else(|e| panic!("cannot generate a key for the mock issuer: {}", e));
+        let (key, other_key) = (generate(), generate());
+        let jwks = JwkSet::from_keys(&[&key]);
+        // JwkSet::from_keys always sets a kid.
+        let kid = jwks.keys[0].kid.clone().unwrap();
+        MockIssuer {
+            issuer: String::from(issuer),
+            audience: String::from(audience),
+            kid,
+            key,
+            other_key,
+            jwks,
+        }
+    }
+
+    /// Returns the issuer's name, the `iss` of its tokens.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Returns the audience of the issuer's tokens, their `aud`.
+    pub fn audience(&self) -> &str {
+        &self.audience
+    }
+
+    /// Returns the `kid` of the issuer's key.
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// Returns the issuer's JWK Set, which holds the public half of its key.
+    pub fn jwks(&self) -> &JwkSet {
+        &self.jwks
+    }
+
+    /// Serves the issuer's JWK Set over HTTP on a free port of the loopback interface, from a
+    /// thread which runs for the rest of the process, and returns its URL. Every request is
+    /// answered with the set, whatever its path.
+    pub fn serve(&self) -> io::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("http://{}/.well-known/jwks.json", listener.local_addr()?);
+        let body = self.jwks.to_json();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                // A client which hangs up early only fails its own request.
+                let _ = respond(stream, &body);
+            }
+        });
+        Ok(url)
+    }
+
+    /// Mints a token carrying `claims` and the issuer's `iss` and `aud`, which was issued a minute
+    /// ago and expires in an hour, as `valid` does.
+    pub fn token(&self, claims: &ClaimSet) -> String {
+        self.mint(valid(claims), &self.audience, &self.key)
+    }
+
+    /// Mints a token as `token` does, but with `defect`.
+    pub fn defective_token(&self, claims: &ClaimSet, defect: Defect) -> String {
+        match defect {
+            Defect::Expired => self.mint(expired(claims), &self.audience, &self.key),
+            Defect::NotYetValid => self.mint(not_yet_valid(claims), &self.audience, &self.key),
+            Defect::WrongAudience => self.mint(valid(claims), WRONG_AUDIENCE, &self.key),
+            Defect::BadSignature => self.mint(valid(claims), &self.audience, &self.other_key),
+        }
+    }
+
+    fn mint(&self, mut jwt: JWT, audience: &str, key: &PrivateKey) -> String {
+        for (claim_name, claim_value) in [("iss", &self.issuer[..]), ("aud", audience)] {
+            let claim = Claim::parse(String::from(claim_name), Value::from(claim_value))
+                .unwrap_or_else(|e| panic!("invalid \"{}\" claim: {}", claim_name, e));
+            jwt.claim_set.claims.insert(String::from(claim_name), claim);
+        }
+        jwt.header.alg = Alg::ES256;
+        jwt.header.kid = Some(self.kid.clone());
+        // The key was generated for ES256.
+        jwt.sign_key(key).unwrap()
+    }
+}
+
+// Answers the HTTP request on `stream`, whatever it is, with `body` as JSON.
+fn respond(stream: TcpStream, body: &str) -> io::Result<()> {
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    // The request head ends with an empty line.
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+    write!(
+        &stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        body.len(), body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assert_claims() {
+        let mut claims = ClaimSet::new();
+        claims.insert(Claim::parse(String::from("sub"), Value::from("u1")).unwrap()).unwrap();
+        assert_claims!(valid(&claims), sub == "u1", sub != "u2", exp within "1h", iat within "1m");
+        assert_claims!(expired(&claims), exp within "61m",);
+        assert_claims!(not_yet_valid(&claims), nbf within "1h", exp != 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected claim \"sub\" to be \"u2\", but it is \"u1\"")]
+    fn test_assert_claims_eq_failure() {
+        let mut claims = ClaimSet::new();
+        claims.insert(Claim::parse(String::from("sub"), Value::from("u1")).unwrap()).unwrap();
+        assert_claims!(valid(&claims), sub == "u2");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected claim \"exp\" to be within 30m")]
+    fn test_assert_claims_within_failure() {
+        assert_claims!(expired(&ClaimSet::new()), exp within "30m");
+    }
+
+    #[test]
+    fn test_mock_issuer() {
+        use crate::claims::Validator;
+        use crate::err::{JWTError, ValidationError};
+        use crate::verify::VerifyOptions;
+        use crate::JsonSerializable;
+
+        let issuer = MockIssuer::new("https://issuer.example", "api");
+        let validator = Validator::new().issuer(issuer.issuer()).audience(issuer.audience());
+        let verify = |token: &str| {
+            let jwt = JWT::decode_unverified(token)?
+                .verify_with_keys(issuer.jwks(), &VerifyOptions::new(&[Alg::ES256]))?;
+            validator.validate_jwt(&jwt).map_err(JWTError::ValidationError)?;
+            Ok(jwt)
+        };
+
+        let claims = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+        let jwt: crate::err::Result<_> = verify(&issuer.token(&claims));
+        assert_claims!(jwt.unwrap(), sub == "u1", iss == "https://issuer.example", aud == "api");
+        assert!(matches!(verify(&issuer.defective_token(&claims, Defect::Expired)),
+            Err(JWTError::ValidationError(ValidationError::Expired))));
+        assert!(matches!(verify(&issuer.defective_token(&claims, Defect::NotYetValid)),
+            Err(JWTError::ValidationError(ValidationError::NotYetValid))));
+        assert!(matches!(verify(&issuer.defective_token(&claims, Defect::WrongAudience)),
+            Err(JWTError::ValidationError(ValidationError::InvalidClaim(claim)))
+                if claim == "aud"));
+        assert!(matches!(verify(&issuer.defective_token(&claims, Defect::BadSignature)),
+            Err(JWTError::SignatureError)));
+    }
+
+    #[test]
+    fn test_mock_issuer_serve() {
+        use std::io::Read;
+
+        let issuer = MockIssuer::new("https://issuer.example", "api");
+        let url = issuer.serve().unwrap();
+        let address = url.trim_start_matches("http://").split('/').next().unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /.well-known/jwks.json HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        let jwks = JwkSet::from_json(body).unwrap();
+        assert_eq!(jwks.find(issuer.kid()).unwrap().alg, Some(Alg::ES256));
+    }
+}
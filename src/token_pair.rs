@@ -0,0 +1,143 @@
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::claims::{Claim, ClaimSet};
+use crate::err;
+use crate::JWT;
+
+/// The name of the claim distinguishing access tokens from refresh tokens.
+pub const TOKEN_USE_CLAIM: &str = "token_use";
+/// The name of the claim linking an access token to the refresh token it was issued alongside.
+pub const LINEAGE_CLAIM: &str = "lineage";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The two kinds of token in an access/refresh token pair.
+pub enum TokenUse {
+    Access,
+    Refresh,
+}
+
+impl TokenUse {
+    /// Returns the value of the `token_use` claim for this kind of token.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenUse::Access => "access",
+            TokenUse::Refresh => "refresh",
+        }
+    }
+
+    /// Checks that `claim_set` was issued for this use, returning a `err::JWTError::SchemaError`
+    /// if its `token_use` claim is missing or different. Run this wherever tokens are accepted,
+    /// so that a refresh token cannot be used as an access token or the other way around, or
+    /// have a `claims::Validator` check it (see `Validator::token_use`).
+    pub fn check(&self, claim_set: &ClaimSet) -> err::Result<()> {
+        let token_use = claim_set.get(TOKEN_USE_CLAIM)?;
+        if token_use.claim_value.as_str() == Some(self.as_str()) {
+            Ok(())
+        } else {
+            Err(err::JWTError::SchemaError)
+        }
+    }
+}
+
+#[derive(Debug)]
+/// A linked access token and refresh token, as minted by `TokenPair::issue`.
+///
+/// Both tokens carry the claims they were issued from, an `iat`, an `exp`, a unique `jti`, and a
+/// `token_use` claim. The refresh token's `jti` identifies the lineage of the pair, and the access
+/// token carries it in its `lineage` claim; access tokens later minted from the same refresh
+/// token should carry the same `lineage`, so that revoking a refresh token can revoke every
+/// access token descended from it.
+///
+/// The tokens are returned with the default (unsecured) header; set the header appropriate to
+/// your signing key before encoding them.
+///
+/// # Examples
+/// ```
+/// use jwt::claims::ClaimSet;
+/// use jwt::token_pair::{TokenPair, TokenUse};
+/// use jwt::JsonSerializable;
+///
+/// let claims = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+/// let pair = TokenPair::issue(&claims, 1600000000, 900, 1209600).unwrap();
+/// assert!(TokenUse::Access.check(&pair.access.claim_set).is_ok());
+/// assert!(TokenUse::Access.check(&pair.refresh.claim_set).is_err());
+/// ```
+pub struct TokenPair {
+    pub access: JWT,
+    pub refresh: JWT,
+}
+
+fn insert_claim(claim_set: &mut ClaimSet, claim_name: &str, claim_value: Value)
+    -> err::Result<()> {
+    claim_set.insert(Claim::parse(String::from(claim_name), claim_value)?)
+}
+
+fn mint(claims: &ClaimSet, token_use: TokenUse, issued_at: u64, ttl: u64)
+    -> err::Result<ClaimSet> {
+    let exp = issued_at.checked_add(ttl).ok_or(err::JWTError::SchemaError)?;
+    let mut claim_set = claims.clone();
+    insert_claim(&mut claim_set, "iat", Value::from(issued_at))?;
+    insert_claim(&mut claim_set, "exp", Value::from(exp))?;
+    insert_claim(&mut claim_set, "jti", Value::from(Uuid::new_v4().to_string()))?;
+    insert_claim(&mut claim_set, TOKEN_USE_CLAIM, Value::from(token_use.as_str()))?;
+    Ok(claim_set)
+}
+
+impl TokenPair {
+    /// Mints an access token valid for `access_ttl` seconds and a refresh token valid for
+    /// `refresh_ttl` seconds, both issued at `issued_at` (a NumericDate) and carrying `claims`.
+    /// Returns a `err::JWTError::SchemaError` if `claims` already contains one of the claims the
+    /// pair sets (`iat`, `exp`, `jti`, `token_use`, `lineage`), or if an `exp` would overflow.
+    pub fn issue(claims: &ClaimSet, issued_at: u64, access_ttl: u64, refresh_ttl: u64)
+        -> err::Result<TokenPair> {
+        let refresh = mint(claims, TokenUse::Refresh, issued_at, refresh_ttl)?;
+        let lineage = refresh.get("jti")?.claim_value.clone();
+        let mut access = mint(claims, TokenUse::Access, issued_at, access_ttl)?;
+        insert_claim(&mut access, LINEAGE_CLAIM, lineage)?;
+
+        let mut pair = TokenPair { access: JWT::new(), refresh: JWT::new() };
+        pair.access.claim_set = access;
+        pair.refresh.claim_set = refresh;
+        Ok(pair)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::JsonSerializable;
+
+    #[test]
+    fn test_issue() {
+        let claims = ClaimSet::decode_str("{\"sub\": \"u1\"}").unwrap();
+        let pair = TokenPair::issue(&claims, 100, 10, 1000).unwrap();
+        let access = &pair.access.claim_set;
+        let refresh = &pair.refresh.claim_set;
+
+        assert_eq!(access.get("sub").unwrap().claim_value, "u1");
+        assert_eq!(refresh.get("sub").unwrap().claim_value, "u1");
+        assert_eq!(access.get("exp").unwrap().claim_value, 110);
+        assert_eq!(refresh.get("exp").unwrap().claim_value, 1100);
+        let lineage = &refresh.get("jti").unwrap().claim_value;
+        assert_ne!(&access.get("jti").unwrap().claim_value, lineage);
+        assert_eq!(&access.get("lineage").unwrap().claim_value, lineage);
+    }
+
+    #[test]
+    fn test_check_token_use() {
+        let pair = TokenPair::issue(&ClaimSet::new(), 0, 1, 2).unwrap();
+        assert!(TokenUse::Access.check(&pair.access.claim_set).is_ok());
+        assert!(TokenUse::Refresh.check(&pair.refresh.claim_set).is_ok());
+        assert!(TokenUse::Refresh.check(&pair.access.claim_set).is_err());
+        assert!(TokenUse::Access.check(&ClaimSet::new()).is_err());
+    }
+
+    #[test]
+    fn test_issue_conflicting_claims() {
+        let claims = ClaimSet::decode_str("{\"token_use\": \"access\"}").unwrap();
+        assert!(matches!(TokenPair::issue(&claims, 0, 1, 2), Err(err::JWTError::SchemaError)));
+        let overflow = TokenPair::issue(&ClaimSet::new(), u64::MAX - 1, 1, 2);
+        assert!(matches!(overflow, Err(err::JWTError::SchemaError)));
+    }
+}
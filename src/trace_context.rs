@@ -0,0 +1,161 @@
+//! Propagates W3C trace context through tokens, so that a distributed trace can follow a workflow
+//! in which a token is issued by one service and verified by another later on, asynchronously.
+//!
+//! The issuer embeds the `traceparent` of its current span as a claim, and the verifier extracts
+//! it and attaches it as the remote parent of the spans it goes on to start.
+//!
+//! # Examples
+//! ```
+//! use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId};
+//! use opentelemetry::trace::TraceState;
+//! use opentelemetry::Context;
+//! use jwt::claims::ClaimSet;
+//! use jwt::trace_context;
+//!
+//! let span_context = SpanContext::new(
+//!     TraceId::from(7), SpanId::from(9), TraceFlags::SAMPLED, false, TraceState::default()
+//! );
+//! let cx = Context::new().with_remote_span_context(span_context);
+//!
+//! let mut claim_set = ClaimSet::new();
+//! trace_context::embed(&mut claim_set, &cx).unwrap();
+//! let extracted = trace_context::extract(&claim_set).unwrap();
+//! assert_eq!(extracted.trace_id(), TraceId::from(7));
+//! ```
+
+use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+use opentelemetry::{Context, ContextGuard};
+use serde_json::Value;
+
+use crate::claims::{Claim, ClaimSet};
+use crate::err;
+
+/// The name of the claim holding the W3C `traceparent` of the span a token was issued in.
+pub const TRACEPARENT_CLAIM: &str = "traceparent";
+
+// The only traceparent version defined by W3C Trace Context Level 1.
+const VERSION: &str = "00";
+
+/// Formats the span context of `cx` as a W3C `traceparent`, or returns `None` if `cx` has no
+/// valid span context.
+pub fn traceparent(cx: &Context) -> Option<String> {
+    let span = cx.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return None
+    }
+    Some(format!(
+        "{}-{:032x}-{:016x}-{:02x}",
+        VERSION,
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags()
+    ))
+}
+
+/// Parses a W3C `traceparent` into the remote span context it names. Returns `None` unless it is
+/// a well-formed version 00 `traceparent` with non-zero trace and span ids.
+pub fn parse_traceparent(traceparent: &str) -> Option<SpanContext> {
+    let parts = traceparent.split('-').collect::<Vec<&str>>();
+    let hex = |part: &str, len: usize| -> bool {
+        part.len() == len && part.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    };
+    match parts[..] {
+        [version, trace_id, span_id, flags] if version == VERSION && hex(trace_id, 32) &&
+            hex(span_id, 16) && hex(flags, 2) => {
+            let span_context = SpanContext::new(
+                TraceId::from_hex(trace_id).ok()?,
+                SpanId::from_hex(span_id).ok()?,
+                TraceFlags::new(u8::from_str_radix(flags, 16).ok()?),
+                true,
+                TraceState::default(),
+            );
+            Some(span_context).filter(SpanContext::is_valid)
+        }
+        _ => None,
+    }
+}
+
+/// Embeds the trace context of `cx` in `claim_set` as a `traceparent` claim, doing nothing if
+/// `cx` has no valid span context. Returns a `err::JWTError::SchemaError` if `claim_set` already
+/// has a `traceparent` claim.
+pub fn embed(claim_set: &mut ClaimSet, cx: &Context) -> err::Result<()> {
+    match traceparent(cx) {
+        Some(traceparent) => claim_set.insert(
+            Claim::parse(String::from(TRACEPARENT_CLAIM), Value::from(traceparent))?
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Embeds the current trace context in `claim_set`, as `embed` does. Call this when issuing a
+/// token from within the span the token should be traced back to.
+pub fn embed_current(claim_set: &mut ClaimSet) -> err::Result<()> {
+    embed(claim_set, &Context::current())
+}
+
+/// Extracts the remote span context carried by the `traceparent` claim of `claim_set`, if it has
+/// a well-formed one.
+pub fn extract(claim_set: &ClaimSet) -> Option<SpanContext> {
+    claim_set.get(TRACEPARENT_CLAIM).ok()
+        .and_then(|claim| claim.claim_value.as_str())
+        .and_then(parse_traceparent)
+}
+
+/// Returns `cx` with the span context carried by `claim_set` as its remote parent, or a copy of
+/// `cx` if `claim_set` carries none.
+pub fn with_token_context(cx: &Context, claim_set: &ClaimSet) -> Context {
+    match extract(claim_set) {
+        Some(span_context) => cx.with_remote_span_context(span_context),
+        None => cx.clone(),
+    }
+}
+
+/// Attaches the span context carried by `claim_set` to the current context until the returned
+/// guard is dropped, so that spans started while verifying and acting on a token join the trace
+/// it was issued in.
+pub fn attach(claim_set: &ClaimSet) -> ContextGuard {
+    with_token_context(&Context::current(), claim_set).attach()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_roundtrip() {
+        let span_context = parse_traceparent(TRACEPARENT).unwrap();
+        assert!(span_context.is_remote());
+        assert!(span_context.is_sampled());
+        let cx = Context::new().with_remote_span_context(span_context);
+        assert_eq!(traceparent(&cx).as_deref(), Some(TRACEPARENT));
+
+        let mut claim_set = ClaimSet::new();
+        embed(&mut claim_set, &cx).unwrap();
+        assert_eq!(claim_set.get(TRACEPARENT_CLAIM).unwrap().claim_value, TRACEPARENT);
+        assert!(embed(&mut claim_set, &cx).is_err());
+
+        let _guard = attach(&claim_set);
+        assert_eq!(traceparent(&Context::current()).as_deref(), Some(TRACEPARENT));
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut claim_set = ClaimSet::new();
+        embed(&mut claim_set, &Context::new()).unwrap();
+        assert!(claim_set.claims.is_empty());
+        assert!(extract(&claim_set).is_none());
+
+        for traceparent in &[
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-+bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            assert!(parse_traceparent(traceparent).is_none(), "{}", traceparent);
+        }
+    }
+}
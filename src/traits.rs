@@ -1,13 +1,136 @@
+use std::time::SystemTime;
+
+use crate::claims::ClaimSet;
+use crate::config::ParseConfig;
 use crate::err;
+use crate::header::Alg;
 
 /// Structs implementing the `JsonSerializable` trait are losslessly transformable to and from
 /// (optionally base64 encoded) JSON and back again.
 ///
-/// `ClaimSet` and `JWTHeader` implement this trait. `JWT` implements this trait, transitively
-/// using those implementations as part of its own implementation.
+/// `ClaimSet` and `JWTHeader` implement this trait. `JWT` does not: a base64-encoded token is
+/// decoded with `JWT::decode_unverified`, so that its claims are only read once it is verified.
+///
+/// Decoding is controlled by a `ParseConfig`. Implementors provide `decode_str_with` and
+/// `decode_b64_with`; `decode_str` and `decode_b64` decode using the default configuration.
 pub trait JsonSerializable: Sized {
     fn encode_str(&self) -> String;
     fn encode_b64(&self) -> String;
-    fn decode_str(input: &str) -> err::Result<Self>;
-    fn decode_b64(input: &str) -> err::Result<Self>;
-}
\ No newline at end of file
+    fn decode_str_with(input: &str, config: &ParseConfig) -> err::Result<Self>;
+    fn decode_b64_with(input: &str, config: &ParseConfig) -> err::Result<Self>;
+
+    fn decode_str(input: &str) -> err::Result<Self> {
+        Self::decode_str_with(input, &ParseConfig::default())
+    }
+
+    fn decode_b64(input: &str) -> err::Result<Self> {
+        Self::decode_b64_with(input, &ParseConfig::default())
+    }
+}
+
+/// Signs tokens with one algorithm, for `JWT::sign_with`. Implement this to sign with keys this
+/// crate does not hold itself, such as keys in a hardware token.
+///
+/// The elliptic curve and Ed25519 private keys in `keys` implement this trait.
+pub trait Signer {
+    /// The algorithm this signer signs with, which the header of every token it signs must name.
+    fn alg(&self) -> Alg;
+
+    /// Computes the signature of `signing_input`, the base64url-encoded header and payload of a
+    /// token separated by a period.
+    fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>>;
+}
+
+/// Signs tokens with one algorithm without blocking, for `JWT::encode_b64_async`. Implement this
+/// to sign with keys held by a remote service, such as a cloud KMS or a vault.
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+pub trait AsyncSigner: Send + Sync {
+    /// The algorithm this signer signs with, which the header of every token it signs must name.
+    fn alg(&self) -> Alg;
+
+    /// The ID of the key this signer signs with, which `JWT::encode_b64_async` puts in the `kid`
+    /// header parameter of the tokens it signs, or `None` if the key has none.
+    fn kid(&self) -> Option<&str> {
+        None
+    }
+
+    /// Computes the signature of `signing_input`, as `Signer::sign` does.
+    async fn sign(&self, signing_input: &[u8]) -> err::Result<Vec<u8>>;
+}
+
+/// Verifies the signatures of tokens signed with one algorithm, for `JWT::verify_with`.
+///
+/// The elliptic curve and Ed25519 public keys in `keys` implement this trait.
+pub trait Verifier {
+    /// The algorithm this verifier checks signatures made with. Tokens whose header names any
+    /// other algorithm are rejected without calling `verify`.
+    fn alg(&self) -> Alg;
+
+    /// Returns whether `signature` is a valid signature of `signing_input`.
+    fn verify(&self, signing_input: &[u8], signature: &[u8]) -> bool;
+}
+
+/// Tells the time, for `claims::Validator`. Validating against a clock other than `SystemClock`
+/// lets tests, and tools replaying old traffic, validate tokens as of any time.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// The system clock. This is the clock `claims::Validator` uses unless given another.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A clock stopped at a given time.
+///
+/// # Examples
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use jwt::traits::{Clock, FixedClock};
+///
+/// let clock = FixedClock(UNIX_EPOCH + Duration::from_secs(1_600_000_000));
+/// assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(1_600_000_000));
+/// ```
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// Asks an authorization server about tokens which cannot be verified locally, for
+/// `introspection::verify_or_introspect`. `introspection::IntrospectionClient` implements this
+/// over RFC 7662, as does any function from a token to its claims.
+pub trait Introspector {
+    /// Returns the `ClaimSet` of `token` if the server reports it active, or `None` if it does
+    /// not.
+    fn introspect(&self, token: &str) -> err::Result<Option<ClaimSet>>;
+}
+
+impl<F> Introspector for F where F: Fn(&str) -> err::Result<Option<ClaimSet>> {
+    fn introspect(&self, token: &str) -> err::Result<Option<ClaimSet>> {
+        self(token)
+    }
+}
+
+/// Remembers the `jti` claims of tokens a `claims::Validator` has accepted, so that each token
+/// can be used only once. Implement this over shared storage where several instances of a
+/// service must not accept the same token; `replay::MemoryReplayStore` serves a single process.
+pub trait ReplayStore: Send + Sync {
+    /// Records `jti` as seen until `exp` (seconds since the epoch), after which the token it
+    /// identifies is rejected anyway, returning whether it had already been seen.
+    fn seen(&self, jti: &str, exp: u64) -> bool;
+
+    /// Returns whether `jti` has been recorded and has not expired since, without recording it.
+    /// `claims::Validator::require_chain` checks this of the `prev_jti` of re-issued tokens.
+    fn recorded(&self, jti: &str) -> bool;
+}
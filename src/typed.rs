@@ -0,0 +1,193 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::claims::ClaimSet;
+use crate::err;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// The value of an `aud` claim, which RFC 7519 allows to be either a single audience or an array
+/// of them.
+///
+/// # Examples
+/// ```
+/// use jwt::claims::{Audience, ClaimSet};
+/// use jwt::JsonSerializable;
+///
+/// let cs = ClaimSet::decode_str("{\"aud\": \"api\"}").unwrap();
+/// let aud = Audience::from_claim_set(&cs).unwrap().unwrap();
+/// assert_eq!(aud.as_slice(), ["api"]);
+/// assert!(aud.contains_any(&["web", "api"]));
+/// ```
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    /// Reads the `aud` claim of `claim_set`, returning `None` if it has none, or a
+    /// `err::JWTError::SchemaError` if it is neither a string nor an array of strings.
+    pub fn from_claim_set(claim_set: &ClaimSet) -> err::Result<Option<Audience>> {
+        match claim_set.claims.get("aud") {
+            Some(claim) => serde_json::from_value(claim.claim_value.clone())
+                .map(Some).map_err(|_| err::JWTError::SchemaError),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the audiences named, whichever way they are represented.
+    pub fn as_slice(&self) -> &[String] {
+        match self {
+            Audience::Single(audience) => std::slice::from_ref(audience),
+            Audience::Multiple(audiences) => audiences,
+        }
+    }
+
+    /// Returns whether any of `audiences` is named, comparing them exactly.
+    pub fn contains_any(&self, audiences: &[&str]) -> bool {
+        self.as_slice().iter().any(|audience| audiences.contains(&audience.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// The registered claims of RFC 7519 section 4.1, each of which is optional. NumericDates are
+/// held as whole seconds since the epoch.
+pub struct RegisteredClaims {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A typed view of a claim set: its `RegisteredClaims`, the application's own claims `T`, and
+/// an `extra` map holding every claim neither of those recognizes, so that decoding and
+/// re-encoding a claim set does not lose unknown claims.
+///
+/// The three parts are flattened into one JSON object, with serde's `flatten` semantics: the
+/// registered claims take their names first, then `T` takes the names of its fields, and `extra`
+/// gets the rest. `T` should therefore not flatten a map of its own.
+///
+/// # Examples
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use jwt::claims::ClaimSet;
+/// use jwt::typed::Claims;
+/// use jwt::JsonSerializable;
+///
+/// #[derive(Serialize, Deserialize)]
+/// struct Custom { tenant: String }
+///
+/// let cs = ClaimSet::decode_str(
+///     "{\"sub\": \"u1\", \"tenant\": \"acme\", \"https://example.com/role\": \"admin\"}"
+/// ).unwrap();
+/// let claims: Claims<Custom> = Claims::from_claim_set(&cs).unwrap();
+/// assert_eq!(claims.registered.sub.as_deref(), Some("u1"));
+/// assert_eq!(claims.custom.tenant, "acme");
+/// assert_eq!(claims.extra["https://example.com/role"], "admin");
+/// ```
+pub struct Claims<T> {
+    #[serde(flatten)]
+    pub registered: RegisteredClaims,
+    #[serde(flatten)]
+    pub custom: T,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl<T: Serialize + DeserializeOwned> Claims<T> {
+    /// Constructs a `Claims` with the given custom claims and no others.
+    pub fn new(custom: T) -> Claims<T> {
+        Claims { registered: RegisteredClaims::default(), custom, extra: Map::new() }
+    }
+
+    /// Reads a `Claims` out of a `ClaimSet`, returning a `err::JWTError::ParseError` if a claim
+    /// does not have the type its field requires.
+    pub fn from_claim_set(claim_set: &ClaimSet) -> err::Result<Claims<T>> {
+        // Serializing a ClaimSet cannot fail.
+        serde_json::from_value(serde_json::to_value(claim_set).unwrap())
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }
+
+    /// Converts the `Claims` into a `ClaimSet`. Returns a `err::JWTError::ParseError` if `T` does
+    /// not serialize to a JSON object, or if it or `extra` has a claim which `Claim::parse`
+    /// rejects.
+    pub fn to_claim_set(&self) -> err::Result<ClaimSet> {
+        serde_json::to_value(self)
+            .and_then(serde_json::from_value)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::JsonSerializable;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Custom {
+        tenant: String,
+        #[serde(default)]
+        roles: Vec<String>,
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let cs = ClaimSet::decode_str(
+            "{\"iss\": \"idp\", \"aud\": [\"a\", \"b\"], \"exp\": 10, \"tenant\": \"acme\", \
+              \"other\": {\"x\": 1}}"
+        ).unwrap();
+        let claims: Claims<Custom> = Claims::from_claim_set(&cs).unwrap();
+        assert_eq!(claims.registered.iss.as_deref(), Some("idp"));
+        assert_eq!(
+            claims.registered.aud,
+            Some(Audience::Multiple(vec![String::from("a"), String::from("b")]))
+        );
+        assert_eq!(claims.registered.exp, Some(10));
+        assert_eq!(claims.custom, Custom { tenant: String::from("acme"), roles: vec![] });
+        assert_eq!(claims.extra.keys().collect::<Vec<&String>>(), vec!["other"]);
+
+        let out = claims.to_claim_set().unwrap();
+        assert_eq!(out.get("other").unwrap().claim_value, serde_json::json!({"x": 1}));
+        assert_eq!(out.get("roles").unwrap().claim_value, serde_json::json!([]));
+        assert!(out.get("sub").is_err());
+        assert_eq!(out.claims.len(), 6);
+    }
+
+    #[test]
+    fn test_from_claim_set_mistyped() {
+        let cs = ClaimSet::decode_str("{\"tenant\": 1}").unwrap();
+        assert!(matches!(
+            Claims::<Custom>::from_claim_set(&cs), Err(err::JWTError::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_audience() {
+        let cs = ClaimSet::decode_str("{\"aud\": [\"a\", \"b\"]}").unwrap();
+        let aud = Audience::from_claim_set(&cs).unwrap().unwrap();
+        assert_eq!(aud.as_slice(), ["a", "b"]);
+        assert!(aud.contains_any(&["b"]) && !aud.contains_any(&["c"]) && !aud.contains_any(&[]));
+        assert_eq!(Audience::from_claim_set(&ClaimSet::new()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_new() {
+        let mut claims = Claims::new(Custom { tenant: String::from("acme"), roles: vec![] });
+        claims.registered.aud = Some(Audience::Single(String::from("api")));
+        let cs = claims.to_claim_set().unwrap();
+        assert_eq!(cs.get("aud").unwrap().claim_value, "api");
+        assert_eq!(cs.get("tenant").unwrap().claim_value, "acme");
+    }
+}
@@ -0,0 +1,294 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::claims::{Claim, ClaimSet};
+use crate::err;
+
+/// The base `@context` URI which every verifiable credential and presentation must list first.
+pub const CREDENTIALS_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A **verifiable credential** is a set of claims made by an issuer about a subject, as defined
+/// by the W3C Verifiable Credentials Data Model. When a credential is carried in a JWT it lives
+/// in the `vc` claim, and the properties which have a registered JWT equivalent are moved out of
+/// it: `issuer` becomes `iss`, `id` becomes `jti`, and `credentialSubject.id` becomes `sub`.
+///
+/// Properties without a dedicated field (`credentialStatus`, `evidence`, and so on) are kept in
+/// `extra`, so that they survive a round-trip.
+///
+/// # Examples
+/// ```
+/// use jwt::vc::VerifiableCredential;
+/// use serde_json::json;
+///
+/// // Issue a credential as a ClaimSet...
+/// let subject = json!({"id": "did:example:abc", "degree": "BSc"});
+/// let vc = VerifiableCredential::new(&["UniversityDegreeCredential"], subject);
+/// let cs = vc.issue("https://example.edu/issuers/14", 1541493724).unwrap();
+/// assert_eq!(cs.get("sub").unwrap().claim_value, "did:example:abc");
+///
+/// // ...and read it back out again.
+/// let vc = VerifiableCredential::from_claim_set(&cs).unwrap();
+/// assert_eq!(vc.credential_subject["id"], "did:example:abc");
+/// ```
+pub struct VerifiableCredential {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    #[serde(rename = "credentialSubject")]
+    pub credential_subject: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A **verifiable presentation** bundles one or more verifiable credentials for presentation by
+/// a holder to a verifier. When carried in a JWT it lives in the `vp` claim; the `holder`
+/// becomes `iss` and `id` becomes `jti`.
+///
+/// Credentials are usually embedded in `verifiable_credential` as compact JWT strings, but the
+/// data model also allows embedded JSON objects, so these are kept as plain JSON values.
+pub struct VerifiablePresentation {
+    #[serde(rename = "@context")]
+    pub context: Vec<String>,
+    #[serde(rename = "type")]
+    pub types: Vec<String>,
+    #[serde(rename = "verifiableCredential", default, skip_serializing_if = "Vec::is_empty")]
+    pub verifiable_credential: Vec<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+// Builds the `@context` and `type` lists shared by credentials and presentations, making sure the
+// mandatory base entry comes first.
+fn base_lists(base_type: &str, types: &[&str]) -> (Vec<String>, Vec<String>) {
+    let context = vec![String::from(CREDENTIALS_CONTEXT)];
+    let mut all_types = vec![String::from(base_type)];
+    for t in types {
+        if *t != base_type {
+            all_types.push(String::from(*t));
+        }
+    }
+    (context, all_types)
+}
+
+// Checks the `@context` and `type` lists of a decoded credential or presentation.
+fn check_lists(context: &[String], types: &[String], base_type: &str) -> err::Result<()> {
+    if context.first().map(|c| c.as_str()) != Some(CREDENTIALS_CONTEXT) {
+        return Err(err::JWTError::SchemaError)
+    }
+    if !types.iter().any(|t| t == base_type) {
+        return Err(err::JWTError::SchemaError)
+    }
+    Ok(())
+}
+
+// Returns the string value of an (optional) claim, or a `SchemaError` if it is not a string.
+fn optional_str_claim(claim_set: &ClaimSet, claim_name: &str) -> err::Result<Option<String>> {
+    match claim_set.claims.get(claim_name) {
+        None => Ok(None),
+        Some(claim) => claim.claim_value.as_str()
+            .map(|s| Some(String::from(s)))
+            .ok_or(err::JWTError::SchemaError),
+    }
+}
+
+fn insert_claim(claim_set: &mut ClaimSet, claim_name: &str, claim_value: Value) -> err::Result<()> {
+    claim_set.insert(Claim::parse(String::from(claim_name), claim_value)?)
+}
+
+impl VerifiableCredential {
+    /// Constructs a new credential about `credential_subject`. The base context and the
+    /// `VerifiableCredential` type are filled in automatically; `types` lists any additional
+    /// credential types. A `credential_subject` which is not a JSON object is treated as empty.
+    pub fn new(types: &[&str], credential_subject: Value) -> VerifiableCredential {
+        let (context, types) = base_lists("VerifiableCredential", types);
+        let credential_subject = match credential_subject {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        VerifiableCredential { context, types, credential_subject, extra: Map::new() }
+    }
+
+    /// Issuance preset. Maps the credential into a `ClaimSet` per the VC JWT encoding rules,
+    /// setting `iss` to `issuer` and `nbf` to `issued_at` (a NumericDate). `sub` and `jti` are
+    /// set from `credentialSubject.id` and `id` respectively, when present.
+    ///
+    /// Further claims (`exp`, `aud`, ...) may be inserted into the returned `ClaimSet` as usual.
+    pub fn issue(&self, issuer: &str, issued_at: u64) -> err::Result<ClaimSet> {
+        let mut vc = self.clone();
+        let mut claim_set = ClaimSet::new();
+
+        insert_claim(&mut claim_set, "iss", Value::from(issuer))?;
+        insert_claim(&mut claim_set, "nbf", Value::from(issued_at))?;
+        if let Some(sub) = vc.credential_subject.remove("id") {
+            insert_claim(&mut claim_set, "sub", sub)?;
+        }
+        if let Some(jti) = vc.extra.remove("id") {
+            insert_claim(&mut claim_set, "jti", jti)?;
+        }
+        // The issuer is carried by iss; drop any duplicate copy from the vc claim.
+        vc.extra.remove("issuer");
+
+        let vc = serde_json::to_value(&vc)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        insert_claim(&mut claim_set, "vc", vc)?;
+        Ok(claim_set)
+    }
+
+    /// Verification preset. Extracts the credential from the `vc` claim of a `ClaimSet`, checking
+    /// that it is well-formed and that `iss` and `nbf` are present, and restores the properties
+    /// which were moved into registered claims on issuance. Returns a `SchemaError` if the claim
+    /// set does not carry a valid credential.
+    ///
+    /// Note that this only validates the shape of the credential; it says nothing about whether
+    /// the token carrying it is authentic.
+    pub fn from_claim_set(claim_set: &ClaimSet) -> err::Result<VerifiableCredential> {
+        let vc = claim_set.get("vc")?;
+        let mut vc: VerifiableCredential = serde_json::from_value(vc.claim_value.clone())
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        check_lists(&vc.context, &vc.types, "VerifiableCredential")?;
+
+        let issuer = optional_str_claim(claim_set, "iss")?.ok_or(err::JWTError::SchemaError)?;
+        if !claim_set.get("nbf")?.claim_value.is_u64() {
+            return Err(err::JWTError::SchemaError)
+        }
+        vc.extra.insert(String::from("issuer"), Value::from(issuer));
+
+        if let Some(sub) = optional_str_claim(claim_set, "sub")? {
+            // If the subject id was left in the vc claim as well, the two must agree.
+            match vc.credential_subject.get("id") {
+                Some(id) if id != &Value::from(sub.as_str()) => {
+                    return Err(err::JWTError::SchemaError)
+                },
+                _ => { vc.credential_subject.insert(String::from("id"), Value::from(sub)); },
+            }
+        }
+        if let Some(jti) = optional_str_claim(claim_set, "jti")? {
+            vc.extra.insert(String::from("id"), Value::from(jti));
+        }
+        Ok(vc)
+    }
+}
+
+impl VerifiablePresentation {
+    /// Constructs a new presentation of the given `credentials` (typically compact VC JWTs). The
+    /// base context and the `VerifiablePresentation` type are filled in automatically.
+    pub fn new(types: &[&str], credentials: Vec<Value>) -> VerifiablePresentation {
+        let (context, types) = base_lists("VerifiablePresentation", types);
+        VerifiablePresentation {
+            context, types, verifiable_credential: credentials, extra: Map::new()
+        }
+    }
+
+    /// Issuance preset. Maps the presentation into a `ClaimSet`, setting `iss` to the `holder`,
+    /// `aud` to the intended verifier, and `nbf` to `issued_at`. `jti` is set from `id`, when
+    /// present.
+    pub fn issue(&self, holder: &str, audience: &str, issued_at: u64) -> err::Result<ClaimSet> {
+        let mut vp = self.clone();
+        let mut claim_set = ClaimSet::new();
+
+        insert_claim(&mut claim_set, "iss", Value::from(holder))?;
+        insert_claim(&mut claim_set, "aud", Value::from(audience))?;
+        insert_claim(&mut claim_set, "nbf", Value::from(issued_at))?;
+        if let Some(jti) = vp.extra.remove("id") {
+            insert_claim(&mut claim_set, "jti", jti)?;
+        }
+        vp.extra.remove("holder");
+
+        let vp = serde_json::to_value(&vp)
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        insert_claim(&mut claim_set, "vp", vp)?;
+        Ok(claim_set)
+    }
+
+    /// Verification preset. Extracts the presentation from the `vp` claim of a `ClaimSet`,
+    /// checking that it is well-formed and that `iss` and `aud` are present, and restores the
+    /// `holder` and `id` properties. Returns a `SchemaError` if the claim set does not carry a
+    /// valid presentation.
+    pub fn from_claim_set(claim_set: &ClaimSet) -> err::Result<VerifiablePresentation> {
+        let vp = claim_set.get("vp")?;
+        let mut vp: VerifiablePresentation = serde_json::from_value(vp.claim_value.clone())
+            .map_err(|e| { err::JWTError::ParseError(format!("{}", e)) })?;
+        check_lists(&vp.context, &vp.types, "VerifiablePresentation")?;
+
+        let holder = optional_str_claim(claim_set, "iss")?.ok_or(err::JWTError::SchemaError)?;
+        claim_set.get("aud")?;
+        vp.extra.insert(String::from("holder"), Value::from(holder));
+
+        if let Some(jti) = optional_str_claim(claim_set, "jti")? {
+            vp.extra.insert(String::from("id"), Value::from(jti));
+        }
+        Ok(vp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_vc_issue() {
+        let mut vc = VerifiableCredential::new(
+            &["AlumniCredential"], json!({"id": "did:example:abc", "alumniOf": "Example U"})
+        );
+        vc.extra.insert(String::from("id"), json!("http://example.edu/credentials/3732"));
+        let cs = vc.issue("https://example.edu/issuers/14", 1541493724).unwrap();
+
+        assert_eq!(cs.get("iss").unwrap().claim_value, "https://example.edu/issuers/14");
+        assert_eq!(cs.get("sub").unwrap().claim_value, "did:example:abc");
+        assert_eq!(cs.get("jti").unwrap().claim_value, "http://example.edu/credentials/3732");
+        assert_eq!(cs.get("nbf").unwrap().claim_value, 1541493724);
+
+        let vc_claim = &cs.get("vc").unwrap().claim_value;
+        assert_eq!(vc_claim["type"], json!(["VerifiableCredential", "AlumniCredential"]));
+        assert!(vc_claim["credentialSubject"].get("id").is_none());
+        assert!(vc_claim.get("id").is_none());
+    }
+
+    #[test]
+    fn test_vc_roundtrip() {
+        let vc = VerifiableCredential::new(&[], json!({"id": "did:example:abc"}));
+        let cs = vc.issue("did:example:issuer", 1).unwrap();
+        let decoded = VerifiableCredential::from_claim_set(&cs).unwrap();
+        assert_eq!(decoded.credential_subject["id"], "did:example:abc");
+        assert_eq!(decoded.extra["issuer"], "did:example:issuer");
+        assert_eq!(decoded.types, vec!["VerifiableCredential"]);
+    }
+
+    #[test]
+    fn test_vc_from_claim_set_bad_context() {
+        let vc = VerifiableCredential::new(&[], json!({}));
+        let mut cs = ClaimSet::new();
+        let mut vc_value = serde_json::to_value(&vc).unwrap();
+        vc_value["@context"] = json!(["https://example.com/not-the-base-context"]);
+        insert_claim(&mut cs, "iss", json!("did:example:issuer")).unwrap();
+        insert_claim(&mut cs, "nbf", json!(1)).unwrap();
+        insert_claim(&mut cs, "vc", vc_value).unwrap();
+        assert!(matches!(
+            VerifiableCredential::from_claim_set(&cs), Err(err::JWTError::SchemaError)
+        ));
+    }
+
+    #[test]
+    fn test_vc_from_claim_set_missing_iss() {
+        let vc = VerifiableCredential::new(&[], json!({}));
+        let mut cs = ClaimSet::new();
+        insert_claim(&mut cs, "nbf", json!(1)).unwrap();
+        insert_claim(&mut cs, "vc", serde_json::to_value(&vc).unwrap()).unwrap();
+        assert!(VerifiableCredential::from_claim_set(&cs).is_err());
+    }
+
+    #[test]
+    fn test_vp_roundtrip() {
+        let vp = VerifiablePresentation::new(&[], vec![json!("eyJhbGciOiAibm9uZSJ9..")]);
+        let cs = vp.issue("did:example:holder", "did:example:verifier", 1).unwrap();
+        assert_eq!(cs.get("aud").unwrap().claim_value, "did:example:verifier");
+
+        let decoded = VerifiablePresentation::from_claim_set(&cs).unwrap();
+        assert_eq!(decoded.extra["holder"], "did:example:holder");
+        assert_eq!(decoded.verifiable_credential.len(), 1);
+    }
+}
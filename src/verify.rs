@@ -0,0 +1,412 @@
+use std::fmt;
+use std::ops::Deref;
+
+use crate::err;
+use crate::header::{Alg, JWTHeader};
+use crate::jwk::JwkSet;
+use crate::keys::VerificationKey;
+use crate::JWT;
+
+// The smallest RSA modulus, in bits, NIST SP 800-131A allows for signatures.
+const MIN_RSA_BITS: usize = 2048;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Options for `UnverifiedJWT::verify`. The algorithms tokens may be signed with
+/// must be listed explicitly: a token whose header names any other is rejected before its
+/// signature is checked, so that an attacker cannot pick a weaker algorithm than the one the
+/// verifier expects.
+///
+/// Unsecured tokens (`"alg": "none"`) are rejected whatever `algorithms` holds, unless allowed
+/// with `VerifyOptions::allow_unsecured`.
+///
+/// Keys weaker than the algorithm they are used with calls for are rejected with a
+/// `err::JWTError::KeyTooWeak`: HMAC keys shorter than the output of their hash (RFC 7518 section
+/// 3.2), and RSA keys with a modulus under `min_rsa_bits`, 2048 by default.
+///
+/// # Examples
+/// ```
+/// use jwt::{JsonSerializable, JWT};
+/// use jwt::header::Alg;
+/// use jwt::verify::VerifyOptions;
+///
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// let key = [7; 32];
+/// let token = jwt.sign(&key).unwrap();
+/// let jwt = JWT::decode_unverified(&token).unwrap();
+/// assert!(jwt.verify(&key, &VerifyOptions::new(&[Alg::HS256])).is_ok());
+/// assert!(jwt.verify(&key, &VerifyOptions::new(&[Alg::HS512])).is_err());
+/// assert!(jwt.verify(&key[..16], &VerifyOptions::new(&[Alg::HS256])).is_err());
+/// ```
+pub struct VerifyOptions {
+    pub algorithms: Vec<Alg>,
+    pub allow_unsecured: bool,
+    pub min_rsa_bits: usize,
+    pub allow_short_hmac_keys: bool,
+}
+
+impl VerifyOptions {
+    /// Constructs options accepting tokens signed with any of `algorithms`.
+    pub fn new(algorithms: &[Alg]) -> VerifyOptions {
+        VerifyOptions {
+            algorithms: algorithms.to_vec(),
+            allow_unsecured: false,
+            min_rsa_bits: MIN_RSA_BITS,
+            allow_short_hmac_keys: false,
+        }
+    }
+
+    /// Also accepts unsecured tokens, which carry no signature and so prove nothing about who
+    /// issued them. Only use this where tokens reach the verifier over an otherwise authenticated
+    /// channel.
+    pub fn allow_unsecured(mut self) -> VerifyOptions {
+        self.allow_unsecured = true;
+        self
+    }
+
+    /// Sets the smallest RSA modulus, in bits, accepted for verification.
+    pub fn min_rsa_bits(mut self, bits: usize) -> VerifyOptions {
+        self.min_rsa_bits = bits;
+        self
+    }
+
+    /// Also accepts HMAC keys shorter than the output of their hash, which some legacy issuers
+    /// sign with. Short keys are open to brute force, so prefer rotating them.
+    pub fn allow_short_hmac_keys(mut self) -> VerifyOptions {
+        self.allow_short_hmac_keys = true;
+        self
+    }
+
+    /// Returns whether tokens whose header names `alg` are acceptable.
+    pub fn allows(&self, alg: Alg) -> bool {
+        if alg == Alg::None { self.allow_unsecured } else { self.algorithms.contains(&alg) }
+    }
+
+    // Checks that `key` is strong enough to verify tokens signed with `alg`, returning a
+    // err::JWTError::KeyTooWeak if it is not.
+    pub(crate) fn check_key(&self, alg: Alg, key: &VerificationKey) -> err::Result<()> {
+        match key {
+            VerificationKey::Hmac(key) if !self.allow_short_hmac_keys => {
+                let min = match alg {
+                    Alg::HS256 => 32,
+                    Alg::HS384 => 48,
+                    Alg::HS512 => 64,
+                    _ => return Ok(()),
+                };
+                if key.len() < min {
+                    return Err(err::JWTError::KeyTooWeak(format!(
+                        "{} needs an HMAC key of at least {} bytes, not {}", alg.as_str(), min,
+                        key.len()
+                    )))
+                }
+            }
+            VerificationKey::Rsa(key) if key.bits() < self.min_rsa_bits => {
+                return Err(err::JWTError::KeyTooWeak(format!(
+                    "RSA keys must have at least {} bits, not {}", self.min_rsa_bits, key.bits()
+                )))
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A `JWT` whose signature has been verified. Only verification constructs one, so code which
+/// takes a `VerifiedJWT` cannot be handed claims nobody checked.
+///
+/// It dereferences to the `JWT` it wraps, whose header and claim set may be read as usual.
+///
+/// # Examples
+/// ```
+/// use jwt::{JsonSerializable, JWT};
+/// use jwt::header::Alg;
+/// use jwt::verify::{VerifiedJWT, VerifyOptions};
+///
+/// fn subject(jwt: &VerifiedJWT) -> String {
+///     jwt.claim_set.get("sub").unwrap().claim_value.to_string()
+/// }
+///
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// let token = jwt.sign(&[7; 32]).unwrap();
+/// let jwt = JWT::decode_unverified(&token).unwrap();
+/// let verified = jwt.verify(&[7; 32], &VerifyOptions::new(&[Alg::HS256])).unwrap();
+/// assert_eq!(subject(&verified), "\"u1\"");
+/// ```
+pub struct VerifiedJWT {
+    jwt: JWT,
+}
+
+impl VerifiedJWT {
+    // Wraps `jwt`, whose signature the caller has verified.
+    pub(crate) fn new(jwt: JWT) -> VerifiedJWT {
+        VerifiedJWT { jwt }
+    }
+
+    /// Returns the verified `JWT`.
+    pub fn into_inner(self) -> JWT {
+        self.jwt
+    }
+}
+
+impl Deref for VerifiedJWT {
+    type Target = JWT;
+
+    fn deref(&self) -> &JWT {
+        &self.jwt
+    }
+}
+
+#[derive(Clone)]
+/// A decoded `JWT` whose signature has not been checked yet, as returned by
+/// `JWT::decode_unverified`. Its header may be read, to choose the key to verify it with, but its
+/// claim set is only reachable by verifying it, or by explicitly opting out of verification with
+/// `UnverifiedJWT::dangerously_assume_verified`. For the same reason, it does not implement
+/// `PartialEq` or `Hash`, and its `Debug` output shows only the header.
+///
+/// # Examples
+/// ```
+/// use jwt::JWT;
+/// use jwt::header::Alg;
+/// use jwt::verify::VerifyOptions;
+///
+/// let key = [7; 32];
+/// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+/// jwt.header.alg = Alg::HS256;
+/// let token = jwt.sign(&key).unwrap();
+///
+/// let unverified = JWT::decode_unverified(&token).unwrap();
+/// assert_eq!(unverified.header().alg, Alg::HS256);
+/// let options = VerifyOptions::new(&[Alg::HS256]);
+/// assert!(unverified.verify(&[8; 32], &options).is_err());
+/// assert_eq!(unverified.verify(&key, &options).unwrap().claim_set, jwt.claim_set);
+/// ```
+pub struct UnverifiedJWT {
+    jwt: JWT,
+}
+
+impl UnverifiedJWT {
+    // Wraps `jwt`, whose signature has not been verified.
+    pub(crate) fn new(jwt: JWT) -> UnverifiedJWT {
+        UnverifiedJWT { jwt }
+    }
+
+    /// Returns the header of the token. Like the claim set, it is whatever the sender chose, so
+    /// only use it to decide how to verify the token.
+    pub fn header(&self) -> &JWTHeader {
+        &self.jwt.header
+    }
+
+    // Returns the token's `iss` claim, to choose the keys to verify the token with by. Returns a
+    // MissingClaim or InvalidClaim error for iss if the token has none, or one which is not a
+    // string.
+    pub(crate) fn issuer(&self) -> err::Result<&str> {
+        let iss = self.jwt.claim_set.get("iss").map_err(|_| {
+            err::JWTError::ValidationError(err::ValidationError::MissingClaim(String::from("iss")))
+        })?;
+        iss.claim_value.as_str().ok_or_else(|| {
+            err::JWTError::ValidationError(err::ValidationError::InvalidClaim(String::from("iss")))
+        })
+    }
+
+    /// Verifies the signature of the token under `key` with the algorithm its header names,
+    /// returning it as a `VerifiedJWT`. `key` may be an HMAC key or a reference to a public key
+    /// (see `keys::VerificationKey`). The signature is checked against the raw segments the token
+    /// was decoded from.
+    ///
+    /// The header must name one of the algorithms `options` allows; otherwise a
+    /// `err::JWTError::SignatureError` is returned without checking the signature. A
+    /// `err::JWTError::KeyTooWeak` is returned if `key` falls short of the strength `options`
+    /// requires, and a `err::JWTError::SignatureError` if the signature does not match or the
+    /// header names an algorithm `key` is not for. Unsecured tokens are only accepted if
+    /// `options` allows them, and then only without a signature.
+    ///
+    /// # Examples
+    /// ```
+    /// use jwt::JWT;
+    /// use jwt::header::Alg;
+    /// use jwt::keys::Ed25519PrivateKey;
+    /// use jwt::verify::VerifyOptions;
+    ///
+    /// let key = Ed25519PrivateKey::from_seed(&[7; 32]).unwrap();
+    /// let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+    /// jwt.header.alg = Alg::EdDSA;
+    /// let token = jwt.sign_eddsa(&key).unwrap();
+    ///
+    /// let decoded = JWT::decode_unverified(&token).unwrap();
+    /// let options = VerifyOptions::new(&[Alg::EdDSA]);
+    /// assert_eq!(decoded.verify(&key.public_key(), &options).unwrap().claim_set, jwt.claim_set);
+    /// assert!(decoded.verify(&[7; 32], &options).is_err());
+    /// ```
+    pub fn verify<'a, K>(&self, key: K, options: &VerifyOptions) -> err::Result<VerifiedJWT>
+        where K: Into<VerificationKey<'a>> {
+        self.jwt.verify(key, options)
+    }
+
+    /// Verifies the signature of the token, as `UnverifiedJWT::verify` does, under the key of
+    /// `keys` which its header's `kid` names or, if it names none, under each key of `keys` which
+    /// suits its algorithm in turn. Returns a `err::JWTError::UnknownKid` if the header names a
+    /// `kid` none of `keys` has, so that the caller can fetch the issuer's keys again, and a
+    /// `err::JWTError::SignatureError` if no key verifies the signature.
+    pub fn verify_with_keys(&self, keys: &JwkSet, options: &VerifyOptions)
+        -> err::Result<VerifiedJWT> {
+        let alg = self.header().alg;
+        if !options.allows(alg) {
+            return Err(err::JWTError::SignatureError)
+        }
+        // Unsecured tokens have no key to select.
+        if alg == Alg::None {
+            return self.verify(VerificationKey::Hmac(&[]), options)
+        }
+        let mut result = Err(err::JWTError::SignatureError);
+        for jwk in keys.select(self.header())? {
+            result = self.verify(jwk, options);
+            if result.is_ok() {
+                break
+            }
+        }
+        result
+    }
+
+    /// Returns the token without verifying its signature. Its claims may have been forged, so
+    /// only use this where the token's integrity is established some other way, or where its
+    /// claims are merely displayed.
+    pub fn dangerously_assume_verified(self) -> JWT {
+        self.jwt
+    }
+}
+
+// The claim set is only reachable once the token has been verified, so it is left out.
+impl fmt::Debug for UnverifiedJWT {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnverifiedJWT")
+            .field("header", &self.jwt.header)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jwk::{Jwk, JwkKey};
+    use crate::keys::{EcPrivateKey, RsaPublicKey};
+
+    #[test]
+    fn test_verify_options() {
+        let key = EcPrivateKey::from_pem(include_str!("testdata/ec_p256_private_sec1.pem"))
+            .unwrap();
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        jwt.header.alg = Alg::ES256;
+        let token = jwt.sign_ec(&key).unwrap();
+        let jwt = JWT::decode_unverified(&token).unwrap();
+
+        let options = VerifyOptions::new(&[Alg::RS256, Alg::ES256]);
+        assert!(options.allows(Alg::ES256) && !options.allows(Alg::HS256));
+        assert!(jwt.verify(&key.public_key(), &options).is_ok());
+        let options = VerifyOptions::new(&[Alg::RS256]);
+        assert!(matches!(
+            jwt.verify(&key.public_key(), &options), Err(err::JWTError::SignatureError)
+        ));
+        assert!(jwt.verify(&key.public_key(), &VerifyOptions::new(&[])).is_err());
+    }
+
+    #[test]
+    fn test_allow_unsecured() {
+        let jwt = JWT::decode_b64("eyJhbGciOiAibm9uZSJ9.e30=.").unwrap();
+        let options = VerifyOptions::new(&[Alg::None, Alg::HS256]);
+        assert!(!options.allows(Alg::None));
+        assert!(matches!(
+            jwt.verify(b"key", &options), Err(err::JWTError::SignatureError)
+        ));
+        let options = VerifyOptions::new(&[Alg::HS256]).allow_unsecured();
+        assert_eq!(*jwt.verify(b"key", &options).unwrap(), jwt);
+
+        // An unsecured token may not carry a signature.
+        let jwt = JWT::decode_b64("eyJhbGciOiAibm9uZSJ9.e30=.c2ln").unwrap();
+        assert!(jwt.verify(b"key", &options).is_err());
+    }
+
+    #[test]
+    fn test_unverified() {
+        let key = EcPrivateKey::from_pem(include_str!("testdata/ec_p256_private_sec1.pem"))
+            .unwrap();
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        jwt.header.alg = Alg::ES256;
+        let token = jwt.sign_ec(&key).unwrap();
+
+        let unverified = JWT::decode_unverified(&token).unwrap();
+        assert_eq!(*unverified.header(), jwt.header);
+        let options = VerifyOptions::new(&[Alg::ES256]);
+        let verified = unverified.verify(&key.public_key(), &options).unwrap();
+        assert_eq!(verified.claim_set, jwt.claim_set);
+        let options = VerifyOptions::new(&[Alg::ES384]);
+        assert!(unverified.verify(&key.public_key(), &options).is_err());
+        assert_eq!(unverified.dangerously_assume_verified().claim_set, jwt.claim_set);
+
+        // Only compact, base64url-encoded tokens are accepted.
+        assert!(JWT::decode_unverified(&token.replace('.', "\n.\n")).is_err());
+    }
+
+    #[test]
+    fn test_verify_with_keys() {
+        let oct = |kid: Option<&str>, key: &[u8]| Jwk {
+            kid: kid.map(String::from), alg: None, key_use: None, key: JwkKey::Oct(key.to_vec())
+        };
+        let keys = JwkSet {
+            keys: vec![oct(None, &[7; 32]), oct(None, &[8; 32]), oct(Some("k9"), &[9; 32])],
+        };
+        let options = VerifyOptions::new(&[Alg::HS256]);
+        let mut jwt = JWT::from_plain_str("{\"sub\": \"u1\"}").unwrap();
+        jwt.header.alg = Alg::HS256;
+
+        // Without a kid, each key is tried.
+        let token = jwt.sign(&[8; 32]).unwrap();
+        let verified = JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options);
+        assert_eq!(verified.unwrap().claim_set, jwt.claim_set);
+        let token = jwt.sign(&[6; 32]).unwrap();
+        assert!(matches!(
+            JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options),
+            Err(err::JWTError::SignatureError)
+        ));
+
+        // With one, only the key it names.
+        jwt.header.kid = Some(String::from("k9"));
+        let token = jwt.sign(&[9; 32]).unwrap();
+        assert!(JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options).is_ok());
+        let token = jwt.sign(&[7; 32]).unwrap();
+        assert!(JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options).is_err());
+        jwt.header.kid = Some(String::from("k10"));
+        let token = jwt.sign(&[7; 32]).unwrap();
+        assert!(matches!(
+            JWT::decode_unverified(&token).unwrap().verify_with_keys(&keys, &options),
+            Err(err::JWTError::UnknownKid(kid)) if kid == "k10"
+        ));
+        // Algorithms the options do not allow are rejected before any key is selected.
+        assert!(matches!(
+            JWT::decode_unverified(&token).unwrap()
+                .verify_with_keys(&keys, &VerifyOptions::new(&[Alg::HS512])),
+            Err(err::JWTError::SignatureError)
+        ));
+    }
+
+    #[test]
+    fn test_check_key() {
+        let options = VerifyOptions::new(&[Alg::HS256, Alg::HS512, Alg::RS256]);
+        let key = [7; 48];
+        assert!(options.check_key(Alg::HS256, &(&key).into()).is_ok());
+        assert!(matches!(
+            options.check_key(Alg::HS512, &(&key).into()), Err(err::JWTError::KeyTooWeak(_))
+        ));
+        let options = options.allow_short_hmac_keys();
+        assert!(options.check_key(Alg::HS512, &(&key[..4]).into()).is_ok());
+
+        let key = RsaPublicKey::from_pem(include_str!("testdata/rsa_public.pem")).unwrap();
+        assert_eq!(key.bits(), 2048);
+        assert!(options.check_key(Alg::RS256, &(&key).into()).is_ok());
+        let options = options.min_rsa_bits(3072);
+        assert!(matches!(
+            options.check_key(Alg::RS256, &(&key).into()), Err(err::JWTError::KeyTooWeak(_))
+        ));
+    }
+}